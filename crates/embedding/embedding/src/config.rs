@@ -11,6 +11,14 @@ pub trait EmbeddingConfig: Send + Sync {
     fn openai_api_key(&self) -> &str;
     /// Optional base URL for OpenAI-compatible embedding (OPENAI_BASE_URL). When set, e.g. for Big Model, embedding requests use this URL.
     fn openai_base_url(&self) -> Option<&str>;
+    /// Embedding model (EMBEDDING_MODEL). None = the provider's default model.
+    fn model(&self) -> Option<&str> {
+        None
+    }
+    /// Vector dimension of the model (EMBEDDING_DIM). None = known from the model name.
+    fn dimension(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Embedding config loaded from environment variables.
@@ -20,6 +28,8 @@ pub struct EnvEmbeddingConfig {
     pub bigmodel_api_key: String,
     pub openai_api_key: String,
     pub openai_base_url: Option<String>,
+    pub embedding_model: Option<String>,
+    pub embedding_dim: Option<usize>,
 }
 
 impl EmbeddingConfig for EnvEmbeddingConfig {
@@ -35,6 +45,12 @@ impl EmbeddingConfig for EnvEmbeddingConfig {
     fn openai_base_url(&self) -> Option<&str> {
         self.openai_base_url.as_deref().filter(|s| !s.is_empty())
     }
+    fn model(&self) -> Option<&str> {
+        self.embedding_model.as_deref().filter(|s| !s.is_empty())
+    }
+    fn dimension(&self) -> Option<usize> {
+        self.embedding_dim
+    }
 }
 
 impl EnvEmbeddingConfig {
@@ -47,11 +63,20 @@ impl EnvEmbeddingConfig {
             .unwrap_or_default();
         let openai_api_key = env::var("OPENAI_API_KEY").unwrap_or_default();
         let openai_base_url = env::var("OPENAI_BASE_URL").ok().filter(|s| !s.trim().is_empty());
+        let embedding_model = env::var("EMBEDDING_MODEL").ok().filter(|s| !s.trim().is_empty());
+        let embedding_dim = match env::var("EMBEDDING_DIM") {
+            Ok(s) if !s.trim().is_empty() => Some(s.trim().parse().map_err(|e| {
+                anyhow::anyhow!("EMBEDDING_DIM must be a positive integer, got {:?}: {}", s, e)
+            })?),
+            _ => None,
+        };
         Ok(Self {
             embedding_provider,
             bigmodel_api_key,
             openai_api_key,
             openai_base_url,
+            embedding_model,
+            embedding_dim,
         })
     }
 
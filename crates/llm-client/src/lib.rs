@@ -28,6 +28,23 @@ pub struct StreamChunk {
     pub done: bool,
 }
 
+/// Token usage of one LLM call, as reported by the provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmUsage {
+    /// Model that served the request.
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// LLM reply text plus token usage (`None` when the provider did not report it).
+#[derive(Debug, Clone)]
+pub struct LlmResponse {
+    pub content: String,
+    pub usage: Option<LlmUsage>,
+}
+
 /// Type-erased callback for stream chunks so that [`LlmClient`] is dyn compatible.
 pub type StreamChunkCallback =
     dyn FnMut(StreamChunk) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send;
@@ -45,6 +62,35 @@ pub trait LlmClient: Send + Sync {
         messages: Vec<ChatMessage>,
         callback: &mut StreamChunkCallback,
     ) -> Result<String>;
+
+    /// Like [`get_llm_response_with_messages`](Self::get_llm_response_with_messages), also returning token usage.
+    /// Default impl reports no usage.
+    async fn get_llm_response_with_messages_and_usage(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<LlmResponse> {
+        let content = self.get_llm_response_with_messages(messages).await?;
+        Ok(LlmResponse {
+            content,
+            usage: None,
+        })
+    }
+
+    /// Like [`get_llm_response_stream_with_messages`](Self::get_llm_response_stream_with_messages), also returning
+    /// token usage. Default impl reports no usage.
+    async fn get_llm_response_stream_with_messages_and_usage(
+        &self,
+        messages: Vec<ChatMessage>,
+        callback: &mut StreamChunkCallback,
+    ) -> Result<LlmResponse> {
+        let content = self
+            .get_llm_response_stream_with_messages(messages, callback)
+            .await?;
+        Ok(LlmResponse {
+            content,
+            usage: None,
+        })
+    }
 }
 
 /// Converts a single [`ChatMessage`] into OpenAI API message format.
@@ -2,11 +2,11 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
-use openai_client::StreamChunk as OpenAIStreamChunk;
+use openai_client::{StreamChunk as OpenAIStreamChunk, TokenUsage};
 use prompt::ChatMessage;
 use tracing::instrument;
 
-use super::{chat_message_to_openai, LlmClient, StreamChunk};
+use super::{chat_message_to_openai, LlmClient, LlmResponse, LlmUsage, StreamChunk};
 
 /// Default system prompt: plain text only, suitable for Telegram (no Markdown/formatting).
 pub const DEFAULT_SYSTEM_CONTENT: &str =
@@ -65,23 +65,41 @@ impl OpenAILlmClient {
     }
 }
 
-#[async_trait]
-impl LlmClient for OpenAILlmClient {
-    #[instrument(skip(self, messages))]
-    async fn get_llm_response_with_messages(&self, messages: Vec<ChatMessage>) -> Result<String> {
-        // Prepend system message, then convert user/assistant messages to OpenAI format.
+impl OpenAILlmClient {
+    /// Prepends the system message and converts the given messages to OpenAI format.
+    fn to_openai_messages(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Vec<openai_client::ChatCompletionRequestMessage>> {
         let mut openai_messages: Vec<openai_client::ChatCompletionRequestMessage> = vec![
             openai_client::ChatCompletionRequestSystemMessageArgs::default()
                 .content(self.system_content().to_string())
                 .build()?
                 .into(),
         ];
-        for msg in &messages {
+        for msg in messages {
             openai_messages.push(chat_message_to_openai(msg)?);
         }
-        self.client
-            .chat_completion(&self.model, openai_messages)
+        Ok(openai_messages)
+    }
+
+    fn to_llm_usage(&self, usage: Option<TokenUsage>) -> Option<LlmUsage> {
+        usage.map(|u| LlmUsage {
+            model: self.model.clone(),
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAILlmClient {
+    #[instrument(skip(self, messages))]
+    async fn get_llm_response_with_messages(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        self.get_llm_response_with_messages_and_usage(messages)
             .await
+            .map(|r| r.content)
     }
 
     #[instrument(skip(self, messages, callback))]
@@ -90,24 +108,49 @@ impl LlmClient for OpenAILlmClient {
         messages: Vec<ChatMessage>,
         callback: &mut super::StreamChunkCallback,
     ) -> Result<String> {
+        self.get_llm_response_stream_with_messages_and_usage(messages, callback)
+            .await
+            .map(|r| r.content)
+    }
+
+    #[instrument(skip(self, messages))]
+    async fn get_llm_response_with_messages_and_usage(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> Result<LlmResponse> {
+        // Prepend system message, then convert user/assistant messages to OpenAI format.
+        let openai_messages = self.to_openai_messages(&messages)?;
+        let (content, usage) = self
+            .client
+            .chat_completion_with_usage(&self.model, openai_messages)
+            .await?;
+        Ok(LlmResponse {
+            content,
+            usage: self.to_llm_usage(usage),
+        })
+    }
+
+    #[instrument(skip(self, messages, callback))]
+    async fn get_llm_response_stream_with_messages_and_usage(
+        &self,
+        messages: Vec<ChatMessage>,
+        callback: &mut super::StreamChunkCallback,
+    ) -> Result<LlmResponse> {
         // Same as non-stream: system first, then converted messages.
-        let mut openai_messages: Vec<openai_client::ChatCompletionRequestMessage> = vec![
-            openai_client::ChatCompletionRequestSystemMessageArgs::default()
-                .content(self.system_content().to_string())
-                .build()?
-                .into(),
-        ];
-        for msg in &messages {
-            openai_messages.push(chat_message_to_openai(msg)?);
-        }
-        self.client
-            .chat_completion_stream(&self.model, openai_messages, |chunk: OpenAIStreamChunk| {
+        let openai_messages = self.to_openai_messages(&messages)?;
+        let (content, usage) = self
+            .client
+            .chat_completion_stream_with_usage(&self.model, openai_messages, |chunk: OpenAIStreamChunk| {
                 callback(StreamChunk {
                     content: chunk.content,
                     done: chunk.done,
                 })
             })
             .await
-            .map_err(|e| anyhow::anyhow!("Stream error: {}", e))
+            .map_err(|e| anyhow::anyhow!("Stream error: {}", e))?;
+        Ok(LlmResponse {
+            content,
+            usage: self.to_llm_usage(usage),
+        })
     }
 }
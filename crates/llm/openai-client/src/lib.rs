@@ -2,8 +2,12 @@
 //!
 //! Thin wrapper around [async-openai] for chat completion (non-stream and stream).
 //! Provides token masking for safe logging and a simple request/response API.
+//! The `*_with_usage` variants also return the token usage reported by the API.
 
-use async_openai::{types::CreateChatCompletionRequestArgs, Client};
+use async_openai::{
+    types::{ChatCompletionStreamOptions, CompletionUsage, CreateChatCompletionRequestArgs},
+    Client,
+};
 use futures::StreamExt;
 use std::sync::Arc;
 use tracing;
@@ -50,6 +54,24 @@ pub struct StreamChunk {
     pub done: bool,
 }
 
+/// Token usage reported by the API for one completion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl From<&CompletionUsage> for TokenUsage {
+    fn from(u: &CompletionUsage) -> Self {
+        Self {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        }
+    }
+}
+
 impl OpenAIClient {
     /// Builds a client using the given API key and default API base URL.
     pub fn new(api_key: String) -> Self {
@@ -92,6 +114,17 @@ impl OpenAIClient {
         model: &str,
         messages: Vec<ChatCompletionRequestMessage>,
     ) -> anyhow::Result<String> {
+        self.chat_completion_with_usage(model, messages)
+            .await
+            .map(|(content, _)| content)
+    }
+
+    /// Same as [`chat_completion`](Self::chat_completion), also returning the token usage when the API reports it.
+    pub async fn chat_completion_with_usage(
+        &self,
+        model: &str,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> anyhow::Result<(String, Option<TokenUsage>)> {
         let message_count = messages.len();
         let masked = self
             .api_key_for_logging
@@ -127,7 +160,10 @@ impl OpenAIClient {
         }
 
         if let Some(choice) = response.choices.first() {
-            Ok(choice.message.content.clone().unwrap_or_default())
+            Ok((
+                choice.message.content.clone().unwrap_or_default(),
+                response.usage.as_ref().map(TokenUsage::from),
+            ))
         } else {
             anyhow::bail!("No response from OpenAI");
         }
@@ -139,8 +175,25 @@ impl OpenAIClient {
         &self,
         model: &str,
         messages: Vec<ChatCompletionRequestMessage>,
-        mut callback: F,
+        callback: F,
     ) -> anyhow::Result<String>
+    where
+        F: FnMut(StreamChunk) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<()>>,
+    {
+        self.chat_completion_stream_with_usage(model, messages, callback)
+            .await
+            .map(|(content, _)| content)
+    }
+
+    /// Same as [`chat_completion_stream`](Self::chat_completion_stream), also returning the token usage.
+    /// Requests `stream_options.include_usage` so the API sends usage on the final chunk.
+    pub async fn chat_completion_stream_with_usage<F, Fut>(
+        &self,
+        model: &str,
+        messages: Vec<ChatCompletionRequestMessage>,
+        mut callback: F,
+    ) -> anyhow::Result<(String, Option<TokenUsage>)>
     where
         F: FnMut(StreamChunk) -> Fut,
         Fut: std::future::Future<Output = anyhow::Result<()>>,
@@ -162,6 +215,9 @@ impl OpenAIClient {
         let request = CreateChatCompletionRequestArgs::default()
             .model(model)
             .messages(messages)
+            .stream_options(ChatCompletionStreamOptions {
+                include_usage: true,
+            })
             .build()?;
 
         if let Ok(json) = serde_json::to_string_pretty(&request) {
@@ -174,6 +230,7 @@ impl OpenAIClient {
         let mut last_update = std::time::Instant::now();
         // Accumulates content since last callback; flushed every ~1s or on finish.
         let mut pending_content = String::new();
+        let mut usage: Option<TokenUsage> = None;
 
         while let Some(result) = stream.next().await {
             match result {
@@ -186,6 +243,7 @@ impl OpenAIClient {
                             total_tokens = u.total_tokens,
                             "OpenAI chat_completion_stream usage"
                         );
+                        usage = Some(TokenUsage::from(u));
                     }
                     if let Some(choice) = chunk.choices.first() {
                         if let Some(content) = &choice.delta.content {
@@ -222,6 +280,6 @@ impl OpenAIClient {
             .await?;
         }
 
-        Ok(full_response)
+        Ok((full_response, usage))
     }
 }
@@ -3,7 +3,7 @@
 //! Defines LanceConfig and its default values. Used when creating a store
 //! via `LanceVectorStore::with_config`. External: memory-lance public API.
 
-use crate::DistanceType;
+use crate::{DistanceType, LanceIndexType};
 
 /// Configuration for LanceVectorStore.
 ///
@@ -19,6 +19,13 @@ use crate::DistanceType;
 /// | `refine_factor` | `Option<u32>` | For IVF-PQ index: fetch limit×refine_factor candidates then reorder by true distance; higher = more accurate |
 /// | `nprobes` | `Option<usize>` | Number of IVF partitions to search; higher = better recall, slower (Lance default 20) |
 /// | `semantic_fetch_multiplier` | `u32` | When filtering by user/conversation, fetch_limit = limit × this; default 10 |
+/// | `vector_index` | `LanceIndexType` | Vector index built by auto-indexing; default IVF-PQ |
+/// | `ivf_pq` | `IvfPqParams` | Build parameters for the IVF-PQ index |
+/// | `hnsw` | `IvfHnswParams` | Build and query parameters for the IVF-HNSW index |
+/// | `auto_index_threshold` | `Option<usize>` | Build `vector_index` once the table has this many rows; `None` = never; default 10 000 |
+/// | `scalar_indexes` | `bool` | Keep BTree indexes on `id`, `user_id` and `conversation_id`; default true |
+/// | `index_check_interval` | `usize` | Rows written between checks for missing indexes; default 100 |
+/// | `optimize_interval` | `Option<usize>` | Rows written between background index optimizations once indexes exist; `None` = never; default 1 000 |
 #[derive(Debug, Clone)]
 pub struct LanceConfig {
     /// Path to the LanceDB database directory
@@ -37,6 +44,58 @@ pub struct LanceConfig {
     pub nprobes: Option<usize>,
     /// When filtering by user_id/conversation_id, fetch_limit = limit × this (min 50). Ensures enough candidates after filter.
     pub semantic_fetch_multiplier: u32,
+    /// Vector index type created once the table crosses `auto_index_threshold`.
+    pub vector_index: LanceIndexType,
+    /// Parameters for `LanceIndexType::IvfPq`.
+    pub ivf_pq: IvfPqParams,
+    /// Parameters for `LanceIndexType::Hnsw`.
+    pub hnsw: IvfHnswParams,
+    /// Row count at which the vector index is built automatically. None = only via `create_index`.
+    /// Ignored when `use_exact_search` is set.
+    pub auto_index_threshold: Option<usize>,
+    /// If true, BTree scalar indexes on id, user_id and conversation_id are created after the first write.
+    pub scalar_indexes: bool,
+    /// Missing indexes are looked for on the first write and then once per this many written rows,
+    /// so writes do not count rows and list indexes every time.
+    pub index_check_interval: usize,
+    /// Once every index exists, rows appended since they were built are only covered by a scan.
+    /// After this many written rows the indexes are optimized (new rows merged in) in the
+    /// background. None = never; run `LanceVectorStore::optimize_indexes` instead.
+    pub optimize_interval: Option<usize>,
+}
+
+/// Build parameters for an IVF-PQ vector index. `None` fields use the Lance defaults
+/// (partitions ≈ sqrt(rows), sub-vectors = dim / 16).
+#[derive(Debug, Clone, Default)]
+pub struct IvfPqParams {
+    /// Number of IVF partitions (k-means clusters).
+    pub num_partitions: Option<u32>,
+    /// Number of PQ sub-vectors; must divide the embedding dimension.
+    pub num_sub_vectors: Option<u32>,
+}
+
+/// Parameters for an IVF-HNSW (scalar-quantized) vector index.
+#[derive(Debug, Clone)]
+pub struct IvfHnswParams {
+    /// Number of IVF partitions. None = Lance default.
+    pub num_partitions: Option<u32>,
+    /// Max edges per node in the HNSW graph (often called `m`).
+    pub num_edges: u32,
+    /// Candidate list size while building the graph.
+    pub ef_construction: u32,
+    /// Candidate list size at query time; higher = better recall, slower. None = Lance default.
+    pub ef_search: Option<usize>,
+}
+
+impl Default for IvfHnswParams {
+    fn default() -> Self {
+        Self {
+            num_partitions: None,
+            num_edges: 20,
+            ef_construction: 300,
+            ef_search: None,
+        }
+    }
 }
 
 impl Default for LanceConfig {
@@ -50,6 +109,13 @@ impl Default for LanceConfig {
             refine_factor: None,
             nprobes: None,
             semantic_fetch_multiplier: 10,
+            vector_index: LanceIndexType::IvfPq,
+            ivf_pq: IvfPqParams::default(),
+            hnsw: IvfHnswParams::default(),
+            auto_index_threshold: Some(10_000),
+            scalar_indexes: true,
+            index_check_interval: 100,
+            optimize_interval: Some(1_000),
         }
     }
 }
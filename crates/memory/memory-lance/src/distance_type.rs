@@ -14,7 +14,6 @@ pub enum DistanceType {
 }
 
 impl DistanceType {
-    #[allow(dead_code)]
     pub(crate) fn as_lance_metric(&self) -> lancedb::DistanceType {
         match self {
             DistanceType::Cosine => lancedb::DistanceType::Cosine,
@@ -1,16 +1,17 @@
 //! Vector index types supported by LanceVectorStore.
 //!
-//! Used when calling `LanceVectorStore::create_index`. External: lancedb index API.
+//! Used when calling `LanceVectorStore::create_index` and for auto-indexing (`LanceConfig::vector_index`).
+//! Build parameters come from `LanceConfig::ivf_pq` / `LanceConfig::hnsw`. External: lancedb index API.
 
 /// Vector index types supported by LanceVectorStore.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LanceIndexType {
     /// Automatically choose the best index type
     Auto,
     /// IVF-PQ (Inverted File with Product Quantization)
     /// Good balance of speed and accuracy for large datasets
     IvfPq,
-    /// HNSW (Hierarchical Navigable Small World)
+    /// IVF-HNSW with scalar quantization (HNSW graph per IVF partition)
     /// Fastest query performance, higher memory usage
     Hnsw,
 }
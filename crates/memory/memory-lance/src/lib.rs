@@ -5,7 +5,8 @@
 //! ## Features
 //!
 //! - **Persistent storage** with Lance format
-//! - **Vector indexing** with IVF-PQ and HNSW
+//! - **Vector indexing** with IVF-PQ and IVF-HNSW, built automatically past a row threshold
+//! - **Scalar indexes** on id, user and conversation so lookups are filtered, not scanned
 //! - **Semantic search** with configurable distance metrics
 //! - **Metadata filtering** for efficient querying
 //!
@@ -28,6 +29,7 @@
 //!     timestamp: Utc::now(),
 //!     tokens: None,
 //!     importance: None,
+//!     expires_at: None,
 //! };
 //! let entry = MemoryEntry::new("Hello world".to_string(), metadata);
 //! store.add(entry).await?;
@@ -44,7 +46,7 @@ mod distance_type;
 mod index_type;
 mod store;
 
-pub use config::LanceConfig;
+pub use config::{IvfHnswParams, IvfPqParams, LanceConfig};
 pub use distance_type::DistanceType;
 pub use index_type::LanceIndexType;
 pub use store::LanceVectorStore;
@@ -4,18 +4,28 @@
 //! External: memory (MemoryStore, MemoryEntry), lancedb, arrow.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use arrow_array::{Array, Float32Array, RecordBatch, RecordBatchIterator, StringArray};
 use arrow_array::types::Float32Type;
 use arrow_schema::{DataType, Field, Schema};
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-
-use telegram_bot::memory_core::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
-use lancedb::query::{ExecutableQuery, QueryBase};
+use tokio::sync::{Mutex, RwLock};
+
+use telegram_bot::memory_core::{
+    EmbeddingGuard, EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryMetadata, MemoryOrder, MemoryPage, MemoryRole,
+    MemoryStore, SearchFilter,
+};
+use lancedb::index::scalar::BTreeIndexBuilder;
+use lancedb::index::vector::{IvfHnswSqIndexBuilder, IvfPqIndexBuilder};
+use lancedb::index::Index;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::{NewColumnTransform, OptimizeAction, OptimizeOptions};
 use futures::TryStreamExt;
 use anyhow::{anyhow, Result};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::config::LanceConfig;
@@ -28,8 +38,26 @@ use crate::index_type::LanceIndexType;
 pub struct LanceVectorStore {
     pub(crate) config: LanceConfig,
     db: Arc<RwLock<lancedb::Connection>>,
+    /// Set once every configured index exists; writes then skip the index check.
+    indexes_ready: AtomicBool,
+    /// Rows written through this store; paces the index checks.
+    rows_written: AtomicUsize,
+    /// Value of `rows_written` at which writes next look for missing indexes.
+    next_index_check: AtomicUsize,
+    /// Rows written since the indexes were last optimized (counted once they all exist).
+    rows_since_optimize: AtomicUsize,
+    /// Set while a background index optimization runs.
+    optimizing: Arc<AtomicBool>,
+    /// Serializes index creation, optimization and removal so they never overlap.
+    index_lock: Arc<Mutex<()>>,
+    /// Model and dimension of the stored vectors (dimension from the table schema, model from the
+    /// `<table>.embedding` file next to the table).
+    embedding_guard: EmbeddingGuard,
 }
 
+/// Columns that get a BTree scalar index (point lookups and scope filters).
+const SCALAR_INDEX_COLUMNS: &[&str] = &["id", "user_id", "conversation_id"];
+
 impl LanceVectorStore {
     /// Creates a new LanceVectorStore with the given database path.
     ///
@@ -69,10 +97,21 @@ impl LanceVectorStore {
         let store = Self {
             config,
             db: Arc::new(RwLock::new(db)),
+            indexes_ready: AtomicBool::new(false),
+            rows_written: AtomicUsize::new(0),
+            next_index_check: AtomicUsize::new(1),
+            rows_since_optimize: AtomicUsize::new(0),
+            optimizing: Arc::new(AtomicBool::new(false)),
+            index_lock: Arc::new(Mutex::new(())),
+            embedding_guard: EmbeddingGuard::default(),
         };
 
-        // Ensure table exists
+        // Ensure table exists, then catch up on indexes for tables written by older versions
         store.ensure_table().await?;
+        let table = store.open_table().await?;
+        store.migrate_expires_at(&table).await?;
+        store.load_embedding_spec(&table).await?;
+        store.ensure_indexes(&table).await;
 
         Ok(store)
     }
@@ -87,27 +126,8 @@ impl LanceVectorStore {
             .map_err(|e| anyhow!("Failed to list tables: {}", e))?;
 
         if !table_names.contains(&self.config.table_name) {
-            // Create schema
-            let schema = Arc::new(Schema::new(vec![
-                Field::new("id", DataType::Utf8, false),
-                Field::new("content", DataType::Utf8, false),
-                Field::new(
-                    "vector",
-                    DataType::FixedSizeList(
-                        Arc::new(Field::new("item", DataType::Float32, true)),
-                        self.config.embedding_dim as i32,
-                    ),
-                    true,
-                ),
-                Field::new("user_id", DataType::Utf8, true),
-                Field::new("conversation_id", DataType::Utf8, true),
-                Field::new("role", DataType::Utf8, false),
-                Field::new("timestamp", DataType::Utf8, false),
-                Field::new("tokens", DataType::UInt32, true),
-                Field::new("importance", DataType::Float32, true),
-            ]));
-
             // Create empty table
+            let schema = Self::batch_schema(self.config.embedding_dim)?;
             db.create_empty_table(&self.config.table_name, schema)
                 .execute()
                 .await
@@ -117,7 +137,93 @@ impl LanceVectorStore {
         Ok(())
     }
 
-    /// Creates a vector index on the table.
+    /// Adds the nullable `expires_at` column to tables created before entries could expire.
+    async fn migrate_expires_at(&self, table: &lancedb::Table) -> Result<()> {
+        let schema = table
+            .schema()
+            .await
+            .map_err(|e| anyhow!("Failed to read table schema: {}", e))?;
+        if schema.field_with_name("expires_at").is_ok() {
+            return Ok(());
+        }
+        table
+            .add_columns(
+                NewColumnTransform::SqlExpressions(vec![(
+                    "expires_at".to_string(),
+                    "CAST(NULL AS STRING)".to_string(),
+                )]),
+                None,
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to add expires_at column: {}", e))?;
+        info!(table = %self.config.table_name, "Added expires_at column to Lance table");
+        Ok(())
+    }
+
+    /// Path of the file recording the embedding model of this table.
+    fn embedding_spec_path(&self) -> PathBuf {
+        Path::new(&self.config.db_path).join(format!("{}.embedding", self.config.table_name))
+    }
+
+    /// Loads the embedding spec: dimension from the table's vector column, model from the spec file.
+    /// A table whose dimension differs from `config.embedding_dim` opens, but every vector write and
+    /// query fails with a mismatch until the store is re-embedded.
+    async fn load_embedding_spec(&self, table: &lancedb::Table) -> Result<()> {
+        let dimension = self.table_dimension(table).await?;
+        let model = match tokio::fs::read_to_string(self.embedding_spec_path()).await {
+            Ok(text) => {
+                let spec = EmbeddingSpecFile::parse(&text)?;
+                if spec.dimension != dimension {
+                    warn!(recorded = %spec, dimension, "Lance embedding spec file disagrees with table schema; using schema");
+                }
+                spec.model
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(anyhow!("Failed to read embedding spec file: {}", e)),
+        };
+        if dimension != self.config.embedding_dim {
+            warn!(
+                table_dim = dimension,
+                config_dim = self.config.embedding_dim,
+                "Lance table dimension differs from LanceConfig.embedding_dim; re-embed the store"
+            );
+        }
+        self.embedding_guard
+            .set(Some(EmbeddingSpec::new(model, dimension)));
+        Ok(())
+    }
+
+    /// Size of the table's fixed-size vector column.
+    async fn table_dimension(&self, table: &lancedb::Table) -> Result<usize> {
+        let schema = table
+            .schema()
+            .await
+            .map_err(|e| anyhow!("Failed to read table schema: {}", e))?;
+        match schema.field_with_name("vector").map(|f| f.data_type()) {
+            Ok(DataType::FixedSizeList(_, n)) => Ok(*n as usize),
+            _ => Err(anyhow!("Table {} has no fixed-size vector column", self.config.table_name)),
+        }
+    }
+
+    async fn save_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<()> {
+        tokio::fs::write(self.embedding_spec_path(), EmbeddingSpecFile::format(spec))
+            .await
+            .map_err(|e| anyhow!("Failed to write embedding spec file: {}", e))
+    }
+
+    /// Opens the memory table.
+    async fn open_table(&self) -> Result<lancedb::Table> {
+        let db = self.db.read().await;
+        db.open_table(&self.config.table_name)
+            .execute()
+            .await
+            .map_err(|e| anyhow!("Failed to open table: {}", e))
+    }
+
+    /// Creates (or replaces) the vector index on the table.
+    ///
+    /// IVF-PQ uses `config.ivf_pq`, HNSW uses `config.hnsw` (IVF-HNSW-SQ); both use `config.distance_type`.
+    /// Lance needs enough rows to train the index (at least a few hundred); on a small table this fails.
     ///
     /// # Arguments
     ///
@@ -129,76 +235,345 @@ impl LanceVectorStore {
     /// store.create_index(LanceIndexType::IvfPq).await?;
     /// ```
     pub async fn create_index(&self, index_type: LanceIndexType) -> Result<()> {
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
+        let table = self.open_table().await?;
+        self.create_vector_index(&table, index_type).await
+    }
 
+    async fn create_vector_index(&self, table: &lancedb::Table, index_type: LanceIndexType) -> Result<()> {
+        let metric = self.config.distance_type.as_lance_metric();
         let index_params = match index_type {
-            LanceIndexType::Auto => lancedb::index::Index::Auto,
-            LanceIndexType::IvfPq => lancedb::index::Index::Auto, // Use Auto for IVF-PQ
-            LanceIndexType::Hnsw => lancedb::index::Index::Auto,  // Use Auto for now
+            LanceIndexType::Auto => Index::Auto,
+            LanceIndexType::IvfPq => {
+                let params = &self.config.ivf_pq;
+                let mut builder = IvfPqIndexBuilder::default().distance_type(metric);
+                if let Some(n) = params.num_partitions {
+                    builder = builder.num_partitions(n);
+                }
+                if let Some(n) = params.num_sub_vectors {
+                    builder = builder.num_sub_vectors(n);
+                }
+                Index::IvfPq(builder)
+            }
+            LanceIndexType::Hnsw => {
+                let params = &self.config.hnsw;
+                let mut builder = IvfHnswSqIndexBuilder::default()
+                    .distance_type(metric)
+                    .num_edges(params.num_edges)
+                    .ef_construction(params.ef_construction);
+                if let Some(n) = params.num_partitions {
+                    builder = builder.num_partitions(n);
+                }
+                Index::IvfHnswSq(builder)
+            }
         };
 
         table
             .create_index(&["vector"], index_params)
+            .replace(true)
             .execute()
             .await
             .map_err(|e| anyhow!("Failed to create index: {}", e))?;
 
+        info!(index_type = ?index_type, "Lance vector index created");
+        Ok(())
+    }
+
+    /// Returns the columns that currently have an index (scalar or vector).
+    pub async fn indexed_columns(&self) -> Result<Vec<String>> {
+        let table = self.open_table().await?;
+        Self::indexed_columns_of(&table).await.map(|c| c.into_iter().collect())
+    }
+
+    async fn indexed_columns_of(table: &lancedb::Table) -> Result<HashSet<String>> {
+        let indices = table
+            .list_indices()
+            .await
+            .map_err(|e| anyhow!("Failed to list indices: {}", e))?;
+        Ok(indices.into_iter().flat_map(|i| i.columns).collect())
+    }
+
+    /// Merges rows written since the indexes were built into them, so indexed queries stop
+    /// scanning those rows. Writes schedule this every `optimize_interval` rows.
+    pub async fn optimize_indexes(&self) -> Result<()> {
+        let table = self.open_table().await?;
+        let _guard = self.index_lock.lock().await;
+        table
+            .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+            .await
+            .map_err(|e| anyhow!("Failed to optimize indexes: {}", e))?;
+        info!("Lance indexes optimized");
         Ok(())
     }
 
+    /// Index upkeep after a write of `rows` rows. Until every index exists, looks for missing ones
+    /// on the first write and then every `index_check_interval` rows; afterwards starts a
+    /// background optimization every `optimize_interval` rows.
+    async fn after_write(&self, table: &lancedb::Table, rows: usize) {
+        let written = self.rows_written.fetch_add(rows, Ordering::AcqRel) + rows;
+        if !self.indexes_ready.load(Ordering::Acquire) {
+            if written >= self.next_index_check.load(Ordering::Acquire) {
+                self.next_index_check.store(
+                    written + self.config.index_check_interval.max(1),
+                    Ordering::Release,
+                );
+                self.ensure_indexes(table).await;
+            }
+            return;
+        }
+        let Some(interval) = self.config.optimize_interval else {
+            return;
+        };
+        let pending = self.rows_since_optimize.fetch_add(rows, Ordering::AcqRel) + rows;
+        if pending >= interval && !self.optimizing.swap(true, Ordering::AcqRel) {
+            self.rows_since_optimize.store(0, Ordering::Release);
+            let table = table.clone();
+            let optimizing = self.optimizing.clone();
+            let index_lock = self.index_lock.clone();
+            tokio::spawn(async move {
+                let _guard = index_lock.lock().await;
+                match table
+                    .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+                    .await
+                {
+                    Ok(_) => info!(rows = pending, "Lance indexes optimized"),
+                    Err(e) => warn!(error = %e, "Lance index optimization failed; new rows stay unindexed"),
+                }
+                optimizing.store(false, Ordering::Release);
+            });
+        }
+    }
+
+    /// Creates missing scalar indexes and, once the table has `auto_index_threshold` vectors, the
+    /// vector index. Called from writes (see `after_write`) until everything exists. Failures are
+    /// logged, never returned: queries stay correct without indexes, only slower.
+    async fn ensure_indexes(&self, table: &lancedb::Table) {
+        if self.indexes_ready.load(Ordering::Acquire) {
+            return;
+        }
+        let _guard = self.index_lock.lock().await;
+        if self.indexes_ready.load(Ordering::Acquire) {
+            return;
+        }
+        match self.try_ensure_indexes(table).await {
+            Ok(true) => self.indexes_ready.store(true, Ordering::Release),
+            Ok(false) => {}
+            Err(e) => warn!(error = %e, "Lance index maintenance failed; queries fall back to scans"),
+        }
+    }
+
+    /// Returns true when no further index work is pending.
+    async fn try_ensure_indexes(&self, table: &lancedb::Table) -> Result<bool> {
+        let rows = table
+            .count_rows(None)
+            .await
+            .map_err(|e| anyhow!("Failed to count rows: {}", e))?;
+        if rows == 0 {
+            // Nothing to train on yet
+            return Ok(false);
+        }
+        let existing = Self::indexed_columns_of(table).await?;
+
+        if self.config.scalar_indexes {
+            for column in SCALAR_INDEX_COLUMNS {
+                if !existing.contains(*column) {
+                    table
+                        .create_index(&[*column], Index::BTree(BTreeIndexBuilder::default()))
+                        .execute()
+                        .await
+                        .map_err(|e| anyhow!("Failed to create scalar index on {}: {}", column, e))?;
+                    info!(column = *column, "Lance scalar index created");
+                }
+            }
+        }
+
+        if existing.contains("vector") || self.config.use_exact_search {
+            return Ok(true);
+        }
+        let Some(threshold) = self.config.auto_index_threshold else {
+            return Ok(true);
+        };
+        if rows < threshold {
+            return Ok(false);
+        }
+        info!(rows, threshold, "Lance table crossed auto-index threshold");
+        self.create_vector_index(table, self.config.vector_index).await?;
+        Ok(true)
+    }
+
+    /// Runs a plain (non-vector) query with `predicate` pushed down and converts every row.
+    /// Expired rows are left out.
+    async fn query_entries(&self, predicate: String, limit: Option<usize>) -> Result<Vec<MemoryEntry>> {
+        let table = self.open_table().await?;
+        let mut query = table
+            .query()
+            .only_if(format!("({}) AND {}", predicate, Self::unexpired_predicate()));
+        if let Some(limit) = limit {
+            query = query.limit(limit);
+        }
+        let batches = query
+            .execute()
+            .await
+            .map_err(|e| anyhow!("Failed to execute query: {}", e))?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
+
+        let mut entries = Vec::new();
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                entries.push(self.batch_to_entry(&batch, row)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Ids of the first `limit` rows matching `predicate` in `order` (expired rows left out). Only
+    /// `id` and `timestamp` are read: Lance scans are unordered, so the sort happens here, and
+    /// whole rows are fetched for the page only.
+    async fn page_ids(&self, predicate: String, order: MemoryOrder, limit: usize) -> Result<Vec<Uuid>> {
+        let table = self.open_table().await?;
+        let batches = table
+            .query()
+            .only_if(format!("({}) AND {}", predicate, Self::unexpired_predicate()))
+            .select(Select::Columns(vec!["id".to_string(), "timestamp".to_string()]))
+            .execute()
+            .await
+            .map_err(|e| anyhow!("Failed to execute query: {}", e))?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
+
+        let mut keys = Vec::new();
+        for batch in &batches {
+            let column = |name: &str| {
+                batch
+                    .column_by_name(name)
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                    .ok_or_else(|| anyhow!("{} column is missing or not StringArray", name))
+            };
+            let ids = column("id")?;
+            let timestamps = column("timestamp")?;
+            for row in 0..batch.num_rows() {
+                let timestamp = DateTime::parse_from_rfc3339(timestamps.value(row))?.with_timezone(&Utc);
+                keys.push((timestamp, Uuid::parse_str(ids.value(row))?));
+            }
+        }
+        keys.sort_unstable();
+        if order == MemoryOrder::NewestFirst {
+            keys.reverse();
+        }
+        keys.truncate(limit);
+        Ok(keys.into_iter().map(|(_, id)| id).collect())
+    }
+
     /// Converts a MemoryEntry to a RecordBatch.
     fn entry_to_batch(&self, entry: &MemoryEntry) -> Result<RecordBatch> {
-        let schema = Self::batch_schema(self.config.embedding_dim)?;
+        self.entries_to_batch(std::slice::from_ref(entry))
+    }
 
-        let id = entry.id.to_string();
-        let content = &entry.content;
-        let user_id = entry.metadata.user_id.as_deref();
-        let conversation_id = entry.metadata.conversation_id.as_deref();
-        let role = format!("{:?}", entry.metadata.role);
-        let timestamp = entry.metadata.timestamp.to_rfc3339();
-        let tokens = entry.metadata.tokens;
-        let importance = entry.metadata.importance;
+    /// Converts many MemoryEntries to one multi-row RecordBatch (one Lance write for the whole slice).
+    /// Replaces the table with one of `LanceConfig.embedding_dim` holding the same rows without
+    /// vectors. The rows are written by the create commit itself, so there is no empty-table window.
+    async fn overwrite_without_vectors(&self, table: &lancedb::Table) -> Result<()> {
+        let batches = table
+            .query()
+            .execute()
+            .await
+            .map_err(|e| anyhow!("Failed to execute query: {}", e))?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
+        let mut entries = Vec::new();
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                let mut entry = self.batch_to_entry(&batch, row)?;
+                entry.embedding = None;
+                entries.push(entry);
+            }
+        }
 
-        // Build columns
-        let id_array = StringArray::from(vec![id.as_str()]);
-        let content_array = StringArray::from(vec![content.as_str()]);
-        let user_id_array = StringArray::from(vec![user_id.unwrap_or("")]);
-        let conversation_id_array = StringArray::from(vec![conversation_id.unwrap_or("")]);
-        let role_array = StringArray::from(vec![role.as_str()]);
-        let timestamp_array = StringArray::from(vec![timestamp.as_str()]);
-
-        // Handle vector column
-        let vector_array = if let Some(embedding) = &entry.embedding {
-            let vec_data: Vec<Option<f32>> = embedding.iter().map(|&x| Some(x)).collect();
-            arrow_array::FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
-                std::iter::once(Some(vec_data)),
-                self.config.embedding_dim as i32,
-            )
+        let db = self.db.read().await;
+        let schema = Self::batch_schema(self.config.embedding_dim)?;
+        let result = if entries.is_empty() {
+            db.create_empty_table(&self.config.table_name, schema)
+                .mode(lancedb::database::CreateTableMode::Overwrite)
+                .execute()
+                .await
         } else {
-            // Create empty/null vector
-            let vec_data: Vec<Option<f32>> = vec![None; self.config.embedding_dim];
-            arrow_array::FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
-                std::iter::once(Some(vec_data)),
-                self.config.embedding_dim as i32,
+            let batch = self.entries_to_batch(&entries)?;
+            db.create_table(
+                &self.config.table_name,
+                RecordBatchIterator::new(vec![Ok(batch)], schema),
             )
+            .mode(lancedb::database::CreateTableMode::Overwrite)
+            .execute()
+            .await
         };
+        result.map_err(|e| anyhow!("Failed to rewrite table: {}", e))?;
+        Ok(())
+    }
 
-        let tokens_array = if let Some(t) = tokens {
-            arrow_array::UInt32Array::from(vec![Some(t)])
-        } else {
-            arrow_array::UInt32Array::from(vec![None as Option<u32>])
-        };
+    fn entries_to_batch(&self, entries: &[MemoryEntry]) -> Result<RecordBatch> {
+        let schema = Self::batch_schema(self.config.embedding_dim)?;
+        let dim = self.config.embedding_dim;
+
+        let ids: Vec<String> = entries.iter().map(|e| e.id.to_string()).collect();
+        let roles: Vec<String> = entries
+            .iter()
+            .map(|e| format!("{:?}", e.metadata.role))
+            .collect();
+        let timestamps: Vec<String> = entries
+            .iter()
+            .map(|e| e.metadata.timestamp.to_rfc3339())
+            .collect();
+        let expirations: Vec<Option<String>> = entries
+            .iter()
+            .map(|e| e.metadata.expires_at.map(|t| t.to_rfc3339()))
+            .collect();
 
-        let importance_array = if let Some(imp) = importance {
-            arrow_array::Float32Array::from(vec![Some(imp)])
-        } else {
-            arrow_array::Float32Array::from(vec![None as Option<f32>])
-        };
+        // Build columns
+        let id_array = StringArray::from(ids.iter().map(String::as_str).collect::<Vec<_>>());
+        let content_array =
+            StringArray::from(entries.iter().map(|e| e.content.as_str()).collect::<Vec<_>>());
+        let user_id_array = StringArray::from(
+            entries
+                .iter()
+                .map(|e| e.metadata.user_id.as_deref().unwrap_or(""))
+                .collect::<Vec<_>>(),
+        );
+        let conversation_id_array = StringArray::from(
+            entries
+                .iter()
+                .map(|e| e.metadata.conversation_id.as_deref().unwrap_or(""))
+                .collect::<Vec<_>>(),
+        );
+        let role_array = StringArray::from(roles.iter().map(String::as_str).collect::<Vec<_>>());
+        let timestamp_array =
+            StringArray::from(timestamps.iter().map(String::as_str).collect::<Vec<_>>());
+
+        // Handle vector column; entries without embedding get a null vector so they read back as
+        // `embedding: None` and never take part in vector search
+        let vector_array = arrow_array::FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            entries.iter().map(|e| {
+                e.embedding
+                    .as_ref()
+                    .map(|embedding| embedding.iter().map(|&x| Some(x)).collect::<Vec<_>>())
+            }),
+            dim as i32,
+        );
+
+        let tokens_array = arrow_array::UInt32Array::from(
+            entries.iter().map(|e| e.metadata.tokens).collect::<Vec<_>>(),
+        );
+        let importance_array = arrow_array::Float32Array::from(
+            entries
+                .iter()
+                .map(|e| e.metadata.importance)
+                .collect::<Vec<_>>(),
+        );
+
+        let expires_at_array =
+            StringArray::from(expirations.iter().map(Option::as_deref).collect::<Vec<_>>());
 
         let batch = RecordBatch::try_new(
             schema,
@@ -212,6 +587,7 @@ impl LanceVectorStore {
                 Arc::new(timestamp_array),
                 Arc::new(tokens_array),
                 Arc::new(importance_array),
+                Arc::new(expires_at_array),
             ],
         )?;
 
@@ -307,6 +683,23 @@ impl LanceVectorStore {
             Some(importance_col.value(row))
         };
 
+        // Absent in batches of tables not yet migrated.
+        let expires_at = match schema.index_of("expires_at") {
+            Ok(i) => {
+                let expires_at_col = batch.column(i).as_any().downcast_ref::<StringArray>()
+                    .ok_or_else(|| anyhow!("Expires-at column is not StringArray"))?;
+                if expires_at_col.is_null(row) {
+                    None
+                } else {
+                    Some(
+                        chrono::DateTime::parse_from_rfc3339(expires_at_col.value(row))?
+                            .with_timezone(&chrono::Utc),
+                    )
+                }
+            }
+            Err(_) => None,
+        };
+
         Ok(MemoryEntry {
             id,
             content,
@@ -318,6 +711,7 @@ impl LanceVectorStore {
                 timestamp,
                 tokens,
                 importance,
+                expires_at,
             },
         })
     }
@@ -341,12 +735,14 @@ impl LanceVectorStore {
             Field::new("timestamp", DataType::Utf8, false),
             Field::new("tokens", DataType::UInt32, true),
             Field::new("importance", DataType::Float32, true),
+            Field::new("expires_at", DataType::Utf8, true),
         ])))
     }
 
     /// Returns the most recent `limit` entries by time (Lance-only, not MemoryStore trait).
     ///
-    /// Full table scan, then sort by `metadata.timestamp` descending in memory and take first `limit`.
+    /// Same as [`list`](MemoryStore::list) over every entry: only `id` and `timestamp` are scanned,
+    /// then the `limit` newest rows are read.
     /// External: reads LanceDB table only, no network.
     ///
     /// # Arguments
@@ -363,34 +759,10 @@ impl LanceVectorStore {
         }
 
         info!(limit = limit, "Querying Lance vector store for list_recent");
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
-
-        let results = table
-            .query()
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to execute query: {}", e))?;
-
-        let batches = results
-            .try_collect::<Vec<_>>()
-            .await
-            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
-
-        let mut entries = Vec::new();
-        for batch in batches {
-            for row in 0..batch.num_rows() {
-                let entry = self.batch_to_entry(&batch, row)?;
-                entries.push(entry);
-            }
-        }
-
-        entries.sort_by(|a, b| b.metadata.timestamp.cmp(&a.metadata.timestamp));
-        entries.truncate(limit);
+        let entries = self
+            .list(&MemoryFilter::default(), MemoryOrder::NewestFirst, limit, None)
+            .await?
+            .entries;
 
         info!(
             requested = limit,
@@ -406,6 +778,94 @@ impl LanceVectorStore {
         s.replace('\'', "''")
     }
 
+    /// Predicate matching rows that have not expired (timestamps are RFC 3339 strings in UTC, so
+    /// they compare in time order).
+    fn unexpired_predicate() -> String {
+        format!(
+            "(expires_at IS NULL OR expires_at > '{}')",
+            Utc::now().to_rfc3339()
+        )
+    }
+
+    /// Builds a Lance SQL predicate for the fields set in `filter` and, when given, the keyset
+    /// `cursor`. Expired rows never match.
+    fn filter_predicate(
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        cursor: Option<&MemoryCursor>,
+    ) -> String {
+        let mut parts = vec![Self::unexpired_predicate()];
+        if let Some(u) = &filter.user_id {
+            parts.push(format!("user_id = '{}'", Self::escape_sql_string(u)));
+        }
+        if let Some(c) = &filter.conversation_id {
+            parts.push(format!("conversation_id = '{}'", Self::escape_sql_string(c)));
+        }
+        if let Some(role) = filter.role {
+            parts.push(format!("role = '{:?}'", role));
+        }
+        if let Some(since) = filter.since {
+            parts.push(format!("timestamp >= '{}'", since.to_rfc3339()));
+        }
+        if let Some(until) = filter.until {
+            parts.push(format!("timestamp < '{}'", until.to_rfc3339()));
+        }
+        if let Some(min) = filter.min_importance {
+            parts.push(format!("importance >= {}", min));
+        }
+        if filter.non_empty {
+            parts.push("content != ''".to_string());
+        }
+        if let Some(cursor) = cursor {
+            let cmp = match order {
+                MemoryOrder::NewestFirst => "<",
+                MemoryOrder::OldestFirst => ">",
+            };
+            let ts = cursor.timestamp.to_rfc3339();
+            parts.push(format!(
+                "(timestamp {cmp} '{ts}' OR (timestamp = '{ts}' AND id {cmp} '{id}'))",
+                cmp = cmp,
+                ts = ts,
+                id = cursor.id
+            ));
+        }
+        parts.join(" AND ")
+    }
+
+    /// Builds the `only_if` predicate for a semantic search: rows with a vector, the metadata filter
+    /// and `id NOT IN (...)`.
+    fn search_predicate(filter: &SearchFilter) -> String {
+        let mut parts: Vec<String> = vec![
+            "vector IS NOT NULL".to_string(),
+            Self::filter_predicate(&filter.metadata, MemoryOrder::default(), None),
+        ];
+        if !filter.exclude_ids.is_empty() {
+            let ids: Vec<String> = filter
+                .exclude_ids
+                .iter()
+                .map(|id| format!("'{}'", id))
+                .collect();
+            parts.push(format!("id NOT IN ({})", ids.join(", ")));
+        }
+        parts.join(" AND ")
+    }
+
+    /// Deletes all rows matching `predicate`; returns how many there were.
+    async fn delete_where(&self, predicate: String) -> Result<u64> {
+        let table = self.open_table().await?;
+        let matching = table
+            .count_rows(Some(predicate.clone()))
+            .await
+            .map_err(|e| anyhow!("Failed to count rows: {}", e))?;
+        if matching > 0 {
+            table
+                .delete(&predicate)
+                .await
+                .map_err(|e| anyhow!("Failed to delete entries: {}", e))?;
+        }
+        Ok(matching as u64)
+    }
+
     /// Converts Lance _distance to similarity score (higher = more similar).
     /// For Cosine distance: Lance typically returns 1 - cos_sim, so similarity = 1.0 - distance.
     /// If _distance column is missing, returns 1.0.
@@ -422,8 +882,9 @@ impl LanceVectorStore {
         } else {
             col.value(row)
         };
-        // Cosine distance in Lance: smaller = more similar; often distance = 1 - cosine_sim.
-        (1.0 - distance).max(0.0).min(1.0)
+        // Cosine distance in Lance is 1 - cosine_sim (range [0, 2]); map back to cosine in [-1, 1]
+        // like the other stores, so opposite vectors rank below orthogonal ones.
+        (1.0 - distance).clamp(-1.0, 1.0)
     }
 }
 
@@ -445,13 +906,10 @@ impl MemoryStore for LanceVectorStore {
             has_embedding = entry.embedding.is_some(),
             "Writing entry to Lance vector store"
         );
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
 
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
+        let table = self.open_table().await?;
 
         let batch = self.entry_to_batch(&entry)?;
         let schema = batch.schema();
@@ -462,6 +920,7 @@ impl MemoryStore for LanceVectorStore {
             .execute()
             .await
             .map_err(|e| anyhow!("Failed to add entry: {}", e))?;
+        self.after_write(&table, 1).await;
 
         info!(
             id = %entry.id,
@@ -472,38 +931,112 @@ impl MemoryStore for LanceVectorStore {
         Ok(())
     }
 
-    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>> {
-        info!(id = %id, "Querying Lance vector store by id");
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.embedding_guard.check_entries(&entries)?;
+
+        let table = self.open_table().await?;
+
+        let batch = self.entries_to_batch(&entries)?;
+        let schema = batch.schema();
+        let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+
+        table
+            .add(reader)
             .execute()
             .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
+            .map_err(|e| anyhow!("Failed to add entries: {}", e))?;
+        self.after_write(&table, entries.len()).await;
 
-        let results = table
-            .query()
-            .execute()
+        info!(count = entries.len(), "Batch written to Lance vector store");
+        Ok(())
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>> {
+        Ok(self.embedding_guard.spec())
+    }
+
+    /// Records the model in the spec file; the dimension must match both the table and `LanceConfig`.
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<()> {
+        if spec.dimension != self.config.embedding_dim {
+            return Err(anyhow!(
+                "embedding model {} does not match LanceConfig.embedding_dim = {}",
+                spec,
+                self.config.embedding_dim
+            ));
+        }
+        if self.embedding_guard.bind(spec)? {
+            self.save_embedding_spec(spec).await?;
+            info!(spec = %spec, "Lance vector store embedding spec recorded");
+        }
+        Ok(())
+    }
+
+    /// Clears every vector in one commit, so a failure leaves the old vectors in place. With the same
+    /// dimension the vectors are nulled in place; a new dimension needs a new vector column, so the
+    /// table is overwritten with its rows (vectors nulled) in a single commit that carries the data.
+    /// Vector indexes are dropped and rebuilt on later writes.
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64> {
+        if spec.dimension != self.config.embedding_dim {
+            return Err(anyhow!(
+                "re-embedding to {} needs LanceConfig.embedding_dim = {} (is {})",
+                spec,
+                spec.dimension,
+                self.config.embedding_dim
+            ));
+        }
+        // Hold the index lock so no write re-creates the vector index on stale vectors meanwhile.
+        let _guard = self.index_lock.lock().await;
+        let table = self.open_table().await?;
+        let cleared = table
+            .count_rows(Some("vector IS NOT NULL".to_string()))
             .await
-            .map_err(|e| anyhow!("Failed to execute query: {}", e))?;
+            .map_err(|e| anyhow!("Failed to count rows: {}", e))? as u64;
 
-        let results = results
-            .try_collect::<Vec<_>>()
+        // The vector index was trained on the old model's vectors; drop it before clearing.
+        let indices = table
+            .list_indices()
             .await
-            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
+            .map_err(|e| anyhow!("Failed to list indices: {}", e))?;
+        for index in indices.into_iter().filter(|i| i.columns.iter().any(|c| c == "vector")) {
+            table
+                .drop_index(&index.name)
+                .await
+                .map_err(|e| anyhow!("Failed to drop index {}: {}", index.name, e))?;
+        }
 
-        for batch in results {
-            for row in 0..batch.num_rows() {
-                let entry = self.batch_to_entry(&batch, row)?;
-                if entry.id == id {
-                    info!(id = %id, found = true, "Lance vector store get returned");
-                    return Ok(Some(entry));
-                }
+        if self.table_dimension(&table).await? == spec.dimension {
+            if cleared > 0 {
+                table
+                    .update()
+                    .only_if("vector IS NOT NULL")
+                    .column("vector", "NULL")
+                    .execute()
+                    .await
+                    .map_err(|e| anyhow!("Failed to clear vectors: {}", e))?;
             }
+        } else {
+            self.overwrite_without_vectors(&table).await?;
         }
 
-        info!(id = %id, found = false, "Lance vector store get returned");
-        Ok(None)
+        self.indexes_ready.store(false, Ordering::Release);
+        self.save_embedding_spec(spec).await?;
+        self.embedding_guard.set(Some(spec.clone()));
+        info!(cleared, spec = %spec, "Lance vector store embeddings reset");
+        Ok(cleared)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>> {
+        info!(id = %id, "Querying Lance vector store by id");
+        let entry = self
+            .query_entries(format!("id = '{}'", id), Some(1))
+            .await?
+            .into_iter()
+            .next();
+        info!(id = %id, found = entry.is_some(), "Lance vector store get returned");
+        Ok(entry)
     }
 
     async fn update(&self, entry: MemoryEntry) -> Result<()> {
@@ -514,12 +1047,7 @@ impl MemoryStore for LanceVectorStore {
     }
 
     async fn delete(&self, id: Uuid) -> Result<()> {
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
+        let table = self.open_table().await?;
 
         let id_str = id.to_string();
         table
@@ -532,33 +1060,9 @@ impl MemoryStore for LanceVectorStore {
 
     async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>> {
         info!(user_id = %user_id, "Querying Lance vector store by user");
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
-
-        let results = table
-            .query()
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to execute query: {}", e))?;
-
-        let results = results
-            .try_collect::<Vec<_>>()
-            .await
-            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
-
-        let mut entries = Vec::new();
-        for batch in results {
-            for row in 0..batch.num_rows() {
-                let entry = self.batch_to_entry(&batch, row)?;
-                if entry.metadata.user_id.as_deref() == Some(user_id) {
-                    entries.push(entry);
-                }
-            }
-        }
+        let mut entries = self
+            .query_entries(format!("user_id = '{}'", Self::escape_sql_string(user_id)), None)
+            .await?;
         entries.sort_by(|a, b| a.metadata.timestamp.cmp(&b.metadata.timestamp));
 
         info!(
@@ -571,33 +1075,12 @@ impl MemoryStore for LanceVectorStore {
 
     async fn search_by_conversation(&self, conversation_id: &str) -> Result<Vec<MemoryEntry>> {
         info!(conversation_id = %conversation_id, "Querying Lance vector store by conversation");
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to open table: {}", e))?;
-
-        let filter = format!("conversation_id = '{}'", Self::escape_sql_string(conversation_id));
-        let results = table
-            .query()
-            .only_if(filter)
-            .execute()
-            .await
-            .map_err(|e| anyhow!("Failed to execute query: {}", e))?;
-
-        let results = results
-            .try_collect::<Vec<_>>()
-            .await
-            .map_err(|e| anyhow!("Failed to collect results: {}", e))?;
-
-        let mut entries = Vec::new();
-        for batch in results {
-            for row in 0..batch.num_rows() {
-                let entry = self.batch_to_entry(&batch, row)?;
-                entries.push(entry);
-            }
-        }
+        let mut entries = self
+            .query_entries(
+                format!("conversation_id = '{}'", Self::escape_sql_string(conversation_id)),
+                None,
+            )
+            .await?;
         entries.sort_by(|a, b| a.metadata.timestamp.cmp(&b.metadata.timestamp));
 
         info!(
@@ -614,12 +1097,26 @@ impl MemoryStore for LanceVectorStore {
         limit: usize,
         user_id: Option<&str>,
         conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>> {
+        self.semantic_search_filtered(
+            query_embedding,
+            limit,
+            &SearchFilter::scoped(user_id, conversation_id),
+        )
+        .await
+    }
+
+    /// The whole filter is pushed down with `only_if`, so `limit` counts only matching rows (no over-fetch).
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
     ) -> Result<Vec<(f32, MemoryEntry)>> {
         info!(
             dimension = query_embedding.len(),
             limit = limit,
-            user_id = ?user_id,
-            conversation_id = ?conversation_id,
+            filter = ?filter,
             "step: embedding Lance semantic search"
         );
         info!(
@@ -628,26 +1125,12 @@ impl MemoryStore for LanceVectorStore {
             limit = limit,
             "Querying Lance vector store semantic_search"
         );
-        let db = self.db.read().await;
-        let table = db
-            .open_table(&self.config.table_name)
-            .execute()
-            .await
-            .map_err(|e| {
-                error!(error = %e, "Lance semantic_search: failed to open table");
-                anyhow!("Failed to open table: {}", e)
-            })?;
+        self.embedding_guard.check_query(query_embedding)?;
+        let table = self.open_table().await.inspect_err(|e| {
+            error!(error = %e, "Lance semantic_search: failed to open table");
+        })?;
 
-        // Push conversation_id and user_id to Lance so we only need limit (no over-fetch).
-        let mut predicate_parts = Vec::new();
-        if let Some(u) = user_id {
-            predicate_parts.push(format!("user_id = '{}'", Self::escape_sql_string(u)));
-        }
-        if let Some(c) = conversation_id {
-            predicate_parts.push(format!("conversation_id = '{}'", Self::escape_sql_string(c)));
-        }
-        let predicate = predicate_parts.join(" AND ");
-        let filter_pushed_down = !predicate.is_empty();
+        let predicate = Self::search_predicate(filter);
 
         let mut vector_query = table
             .query()
@@ -667,9 +1150,9 @@ impl MemoryStore for LanceVectorStore {
                 )
             })?;
 
-        if filter_pushed_down {
-            vector_query = vector_query.only_if(predicate);
-        }
+        vector_query = vector_query
+            .only_if(predicate)
+            .distance_type(self.config.distance_type.as_lance_metric());
         if self.config.use_exact_search {
             vector_query = vector_query.bypass_vector_index();
         }
@@ -679,6 +1162,9 @@ impl MemoryStore for LanceVectorStore {
         if let Some(np) = self.config.nprobes {
             vector_query = vector_query.nprobes(np);
         }
+        if let Some(ef) = self.config.hnsw.ef_search {
+            vector_query = vector_query.ef(ef);
+        }
 
         let results = vector_query
             .limit(limit)
@@ -712,23 +1198,13 @@ impl MemoryStore for LanceVectorStore {
             let distance_col_idx = batch.schema().index_of("_distance").ok();
             for row in 0..batch.num_rows() {
                 let entry = self.batch_to_entry(&batch, row)?;
-                if filter_pushed_down {
-                    scored_entries.push((
-                        Self::distance_to_similarity(&batch, distance_col_idx, row),
-                        entry,
-                    ));
-                } else {
-                    let match_user = user_id
-                        .map(|u| entry.metadata.user_id.as_deref() == Some(u))
-                        .unwrap_or(true);
-                    let match_conv = conversation_id
-                        .map(|c| entry.metadata.conversation_id.as_deref() == Some(c))
-                        .unwrap_or(true);
-                    if match_user && match_conv {
-                        let score = Self::distance_to_similarity(&batch, distance_col_idx, row);
-                        scored_entries.push((score, entry));
-                    }
+                if entry.embedding.is_none() {
+                    continue;
                 }
+                scored_entries.push((
+                    Self::distance_to_similarity(&batch, distance_col_idx, row),
+                    entry,
+                ));
             }
         }
         scored_entries.truncate(limit);
@@ -745,4 +1221,102 @@ impl MemoryStore for LanceVectorStore {
         );
         Ok(scored_entries)
     }
+
+    /// Filter and cursor are pushed down as a predicate. Lance scans are unordered, so the page is
+    /// found from the `id` and `timestamp` columns alone (see `page_ids`) and only its rows are
+    /// read in full.
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage> {
+        // One extra id tells whether a next page exists.
+        let ids = self
+            .page_ids(
+                Self::filter_predicate(filter, order, cursor),
+                order,
+                limit.saturating_add(1),
+            )
+            .await?;
+        let mut entries = if ids.is_empty() {
+            Vec::new()
+        } else {
+            let ids: Vec<String> = ids.iter().map(|id| format!("'{}'", id)).collect();
+            self.query_entries(format!("id IN ({})", ids.join(", ")), None)
+                .await?
+        };
+        entries.sort_by_key(|e| (e.metadata.timestamp, e.id));
+        if order == MemoryOrder::NewestFirst {
+            entries.reverse();
+        }
+        let page = MemoryPage::from_sorted(entries, limit);
+        info!(
+            filter = ?filter,
+            order = ?order,
+            limit = limit,
+            count = page.entries.len(),
+            "Lance vector store list returned"
+        );
+        Ok(page)
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64> {
+        let table = self.open_table().await?;
+        let count = table
+            .count_rows(Some(Self::filter_predicate(filter, MemoryOrder::default(), None)))
+            .await
+            .map_err(|e| anyhow!("Failed to count rows: {}", e))?;
+        Ok(count as u64)
+    }
+
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64> {
+        let deleted = self
+            .delete_where(format!(
+                "conversation_id = '{}'",
+                Self::escape_sql_string(conversation_id)
+            ))
+            .await?;
+        info!(conversation_id = %conversation_id, deleted, "Lance vector store delete_by_conversation");
+        Ok(deleted)
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64> {
+        let deleted = self
+            .delete_where(format!("user_id = '{}'", Self::escape_sql_string(user_id)))
+            .await?;
+        info!(user_id = %user_id, deleted, "Lance vector store delete_by_user");
+        Ok(deleted)
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64> {
+        let deleted = self
+            .delete_where(format!(
+                "expires_at IS NOT NULL AND expires_at <= '{}'",
+                now.to_rfc3339()
+            ))
+            .await?;
+        info!(deleted, "Lance vector store delete_expired");
+        Ok(deleted)
+    }
+}
+
+/// `<table>.embedding` file format: model id on the first line, dimension on the second.
+struct EmbeddingSpecFile;
+
+impl EmbeddingSpecFile {
+    fn format(spec: &EmbeddingSpec) -> String {
+        format!("{}\n{}\n", spec.model, spec.dimension)
+    }
+
+    fn parse(text: &str) -> Result<EmbeddingSpec> {
+        let mut lines = text.lines();
+        let model = lines.next().unwrap_or_default().trim().to_string();
+        let dimension = lines
+            .next()
+            .and_then(|d| d.trim().parse().ok())
+            .ok_or_else(|| anyhow!("Malformed embedding spec file: {:?}", text))?;
+        Ok(EmbeddingSpec::new(model, dimension))
+    }
 }
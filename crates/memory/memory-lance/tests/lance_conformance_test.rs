@@ -0,0 +1,21 @@
+//! Runs the shared MemoryStore conformance suite against LanceVectorStore.
+
+use memory_lance::{LanceConfig, LanceVectorStore};
+use telegram_bot::memory_core::conformance::CONFORMANCE_DIM;
+use tempfile::TempDir;
+
+async fn make() -> (TempDir, LanceVectorStore) {
+    let dir = TempDir::new().expect("tempdir");
+    let config = LanceConfig {
+        db_path: dir.path().join("lance_db").to_string_lossy().to_string(),
+        embedding_dim: CONFORMANCE_DIM,
+        use_exact_search: true,
+        ..LanceConfig::default()
+    };
+    let store = LanceVectorStore::with_config(config)
+        .await
+        .expect("lance store");
+    (dir, store)
+}
+
+telegram_bot::memory_store_conformance_tests!(make());
@@ -43,6 +43,7 @@ fn meta(role: MemoryRole) -> MemoryMetadata {
         timestamp: Utc::now(),
         tokens: Some(10),
         importance: Some(1.0),
+        expires_at: None,
     }
 }
 
@@ -120,7 +121,7 @@ async fn test_lance_semantic_strategy_returns_relevant_message() {
         "semantic search with Lance should return at least one message"
     );
 
-    let first = &messages[0];
+    let first = &messages[0].content;
     assert!(
         first.contains("cat") || first.contains("cats"),
         "nearest to query about cat should be the cat entry, got: {}",
@@ -3,10 +3,11 @@
 //! Uses real Lance store and Zhipu embedding to verify ContextBuilder runs
 //! RecentMessagesStrategy, SemanticSearchStrategy, UserPreferencesStrategy in sequence:
 //! - Write same-session entries (cat/dog/car) and preference text (I like / I prefer)
+//! - Record the matching profile facts (pizza / tea) in a ProfileRepository
 //! - Assert recent_messages / semantic_messages contain recent and semantic hits (including cat)
-//! - Assert user_preferences is non-empty with preference keywords
+//! - Assert user_preferences is non-empty with the recorded preferences
 //!
-//! External: temp dir Lance DB; ContextBuilder, strategies, MemoryStore, EmbeddingService;
+//! External: temp dir Lance DB and SQLite profile DB; ContextBuilder, strategies, MemoryStore, EmbeddingService;
 //! Zhipu API (BIGMODEL_API_KEY or ZHIPUAI_API_KEY, skip if unset).
 
 use chrono::Utc;
@@ -21,6 +22,7 @@ use telegram_bot::memory_strategies::{
     RecentMessagesStrategy, SemanticSearchStrategy, UserPreferencesStrategy,
 };
 use telegram_bot::memory::ContextBuilder;
+use telegram_bot::profile::{FactKind, ProfileRepository};
 use memory_lance::{LanceConfig, LanceVectorStore};
 
 /// Zhipu embedding-2 model dimension
@@ -44,8 +46,9 @@ fn make_zhipu_embedding() -> Option<Arc<dyn EmbeddingService>> {
 /// Steps:
 /// 1. Use Zhipu BigModelEmbedding for vectors; skip if no API key
 /// 2. Create temp Lance DB and write: same-session cat/dog/car entries (with vectors) + preference "I like pizza and I prefer tea"
-/// 3. ContextBuilder with RecentMessagesStrategy, SemanticSearchStrategy, UserPreferencesStrategy
-/// 4. build(); assert recent_messages / semantic_messages contain recent and semantic hits (cat), user_preferences non-empty with keywords
+/// 3. Record the preferences (pizza, tea) for u1 in a temp ProfileRepository
+/// 4. ContextBuilder with RecentMessagesStrategy, SemanticSearchStrategy, UserPreferencesStrategy
+/// 5. build(); assert recent_messages / semantic_messages contain recent and semantic hits (cat), user_preferences lists the preferences
 #[tokio::test]
 async fn test_lance_all_three_strategies_build_context() {
     let embedding = match make_zhipu_embedding() {
@@ -83,6 +86,7 @@ async fn test_lance_all_three_strategies_build_context() {
         timestamp: Utc::now(),
         tokens: Some(10),
         importance: Some(1.0),
+        expires_at: None,
     };
 
     store
@@ -124,10 +128,20 @@ async fn test_lance_all_three_strategies_build_context() {
 
     let store_arc: Arc<dyn MemoryStore> = Arc::new(store);
 
+    let profiles = ProfileRepository::new(&temp_dir.path().join("profiles.db").to_string_lossy())
+        .await
+        .expect("profile repo");
+    for value in ["likes pizza", "prefers tea"] {
+        profiles
+            .record("u1", FactKind::Preference, value, 0.9, Utc::now())
+            .await
+            .expect("record preference");
+    }
+
     let context = ContextBuilder::new(store_arc)
         .with_strategy(Box::new(RecentMessagesStrategy::new(10)))
         .with_strategy(Box::new(SemanticSearchStrategy::new(3, embedding, 0.0)))
-        .with_strategy(Box::new(UserPreferencesStrategy::new()))
+        .with_strategy(Box::new(UserPreferencesStrategy::new().with_profile_repo(profiles)))
         .for_user("u1")
         .for_conversation("c1")
         .with_query("User asks: what do cats eat?")
@@ -145,7 +159,12 @@ async fn test_lance_all_three_strategies_build_context() {
         "recent_messages should contain at least 4 from RecentMessagesStrategy, got {}",
         context.recent_messages.len()
     );
-    let recent_joined = context.recent_messages.join(" ");
+    let recent_joined = context
+        .recent_messages
+        .iter()
+        .map(|m| m.to_line())
+        .collect::<Vec<_>>()
+        .join(" ");
     assert!(
         recent_joined.contains("dogs") || recent_joined.contains("loyal"),
         "recent_messages should include message about dogs, got: {}",
@@ -157,7 +176,12 @@ async fn test_lance_all_three_strategies_build_context() {
         recent_joined
     );
     // SemanticSearchStrategy should return semantic hit about cat for query
-    let semantic_joined = context.semantic_messages.join(" ");
+    let semantic_joined = context
+        .semantic_messages
+        .iter()
+        .map(|m| m.to_line())
+        .collect::<Vec<_>>()
+        .join(" ");
     assert!(
         semantic_joined.contains("cat") || semantic_joined.contains("cats"),
         "semantic_messages should include hit about cat, got: {}",
@@ -169,8 +193,8 @@ async fn test_lance_all_three_strategies_build_context() {
     );
     let prefs = context.user_preferences.as_deref().unwrap();
     assert!(
-        prefs.to_lowercase().contains("like") || prefs.to_lowercase().contains("prefer"),
-        "user_preferences should contain preference keywords, got: {}",
+        prefs.contains("pizza") && prefs.contains("tea"),
+        "user_preferences should list the recorded preferences, got: {}",
         prefs
     );
 }
@@ -8,13 +8,18 @@
 //! - Semantic vector search
 //! - Data persistence (readable after restart)
 //! - list_recent returns N most recent entries by time
+//! - list / count with filter pushdown, delete_by_conversation / delete_by_user
+//! - expired entries hidden from reads and removed by delete_expired
+//! - scalar indexes after the first write, vector index once the auto-index threshold is crossed
 
 use chrono::{Duration, Utc};
 use tempfile::TempDir;
 use uuid::Uuid;
 
-use telegram_bot::memory_core::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
-use memory_lance::LanceVectorStore;
+use telegram_bot::memory_core::{
+    MemoryEntry, MemoryFilter, MemoryMetadata, MemoryOrder, MemoryRole, MemoryStore,
+};
+use memory_lance::{IvfPqParams, LanceConfig, LanceIndexType, LanceVectorStore};
 
 /// Lance vector store verification test
 ///
@@ -70,6 +75,7 @@ async fn test_lance_vector_store_verification() {
         timestamp: Utc::now(),
         tokens: Some(10),
         importance: Some(1.0),
+        expires_at: None,
     };
 
     let entry = MemoryEntry {
@@ -201,6 +207,7 @@ async fn test_lance_vector_query_verification() {
         timestamp: Utc::now(),
         tokens: Some(5),
         importance: Some(1.0),
+        expires_at: None,
     };
 
     let entry_a = MemoryEntry {
@@ -290,6 +297,7 @@ async fn test_semantic_search_regression_golden_cases() {
         timestamp: Utc::now(),
         tokens: Some(5),
         importance: Some(1.0),
+        expires_at: None,
     };
 
     let entry_a = MemoryEntry {
@@ -371,6 +379,7 @@ async fn test_lance_list_recent_returns_ordered_by_timestamp_desc() {
             timestamp: base_time + Duration::seconds(secs_after_base),
             tokens: Some(5),
             importance: Some(1.0),
+            expires_at: None,
         };
         MemoryEntry {
             id: Uuid::new_v4(),
@@ -411,3 +420,217 @@ async fn test_lance_list_recent_returns_ordered_by_timestamp_desc() {
     let empty = store.list_recent(0).await.expect("list_recent(0)");
     assert!(empty.is_empty(), "list_recent(0) should return empty");
 }
+
+/// list / count / delete_by_* on Lance
+///
+/// Checks:
+/// - list pages newest-first through a conversation with a cursor; role filter is pushed down
+/// - count matches the filter
+/// - delete_by_conversation and delete_by_user return the number of deleted rows
+///
+/// External: temp dir Lance DB with four records in two conversations.
+#[tokio::test]
+async fn test_lance_list_count_and_bulk_delete() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let lance_path_str = temp_dir
+        .path()
+        .join("lance_list_db")
+        .to_string_lossy()
+        .to_string();
+    let store = LanceVectorStore::new(&lance_path_str)
+        .await
+        .expect("Failed to create LanceVectorStore");
+
+    const DIM: usize = 1536;
+    let base_time = Utc::now() - Duration::seconds(10);
+    let make_entry = |content: &str, user: &str, conv: &str, role: MemoryRole, secs: i64| MemoryEntry {
+        id: Uuid::new_v4(),
+        content: content.to_string(),
+        embedding: Some(vec![0.1f32; DIM]),
+        metadata: MemoryMetadata {
+            user_id: Some(user.to_string()),
+            conversation_id: Some(conv.to_string()),
+            role,
+            timestamp: base_time + Duration::seconds(secs),
+            tokens: None,
+            importance: None,
+            expires_at: None,
+        },
+    };
+
+    store.add(make_entry("q1", "u1", "c1", MemoryRole::User, 0)).await.expect("add");
+    store.add(make_entry("a1", "u1", "c1", MemoryRole::Assistant, 1)).await.expect("add");
+    store.add(make_entry("q2", "u1", "c1", MemoryRole::User, 2)).await.expect("add");
+    store.add(make_entry("other", "u2", "c2", MemoryRole::User, 3)).await.expect("add");
+
+    let filter = MemoryFilter::for_conversation("c1");
+    assert_eq!(store.count(&filter).await.expect("count"), 3);
+    assert_eq!(
+        store
+            .count(&filter.clone().with_role(MemoryRole::User))
+            .await
+            .expect("count users"),
+        2
+    );
+
+    let page = store
+        .list(&filter, MemoryOrder::NewestFirst, 2, None)
+        .await
+        .expect("list page 1");
+    let contents: Vec<&str> = page.entries.iter().map(|e| e.content.as_str()).collect();
+    assert_eq!(contents, vec!["q2", "a1"]);
+    let rest = store
+        .list(&filter, MemoryOrder::NewestFirst, 2, page.next_cursor.as_ref())
+        .await
+        .expect("list page 2");
+    assert_eq!(rest.entries.len(), 1);
+    assert_eq!(rest.entries[0].content, "q1");
+    assert!(rest.next_cursor.is_none());
+
+    assert_eq!(store.delete_by_conversation("c1").await.expect("delete c1"), 3);
+    assert_eq!(store.delete_by_user("u2").await.expect("delete u2"), 1);
+    assert_eq!(store.count(&MemoryFilter::default()).await.expect("count all"), 0);
+}
+
+/// Expired entries are hidden from reads and removed by the sweep
+///
+/// Checks:
+/// - get / search_by_conversation / list / count / semantic_search skip an expired entry
+/// - delete_expired removes only rows whose expires_at has passed
+///
+/// External: temp dir Lance DB with three records in one conversation.
+#[tokio::test]
+async fn test_lance_expired_entries_hidden_and_swept() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let lance_path_str = temp_dir
+        .path()
+        .join("lance_expiry_db")
+        .to_string_lossy()
+        .to_string();
+    let store = LanceVectorStore::new(&lance_path_str)
+        .await
+        .expect("Failed to create LanceVectorStore");
+
+    const DIM: usize = 1536;
+    let make_entry = |content: &str, expires_in: Option<Duration>| MemoryEntry {
+        id: Uuid::new_v4(),
+        content: content.to_string(),
+        embedding: Some(vec![0.1f32; DIM]),
+        metadata: MemoryMetadata {
+            user_id: Some("u1".to_string()),
+            conversation_id: Some("c1".to_string()),
+            role: MemoryRole::User,
+            timestamp: Utc::now(),
+            tokens: None,
+            importance: None,
+            expires_at: expires_in.map(|d| Utc::now() + d),
+        },
+    };
+    let expired = make_entry("expired", Some(Duration::seconds(-5)));
+    let later = make_entry("later", Some(Duration::hours(1)));
+    store
+        .add_batch(vec![expired.clone(), later.clone(), make_entry("kept", None)])
+        .await
+        .expect("add_batch");
+
+    assert!(store.get(expired.id).await.expect("get").is_none());
+    assert_eq!(
+        store.get(later.id).await.expect("get").expect("later").metadata.expires_at,
+        later.metadata.expires_at
+    );
+    assert_eq!(store.search_by_conversation("c1").await.expect("search").len(), 2);
+    let filter = MemoryFilter::for_conversation("c1");
+    assert_eq!(store.count(&filter).await.expect("count"), 2);
+    let page = store
+        .list(&filter, MemoryOrder::NewestFirst, 10, None)
+        .await
+        .expect("list");
+    assert!(page.entries.iter().all(|e| e.content != "expired"));
+    let hits = store
+        .semantic_search(&vec![0.1f32; DIM], 10, None, Some("c1"))
+        .await
+        .expect("semantic_search");
+    assert_eq!(hits.len(), 2);
+
+    assert_eq!(store.delete_expired(Utc::now()).await.expect("sweep"), 1);
+    assert_eq!(store.delete_expired(Utc::now()).await.expect("sweep again"), 0);
+    assert_eq!(store.count(&filter).await.expect("count"), 2);
+}
+
+/// Scalar and vector indexes are created automatically
+///
+/// Checks:
+/// - First write creates BTree indexes on id, user_id, conversation_id (no vector index yet)
+/// - Crossing `auto_index_threshold` builds the IVF-PQ vector index with the configured parameters
+/// - Rows written afterwards can be merged into the indexes with `optimize_indexes`
+/// - get / search_by_user / semantic_search stay correct with the indexes in place
+///
+/// External: temp dir Lance DB with 300 records of dimension 8.
+#[tokio::test]
+async fn test_lance_auto_indexes_after_threshold() {
+    let temp_dir = TempDir::new().expect("temp dir");
+    let config = LanceConfig {
+        db_path: temp_dir.path().join("lance_index_db").to_string_lossy().to_string(),
+        embedding_dim: 8,
+        vector_index: LanceIndexType::IvfPq,
+        ivf_pq: IvfPqParams {
+            num_partitions: Some(2),
+            num_sub_vectors: Some(2),
+        },
+        auto_index_threshold: Some(300),
+        ..Default::default()
+    };
+    let store = LanceVectorStore::with_config(config)
+        .await
+        .expect("create LanceVectorStore");
+
+    let make_entry = |i: usize| {
+        let mut embedding = vec![0.0f32; 8];
+        embedding[i % 8] = 1.0;
+        embedding[(i / 8) % 8] += 0.5;
+        MemoryEntry {
+            id: Uuid::new_v4(),
+            content: format!("entry {}", i),
+            embedding: Some(embedding),
+            metadata: MemoryMetadata {
+                user_id: Some(format!("u{}", i % 3)),
+                conversation_id: Some(format!("c{}", i % 5)),
+                role: MemoryRole::User,
+                timestamp: Utc::now(),
+                tokens: None,
+                importance: None,
+                expires_at: None,
+            },
+        }
+    };
+
+    let first = make_entry(0);
+    store.add(first.clone()).await.expect("add first");
+    let columns = store.indexed_columns().await.expect("indexed_columns");
+    for column in ["id", "user_id", "conversation_id"] {
+        assert!(columns.iter().any(|c| c == column), "missing scalar index on {}", column);
+    }
+    assert!(!columns.iter().any(|c| c == "vector"), "vector index must wait for the threshold");
+
+    store
+        .add_batch((1..300).map(make_entry).collect())
+        .await
+        .expect("add_batch");
+    let columns = store.indexed_columns().await.expect("indexed_columns");
+    assert!(columns.iter().any(|c| c == "vector"), "vector index after threshold");
+
+    // Rows written after the build are merged into the indexes on demand.
+    store.add(make_entry(300)).await.expect("add after index");
+    store.optimize_indexes().await.expect("optimize_indexes");
+
+    let got = store.get(first.id).await.expect("get").expect("first entry");
+    assert_eq!(got.content, "entry 0");
+    assert_eq!(store.search_by_user("u1").await.expect("search_by_user").len(), 100);
+
+    let results = store
+        .semantic_search(first.embedding.as_ref().unwrap(), 5, Some("u0"), None)
+        .await
+        .expect("semantic_search");
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|(_, e)| e.metadata.user_id.as_deref() == Some("u0")));
+}
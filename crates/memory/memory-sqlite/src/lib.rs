@@ -21,21 +21,17 @@
 //!
 //! ```rust
 //! use memory_sqlite::SQLiteVectorStore;
-//! use memory::{MemoryStore, MemoryEntry, MemoryMetadata, MemoryRole};
+//! use telegram_bot::memory_core::{MemoryStore, MemoryEntry, MemoryMetadata, MemoryRole};
 //! use chrono::Utc;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), anyhow::Error> {
 //!     let store = SQLiteVectorStore::new("memory.db").await?;
 //!
-//!     let metadata = MemoryMetadata {
-//!         user_id: Some("user123".to_string()),
-//!         conversation_id: None,
-//!         role: MemoryRole::User,
-//!         timestamp: Utc::now(),
-//!         tokens: None,
-//!         importance: None,
-//!     };
+//!     let metadata = MemoryMetadata::default()
+//!         .with_user("user123")
+//!         .with_role(MemoryRole::User)
+//!         .with_timestamp(Utc::now());
 //!     let entry = MemoryEntry::new("Hello world".to_string(), metadata);
 //!
 //!     store.add(entry).await?;
@@ -58,21 +54,41 @@
 //!     timestamp TEXT NOT NULL,
 //!     tokens INTEGER,
 //!     importance REAL,
-//!     embedding BLOB
+//!     embedding BLOB,
+//!     expires_at TEXT
+//! );
+//!
+//! CREATE TABLE memory_meta (
+//!     key TEXT PRIMARY KEY,
+//!     value TEXT NOT NULL
 //! );
 //! ```
-
-use sqlx::{sqlite::SqliteConnectOptions, Row, SqlitePool};
-use memory::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
+//!
+//! `memory_meta` records the embedding model and dimension of the stored vectors; writes and
+//! queries of another dimension fail with `EmbeddingMismatch`.
+
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use telegram_bot::memory_core::{
+    EmbeddingGuard, EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryMetadata,
+    MemoryOrder, MemoryPage, MemoryRole, MemoryStore, SearchFilter,
+};
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
 use tracing::info;
 use uuid::Uuid;
 
+/// `memory_meta` keys recording the embedding model and dimension of stored vectors.
+const META_EMBEDDING_MODEL: &str = "embedding_model";
+const META_EMBEDDING_DIM: &str = "embedding_dim";
+
 /// SQLite-based vector store for persistent memory storage.
 #[derive(Clone)]
 pub struct SQLiteVectorStore {
     pool: SqlitePool,
+    embedding_guard: Arc<EmbeddingGuard>,
 }
 
 impl SQLiteVectorStore {
@@ -96,8 +112,14 @@ impl SQLiteVectorStore {
 
         let pool = SqlitePool::connect_with(options).await?;
 
-        let store = Self { pool };
+        let store = Self {
+            pool,
+            embedding_guard: Arc::new(EmbeddingGuard::default()),
+        };
         store.init_schema().await?;
+        store
+            .embedding_guard
+            .set(store.load_embedding_spec().await?);
 
         Ok(store)
     }
@@ -120,6 +142,9 @@ impl SQLiteVectorStore {
     /// - tokens (INTEGER): Optional token count
     /// - importance (REAL): Optional importance score
     /// - embedding (BLOB): Vector embedding as binary data
+    /// - expires_at (TEXT): Optional ISO 8601 expiry; expired entries are hidden from queries
+    ///
+    /// and a `memory_meta` key/value table holding the embedding spec.
     ///
     /// # Indexes Created
     ///
@@ -142,17 +167,88 @@ impl SQLiteVectorStore {
                 timestamp TEXT NOT NULL,
                 tokens INTEGER,
                 importance REAL,
-                embedding BLOB
+                embedding BLOB,
+                expires_at TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_user_id ON memory_entries(user_id);
             CREATE INDEX IF NOT EXISTS idx_conversation_id ON memory_entries(conversation_id);
             CREATE INDEX IF NOT EXISTS idx_timestamp ON memory_entries(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_conversation_timestamp ON memory_entries(conversation_id, timestamp);
+
+            CREATE TABLE IF NOT EXISTS memory_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
             "#
         )
         .execute(&self.pool)
         .await?;
 
+        self.migrate_expires_at().await
+    }
+
+    /// Adds the `expires_at` column to tables created before entries could expire.
+    async fn migrate_expires_at(&self) -> Result<(), anyhow::Error> {
+        let (present,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM pragma_table_info('memory_entries') WHERE name = 'expires_at'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if present == 0 {
+            sqlx::query("ALTER TABLE memory_entries ADD COLUMN expires_at TEXT")
+                .execute(&self.pool)
+                .await?;
+            info!("Added expires_at column to memory_entries");
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_expires_at ON memory_entries(expires_at)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Recorded embedding spec, or one inferred from a stored vector when none was recorded
+    /// (databases written before the spec existed).
+    async fn load_embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        let meta: HashMap<String, String> =
+            sqlx::query_as::<_, (String, String)>("SELECT key, value FROM memory_meta WHERE key IN (?1, ?2)")
+                .bind(META_EMBEDDING_MODEL)
+                .bind(META_EMBEDDING_DIM)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .collect();
+        if let Some(dim) = meta.get(META_EMBEDDING_DIM) {
+            let dimension = dim
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid {} in memory_meta: {:?} ({})", META_EMBEDDING_DIM, dim, e))?;
+            let model = meta.get(META_EMBEDDING_MODEL).cloned().unwrap_or_default();
+            return Ok(Some(EmbeddingSpec::new(model, dimension)));
+        }
+        let bytes: Option<i64> = sqlx::query_scalar(
+            "SELECT length(embedding) FROM memory_entries WHERE embedding IS NOT NULL LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(bytes.map(|b| EmbeddingSpec::inferred(b as usize / 4)))
+    }
+
+    async fn save_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (key, value) in [
+            (META_EMBEDDING_MODEL, spec.model.clone()),
+            (META_EMBEDDING_DIM, spec.dimension.to_string()),
+        ] {
+            sqlx::query(
+                "INSERT INTO memory_meta (key, value) VALUES (?1, ?2) \
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            )
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
@@ -193,6 +289,7 @@ impl SQLiteVectorStore {
         let tokens: Option<i64> = row.try_get("tokens")?;
         let importance: Option<f64> = row.try_get("importance")?;
         let embedding_blob: Option<Vec<u8>> = row.try_get("embedding")?;
+        let expires_at_str: Option<String> = row.try_get("expires_at")?;
 
         let id = Uuid::from_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
 
@@ -208,6 +305,10 @@ impl SQLiteVectorStore {
         let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
             .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
             .with_timezone(&Utc);
+        let expires_at = expires_at_str
+            .map(|s| DateTime::parse_from_rfc3339(&s).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
 
         let embedding = embedding_blob.map(|blob| {
             blob.chunks_exact(4)
@@ -222,6 +323,7 @@ impl SQLiteVectorStore {
             timestamp,
             tokens: tokens.map(|t| t as u32),
             importance: importance.map(|i| i as f32),
+            expires_at,
         };
 
         Ok(MemoryEntry {
@@ -260,6 +362,87 @@ impl SQLiteVectorStore {
     ///
     /// Time complexity: O(n) where n is vector dimensionality.
     /// Memory complexity: O(1) - only accumulators used.
+    /// Builds the bound INSERT statement for one entry.
+    ///
+    /// Shared by `add` (executed on the pool) and `add_batch` (executed inside a transaction).
+    ///
+    /// # Data Transformation
+    ///
+    /// - UUID: Converted to string for TEXT storage
+    /// - Timestamp: Converted to ISO 8601 string (RFC3339)
+    /// - Embedding: Serialized to binary BLOB (little-endian, 4 bytes per float)
+    fn insert_query(entry: &MemoryEntry) -> sqlx::query::Query<'_, Sqlite, SqliteArguments<'_>> {
+        let embedding_blob: Option<Vec<u8>> = entry.embedding.as_ref().map(|embedding| {
+            embedding
+                .iter()
+                .flat_map(|f| f.to_le_bytes().to_vec())
+                .collect()
+        });
+
+        sqlx::query(
+            r#"
+            INSERT INTO memory_entries (
+                id, content, user_id, conversation_id, role, timestamp,
+                tokens, importance, embedding, expires_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            "#
+        )
+        .bind(entry.id.to_string())
+        .bind(&entry.content)
+        .bind(&entry.metadata.user_id)
+        .bind(&entry.metadata.conversation_id)
+        .bind(Self::role_to_str(entry.metadata.role))
+        .bind(entry.metadata.timestamp.to_rfc3339())
+        .bind(entry.metadata.tokens.map(|t| t as i64))
+        .bind(entry.metadata.importance.map(|i| i as f64))
+        .bind(embedding_blob)
+        .bind(entry.metadata.expires_at.map(|t| t.to_rfc3339()))
+    }
+
+    fn role_to_str(role: MemoryRole) -> &'static str {
+        match role {
+            MemoryRole::User => "User",
+            MemoryRole::Assistant => "Assistant",
+            MemoryRole::System => "System",
+        }
+    }
+
+    /// Appends `AND ...` conditions for the fields set in `filter`.
+    ///
+    /// Timestamps are compared as RFC3339 strings, the format they are stored in. Expired entries
+    /// never match.
+    fn push_filter(builder: &mut QueryBuilder<'_, Sqlite>, filter: &MemoryFilter) {
+        builder
+            .push(" AND (expires_at IS NULL OR expires_at > ")
+            .push_bind(Utc::now().to_rfc3339())
+            .push(")");
+        if let Some(user_id) = &filter.user_id {
+            builder.push(" AND user_id = ").push_bind(user_id.clone());
+        }
+        if let Some(conversation_id) = &filter.conversation_id {
+            builder
+                .push(" AND conversation_id = ")
+                .push_bind(conversation_id.clone());
+        }
+        if let Some(role) = filter.role {
+            builder.push(" AND role = ").push_bind(Self::role_to_str(role));
+        }
+        if let Some(since) = filter.since {
+            builder.push(" AND timestamp >= ").push_bind(since.to_rfc3339());
+        }
+        if let Some(until) = filter.until {
+            builder.push(" AND timestamp < ").push_bind(until.to_rfc3339());
+        }
+        if let Some(min_importance) = filter.min_importance {
+            builder
+                .push(" AND importance >= ")
+                .push_bind(min_importance as f64);
+        }
+        if filter.non_empty {
+            builder.push(" AND content != ''");
+        }
+    }
+
     fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         if a.is_empty() || b.is_empty() {
             return 0.0;
@@ -315,40 +498,9 @@ impl MemoryStore for SQLiteVectorStore {
             "Writing entry to SQLite vector store"
         );
 
-        let role_str = match entry.metadata.role {
-            MemoryRole::User => "User",
-            MemoryRole::Assistant => "Assistant",
-            MemoryRole::System => "System",
-        };
-
-        let timestamp_str = entry.metadata.timestamp.to_rfc3339();
-
-        let embedding_blob: Option<Vec<u8>> = entry.embedding.map(|embedding| {
-            embedding
-                .iter()
-                .flat_map(|f| f.to_le_bytes().to_vec())
-                .collect()
-        });
-
-        sqlx::query(
-            r#"
-            INSERT INTO memory_entries (
-                id, content, user_id, conversation_id, role, timestamp,
-                tokens, importance, embedding
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-            "#
-        )
-        .bind(entry.id.to_string())
-        .bind(&entry.content)
-        .bind(&entry.metadata.user_id)
-        .bind(&entry.metadata.conversation_id)
-        .bind(role_str)
-        .bind(&timestamp_str)
-        .bind(entry.metadata.tokens.map(|t| t as i64))
-        .bind(entry.metadata.importance.map(|i| i as f64))
-        .bind(embedding_blob)
-        .execute(&self.pool)
-        .await?;
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
+        Self::insert_query(&entry).execute(&self.pool).await?;
 
         info!(
             id = %entry.id,
@@ -359,6 +511,32 @@ impl MemoryStore for SQLiteVectorStore {
         Ok(())
     }
 
+    /// Adds many memory entries in a single transaction.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes one INSERT per entry inside BEGIN/COMMIT
+    /// - **Storage Persistence**: Either all entries are written or none (rollback on error)
+    ///
+    /// # Performance
+    ///
+    /// - One fsync per batch instead of one per entry; the main win for imports and backfills
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        self.embedding_guard.check_entries(&entries)?;
+        let mut tx = self.pool.begin().await?;
+        for entry in &entries {
+            Self::insert_query(entry).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+
+        info!(count = entries.len(), "Batch written to SQLite vector store");
+        Ok(())
+    }
+
     /// Retrieves a memory entry by its UUID. Returns `None` if not found.
     ///
     /// Queries the database for a specific memory entry using its unique identifier.
@@ -374,9 +552,12 @@ impl MemoryStore for SQLiteVectorStore {
     /// - Fast retrieval due to PRIMARY KEY index on id column
     async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
         info!(id = %id, "Querying SQLite vector store by id");
-        let row = sqlx::query("SELECT * FROM memory_entries WHERE id = ?1")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
+        let row = sqlx::query(
+            "SELECT * FROM memory_entries WHERE id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+        )
+        .bind(id.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .fetch_optional(&self.pool)
             .await?;
 
         let found = row.is_some();
@@ -387,14 +568,15 @@ impl MemoryStore for SQLiteVectorStore {
         }
     }
 
-    /// Updates an existing memory entry.
+    /// Updates a memory entry, inserting it when the id is not stored yet (upsert).
     ///
-    /// Modifies all fields of an existing memory entry in the database. This is
+    /// Modifies all fields of the memory entry in the database. This is
     /// a full replacement operation where all field values are overwritten.
+    /// Upsert matches the other `MemoryStore` implementations (in-memory, Lance).
     ///
     /// # External Interactions
     ///
-    /// - **SQLite Database**: Executes UPDATE statement with WHERE id = ? condition
+    /// - **SQLite Database**: Executes INSERT ... ON CONFLICT(id) DO UPDATE
     /// - **File System**: Writes updated data to database file on disk
     /// - **Storage Persistence**: Changes are immediately persisted
     ///
@@ -403,15 +585,9 @@ impl MemoryStore for SQLiteVectorStore {
     /// Same transformation rules as add() method:
     /// - UUID, timestamp, role, tokens, importance, embedding all converted to storage format
     async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
-        let role_str = match entry.metadata.role {
-            MemoryRole::User => "User",
-            MemoryRole::Assistant => "Assistant",
-            MemoryRole::System => "System",
-        };
-
-        let timestamp_str = entry.metadata.timestamp.to_rfc3339();
-
-        let embedding_blob: Option<Vec<u8>> = entry.embedding.map(|embedding| {
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
+        let embedding_blob: Option<Vec<u8>> = entry.embedding.as_ref().map(|embedding| {
             embedding
                 .iter()
                 .flat_map(|f| f.to_le_bytes().to_vec())
@@ -420,27 +596,32 @@ impl MemoryStore for SQLiteVectorStore {
 
         sqlx::query(
             r#"
-            UPDATE memory_entries SET
-                content = ?1,
-                user_id = ?2,
-                conversation_id = ?3,
-                role = ?4,
-                timestamp = ?5,
-                tokens = ?6,
-                importance = ?7,
-                embedding = ?8
-            WHERE id = ?9
+            INSERT INTO memory_entries (
+                id, content, user_id, conversation_id, role, timestamp,
+                tokens, importance, embedding, expires_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                user_id = excluded.user_id,
+                conversation_id = excluded.conversation_id,
+                role = excluded.role,
+                timestamp = excluded.timestamp,
+                tokens = excluded.tokens,
+                importance = excluded.importance,
+                embedding = excluded.embedding,
+                expires_at = excluded.expires_at
             "#
         )
+        .bind(entry.id.to_string())
         .bind(&entry.content)
         .bind(&entry.metadata.user_id)
         .bind(&entry.metadata.conversation_id)
-        .bind(role_str)
-        .bind(&timestamp_str)
+        .bind(Self::role_to_str(entry.metadata.role))
+        .bind(entry.metadata.timestamp.to_rfc3339())
         .bind(entry.metadata.tokens.map(|t| t as i64))
         .bind(entry.metadata.importance.map(|i| i as f64))
         .bind(embedding_blob)
-        .bind(entry.id.to_string())
+        .bind(entry.metadata.expires_at.map(|t| t.to_rfc3339()))
         .execute(&self.pool)
         .await?;
 
@@ -484,9 +665,13 @@ impl MemoryStore for SQLiteVectorStore {
     /// - Results sorted by timestamp during query execution
     async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
         info!(user_id = %user_id, "Querying SQLite vector store by user");
-        let rows = sqlx::query("SELECT * FROM memory_entries WHERE user_id = ?1 ORDER BY timestamp DESC")
-            .bind(user_id)
-            .fetch_all(&self.pool)
+        let rows = sqlx::query(
+            "SELECT * FROM memory_entries WHERE user_id = ?1 \
+             AND (expires_at IS NULL OR expires_at > ?2) ORDER BY timestamp DESC",
+        )
+        .bind(user_id)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_all(&self.pool)
             .await?;
 
         let mut entries = Vec::new();
@@ -523,9 +708,13 @@ impl MemoryStore for SQLiteVectorStore {
         conversation_id: &str,
     ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
         info!(conversation_id = %conversation_id, "Querying SQLite vector store by conversation");
-        let rows = sqlx::query("SELECT * FROM memory_entries WHERE conversation_id = ?1 ORDER BY timestamp DESC")
-            .bind(conversation_id)
-            .fetch_all(&self.pool)
+        let rows = sqlx::query(
+            "SELECT * FROM memory_entries WHERE conversation_id = ?1 \
+             AND (expires_at IS NULL OR expires_at > ?2) ORDER BY timestamp DESC",
+        )
+        .bind(conversation_id)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_all(&self.pool)
             .await?;
 
         let mut entries = Vec::new();
@@ -585,37 +774,62 @@ impl MemoryStore for SQLiteVectorStore {
         limit: usize,
         user_id: Option<&str>,
         conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.semantic_search_filtered(
+            query_embedding,
+            limit,
+            &SearchFilter::scoped(user_id, conversation_id),
+        )
+        .await
+    }
+
+    /// Performs semantic search restricted by a metadata filter.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes a SELECT with the filter pushed down as WHERE predicates
+    ///   (user, conversation, role, timestamp range, importance, `id NOT IN (...)`)
+    /// - **Memory Operations**: Loads only the matching vectors for similarity calculation
+    ///
+    /// # Filtering
+    ///
+    /// Because filtering happens before scoring, `limit` counts only matching entries.
+    ///
+    /// # Returns
+    ///
+    /// Vector of memory entries sorted by similarity (highest first).
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
     ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
         info!(
             dimension = query_embedding.len(),
             limit = limit,
-            user_id = ?user_id,
-            conversation_id = ?conversation_id,
+            filter = ?filter,
             "step: embedding SQLite semantic search"
         );
-        info!(
-            embedding_len = query_embedding.len(),
-            limit = limit,
-            "Querying SQLite vector store semantic_search"
-        );
-        let rows = sqlx::query("SELECT * FROM memory_entries WHERE embedding IS NOT NULL")
-            .fetch_all(&self.pool)
-            .await?;
+        self.embedding_guard.check_query(query_embedding)?;
+        let mut builder =
+            QueryBuilder::<Sqlite>::new("SELECT * FROM memory_entries WHERE embedding IS NOT NULL");
+        Self::push_filter(&mut builder, &filter.metadata);
+        if !filter.exclude_ids.is_empty() {
+            builder.push(" AND id NOT IN (");
+            let mut ids = builder.separated(", ");
+            for id in &filter.exclude_ids {
+                ids.push_bind(id.to_string());
+            }
+            builder.push(")");
+        }
+        let rows = builder.build().fetch_all(&self.pool).await?;
 
-        let mut similarities: Vec<(f32, MemoryEntry)> = Vec::new();
+        let mut similarities: Vec<(f32, MemoryEntry)> = Vec::with_capacity(rows.len());
         for row in rows {
             let entry = Self::row_to_entry(&row)?;
-            let match_user = user_id
-                .map(|u| entry.metadata.user_id.as_deref() == Some(u))
-                .unwrap_or(true);
-            let match_conv = conversation_id
-                .map(|c| entry.metadata.conversation_id.as_deref() == Some(c))
-                .unwrap_or(true);
-            if match_user && match_conv {
-                if let Some(embedding) = &entry.embedding {
-                    let similarity = Self::cosine_similarity(query_embedding, embedding);
-                    similarities.push((similarity, entry));
-                }
+            if let Some(embedding) = &entry.embedding {
+                let similarity = Self::cosine_similarity(query_embedding, embedding);
+                similarities.push((similarity, entry));
             }
         }
 
@@ -629,14 +843,156 @@ impl MemoryStore for SQLiteVectorStore {
         info!(
             limit = limit,
             count = results.len(),
-            "step: embedding SQLite semantic search done"
+            "SQLite vector store semantic_search returned"
         );
+        Ok(results)
+    }
+
+    /// Lists entries matching a filter, one keyset page at a time.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes a filtered SELECT ordered by (timestamp, id) with LIMIT `limit + 1`
+    /// - **Index Usage**: Utilizes idx_conversation_timestamp for conversation listings
+    ///
+    /// # Pagination
+    ///
+    /// The extra row only signals that another page exists; `next_cursor` points at the last returned entry.
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM memory_entries WHERE 1=1");
+        Self::push_filter(&mut builder, filter);
+        let (cmp, order_by) = match order {
+            MemoryOrder::NewestFirst => ("<", " ORDER BY timestamp DESC, id DESC"),
+            MemoryOrder::OldestFirst => (">", " ORDER BY timestamp ASC, id ASC"),
+        };
+        if let Some(cursor) = cursor {
+            let timestamp = cursor.timestamp.to_rfc3339();
+            builder
+                .push(format!(" AND (timestamp {} ", cmp))
+                .push_bind(timestamp.clone())
+                .push(" OR (timestamp = ")
+                .push_bind(timestamp)
+                .push(format!(" AND id {} ", cmp))
+                .push_bind(cursor.id.to_string())
+                .push("))");
+        }
+        builder.push(order_by);
+        let fetch = i64::try_from(limit.saturating_add(1)).unwrap_or(i64::MAX);
+        builder.push(" LIMIT ").push_bind(fetch);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let entries = rows
+            .iter()
+            .map(Self::row_to_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+
         info!(
+            filter = ?filter,
+            order = ?order,
             limit = limit,
-            count = results.len(),
-            "SQLite vector store semantic_search returned"
+            count = entries.len(),
+            "SQLite vector store list returned"
         );
-        Ok(results)
+        Ok(MemoryPage::from_sorted(entries, limit))
+    }
+
+    /// Counts entries matching a filter.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes SELECT COUNT(*) with the filter conditions
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM memory_entries WHERE 1=1");
+        Self::push_filter(&mut builder, filter);
+        let (count,): (i64,) = builder.build_query_as().fetch_one(&self.pool).await?;
+        Ok(count as u64)
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        Ok(self.embedding_guard.spec())
+    }
+
+    /// Persists the spec in `memory_meta` the first time it is bound.
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        if self.embedding_guard.bind(spec)? {
+            self.save_embedding_spec(spec).await?;
+            info!(spec = %spec, "SQLite vector store embedding spec recorded");
+        }
+        Ok(())
+    }
+
+    /// Clears every embedding, then records `spec`.
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query("UPDATE memory_entries SET embedding = NULL WHERE embedding IS NOT NULL")
+            .execute(&self.pool)
+            .await?;
+        self.save_embedding_spec(spec).await?;
+        self.embedding_guard.set(Some(spec.clone()));
+        info!(cleared = result.rows_affected(), spec = %spec, "SQLite vector store embeddings reset");
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes entries whose `expires_at` is at or before `now` in one statement.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes DELETE with WHERE expires_at <= ? (uses idx_expires_at)
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query(
+            "DELETE FROM memory_entries WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+        )
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        info!(deleted = result.rows_affected(), "SQLite vector store delete_expired");
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes every entry of a conversation in one statement.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes DELETE with WHERE conversation_id = ? condition
+    /// - **Storage Persistence**: Entries are permanently removed, cannot be recovered
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query("DELETE FROM memory_entries WHERE conversation_id = ?1")
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+
+        info!(
+            conversation_id = %conversation_id,
+            deleted = result.rows_affected(),
+            "SQLite vector store delete_by_conversation"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes every entry of a user in one statement.
+    ///
+    /// # External Interactions
+    ///
+    /// - **SQLite Database**: Executes DELETE with WHERE user_id = ? condition
+    /// - **Storage Persistence**: Entries are permanently removed, cannot be recovered
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query("DELETE FROM memory_entries WHERE user_id = ?1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        info!(
+            user_id = %user_id,
+            deleted = result.rows_affected(),
+            "SQLite vector store delete_by_user"
+        );
+        Ok(result.rows_affected())
     }
 }
 
@@ -664,6 +1020,7 @@ mod tests {
             timestamp: Utc::now(),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         MemoryEntry::new(content.to_string(), metadata)
     }
@@ -739,6 +1096,7 @@ mod tests {
             timestamp: Utc::now(),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         let entry1 = MemoryEntry::new("Hello".to_string(), metadata1);
 
@@ -749,6 +1107,7 @@ mod tests {
             timestamp: Utc::now(),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         let entry2 = MemoryEntry::new("World".to_string(), metadata2);
 
@@ -771,6 +1130,7 @@ mod tests {
             timestamp: Utc::now(),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         let mut entry1 = MemoryEntry::new("Hello world".to_string(), metadata1);
         entry1.embedding = Some(vec![1.0, 0.0, 0.0]);
@@ -782,6 +1142,7 @@ mod tests {
             timestamp: Utc::now(),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         let mut entry2 = MemoryEntry::new("Goodbye world".to_string(), metadata2);
         entry2.embedding = Some(vec![0.0, 1.0, 0.0]);
@@ -793,6 +1154,7 @@ mod tests {
             timestamp: Utc::now(),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         let mut entry3 = MemoryEntry::new("Hello there".to_string(), metadata3);
         entry3.embedding = Some(vec![0.9, 0.1, 0.0]);
@@ -0,0 +1,14 @@
+//! Runs the shared MemoryStore conformance suite against memory_sqlite::SQLiteVectorStore.
+
+use memory_sqlite::SQLiteVectorStore;
+use tempfile::TempDir;
+
+async fn make() -> (TempDir, SQLiteVectorStore) {
+    let dir = TempDir::new().expect("tempdir");
+    let store = SQLiteVectorStore::new(&dir.path().join("memory.db").to_string_lossy())
+        .await
+        .expect("sqlite store");
+    (dir, store)
+}
+
+telegram_bot::memory_store_conformance_tests!(make());
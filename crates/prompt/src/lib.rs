@@ -9,6 +9,9 @@
 //! - **Conversation (recent)**: Section title + main dialogue messages
 //! - **Relevant reference (semantic)**: Section title + retrieved reference messages
 //!
+//! [`format_context_as_messages`] instead keeps the conversation as real user/assistant turns and
+//! puts preferences, summaries and retrieved memories in one delimited reference block.
+//!
 //! ## Usage
 //!
 //! Used by the `memory` crate when calling `Context::format_for_model()`.
@@ -70,6 +73,19 @@ pub const SECTION_RECENT: &str = "Conversation (recent):";
 /// Section title for semantically retrieved reference messages.
 pub const SECTION_SEMANTIC: &str = "Relevant reference (semantic):";
 
+/// Section title for summaries of older conversation (memory compaction).
+pub const SECTION_SUMMARY: &str = "Earlier conversation (summary):";
+
+/// Opening delimiter of the reference block built by [`format_context_as_messages`].
+pub const REFERENCE_OPEN: &str = "<reference>";
+
+/// Closing delimiter of the reference block.
+pub const REFERENCE_CLOSE: &str = "</reference>";
+
+/// First line of the reference block, telling the model how to treat it.
+pub const REFERENCE_NOTE: &str =
+    "Background from memory, not part of the current conversation. Use it only where relevant.";
+
 /// Builds context as a single string for AI models (no current question).
 ///
 /// Used when only the context block is needed (e.g. handler returning context string).
@@ -269,3 +285,89 @@ where
     messages.push(ChatMessage::user(current_question));
     messages
 }
+
+/// Builds context as messages with the conversation as genuine turns.
+///
+/// # Order
+///
+/// Optional system (if include_system) → optional User(reference block) → `history` turns as given →
+/// User(current_question).
+///
+/// The reference block is wrapped in [`REFERENCE_OPEN`] / [`REFERENCE_CLOSE`], starts with
+/// [`REFERENCE_NOTE`] and holds, each only when non-empty: "User Preferences: ...", the
+/// [`SECTION_SUMMARY`] lines and the [`SECTION_SEMANTIC`] lines.
+///
+/// # Arguments
+///
+/// * `include_system` - If true and system_message is present, it is the first message (System)
+/// * `system_message` - Optional system instruction
+/// * `user_preferences` - Optional user preferences (reference block)
+/// * `summaries` - Summaries of older conversation (reference block)
+/// * `history` - Recent conversation as user/assistant messages, oldest first
+/// * `references` - Retrieved memory lines (reference block)
+/// * `current_question` - Current user question (last message is User)
+pub fn format_context_as_messages<S, SI, R, RI>(
+    include_system: bool,
+    system_message: Option<&str>,
+    user_preferences: Option<&str>,
+    summaries: S,
+    history: Vec<ChatMessage>,
+    references: R,
+    current_question: &str,
+) -> Vec<ChatMessage>
+where
+    S: IntoIterator<Item = SI>,
+    SI: AsRef<str>,
+    R: IntoIterator<Item = RI>,
+    RI: AsRef<str>,
+{
+    let mut messages = Vec::new();
+    if include_system {
+        if let Some(msg) = system_message {
+            messages.push(ChatMessage::system(msg));
+        }
+    }
+
+    let mut block = String::new();
+    if let Some(prefs) = user_preferences {
+        block.push_str("User Preferences: ");
+        block.push_str(prefs);
+        block.push_str("\n\n");
+    }
+    for (title, lines) in [
+        (SECTION_SUMMARY, collect_lines(summaries)),
+        (SECTION_SEMANTIC, collect_lines(references)),
+    ] {
+        if lines.is_empty() {
+            continue;
+        }
+        block.push_str(title);
+        block.push('\n');
+        for line in &lines {
+            block.push_str(line);
+            block.push('\n');
+        }
+        block.push('\n');
+    }
+    if !block.is_empty() {
+        messages.push(ChatMessage::user(format!(
+            "{}\n{}\n\n{}\n{}",
+            REFERENCE_OPEN,
+            REFERENCE_NOTE,
+            block.trim_end(),
+            REFERENCE_CLOSE
+        )));
+    }
+
+    messages.extend(history);
+    messages.push(ChatMessage::user(current_question));
+    messages
+}
+
+fn collect_lines<I, T>(lines: I) -> Vec<String>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<str>,
+{
+    lines.into_iter().map(|l| l.as_ref().to_string()).collect()
+}
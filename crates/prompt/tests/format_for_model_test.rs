@@ -4,8 +4,9 @@
 //! External interactions: none (pure function tests).
 
 use prompt::{
-    format_for_model, format_for_model_as_messages, format_for_model_as_messages_with_roles,
-    parse_message_line, ChatMessage, MessageRole, SECTION_RECENT, SECTION_SEMANTIC,
+    format_context_as_messages, format_for_model, format_for_model_as_messages,
+    format_for_model_as_messages_with_roles, parse_message_line, ChatMessage, MessageRole,
+    REFERENCE_CLOSE, REFERENCE_OPEN, SECTION_RECENT, SECTION_SEMANTIC, SECTION_SUMMARY,
 };
 
 /// **Test: When include_system is true and system_message is set, output contains "System: {message}".**
@@ -62,8 +63,8 @@ fn format_includes_recent_and_semantic_sections() {
         false,
         None,
         None,
-        &["User: Hi", "Assistant: Hello"],
-        &["User: cat", "Assistant: Cats eat fish."],
+        ["User: Hi", "Assistant: Hello"],
+        ["User: cat", "Assistant: Cats eat fish."],
     );
     assert!(out.contains(SECTION_RECENT));
     assert!(out.contains("User: Hi"));
@@ -134,7 +135,7 @@ fn format_as_messages_context_block_then_question() {
         false,
         None,
         Some("Pref: tea"),
-        &["User: What do dogs eat?", "Assistant: Dogs eat dog food."],
+        ["User: What do dogs eat?", "Assistant: Dogs eat dog food."],
         &[] as &[&str],
         "What about cats?",
     );
@@ -208,7 +209,7 @@ fn format_with_roles_recent_as_one_user_block() {
         false,
         None,
         None,
-        &["User: What do dogs eat?", "Assistant: Dogs eat dog food."],
+        ["User: What do dogs eat?", "Assistant: Dogs eat dog food."],
         &[] as &[&str],
         "What about cats?",
     );
@@ -229,7 +230,7 @@ fn format_with_roles_semantic_only_then_question() {
         None,
         None,
         &[] as &[&str],
-        &["Ref: cats are furry."],
+        ["Ref: cats are furry."],
         "Tell me more.",
     );
     assert_eq!(msgs.len(), 2);
@@ -245,7 +246,7 @@ fn format_with_roles_includes_system_then_recent_then_question() {
         true,
         Some("You are helpful."),
         None,
-        &["User: Hi", "Assistant: Hello"],
+        ["User: Hi", "Assistant: Hello"],
         &[] as &[&str],
         "Bye",
     );
@@ -259,3 +260,50 @@ fn format_with_roles_includes_system_then_recent_then_question() {
     assert!(matches!(msgs[2].role, MessageRole::User));
     assert_eq!(msgs[2].content, "Bye");
 }
+
+/// **Test: History stays as real turns; preferences, summaries and references share one delimited block before them.**
+#[test]
+fn format_context_keeps_turns_and_delimits_reference() {
+    let msgs = format_context_as_messages(
+        true,
+        Some("You are helpful."),
+        Some("likes tea"),
+        ["Earlier: planned a trip"],
+        vec![ChatMessage::user("Hi"), ChatMessage::assistant("Hello")],
+        ["[2026-05-01 10:00] User (42): my flight is on May 3"],
+        "When do I fly?",
+    );
+    assert_eq!(msgs.len(), 5, "system, reference, two turns, question");
+    assert!(matches!(msgs[0].role, MessageRole::System));
+    assert!(matches!(msgs[1].role, MessageRole::User));
+    let block = &msgs[1].content;
+    assert!(block.starts_with(REFERENCE_OPEN));
+    assert!(block.ends_with(REFERENCE_CLOSE));
+    assert!(block.contains("User Preferences: likes tea"));
+    assert!(block.contains(SECTION_SUMMARY));
+    assert!(block.contains(SECTION_SEMANTIC));
+    assert!(block.contains("my flight is on May 3"));
+    assert!(!block.contains(SECTION_RECENT));
+    assert!(matches!(msgs[2].role, MessageRole::User));
+    assert_eq!(msgs[2].content, "Hi");
+    assert!(matches!(msgs[3].role, MessageRole::Assistant));
+    assert_eq!(msgs[3].content, "Hello");
+    assert_eq!(msgs[4].content, "When do I fly?");
+}
+
+/// **Test: Without background there is no reference block; include_system=false drops the system message.**
+#[test]
+fn format_context_without_background_is_turns_then_question() {
+    let msgs = format_context_as_messages(
+        false,
+        Some("You are helpful."),
+        None,
+        &[] as &[&str],
+        vec![ChatMessage::user("Hi")],
+        &[] as &[&str],
+        "Bye",
+    );
+    assert_eq!(msgs.len(), 2);
+    assert_eq!(msgs[0].content, "Hi");
+    assert_eq!(msgs[1].content, "Bye");
+}
@@ -8,6 +8,7 @@ pub mod checkpoint;
 pub mod format;
 pub mod load;
 pub mod llm_request_logging;
+pub mod llm_usage;
 pub mod memory;
 pub mod noop_checkpointer;
 pub mod react;
@@ -34,8 +35,8 @@ pub use load::{
 };
 pub use memory::LongTermMemoryPolicy;
 pub use react::{
-    create_react_runner, last_assistant_content, print_runtime_info, ChatStreamResult, ReactRunner,
-    StreamUpdate, UserProfile,
+    create_react_runner, estimate_turn_usage, last_assistant_content, print_runtime_info,
+    ChatStreamResult, ReactRunner, StreamUpdate, UserProfile,
 };
 pub use telegram_db::{load_all_messages_from_telegram_db, load_messages_from_telegram_db};
 
@@ -0,0 +1,87 @@
+//! LLM client decorator that sums the provider-reported token usage of the think node's calls.
+//!
+//! [`UsageMeter`] is shared between the [`UsageRecordingLlm`] installed in the graph and the
+//! [`ReactRunner`](crate::react::ReactRunner), which takes the meter after each turn and falls back to
+//! [`estimate_turn_usage`](crate::react::estimate_turn_usage) when a call reported no usage.
+
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use langgraph::{AgentError, LlmClient, LlmResponse, Message, MessageChunk};
+
+/// Provider-reported usage summed over the LLM calls since the last [`UsageMeter::take`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeteredUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    /// LLM calls made.
+    pub calls: usize,
+    /// True when at least one call's response carried no usage.
+    pub incomplete: bool,
+}
+
+impl MeteredUsage {
+    /// Returns the usage when every call reported it, `None` otherwise (or when no call was made).
+    pub fn reported(&self) -> Option<(usize, usize)> {
+        (self.calls > 0 && !self.incomplete).then_some((self.prompt_tokens, self.completion_tokens))
+    }
+}
+
+/// Shared accumulator of [`MeteredUsage`].
+#[derive(Clone, Default)]
+pub struct UsageMeter(Arc<Mutex<MeteredUsage>>);
+
+impl UsageMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the usage summed so far and resets the meter.
+    pub fn take(&self) -> MeteredUsage {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn add(&self, response: &LlmResponse) {
+        let mut usage = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        usage.calls += 1;
+        match response.usage {
+            Some(ref u) => {
+                usage.prompt_tokens += u.prompt_tokens as usize;
+                usage.completion_tokens += u.completion_tokens as usize;
+            }
+            None => usage.incomplete = true,
+        }
+    }
+}
+
+/// Wraps an [`LlmClient`] and adds the `usage` of each response to a [`UsageMeter`].
+pub struct UsageRecordingLlm {
+    inner: Box<dyn LlmClient>,
+    meter: UsageMeter,
+}
+
+impl UsageRecordingLlm {
+    pub fn new(inner: Box<dyn LlmClient>, meter: UsageMeter) -> Self {
+        Self { inner, meter }
+    }
+}
+
+#[async_trait]
+impl LlmClient for UsageRecordingLlm {
+    async fn invoke(&self, messages: &[Message]) -> Result<LlmResponse, AgentError> {
+        let response = self.inner.invoke(messages).await?;
+        self.meter.add(&response);
+        Ok(response)
+    }
+
+    async fn invoke_stream(
+        &self,
+        messages: &[Message],
+        chunk_tx: Option<mpsc::Sender<MessageChunk>>,
+    ) -> Result<LlmResponse, AgentError> {
+        let response = self.inner.invoke_stream(messages, chunk_tx).await?;
+        self.meter.add(&response);
+        Ok(response)
+    }
+}
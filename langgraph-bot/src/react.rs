@@ -7,6 +7,7 @@ use anyhow::{anyhow, Result};
 use std::collections::HashSet;
 use std::sync::Arc;
 
+use crate::llm_usage::{UsageMeter, UsageRecordingLlm};
 use crate::memory::LongTermMemoryPolicy;
 use crate::noop_checkpointer::NoOpCheckpointer;
 use crate::tools::StoreGetToolSource;
@@ -21,6 +22,7 @@ use langgraph::{
 use langgraph::stream::{StreamEvent, StreamMode};
 use telegram_bot::embedding::EmbeddingService;
 use telegram_bot::memory::{get_store, MemoryStore};
+use telegram_bot::tokenizer::{load_tokenizer, EnvTokenizerConfig, Tokenizer, TokenizerConfig};
 use tokio_stream::StreamExt;
 use tracing::{debug, info};
 
@@ -46,6 +48,13 @@ pub struct ChatStreamResult {
     pub tools_used: Vec<String>,
     /// When `reply` is empty, short reason for logging (e.g. no final state, or last assistant message had no text).
     pub empty_reply_reason: Option<String>,
+    /// Prompt tokens over all think calls of this turn, as reported by the API (estimated with
+    /// [`estimate_turn_usage`] when a call reported none).
+    pub prompt_tokens: usize,
+    /// Completion tokens over all think calls of this turn.
+    pub completion_tokens: usize,
+    /// True when the token counts are estimated rather than reported by the API.
+    pub usage_estimated: bool,
 }
 
 /// Incremental update during streaming: chunk of reply text, or current steps/tools for live header.
@@ -110,6 +119,14 @@ pub struct ReactRunner {
     pub(super) checkpointer: Arc<dyn langgraph::memory::Checkpointer<ReActState>>,
     /// Custom system prompt from env; when None, uses REACT_SYSTEM_PROMPT.
     pub(super) system_prompt: Option<String>,
+    /// Chat model used by the think node (OPENAI_MODEL); recorded in the usage ledger.
+    pub(super) model: String,
+    /// Tokenizer of `model`, for [`estimate_turn_usage`].
+    pub(super) tokenizer: Arc<dyn Tokenizer>,
+    /// API-reported usage of the think node's calls; taken after each turn.
+    pub(super) usage_meter: UsageMeter,
+    /// Held for the whole turn so calls of concurrent turns on a shared runner do not mix in the meter.
+    pub(super) turn_lock: tokio::sync::Mutex<()>,
 }
 
 /// Builds initial ReActState for one turn: from checkpoint (if thread_id + checkpointer) or fresh with system + user.
@@ -179,6 +196,29 @@ pub fn last_assistant_content(state: &ReActState) -> String {
         .unwrap_or_default()
 }
 
+/// Estimates `(prompt_tokens, completion_tokens)` of one turn from its final state.
+///
+/// Fallback when the API reports no usage: each Assistant message is counted as one think call whose
+/// prompt is every message before it. Tool results not kept in `messages` are not counted.
+pub fn estimate_turn_usage(state: &ReActState, tokenizer: &dyn Tokenizer) -> (usize, usize) {
+    let mut prompt_tokens = 0;
+    let mut completion_tokens = 0;
+    let mut context_tokens = 0;
+    for m in &state.messages {
+        let tokens = match m {
+            Message::System(s) | Message::User(s) => tokenizer.count_tokens(s),
+            Message::Assistant(s) => {
+                prompt_tokens += context_tokens;
+                let t = tokenizer.count_tokens(s);
+                completion_tokens += t;
+                t
+            }
+        };
+        context_tokens += tokens;
+    }
+    (prompt_tokens, completion_tokens)
+}
+
 /// Redacts embedding/vector content from JSON for logging (do not print raw vector data).
 /// Returns a copy with array values replaced by a placeholder like "[redacted, N dims]".
 fn redact_vectors_for_log(value: &serde_json::Value) -> serde_json::Value {
@@ -353,7 +393,9 @@ async fn compile_react_runner(
     )
     .await
     .map_err(|e| anyhow!("ChatOpenAI::new_with_tool_source: {}", e))?;
-    let llm: Box<dyn langgraph::LlmClient> = Box::new(llm);
+    let usage_meter = UsageMeter::new();
+    let llm: Box<dyn langgraph::LlmClient> =
+        Box::new(UsageRecordingLlm::new(Box::new(llm), usage_meter.clone()));
     let think = ThinkNode::new(llm);
     let act = ActNode::new(tool_source).with_handle_tool_errors(HandleToolErrors::Custom(
         tool_error_handler_with_key_logging(),
@@ -391,10 +433,19 @@ async fn compile_react_runner(
             .compile_with_checkpointer(Arc::clone(&checkpointer))
             .map_err(|e| anyhow!("compile_with_checkpointer: {}", e))?
     };
+    let tokenizer_cfg = EnvTokenizerConfig::from_env().ok();
+    let tokenizer = load_tokenizer(
+        tokenizer_cfg.as_ref().map(|c| c as &dyn TokenizerConfig),
+        &model,
+    );
     Ok(ReactRunner {
         compiled,
         checkpointer,
         system_prompt: config.system_prompt.clone(),
+        model,
+        tokenizer,
+        usage_meter,
+        turn_lock: tokio::sync::Mutex::new(()),
     })
 }
 
@@ -540,6 +591,11 @@ pub async fn print_runtime_info() -> Result<()> {
 }
 
 impl ReactRunner {
+    /// Chat model used by the think node.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     /// Streams one turn: builds initial state, runs graph with Messages + Values + Tasks modes,
     /// calls `on_update` for each chunk (Chunk), and when steps/tools change (Steps, Tools); returns final result.
     ///
@@ -552,6 +608,8 @@ impl ReactRunner {
         user_profile: Option<&UserProfile>,
         user_message_already_in_checkpoint: bool,
     ) -> Result<ChatStreamResult> {
+        let _turn = self.turn_lock.lock().await;
+        self.usage_meter.take();
         let mut config = RunnableConfig::default();
         config.thread_id = Some(thread_id.to_string());
         // Long-term memory: run-time user_id from UserProfile for per-Telegram-user isolation.
@@ -680,6 +738,14 @@ impl ReactRunner {
         } else {
             None
         };
+        let metered = self.usage_meter.take();
+        let usage_estimated = metered.reported().is_none();
+        let (prompt_tokens, completion_tokens) = metered.reported().unwrap_or_else(|| {
+            final_state
+                .as_ref()
+                .map(|s| estimate_turn_usage(s, self.tokenizer.as_ref()))
+                .unwrap_or_default()
+        });
         info!(
             steps_count = steps.len(),
             reply_len = reply.len(),
@@ -687,6 +753,9 @@ impl ReactRunner {
             messages_len = messages_len,
             tools_used = ?tools_used,
             empty_reply_reason = ?empty_reply_reason,
+            prompt_tokens,
+            completion_tokens,
+            usage_estimated,
             "Stream ended: final state summary"
         );
         Ok(ChatStreamResult {
@@ -694,12 +763,16 @@ impl ReactRunner {
             steps,
             tools_used,
             empty_reply_reason,
+            prompt_tokens,
+            completion_tokens,
+            usage_estimated,
         })
     }
 
     /// Runs one turn with the given `user_id` so the model can remember `content` in long-term memory (if not already).
     /// Used by [`EnsureLongTermMemoryHandler`] to ensure bot identity and user profile are stored. Drains the stream and ignores output.
     pub async fn ensure_remember(&self, user_id: &str, content: &str) -> Result<()> {
+        let _turn = self.turn_lock.lock().await;
         let mut config = RunnableConfig::default();
         config.thread_id = Some(format!("ensure_{}", user_id));
         config.user_id = Some(user_id.to_string());
@@ -719,6 +792,7 @@ impl ReactRunner {
         ]);
         let mut stream = self.compiled.stream(state, Some(config), modes);
         while stream.next().await.is_some() {}
+        self.usage_meter.take();
         Ok(())
     }
 }
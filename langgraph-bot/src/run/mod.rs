@@ -6,12 +6,14 @@ use langgraph::ConfigSection;
 use telegram_bot::{load_config, Bot, TelegramBot};
 use telegram_bot::embedding::EmbeddingService;
 use telegram_bot::memory::MemoryStore;
+use telegram_bot::storage::UsageRepository;
 use telegram_llm_bot::run_bot_with_custom_handler;
 use crate::telegram::{AgentHandler, EnsureLongTermMemoryHandler, EnsureThenAgentHandler, RunnerResolver};
 use crate::{create_react_runner, ReactRunner};
 
 /// Builds the handler chain used by `run_telegram`: EnsureThenAgentHandler → EnsureLongTermMemoryHandler → AgentHandler.
 /// When `memory_store` and `embedding_service` are `None`, `RunnerResolver` is created with `None, None` (e.g. for tests).
+/// When `usage_repo` is set, the agent records each turn's estimated token usage there.
 #[allow(clippy::too_many_arguments)]
 pub fn build_run_telegram_handler(
     runner: Arc<ReactRunner>,
@@ -21,6 +23,7 @@ pub fn build_run_telegram_handler(
     placeholder_message: String,
     memory_store: Option<Arc<dyn MemoryStore>>,
     embedding_service: Option<Arc<dyn EmbeddingService>>,
+    usage_repo: Option<UsageRepository>,
 ) -> Arc<dyn telegram_bot::Handler> {
     let runner_resolver = Arc::new(RunnerResolver::new(
         runner.clone(),
@@ -35,6 +38,10 @@ pub fn build_run_telegram_handler(
         bot_user,
         placeholder_message,
     );
+    let agent_handler = match usage_repo {
+        Some(repo) => agent_handler.with_usage_repo(repo),
+        None => agent_handler,
+    };
     Arc::new(EnsureThenAgentHandler::new(ensure_handler, agent_handler))
 }
 
@@ -81,6 +88,7 @@ pub async fn run_telegram(token: Option<String>) -> Result<()> {
             placeholder_message.clone(),
             Some(components.memory_store.clone()),
             Some(components.embedding_service.clone()),
+            Some(components.usage_repo.as_ref().clone()),
         )
     })
     .await
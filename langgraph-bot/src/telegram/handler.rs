@@ -9,6 +9,7 @@
 //!
 //! - **[`AgentHandler`]** – Handler that runs the ReAct agent on reply-to-bot or @mention; implements [`Handler`](telegram_bot::Handler).
 //! - **[`AgentHandler::new`]** – Constructs an `AgentHandler` with runner, bot, and placeholder message.
+//! - **[`AgentHandler::with_usage_repo`]** – Records the token usage of each turn in the `llm_usage` ledger.
 //! - **[`AgentHandler::get_question`]** – Returns the user question if the message should trigger the agent (reply-to-bot or @mention); otherwise `None`.
 //! - **[`AgentHandler::thread_id`]** – Returns the thread ID (one per chat).
 //! - **`Handler::handle`** (on `AgentHandler`) – Telegram entry: decides trigger, enqueues per-chat, returns immediately with `Continue` or `Stop`.
@@ -22,10 +23,11 @@ use std::sync::Arc;
 use telegram_bot::embedding::EmbeddingService;
 use telegram_bot::memory::MemoryStore;
 use telegram_bot::mention;
+use telegram_bot::storage::{UsageRecord, UsageRepository, USAGE_KIND_CHAT};
 use telegram_bot::{Bot, Chat, Handler, HandlerResponse, Message, Result, User as TelegramUser};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
 // ---------- User-facing messages (shown in Telegram) ----------
 const MSG_SEND_FAILED: &str = "发送失败，请稍后再试。";
@@ -33,6 +35,9 @@ const MSG_PROCESSING_FAILED: &str = "处理时出错，请稍后再试。";
 /// Shown when the agent completed (e.g. tool use) but returned no assistant text (e.g. only remember tool call).
 const MSG_EMPTY_REPLY_FALLBACK: &str = "已处理。（本次无文字回复）";
 
+/// Handler name recorded in the usage ledger.
+const USAGE_HANDLER: &str = "react";
+
 // ---------- Type aliases (all user-visible strings are in the constants above) ----------
 
 /// One item in the per-chat queue: the Telegram message and the extracted question text.
@@ -98,6 +103,8 @@ pub struct AgentHandler {
     bot_user: Arc<tokio::sync::RwLock<Option<TelegramUser>>>,
     placeholder_message: String,
     message_queues: dashmap::DashMap<String, QueueSender>,
+    /// When set, each turn's token usage is recorded per chat/user.
+    usage_repo: Option<UsageRepository>,
 }
 
 impl AgentHandler {
@@ -117,9 +124,16 @@ impl AgentHandler {
             bot_user,
             placeholder_message,
             message_queues: dashmap::DashMap::new(),
+            usage_repo: None,
         }
     }
 
+    /// Records the token usage of each agent turn in the given ledger.
+    pub fn with_usage_repo(mut self, usage_repo: UsageRepository) -> Self {
+        self.usage_repo = Some(usage_repo);
+        self
+    }
+
     /// Returns the full bot identity from getMe (id, username, first_name, last_name), if already available.
     pub async fn bot_user(&self) -> Option<TelegramUser> {
         self.bot_user.read().await.clone()
@@ -155,11 +169,13 @@ impl AgentHandler {
         let runner_resolver = Arc::clone(&self.runner_resolver);
         let bot = self.bot.clone();
         let placeholder_message = self.placeholder_message.clone();
+        let usage_repo = self.usage_repo.clone();
         tokio::spawn(Self::process_queue_loop(
             rx,
             runner_resolver,
             bot,
             placeholder_message,
+            usage_repo,
             thread_id,
         ));
         tx
@@ -171,6 +187,7 @@ impl AgentHandler {
         runner_resolver: Arc<RunnerResolver>,
         bot: Arc<dyn Bot>,
         placeholder_message: String,
+        usage_repo: Option<UsageRepository>,
         thread_id: String,
     ) {
         while let Some((message, question)) = rx.recv().await {
@@ -185,6 +202,7 @@ impl AgentHandler {
                 &message,
                 &question,
                 &placeholder_message,
+                usage_repo.as_ref(),
             )
             .await
             {
@@ -249,6 +267,32 @@ impl AgentHandler {
         }
     }
 
+    /// Records the turn's usage under the runner's model, flagged as estimated when the API reported
+    /// none. Failures are logged only.
+    async fn record_usage(
+        usage_repo: &UsageRepository,
+        model: &str,
+        message: &Message,
+        result: &ChatStreamResult,
+    ) {
+        let record = UsageRecord::new(
+            model,
+            USAGE_HANDLER,
+            USAGE_KIND_CHAT,
+            result.prompt_tokens as i64,
+            result.completion_tokens as i64,
+        )
+        .for_message(Some(message.chat.id), Some(message.user.id));
+        let record = if result.usage_estimated {
+            record.estimated()
+        } else {
+            record
+        };
+        if let Err(e) = usage_repo.record(&record).await {
+            warn!(error = %e, "Failed to record agent usage");
+        }
+    }
+
     /// Edits the message to the final text. Logs only if the error is not "message is not modified" (best-effort edit).
     async fn apply_final_edit(
         bot: &Arc<dyn Bot>,
@@ -263,13 +307,14 @@ impl AgentHandler {
         }
     }
 
-    /// 1) Send placeholder; 2) spawn stream-edit loop; 3) run agent stream (no short-term memory); 4) apply final edit or error message.
+    /// 1) Send placeholder; 2) spawn stream-edit loop; 3) run agent stream (no short-term memory); 4) record usage and apply final edit or error message.
     async fn process_message(
         runner_resolver: &Arc<RunnerResolver>,
         bot: &Arc<dyn Bot>,
         message: &Message,
         question: &str,
         placeholder_message: &str,
+        usage_repo: Option<&UsageRepository>,
     ) -> Result<HandlerResponse> {
         let thread_id = Self::thread_id(message);
         let runner = runner_resolver
@@ -316,6 +361,9 @@ impl AgentHandler {
 
         match stream_result {
             Ok(result) => {
+                if let Some(repo) = usage_repo {
+                    Self::record_usage(repo, runner.model(), message, &result).await;
+                }
                 let reply_is_fallback = result.reply.trim().is_empty();
                 let reply_text = Self::pick_reply_text(&result, &thread_id);
                 let text = format_reply_with_process_and_tools(
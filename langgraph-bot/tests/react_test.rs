@@ -6,11 +6,13 @@
 use anyhow::Result;
 use langgraph::Message;
 use langgraph::ReActState;
+use langgraph_bot::llm_usage::MeteredUsage;
 use langgraph_bot::{
-    create_react_runner, last_assistant_content,
+    create_react_runner, estimate_turn_usage, last_assistant_content,
     run_chat_stream,
 };
 use std::path::PathBuf;
+use telegram_bot::tokenizer::EstimateTokenizer;
 use tempfile::TempDir;
 
 /// Helper: Creates a temporary DB path for testing.
@@ -83,6 +85,49 @@ fn last_assistant_content_helper() {
     assert_eq!(last_assistant_content(&state), "b");
 }
 
+/// **Test: estimate_turn_usage counts each Assistant message as one call prompted by all prior messages.**
+#[test]
+fn estimate_turn_usage_helper() {
+    let state = ReActState {
+        messages: vec![Message::system("s"), Message::user("u")],
+        tool_calls: vec![],
+        tool_results: vec![],
+        turn_count: 0,
+    };
+    assert_eq!(estimate_turn_usage(&state, &EstimateTokenizer), (0, 0));
+
+    // Each short message estimates to 1 token: first call sees 2, second call sees 3.
+    let state = ReActState {
+        messages: vec![
+            Message::system("s"),
+            Message::user("u"),
+            Message::Assistant("a".to_string()),
+            Message::Assistant("b".to_string()),
+        ],
+        tool_calls: vec![],
+        tool_results: vec![],
+        turn_count: 0,
+    };
+    assert_eq!(estimate_turn_usage(&state, &EstimateTokenizer), (5, 2));
+}
+
+/// **Test: metered usage is used only when every call of the turn reported it.**
+#[test]
+fn metered_usage_reported_only_when_complete() {
+    assert_eq!(MeteredUsage::default().reported(), None);
+
+    let mut usage = MeteredUsage {
+        prompt_tokens: 120,
+        completion_tokens: 30,
+        calls: 2,
+        incomplete: false,
+    };
+    assert_eq!(usage.reported(), Some((120, 30)));
+
+    usage.incomplete = true;
+    assert_eq!(usage.reported(), None);
+}
+
 /// **Test: run_chat_stream invokes on_chunk and returns non-empty final reply.**
 #[tokio::test]
 async fn run_chat_stream_invokes_on_chunk() -> Result<()> {
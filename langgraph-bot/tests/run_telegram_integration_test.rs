@@ -94,6 +94,7 @@ async fn run_telegram_handler_chain_final_edit_non_empty() {
                 placeholder.clone(),
                 None, // simplify: RunnerResolver with None, None
                 None,
+                None,
             )
         },
     )
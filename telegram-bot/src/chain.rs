@@ -4,6 +4,7 @@
 //! order (any false stops the chain); then handle runs until Stop or Reply; then all after run in reverse.
 
 use crate::core::{Handler, HandlerResponse, Message, Result};
+use crate::embedding::{with_usage_scope, UsageScope};
 use std::sync::Arc;
 use tracing::{debug, info, instrument};
 
@@ -27,9 +28,15 @@ impl HandlerChain {
         self
     }
 
-    /// Runs all before → handle until Stop/Reply → all after in reverse.
+    /// Runs all before → handle until Stop/Reply → all after in reverse. Embedding usage recorded
+    /// meanwhile is attributed to the message's chat and user.
     #[instrument(skip(self, message))]
     pub async fn handle(&self, message: &Message) -> Result<HandlerResponse> {
+        let scope = UsageScope::new(Some(message.chat.id), Some(message.user.id));
+        with_usage_scope(scope, self.run(message)).await
+    }
+
+    async fn run(&self, message: &Message) -> Result<HandlerResponse> {
         let mut final_response = HandlerResponse::Continue;
 
         info!(
@@ -22,6 +22,45 @@ pub enum Commands {
         #[arg(short, long)]
         token: Option<String>,
     },
+    /// Re-encrypt stored messages and memory entries under the active key (ENCRYPTION_KEY_ID).
+    Reencrypt {
+        /// Rows per transaction when rewriting messages.
+        #[arg(long, default_value_t = 500)]
+        batch_size: u32,
+    },
+    /// Re-embed memory entries with the configured embedding model (EMBEDDING_MODEL / EMBEDDING_DIM).
+    Reembed {
+        /// Entries per embedding request.
+        #[arg(long, default_value_t = 64)]
+        batch_size: usize,
+    },
+    /// Import stored messages into memory (entries embedded and written in batches); messages already
+    /// imported are skipped.
+    Backfill {
+        /// Entries per embedding request and batch write.
+        #[arg(long, default_value_t = 64)]
+        batch_size: usize,
+    },
+    /// Inspect or purge the write outbox (failed memory and message writes awaiting retry).
+    Outbox {
+        #[command(subcommand)]
+        action: OutboxAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum OutboxAction {
+    /// Show pending and dead-letter counts and list items (dead letters unless --pending).
+    List {
+        /// List pending items instead of dead letters.
+        #[arg(long)]
+        pending: bool,
+        /// Maximum number of items listed.
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Delete all dead letters.
+    Purge,
 }
 
 /// Load BotConfig from environment. If `token` is provided it overrides BOT_TOKEN.
@@ -4,12 +4,20 @@ use anyhow::Result;
 use std::sync::Arc;
 use crate::chain::HandlerChain;
 use crate::core::{Bot as CoreBot, Handler, User};
-use crate::embedding::{BigModelEmbedding, OpenAIEmbedding};
+use crate::embedding::{configured_embedding_spec, create_embedding_service, EmbeddingConfig};
+use crate::encryption::{EncryptedMemoryStore, FieldCipher};
 use crate::handlers::{MemoryHandler, PersistenceHandler};
-use crate::memory::{InMemoryVectorStore, MemoryStore, SQLiteVectorStore};
-use crate::storage::MessageRepository;
+use crate::memory::{
+    spawn_replication, spawn_snapshot_autosave, CachedMemoryStore, CompactionPolicy, HnswParams,
+    InMemoryVectorStore, MemoryCompactor, MemoryEvents, MemoryStore, MemoryWritePipeline,
+    ObservableMemoryStore, Replication, SQLiteVectorStore, TtlPolicy, DEFAULT_DEDUP_WINDOW,
+};
+use crate::outbox::OutboxRepository;
+use crate::profile::{ProfileRepository, ProfileUpdater};
+use crate::retention::DataEraser;
+use crate::storage::{MessageRepository, UsageRepository};
 use teloxide::prelude::*;
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 
 use super::config::{AppExtensions, BotConfig};
 
@@ -26,7 +34,31 @@ pub struct BotComponents {
     pub bot_user: Arc<tokio::sync::RwLock<Option<User>>>,
     pub memory_store: Arc<dyn MemoryStore>,
     pub recent_store: Option<Arc<dyn MemoryStore>>,
+    /// Change feed of `memory_store`: every write through it is published here. A separate recent
+    /// store is already subscribed and mirrors it; indexes, audit logs etc. can subscribe too.
+    pub memory_events: MemoryEvents,
+    /// Replication of `memory_events` into a separate recent store (none when there is no separate one).
+    pub recent_replication: Option<Arc<Replication>>,
     pub embedding_service: Arc<dyn crate::embedding::EmbeddingService>,
+    /// Token usage ledger (same database as `repo`); handlers record LLM calls here.
+    pub usage_repo: Arc<UsageRepository>,
+    /// Failed memory and message writes awaiting retry (same database as `repo`); handlers queue
+    /// writes here and the runner's outbox worker replays them.
+    pub outbox: OutboxRepository,
+    /// Importance scoring and duplicate suppression for memory writes (MemoryHandler). Applications may
+    /// install another scorer (e.g. LLM-based) with [`MemoryWritePipeline::set_scorer`] before the chain is built.
+    pub memory_write_pipeline: Arc<MemoryWritePipeline>,
+    /// Summarizes old memory windows when MEMORY_COMPACTION_AFTER_DAYS is set. It has no summarizer
+    /// until the application installs one ([`MemoryCompactor::set_summarizer`]); the runner only
+    /// schedules it then.
+    pub memory_compactor: Option<Arc<MemoryCompactor>>,
+    /// Expiry for new memory entries (MEMORY_TTL_*); MemoryHandler stamps `expires_at` with it.
+    pub memory_ttl_policy: TtlPolicy,
+    /// Facts extracted about each user (same database as `repo`); UserPreferencesStrategy reads them.
+    pub profile_repo: ProfileRepository,
+    /// Extracts user facts from the messages MemoryHandler saves when PROFILE_EXTRACTION is on. It has
+    /// no extractor until the application installs one ([`ProfileUpdater::set_extractor`]).
+    pub profile_updater: Option<Arc<ProfileUpdater>>,
 }
 
 /// Creates the primary memory store and optional recent store from config.
@@ -48,19 +80,39 @@ pub async fn create_memory_stores(
             Arc::new(InMemoryVectorStore::new())
         }
         "sqlite" => {
-            info!(db_path = %mem_cfg.sqlite_path(), "Using SQLite vector store");
-            Arc::new(
-                SQLiteVectorStore::new(mem_cfg.sqlite_path())
+            info!(db_path = %mem_cfg.sqlite_path(), hnsw = mem_cfg.sqlite_hnsw(), "Using SQLite vector store");
+            let mut store = SQLiteVectorStore::new(mem_cfg.sqlite_path())
+                .await
+                .map_err(|e| {
+                    error!(error = %e, "Failed to initialize SQLite store");
+                    anyhow::anyhow!("Failed to initialize SQLite store: {}", e)
+                })?;
+            if mem_cfg.sqlite_hnsw() {
+                let index_path = format!("{}.hnsw", mem_cfg.sqlite_path());
+                store = store
+                    .with_hnsw(index_path, HnswParams::default())
                     .await
                     .map_err(|e| {
-                        error!(error = %e, "Failed to initialize SQLite store");
-                        anyhow::anyhow!("Failed to initialize SQLite store: {}", e)
-                    })?,
-            )
+                        error!(error = %e, "Failed to initialize SQLite HNSW index");
+                        anyhow::anyhow!("Failed to initialize SQLite HNSW index: {}", e)
+                    })?;
+            }
+            Arc::new(store)
         }
         _ => {
-            info!("Using in-memory vector store");
-            Arc::new(InMemoryVectorStore::new())
+            info!(snapshot_path = ?mem_cfg.snapshot_path(), "Using in-memory vector store");
+            let mut store = InMemoryVectorStore::new();
+            if let Some(path) = mem_cfg.snapshot_path() {
+                store = store.with_snapshot(path).await.map_err(|e| {
+                    error!(error = %e, "Failed to load in-memory store snapshot");
+                    anyhow::anyhow!("Failed to load in-memory store snapshot: {}", e)
+                })?;
+                if mem_cfg.snapshot_interval_secs() > 0 {
+                    let interval = std::time::Duration::from_secs(mem_cfg.snapshot_interval_secs());
+                    spawn_snapshot_autosave(store.clone(), interval);
+                }
+            }
+            Arc::new(store)
         }
     };
 
@@ -100,18 +152,88 @@ pub async fn build_bot_components(
         .embedding_config()
         .ok_or_else(|| anyhow::anyhow!("Embedding config required"))?;
 
-    let repo = Arc::new(
-        MessageRepository::new(config.base().database_url.as_str())
-            .await
-            .map_err(|e| {
-                error!(
-                    error = %e,
-                    database_url = %config.base().database_url,
-                    "Failed to initialize message storage"
-                );
-                anyhow::anyhow!("Failed to initialize message storage: {}", e)
-            })?,
-    );
+    let repo = MessageRepository::new(config.base().database_url.as_str())
+        .await
+        .map_err(|e| {
+            error!(
+                error = %e,
+                database_url = %config.base().database_url,
+                "Failed to initialize message storage"
+            );
+            anyhow::anyhow!("Failed to initialize message storage: {}", e)
+        })?;
+
+    let cipher = config
+        .extensions()
+        .encryption_config()
+        .and_then(|c| c.cipher());
+    let (repo, memory_store, recent_store) = match cipher.clone() {
+        Some(cipher) => {
+            info!(active_key_id = %cipher.active_key_id(), "Encryption at rest enabled");
+            let (memory_store, recent_store) =
+                encrypt_memory_stores(memory_store, recent_store, cipher.clone());
+            (repo.with_cipher(cipher), memory_store, recent_store)
+        }
+        None => (repo, memory_store, recent_store),
+    };
+    let repo = Arc::new(repo);
+
+    let mem_cfg = config
+        .extensions()
+        .memory_config()
+        .ok_or_else(|| anyhow::anyhow!("Memory config required"))?;
+    let (memory_store, recent_store) = if mem_cfg.cache_capacity() > 0 {
+        info!(
+            capacity = mem_cfg.cache_capacity(),
+            window = mem_cfg.cache_window(),
+            "Memory read cache enabled"
+        );
+        cache_memory_stores(
+            memory_store,
+            recent_store,
+            mem_cfg.cache_capacity(),
+            mem_cfg.cache_window(),
+        )
+    } else {
+        (memory_store, recent_store)
+    };
+    let memory_events = MemoryEvents::default();
+    let (memory_store, recent_store, recent_replication) =
+        observe_memory_stores(memory_store, recent_store, &memory_events);
+
+    let usage_repo = UsageRepository::new(config.base().database_url.as_str())
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to initialize usage ledger");
+            anyhow::anyhow!("Failed to initialize usage ledger: {}", e)
+        })?;
+
+    let outbox = OutboxRepository::new(config.base().database_url.as_str())
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to initialize write outbox");
+            anyhow::anyhow!("Failed to initialize write outbox: {}", e)
+        })?;
+    let outbox = match cipher {
+        Some(ref cipher) => outbox.with_cipher(cipher.clone()),
+        None => outbox,
+    };
+
+    let profile_repo = ProfileRepository::new(config.base().database_url.as_str())
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to initialize user profiles");
+            anyhow::anyhow!("Failed to initialize user profiles: {}", e)
+        })?;
+    let profile_repo = match cipher {
+        Some(ref cipher) => profile_repo.with_cipher(cipher.clone()),
+        None => profile_repo,
+    };
+    let profile_updater = config
+        .extensions()
+        .profile_config()
+        .filter(|c| c.extraction_enabled())
+        .map(|c| Arc::new(ProfileUpdater::from_config(profile_repo.clone(), c)));
 
     let teloxide_bot = {
         let bot = Bot::new(config.base().bot_token.clone());
@@ -131,26 +253,48 @@ pub async fn build_bot_components(
     let bot_username = Arc::new(tokio::sync::RwLock::new(None));
     let bot_user = Arc::new(tokio::sync::RwLock::new(None));
 
-    let embedding_service: Arc<dyn crate::embedding::EmbeddingService> = match emb_cfg.provider() {
-        "zhipuai" => {
-            if emb_cfg.bigmodel_api_key().is_empty() {
-                error!("EMBEDDING_PROVIDER=zhipuai but BIGMODEL_API_KEY / ZHIPUAI_API_KEY not set");
-                return Err(anyhow::anyhow!(
-                    "BIGMODEL_API_KEY or ZHIPUAI_API_KEY required when EMBEDDING_PROVIDER=zhipuai"
-                ));
-            }
-            info!("Using BigModel (Zhipu AI) embedding");
-            Arc::new(BigModelEmbedding::with_api_key(
-                emb_cfg.bigmodel_api_key().to_string(),
-            ))
-        }
-        _ => {
-            info!("Using OpenAI embedding");
-            Arc::new(OpenAIEmbedding::with_api_key_and_base_url(
-                emb_cfg.openai_api_key().to_string(),
-                emb_cfg.openai_base_url(),
-            ))
+    let embedding_service = create_embedding_service(
+        emb_cfg,
+        config.extensions().tokenizer_config(),
+        usage_repo.clone(),
+    )
+    .inspect_err(|e| {
+        error!(error = %e, "Failed to build embedding service");
+    })?;
+    bind_embedding_spec(emb_cfg, &memory_store, recent_store.as_ref()).await?;
+
+    let memory_write_pipeline = Arc::new(
+        MemoryWritePipeline::default()
+            .with_min_importance(mem_cfg.min_importance())
+            .with_dedup(mem_cfg.dedup_threshold(), DEFAULT_DEDUP_WINDOW),
+    );
+
+    let memory_compactor = match CompactionPolicy::from_config(mem_cfg) {
+        Some(policy) => {
+            let compactor = MemoryCompactor::new(
+                memory_store.clone(),
+                embedding_service.clone(),
+                policy,
+            );
+            let compactor = match mem_cfg.compaction_archive_path() {
+                Some(path) => {
+                    info!(archive_path = %path, "Compacted memory entries will be archived");
+                    let archive: Arc<dyn MemoryStore> =
+                        Arc::new(SQLiteVectorStore::new(path).await.map_err(|e| {
+                            error!(error = %e, "Failed to open memory archive store");
+                            anyhow::anyhow!("Failed to open memory archive store: {}", e)
+                        })?);
+                    let archive: Arc<dyn MemoryStore> = match cipher {
+                        Some(cipher) => Arc::new(EncryptedMemoryStore::new(archive, cipher)),
+                        None => archive,
+                    };
+                    compactor.with_archive_store(archive)
+                }
+                None => compactor,
+            };
+            Some(Arc::new(compactor))
         }
+        None => None,
     };
 
     Ok(BotComponents {
@@ -161,23 +305,157 @@ pub async fn build_bot_components(
         bot_user,
         memory_store,
         recent_store,
+        memory_events,
+        recent_replication,
         embedding_service,
+        usage_repo: Arc::new(usage_repo),
+        outbox,
+        memory_write_pipeline,
+        memory_compactor,
+        memory_ttl_policy: mem_cfg.ttl_policy(),
+        profile_repo,
+        profile_updater,
     })
 }
 
+/// Records the configured embedding model/dimension in the memory stores, failing when they hold
+/// vectors from another model (run `reembed` to migrate). Unknown dimensions are only logged.
+async fn bind_embedding_spec(
+    emb_cfg: &dyn EmbeddingConfig,
+    memory_store: &Arc<dyn MemoryStore>,
+    recent_store: Option<&Arc<dyn MemoryStore>>,
+) -> Result<()> {
+    let Some(spec) = configured_embedding_spec(emb_cfg) else {
+        warn!(
+            provider = %emb_cfg.provider(),
+            "Embedding dimension unknown for the configured model; set EMBEDDING_DIM to check stored vectors"
+        );
+        return Ok(());
+    };
+    memory_store.bind_embedding_spec(&spec).await.inspect_err(|e| {
+        error!(error = %e, "Memory store does not match the embedding model");
+    })?;
+    if let Some(recent) = recent_store {
+        if !std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            recent.bind_embedding_spec(&spec).await.inspect_err(|e| {
+                error!(error = %e, "Recent store does not match the embedding model");
+            })?;
+        }
+    }
+    info!(embedding = %spec, "Embedding spec bound to memory stores");
+    Ok(())
+}
+
+/// Wraps the memory stores in [`EncryptedMemoryStore`]. When the recent store is the primary store
+/// (same instance), the same wrapper is reused so MemoryHandler still recognizes it and writes once.
+fn encrypt_memory_stores(
+    memory_store: Arc<dyn MemoryStore>,
+    recent_store: Option<Arc<dyn MemoryStore>>,
+    cipher: Arc<FieldCipher>,
+) -> (Arc<dyn MemoryStore>, Option<Arc<dyn MemoryStore>>) {
+    let encrypted: Arc<dyn MemoryStore> =
+        Arc::new(EncryptedMemoryStore::new(memory_store.clone(), cipher.clone()));
+    let recent_store = recent_store.map(|recent| {
+        if std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            encrypted.clone()
+        } else {
+            Arc::new(EncryptedMemoryStore::new(recent, cipher.clone())) as Arc<dyn MemoryStore>
+        }
+    });
+    (encrypted, recent_store)
+}
+
+/// Wraps the memory stores in [`CachedMemoryStore`] (decrypted entries are cached, so reads skip the
+/// cipher too). A recent store that is the primary store shares its wrapper, as in [`encrypt_memory_stores`].
+fn cache_memory_stores(
+    memory_store: Arc<dyn MemoryStore>,
+    recent_store: Option<Arc<dyn MemoryStore>>,
+    capacity: usize,
+    window: usize,
+) -> (Arc<dyn MemoryStore>, Option<Arc<dyn MemoryStore>>) {
+    let cache = |store: Arc<dyn MemoryStore>| -> Arc<dyn MemoryStore> {
+        Arc::new(CachedMemoryStore::new(store, capacity).with_window(window))
+    };
+    let cached = cache(memory_store.clone());
+    let recent_store = recent_store.map(|recent| {
+        if std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            cached.clone()
+        } else {
+            cache(recent)
+        }
+    });
+    (cached, recent_store)
+}
+
+/// Wraps the primary store in [`ObservableMemoryStore`] publishing on `events`. A separate recent store
+/// subscribes and mirrors every write (messages, merges, compaction summaries, outbox retries); the
+/// returned [`Replication`] lets the [`MemoryHandler`] wait for it. A recent store that is the primary
+/// store shares its wrapper.
+fn observe_memory_stores(
+    memory_store: Arc<dyn MemoryStore>,
+    recent_store: Option<Arc<dyn MemoryStore>>,
+    events: &MemoryEvents,
+) -> (
+    Arc<dyn MemoryStore>,
+    Option<Arc<dyn MemoryStore>>,
+    Option<Arc<Replication>>,
+) {
+    let observed: Arc<dyn MemoryStore> =
+        Arc::new(ObservableMemoryStore::new(memory_store.clone(), events.clone()));
+    let mut replication = None;
+    let recent_store = recent_store.map(|recent| {
+        if std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            observed.clone()
+        } else {
+            replication = Some(Arc::new(spawn_replication(events, recent.clone())));
+            recent
+        }
+    });
+    (observed, recent_store, replication)
+}
+
 /// Builds the handler chain (persistence → memory → LLM handler). LLM handler is injected from outside.
 pub fn build_handler_chain(
     components: &BotComponents,
     handler: Arc<dyn Handler>,
 ) -> HandlerChain {
     let persistence = Arc::new(PersistenceHandler::new(components.repo.as_ref().clone()));
-    let memory = Arc::new(MemoryHandler::with_store_and_embedding(
+    let memory = MemoryHandler::with_store_and_embedding(
         components.memory_store.clone(),
         components.embedding_service.clone(),
-        components.recent_store.clone(),
-    ));
+    )
+    .with_write_pipeline(components.memory_write_pipeline.clone())
+    .with_ttl_policy(components.memory_ttl_policy.clone())
+    .with_outbox(components.outbox.clone());
+    let memory = match components.recent_replication {
+        Some(ref replication) => memory.with_recent_replication(replication.clone()),
+        None => memory,
+    };
+    let memory = match components.profile_updater {
+        Some(ref updater) => memory.with_profile_updater(updater.clone()),
+        None => memory,
+    };
     HandlerChain::new()
         .add_handler(persistence)
-        .add_handler(memory)
+        .add_handler(Arc::new(memory))
         .add_handler(handler)
 }
+
+/// Builds a [`DataEraser`] over the message repository, the user profiles, the usage ledger, the primary memory store
+/// (labelled with `primary_label`, e.g. the store type "lance" or "sqlite"), the optional recent
+/// store and the compaction archive, if any.
+pub fn build_data_eraser(components: &BotComponents, primary_label: &str) -> DataEraser {
+    let eraser = DataEraser::new(components.repo.as_ref().clone())
+        .with_profile_repo(components.profile_repo.clone())
+        .with_outbox(components.outbox.clone())
+        .with_usage_repo(components.usage_repo.as_ref().clone())
+        .with_store(primary_label, components.memory_store.clone());
+    let eraser = match components.recent_store {
+        Some(ref recent) => eraser.with_store("recent", recent.clone()),
+        None => eraser,
+    };
+    match components.memory_compactor.as_ref().and_then(|c| c.archive_store()) {
+        Some(archive) => eraser.with_store("archive", archive.clone()),
+        None => eraser,
+    }
+}
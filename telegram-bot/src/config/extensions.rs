@@ -1,25 +1,55 @@
-//! App extensions trait and default implementation (memory, embedding, optional LLM system prompt).
+//! App extensions trait and default implementation (memory, embedding, retention, encryption, outbox, tokenizer, profile, optional LLM system prompt).
 //! LLM config (model, API, etc.) is implemented externally in llm-client.
 
 use anyhow::Result;
 use std::env;
 use crate::embedding::{EmbeddingConfig, EnvEmbeddingConfig};
+use crate::encryption::{EncryptionConfig, EnvEncryptionConfig};
 use crate::memory::{EnvMemoryConfig, MemoryConfig};
+use crate::outbox::{EnvOutboxConfig, OutboxConfig};
+use crate::profile::{EnvProfileConfig, ProfileConfig};
+use crate::retention::{EnvRetentionConfig, RetentionConfig};
+use crate::tokenizer::{EnvTokenizerConfig, TokenizerConfig};
 
 /// Application extension config. Implement this trait to inject custom config.
 pub trait AppExtensions: Send + Sync {
     fn memory_config(&self) -> Option<&dyn MemoryConfig>;
     fn embedding_config(&self) -> Option<&dyn EmbeddingConfig>;
+    /// Retention config (RETENTION_DAYS etc.). Default impl returns None (no retention job).
+    fn retention_config(&self) -> Option<&dyn RetentionConfig> {
+        None
+    }
+    /// Encryption-at-rest config (ENCRYPTION_KEY etc.). Default impl returns None (plaintext storage).
+    fn encryption_config(&self) -> Option<&dyn EncryptionConfig> {
+        None
+    }
+    /// Outbox retry config (OUTBOX_MAX_ATTEMPTS etc.). Default impl returns None (default retry policy).
+    fn outbox_config(&self) -> Option<&dyn OutboxConfig> {
+        None
+    }
+    /// Tokenizer config (TOKENIZER_VOCAB_DIR etc.). Default impl returns None (estimated token counts).
+    fn tokenizer_config(&self) -> Option<&dyn TokenizerConfig> {
+        None
+    }
+    /// User profile config (PROFILE_EXTRACTION etc.). Default impl returns None (no fact extraction).
+    fn profile_config(&self) -> Option<&dyn ProfileConfig> {
+        None
+    }
     /// LLM system prompt (LLM_SYSTEM_PROMPT or SYSTEM_PROMPT). Default impl returns None.
     fn llm_system_prompt(&self) -> Option<&str> {
         None
     }
 }
 
-/// Base extensions: memory + embedding + optional LLM system prompt. Used by telegram-bot framework.
+/// Base extensions: memory + embedding + retention + encryption + outbox + tokenizer + profile + optional LLM system prompt. Used by telegram-bot framework.
 pub struct BaseAppExtensions {
     pub memory: EnvMemoryConfig,
     pub embedding: EnvEmbeddingConfig,
+    pub retention: EnvRetentionConfig,
+    pub encryption: EnvEncryptionConfig,
+    pub outbox: EnvOutboxConfig,
+    pub tokenizer: EnvTokenizerConfig,
+    pub profile: EnvProfileConfig,
     pub llm_system_prompt: Option<String>,
 }
 
@@ -30,17 +60,37 @@ impl AppExtensions for BaseAppExtensions {
     fn embedding_config(&self) -> Option<&dyn EmbeddingConfig> {
         Some(&self.embedding)
     }
+    fn retention_config(&self) -> Option<&dyn RetentionConfig> {
+        Some(&self.retention)
+    }
+    fn encryption_config(&self) -> Option<&dyn EncryptionConfig> {
+        Some(&self.encryption)
+    }
+    fn outbox_config(&self) -> Option<&dyn OutboxConfig> {
+        Some(&self.outbox)
+    }
+    fn tokenizer_config(&self) -> Option<&dyn TokenizerConfig> {
+        Some(&self.tokenizer)
+    }
+    fn profile_config(&self) -> Option<&dyn ProfileConfig> {
+        Some(&self.profile)
+    }
     fn llm_system_prompt(&self) -> Option<&str> {
         self.llm_system_prompt.as_deref()
     }
 }
 
 impl BaseAppExtensions {
-    /// Load from environment variables (memory + embedding + optional LLM system prompt).
+    /// Load from environment variables (memory + embedding + retention + encryption + outbox + tokenizer + profile + optional LLM system prompt).
     pub fn from_env() -> Result<Self> {
         let memory = EnvMemoryConfig::from_env()?;
         let embedding = EnvEmbeddingConfig::from_env()?;
         embedding.validate()?;
+        let retention = EnvRetentionConfig::from_env()?;
+        let encryption = EnvEncryptionConfig::from_env()?;
+        let outbox = EnvOutboxConfig::from_env()?;
+        let tokenizer = EnvTokenizerConfig::from_env()?;
+        let profile = EnvProfileConfig::from_env()?;
         let llm_system_prompt = env::var("LLM_SYSTEM_PROMPT")
             .or_else(|_| env::var("SYSTEM_PROMPT"))
             .ok()
@@ -48,6 +98,11 @@ impl BaseAppExtensions {
         Ok(Self {
             memory,
             embedding,
+            retention,
+            encryption,
+            outbox,
+            tokenizer,
+            profile,
             llm_system_prompt,
         })
     }
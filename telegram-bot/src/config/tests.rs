@@ -2,6 +2,7 @@
 
 use crate::config::bot_config::BotConfig;
 use crate::config::{AppExtensions, BaseAppExtensions};
+use crate::memory::MemoryRole;
 use serial_test::serial;
 use std::env;
 
@@ -26,6 +27,26 @@ fn test_load_config_with_defaults() {
     env::remove_var("MEMORY_RELEVANT_TOP_K");
     env::remove_var("MEMORY_RECENT_USE_SQLITE");
     env::remove_var("MEMORY_SEMANTIC_MIN_SCORE");
+    env::remove_var("MEMORY_SEMANTIC_DECAY_HALF_LIFE");
+    env::remove_var("MEMORY_SEMANTIC_MMR_LAMBDA");
+    env::remove_var("MEMORY_HYBRID_SEARCH");
+    env::remove_var("MEMORY_HYBRID_VECTOR_WEIGHT");
+    env::remove_var("MEMORY_HYBRID_KEYWORD_WEIGHT");
+    env::remove_var("MEMORY_IMPORTANCE_SCORER");
+    env::remove_var("MEMORY_MIN_IMPORTANCE");
+    env::remove_var("MEMORY_DEDUP_THRESHOLD");
+    env::remove_var("MEMORY_IMPORTANCE_WEIGHT");
+    env::remove_var("MEMORY_COMPACTION_AFTER_DAYS");
+    env::remove_var("MEMORY_COMPACTION_WINDOW_HOURS");
+    env::remove_var("MEMORY_COMPACTION_ARCHIVE_PATH");
+    env::remove_var("MEMORY_SUMMARY_LIMIT");
+    env::remove_var("MEMORY_CACHE_CAPACITY");
+    env::remove_var("MEMORY_CACHE_WINDOW");
+    env::remove_var("MEMORY_TTL_USER_HOURS");
+    env::remove_var("MEMORY_TTL_ASSISTANT_HOURS");
+    env::remove_var("MEMORY_TTL_SYSTEM_HOURS");
+    env::remove_var("MEMORY_TTL_SOURCES");
+    env::remove_var("MEMORY_TTL_SWEEP_INTERVAL_SECS");
     env::remove_var("TELEGRAM_EDIT_INTERVAL_SECS");
 
     let config = BotConfig::load(None).unwrap();
@@ -44,6 +65,22 @@ fn test_load_config_with_defaults() {
     assert_eq!(mem.relevant_top_k(), 5);
     assert_eq!(mem.recent_use_sqlite(), false);
     assert_eq!(mem.semantic_min_score(), 0.0);
+    assert!(mem.semantic_decay_half_life_hours().is_none());
+    assert!(mem.semantic_mmr_lambda().is_none());
+    assert!(!mem.hybrid_search());
+    assert_eq!(mem.hybrid_weights(), crate::memory::HybridWeights::default());
+    assert_eq!(mem.importance_scorer(), "heuristic");
+    assert_eq!(mem.min_importance(), 0.0);
+    assert!(mem.dedup_threshold().is_none());
+    assert_eq!(mem.importance_weight(), 0.0);
+    assert!(mem.compaction_after_days().is_none());
+    assert_eq!(mem.compaction_window_hours(), 24);
+    assert!(mem.compaction_archive_path().is_none());
+    assert_eq!(mem.summary_limit(), 3);
+    assert_eq!(mem.cache_capacity(), 0);
+    assert_eq!(mem.cache_window(), 500);
+    assert!(!mem.ttl_policy().is_enabled());
+    assert_eq!(mem.ttl_sweep_interval_secs(), 3600);
     assert_eq!(config.telegram_edit_interval_secs(), 5);
 }
 
@@ -71,6 +108,10 @@ fn test_load_config_with_custom_values() {
     env::remove_var("MEMORY_RELEVANT_TOP_K");
     env::remove_var("MEMORY_RECENT_USE_SQLITE");
     env::remove_var("MEMORY_SEMANTIC_MIN_SCORE");
+    env::set_var("MEMORY_SEMANTIC_DECAY_HALF_LIFE", "168");
+    env::set_var("MEMORY_SEMANTIC_MMR_LAMBDA", "0.7");
+    env::set_var("MEMORY_TTL_ASSISTANT_HOURS", "48");
+    env::set_var("MEMORY_TTL_SOURCES", "group:12,supergroup:12");
     env::set_var("TELEGRAM_EDIT_INTERVAL_SECS", "10");
 
     let config = BotConfig::load(None).unwrap();
@@ -82,7 +123,23 @@ fn test_load_config_with_custom_values() {
     assert_eq!(mem.store_type(), "sqlite");
     assert_eq!(mem.sqlite_path(), "/tmp/memory.db");
     assert_eq!(config.extensions().embedding_config().unwrap().provider(), "openai");
-
+    assert_eq!(mem.semantic_decay_half_life_hours(), Some(168));
+    assert_eq!(mem.semantic_mmr_lambda(), Some(0.7));
+    let ttl = mem.ttl_policy();
+    assert_eq!(
+        ttl.ttl_for(MemoryRole::Assistant, Some("private")),
+        Some(chrono::Duration::hours(48))
+    );
+    assert_eq!(
+        ttl.ttl_for(MemoryRole::User, Some("supergroup")),
+        Some(chrono::Duration::hours(12))
+    );
+    assert_eq!(ttl.ttl_for(MemoryRole::User, Some("private")), None);
+
+    env::remove_var("MEMORY_SEMANTIC_DECAY_HALF_LIFE");
+    env::remove_var("MEMORY_SEMANTIC_MMR_LAMBDA");
+    env::remove_var("MEMORY_TTL_ASSISTANT_HOURS");
+    env::remove_var("MEMORY_TTL_SOURCES");
     env::remove_var("TELEGRAM_EDIT_INTERVAL_SECS");
 }
 
@@ -1,5 +1,112 @@
-//! Text embeddings: re-exports from embedding crates.
+//! Text embeddings: re-exports from embedding crates, plus the usage-recording decorator.
+
+mod usage;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use tracing::info;
 
 pub use bigmodel_embedding::BigModelEmbedding;
 pub use embedding::{EmbeddingConfig, EmbeddingService, EnvEmbeddingConfig};
 pub use openai_embedding::OpenAIEmbedding;
+pub use usage::{with_usage_scope, UsageScope, UsageTrackingEmbedding};
+
+use crate::memory_core::EmbeddingSpec;
+use crate::storage::UsageRepository;
+use crate::tokenizer::{load_tokenizer, TokenizerConfig};
+
+/// Default model of each provider (what the embedding crates use when EMBEDDING_MODEL is unset).
+pub fn default_model(provider: &str) -> &'static str {
+    match provider {
+        "zhipuai" => "embedding-2",
+        _ => "text-embedding-3-small",
+    }
+}
+
+/// Vector dimension of well-known embedding models; None for anything else (set EMBEDDING_DIM).
+pub fn known_dimension(model: &str) -> Option<usize> {
+    match model {
+        "text-embedding-3-small" | "text-embedding-ada-002" => Some(1536),
+        "text-embedding-3-large" => Some(3072),
+        "embedding-2" => Some(1024),
+        "embedding-3" => Some(2048),
+        _ => None,
+    }
+}
+
+/// Model and dimension the configured embedding service produces. None when the dimension is
+/// neither configured nor known from the model name.
+pub fn configured_embedding_spec(cfg: &dyn EmbeddingConfig) -> Option<EmbeddingSpec> {
+    let model = cfg.model().unwrap_or_else(|| default_model(cfg.provider()));
+    let dimension = cfg.dimension().or_else(|| known_dimension(model))?;
+    Some(EmbeddingSpec::new(model, dimension))
+}
+
+/// Builds the embedding service for the configured provider and model, recording usage in
+/// `usage_repo` with token counts from the model's tokenizer (see [`load_tokenizer`]).
+pub fn create_embedding_service(
+    cfg: &dyn EmbeddingConfig,
+    tokenizer_cfg: Option<&dyn TokenizerConfig>,
+    usage_repo: UsageRepository,
+) -> Result<Arc<dyn EmbeddingService>> {
+    let (inner, model): (Arc<dyn EmbeddingService>, String) = match cfg.provider() {
+        "zhipuai" => {
+            if cfg.bigmodel_api_key().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "BIGMODEL_API_KEY or ZHIPUAI_API_KEY required when EMBEDDING_PROVIDER=zhipuai"
+                ));
+            }
+            let mut embedding = BigModelEmbedding::with_api_key(cfg.bigmodel_api_key().to_string());
+            if let Some(model) = cfg.model() {
+                embedding = embedding.with_model(model.to_string());
+            }
+            info!(model = %embedding.model(), "Using BigModel (Zhipu AI) embedding");
+            let model = embedding.model().to_string();
+            (Arc::new(embedding), model)
+        }
+        _ => {
+            let mut embedding = OpenAIEmbedding::with_api_key_and_base_url(
+                cfg.openai_api_key().to_string(),
+                cfg.openai_base_url(),
+            );
+            if let Some(model) = cfg.model() {
+                embedding = embedding.with_model(model.to_string());
+            }
+            info!(model = %embedding.model(), "Using OpenAI embedding");
+            let model = embedding.model().to_string();
+            (Arc::new(embedding), model)
+        }
+    };
+    let tokenizer = load_tokenizer(tokenizer_cfg, &model);
+    let tracked = UsageTrackingEmbedding::new(inner, model, usage_repo).with_tokenizer(tokenizer);
+    Ok(Arc::new(tracked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_embedding_spec() {
+        let mut cfg = EnvEmbeddingConfig {
+            embedding_provider: "zhipuai".to_string(),
+            bigmodel_api_key: String::new(),
+            openai_api_key: String::new(),
+            openai_base_url: None,
+            embedding_model: None,
+            embedding_dim: None,
+        };
+        assert_eq!(
+            configured_embedding_spec(&cfg),
+            Some(EmbeddingSpec::new("embedding-2", 1024))
+        );
+        cfg.embedding_model = Some("custom-model".to_string());
+        assert_eq!(configured_embedding_spec(&cfg), None);
+        cfg.embedding_dim = Some(768);
+        assert_eq!(
+            configured_embedding_spec(&cfg),
+            Some(EmbeddingSpec::new("custom-model", 768))
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Embedding decorator that records estimated token usage in the `llm_usage` ledger.
+
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+
+use super::EmbeddingService;
+use crate::storage::{UsageRecord, UsageRepository, USAGE_KIND_EMBEDDING};
+use crate::tokenizer::{EstimateTokenizer, Tokenizer};
+
+/// Handler name recorded for embedding calls.
+const EMBEDDING_HANDLER: &str = "embedding";
+
+/// Chat and user that embedding calls made within [`with_usage_scope`] are attributed to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageScope {
+    /// Chat the calls are made for.
+    pub chat_id: Option<i64>,
+    /// User whose message triggered the calls.
+    pub user_id: Option<i64>,
+}
+
+impl UsageScope {
+    /// Creates a scope for the given chat and user.
+    pub fn new(chat_id: Option<i64>, user_id: Option<i64>) -> Self {
+        Self { chat_id, user_id }
+    }
+}
+
+tokio::task_local! {
+    static USAGE_SCOPE: UsageScope;
+}
+
+/// Runs `fut` with embedding usage attributed to `scope`. The scope does not follow tasks spawned
+/// from `fut`.
+pub async fn with_usage_scope<F: Future>(scope: UsageScope, fut: F) -> F::Output {
+    USAGE_SCOPE.scope(scope, fut).await
+}
+
+/// Wraps an [`EmbeddingService`] and records one usage row per call, attributed to the chat/user
+/// of the enclosing [`with_usage_scope`] (none outside one). Token counts are computed from the
+/// input text with the model's tokenizer (estimated without one). Recording failures are logged and
+/// never fail the embedding call.
+pub struct UsageTrackingEmbedding {
+    inner: Arc<dyn EmbeddingService>,
+    model: String,
+    usage_repo: UsageRepository,
+    tokenizer: Arc<dyn Tokenizer>,
+}
+
+impl UsageTrackingEmbedding {
+    pub fn new(
+        inner: Arc<dyn EmbeddingService>,
+        model: impl Into<String>,
+        usage_repo: UsageRepository,
+    ) -> Self {
+        Self {
+            inner,
+            model: model.into(),
+            usage_repo,
+            tokenizer: Arc::new(EstimateTokenizer),
+        }
+    }
+
+    /// Counts input tokens with `tokenizer` instead of estimating them.
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    async fn record(&self, prompt_tokens: usize) {
+        let scope = USAGE_SCOPE.try_with(|s| *s).unwrap_or_default();
+        let record = UsageRecord::new(
+            self.model.clone(),
+            EMBEDDING_HANDLER,
+            USAGE_KIND_EMBEDDING,
+            prompt_tokens as i64,
+            0,
+        )
+        .for_message(scope.chat_id, scope.user_id)
+        .estimated();
+        if let Err(e) = self.usage_repo.record(&record).await {
+            warn!(error = %e, model = %self.model, "Failed to record embedding usage");
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingService for UsageTrackingEmbedding {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, anyhow::Error> {
+        let embedding = self.inner.embed(text).await?;
+        self.record(self.tokenizer.count_tokens(text)).await;
+        Ok(embedding)
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, anyhow::Error> {
+        let embeddings = self.inner.embed_batch(texts).await?;
+        self.record(texts.iter().map(|t| self.tokenizer.count_tokens(t)).sum())
+            .await;
+        Ok(embeddings)
+    }
+}
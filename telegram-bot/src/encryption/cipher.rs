@@ -0,0 +1,214 @@
+//! Field-level AEAD cipher (XChaCha20-Poly1305) with key ids for rotation.
+//!
+//! Encrypted values are self-describing strings: `enc:v1:<key_id>:<base64(nonce || ciphertext)>`.
+//! The key id is authenticated as associated data, so a value cannot be moved to another key.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Prefix of every encrypted value.
+const ENVELOPE_PREFIX: &str = "enc:v1:";
+/// XChaCha20 nonce length in bytes (random nonces are safe at this size).
+const NONCE_LEN: usize = 24;
+/// Key length in bytes.
+pub const KEY_LEN: usize = 32;
+
+/// Errors from encrypting or decrypting a field.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EncryptionError {
+    #[error("Invalid key id {0:?}: must be non-empty and must not contain ':'")]
+    InvalidKeyId(String),
+    #[error("Unknown encryption key id: {0}")]
+    UnknownKey(String),
+    #[error("Malformed encrypted value: {0}")]
+    Malformed(String),
+    #[error("Encryption failed")]
+    Encrypt,
+    #[error("Decryption failed for key id {0} (wrong key or tampered data)")]
+    Decrypt(String),
+}
+
+/// Encrypts and decrypts text fields with the active key; older keys stay available for decryption.
+#[derive(Clone)]
+pub struct FieldCipher {
+    active_key_id: String,
+    keys: HashMap<String, XChaCha20Poly1305>,
+}
+
+impl fmt::Debug for FieldCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut key_ids: Vec<&String> = self.keys.keys().collect();
+        key_ids.sort();
+        f.debug_struct("FieldCipher")
+            .field("active_key_id", &self.active_key_id)
+            .field("key_ids", &key_ids)
+            .finish()
+    }
+}
+
+impl FieldCipher {
+    /// Creates a cipher whose active (encrypting) key is `key` under `key_id`.
+    pub fn new(key_id: impl Into<String>, key: &[u8; KEY_LEN]) -> Result<Self, EncryptionError> {
+        let key_id = key_id.into();
+        validate_key_id(&key_id)?;
+        let mut keys = HashMap::new();
+        keys.insert(key_id.clone(), XChaCha20Poly1305::new(key.into()));
+        Ok(Self {
+            active_key_id: key_id,
+            keys,
+        })
+    }
+
+    /// Adds a decrypt-only key (e.g. a retired key whose rows have not been re-encrypted yet).
+    /// Does not replace the active key.
+    pub fn with_key(mut self, key_id: impl Into<String>, key: &[u8; KEY_LEN]) -> Result<Self, EncryptionError> {
+        let key_id = key_id.into();
+        validate_key_id(&key_id)?;
+        if key_id != self.active_key_id {
+            self.keys.insert(key_id, XChaCha20Poly1305::new(key.into()));
+        }
+        Ok(self)
+    }
+
+    /// Id of the key used for new encryptions.
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    /// Encrypts `plaintext` with the active key and returns the envelope string.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, EncryptionError> {
+        let cipher = &self.keys[&self.active_key_id];
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let aad = associated_data(&self.active_key_id);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::Encrypt)?;
+        let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}:{}", aad, BASE64.encode(sealed)))
+    }
+
+    /// Decrypts an envelope string. Values without the envelope prefix are plaintext written before
+    /// encryption was enabled and are returned unchanged.
+    pub fn decrypt(&self, value: &str) -> Result<String, EncryptionError> {
+        let Some(key_id) = Self::key_id_of(value) else {
+            return Ok(value.to_string());
+        };
+        let cipher = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.to_string()))?;
+        let encoded = &value[ENVELOPE_PREFIX.len() + key_id.len() + 1..];
+        let sealed = BASE64
+            .decode(encoded)
+            .map_err(|e| EncryptionError::Malformed(e.to_string()))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(EncryptionError::Malformed("value shorter than nonce".to_string()));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let aad = associated_data(key_id);
+        let plaintext = cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::Decrypt(key_id.to_string()))?;
+        String::from_utf8(plaintext).map_err(|e| EncryptionError::Malformed(e.to_string()))
+    }
+
+    /// Returns the key id of an encrypted value, or `None` for plaintext.
+    pub fn key_id_of(value: &str) -> Option<&str> {
+        let rest = value.strip_prefix(ENVELOPE_PREFIX)?;
+        let (key_id, _) = rest.split_once(':')?;
+        Some(key_id)
+    }
+
+    /// True when `value` is already encrypted with the active key (no re-encryption needed).
+    pub fn is_current(&self, value: &str) -> bool {
+        Self::key_id_of(value) == Some(self.active_key_id.as_str())
+    }
+
+    /// Decrypts `value` (any known key, or plaintext) and encrypts it again with the active key.
+    pub fn reencrypt(&self, value: &str) -> Result<String, EncryptionError> {
+        self.encrypt(&self.decrypt(value)?)
+    }
+}
+
+fn validate_key_id(key_id: &str) -> Result<(), EncryptionError> {
+    if key_id.is_empty() || key_id.contains(':') {
+        return Err(EncryptionError::InvalidKeyId(key_id.to_string()));
+    }
+    Ok(())
+}
+
+/// Envelope header (`enc:v1:<key_id>`), also used as AEAD associated data.
+fn associated_data(key_id: &str) -> String {
+    format!("{}{}", ENVELOPE_PREFIX, key_id)
+}
+
+/// Decodes a base64 key and checks its length.
+pub(crate) fn decode_key(encoded: &str) -> anyhow::Result<[u8; KEY_LEN]> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| anyhow::anyhow!("encryption key is not valid base64: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|b: Vec<u8>| anyhow::anyhow!("encryption key must be {} bytes, got {}", KEY_LEN, b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_rotation() {
+        let old = FieldCipher::new("k1", &[1u8; KEY_LEN]).unwrap();
+        let sealed = old.encrypt("hello").unwrap();
+        assert!(sealed.starts_with("enc:v1:k1:"));
+        assert_ne!(sealed, old.encrypt("hello").unwrap(), "nonces must differ");
+        assert_eq!(old.decrypt(&sealed).unwrap(), "hello");
+        assert_eq!(old.decrypt("plain text").unwrap(), "plain text");
+
+        let new = FieldCipher::new("k2", &[2u8; KEY_LEN])
+            .unwrap()
+            .with_key("k1", &[1u8; KEY_LEN])
+            .unwrap();
+        assert!(!new.is_current(&sealed));
+        let rotated = new.reencrypt(&sealed).unwrap();
+        assert!(new.is_current(&rotated));
+        assert_eq!(new.decrypt(&rotated).unwrap(), "hello");
+        assert_eq!(
+            old.decrypt(&rotated),
+            Err(EncryptionError::UnknownKey("k2".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_tampered_value_is_rejected() {
+        let cipher = FieldCipher::new("k1", &[1u8; KEY_LEN]).unwrap();
+        let sealed = cipher.encrypt("hello").unwrap();
+        // Pretend the value was sealed under another key id: AAD mismatch.
+        let other = FieldCipher::new("k1", &[1u8; KEY_LEN])
+            .unwrap()
+            .with_key("k0", &[1u8; KEY_LEN])
+            .unwrap();
+        let moved = sealed.replacen("enc:v1:k1:", "enc:v1:k0:", 1);
+        assert_eq!(other.decrypt(&moved), Err(EncryptionError::Decrypt("k0".to_string())));
+        assert!(FieldCipher::new("a:b", &[0u8; KEY_LEN]).is_err());
+    }
+}
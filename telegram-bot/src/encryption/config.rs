@@ -0,0 +1,109 @@
+//! Encryption-at-rest configuration: trait and env-based implementation.
+
+use anyhow::{Context as _, Result};
+use std::env;
+use std::sync::Arc;
+
+use super::cipher::{decode_key, FieldCipher, KEY_LEN};
+
+/// Encryption-at-rest configuration interface.
+pub trait EncryptionConfig: Send + Sync {
+    /// Cipher for content columns; `None` keeps storing plaintext.
+    fn cipher(&self) -> Option<Arc<FieldCipher>>;
+}
+
+/// Encryption config loaded from environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct EnvEncryptionConfig {
+    pub cipher: Option<Arc<FieldCipher>>,
+}
+
+impl EncryptionConfig for EnvEncryptionConfig {
+    fn cipher(&self) -> Option<Arc<FieldCipher>> {
+        self.cipher.clone()
+    }
+}
+
+impl EnvEncryptionConfig {
+    /// Load from environment variables. Encryption is disabled when neither key variable is set.
+    ///
+    /// - `ENCRYPTION_KEY`: base64-encoded 32-byte key (the active key)
+    /// - `ENCRYPTION_KEY_ID`: id of the active key (default `k1`; with a key file, selects the active key)
+    /// - `ENCRYPTION_KEY_FILE`: file with `key_id=base64key` lines; keys other than the active one are
+    ///   used for decryption only. Without `ENCRYPTION_KEY`/`ENCRYPTION_KEY_ID`, the last key is active.
+    pub fn from_env() -> Result<Self> {
+        let key = env::var("ENCRYPTION_KEY").ok().filter(|s| !s.trim().is_empty());
+        let key_id = env::var("ENCRYPTION_KEY_ID").ok().filter(|s| !s.trim().is_empty());
+        let key_file = env::var("ENCRYPTION_KEY_FILE").ok().filter(|s| !s.trim().is_empty());
+
+        let mut keys: Vec<(String, [u8; KEY_LEN])> = match key_file {
+            Some(path) => {
+                let contents = std::fs::read_to_string(&path)
+                    .with_context(|| format!("ENCRYPTION_KEY_FILE: cannot read {}", path))?;
+                parse_key_file(&contents)?
+            }
+            None => Vec::new(),
+        };
+        let active_id = match key {
+            Some(encoded) => {
+                let id = key_id.unwrap_or_else(|| "k1".to_string());
+                let bytes = decode_key(&encoded).context("ENCRYPTION_KEY")?;
+                keys.retain(|(k, _)| *k != id);
+                keys.push((id.clone(), bytes));
+                id
+            }
+            None => match (key_id, keys.last()) {
+                (Some(id), _) => id,
+                (None, Some((id, _))) => id.clone(),
+                (None, None) => return Ok(Self::default()),
+            },
+        };
+
+        let (_, active_key) = keys
+            .iter()
+            .find(|(id, _)| *id == active_id)
+            .ok_or_else(|| anyhow::anyhow!("ENCRYPTION_KEY_ID={} not found in key file", active_id))?;
+        let mut cipher = FieldCipher::new(active_id.clone(), active_key)?;
+        for (id, bytes) in &keys {
+            cipher = cipher.with_key(id.clone(), bytes)?;
+        }
+        Ok(Self {
+            cipher: Some(Arc::new(cipher)),
+        })
+    }
+}
+
+/// Parses `key_id=base64key` lines; blank lines and `#` comments are ignored.
+pub(crate) fn parse_key_file(contents: &str) -> Result<Vec<(String, [u8; KEY_LEN])>> {
+    let mut keys = Vec::new();
+    for (n, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (id, encoded) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("ENCRYPTION_KEY_FILE line {}: expected key_id=base64key", n + 1))?;
+        let bytes = decode_key(encoded).with_context(|| format!("ENCRYPTION_KEY_FILE line {}", n + 1))?;
+        keys.push((id.trim().to_string(), bytes));
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine as _;
+
+    #[test]
+    fn test_parse_key_file() {
+        let k1 = base64::engine::general_purpose::STANDARD.encode([1u8; KEY_LEN]);
+        let contents = format!("# old key\nk1={}\n\nk2 = {}\n", k1, k1);
+        let keys = parse_key_file(&contents).unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].0, "k1");
+        assert_eq!(keys[1].0, "k2");
+        assert!(parse_key_file("k1").is_err());
+        assert!(parse_key_file("k1=c2hvcnQ=").is_err());
+    }
+}
@@ -0,0 +1,15 @@
+//! Encryption at rest for content columns (messages, memory entries).
+//!
+//! [`FieldCipher`] seals text with XChaCha20-Poly1305 under a key id stored in each value, so keys can be
+//! rotated: old keys stay in the keyring for decryption and [`reencrypt_all`] rewrites rows under the active key.
+//! Embeddings are not encrypted, so semantic search is unaffected.
+
+mod cipher;
+mod config;
+mod reencrypt;
+mod store;
+
+pub use cipher::{EncryptionError, FieldCipher, KEY_LEN};
+pub use config::{EncryptionConfig, EnvEncryptionConfig};
+pub use reencrypt::{reencrypt_all, ReencryptReport};
+pub use store::EncryptedMemoryStore;
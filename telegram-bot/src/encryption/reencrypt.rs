@@ -0,0 +1,79 @@
+//! Key rotation: re-encrypts messages, memory entries, user facts and outbox payloads under the
+//! active key.
+
+use anyhow::{Context as _, Result};
+use std::sync::Arc;
+use tracing::info;
+
+use super::cipher::FieldCipher;
+use super::store::EncryptedMemoryStore;
+use crate::memory_core::MemoryStore;
+use crate::outbox::OutboxRepository;
+use crate::profile::ProfileRepository;
+use crate::storage::MessageRepository;
+
+/// Rows rewritten by one [`reencrypt_all`] run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReencryptReport {
+    /// Key id all rewritten rows are now sealed with.
+    pub active_key_id: String,
+    /// Rows rewritten in the messages table.
+    pub messages: u64,
+    /// Entries rewritten per memory store (label, count), in the given order.
+    pub memory_entries: Vec<(String, u64)>,
+    /// Values rewritten in the user profiles.
+    pub profile_facts: u64,
+    /// Payloads rewritten in the write outbox.
+    pub outbox_items: u64,
+}
+
+/// Re-encrypts every message, memory entry, user fact and queued outbox payload that is plaintext
+/// or sealed with a non-active key.
+///
+/// `stores` are the raw (unwrapped) memory stores; each is paged over in full, so entries of chats
+/// missing from the messages table are rewritten too. Rows already sealed with the active key are
+/// skipped, so the command can be re-run after an interruption.
+pub async fn reencrypt_all(
+    repo: &MessageRepository,
+    profiles: &ProfileRepository,
+    outbox: &OutboxRepository,
+    stores: &[(String, Arc<dyn MemoryStore>)],
+    cipher: Arc<FieldCipher>,
+    batch_size: u32,
+) -> Result<ReencryptReport> {
+    info!(active_key_id = %cipher.active_key_id(), "Re-encrypting stored content");
+    let mut report = ReencryptReport {
+        active_key_id: cipher.active_key_id().to_string(),
+        ..Default::default()
+    };
+
+    for (name, store) in stores {
+        let rewritten = EncryptedMemoryStore::new(store.clone(), cipher.clone())
+            .reencrypt_entries(batch_size as usize)
+            .await
+            .with_context(|| format!("reencrypt: store {} failed", name))?;
+        info!(store = %name, rewritten = rewritten, "Memory store re-encrypted");
+        report.memory_entries.push((name.clone(), rewritten));
+    }
+
+    report.messages = repo
+        .clone()
+        .with_cipher(cipher.clone())
+        .reencrypt_messages(batch_size)
+        .await
+        .context("reencrypt: messages failed")?;
+    report.profile_facts = profiles
+        .clone()
+        .with_cipher(cipher.clone())
+        .reencrypt_values(batch_size)
+        .await
+        .context("reencrypt: user facts failed")?;
+    report.outbox_items = outbox
+        .clone()
+        .with_cipher(cipher)
+        .reencrypt_payloads()
+        .await
+        .context("reencrypt: outbox failed")?;
+    info!(report = ?report, "Re-encryption finished");
+    Ok(report)
+}
@@ -0,0 +1,189 @@
+//! [`EncryptedMemoryStore`]: encrypts `MemoryEntry.content` before it reaches the inner store.
+//!
+//! Embeddings are passed through unchanged so vector search (SQLite scan, Lance ANN) keeps working;
+//! only the text is sealed. The key id travels inside each encrypted value. Keyword search cannot
+//! use the inner store's full-text index (it holds ciphertext), so it keeps the trait default,
+//! which ranks decrypted entries in process. Empty content is stored as is, so
+//! [`MemoryFilter::non_empty`] still applies in the inner store.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::cipher::FieldCipher;
+use crate::memory_core::{
+    EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryOrder, MemoryPage, MemoryStore,
+    SearchFilter,
+};
+
+/// Wrapper around a [`MemoryStore`] that encrypts entry content on write and decrypts it on read.
+#[derive(Clone)]
+pub struct EncryptedMemoryStore {
+    inner: Arc<dyn MemoryStore>,
+    cipher: Arc<FieldCipher>,
+}
+
+impl EncryptedMemoryStore {
+    pub fn new(inner: Arc<dyn MemoryStore>, cipher: Arc<FieldCipher>) -> Self {
+        Self { inner, cipher }
+    }
+
+    /// Re-encrypts every entry of the inner store that is plaintext or sealed with a non-active key,
+    /// paging over the whole store `batch_size` entries at a time. Returns the number of entries
+    /// rewritten.
+    pub async fn reencrypt_entries(&self, batch_size: usize) -> Result<u64, anyhow::Error> {
+        let filter = MemoryFilter::default();
+        let mut rewritten = 0;
+        let mut cursor = None;
+        loop {
+            let page = self
+                .inner
+                .list(&filter, MemoryOrder::OldestFirst, batch_size, cursor.as_ref())
+                .await?;
+            for mut entry in page.entries {
+                if entry.content.is_empty() || self.cipher.is_current(&entry.content) {
+                    continue;
+                }
+                entry.content = self.cipher.reencrypt(&entry.content)?;
+                self.inner.update(entry).await?;
+                rewritten += 1;
+            }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(rewritten)
+    }
+
+    fn seal(&self, mut entry: MemoryEntry) -> Result<MemoryEntry, anyhow::Error> {
+        if !entry.content.is_empty() {
+            entry.content = self.cipher.encrypt(&entry.content)?;
+        }
+        Ok(entry)
+    }
+
+    fn open(&self, mut entry: MemoryEntry) -> Result<MemoryEntry, anyhow::Error> {
+        entry.content = self.cipher.decrypt(&entry.content)?;
+        Ok(entry)
+    }
+
+    fn open_all(&self, entries: Vec<MemoryEntry>) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        entries.into_iter().map(|e| self.open(e)).collect()
+    }
+}
+
+#[async_trait]
+impl MemoryStore for EncryptedMemoryStore {
+    async fn add(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        self.inner.add(self.seal(entry)?).await
+    }
+
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.inner.flush().await
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        self.inner.embedding_spec().await
+    }
+
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        self.inner.bind_embedding_spec(spec).await
+    }
+
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        self.inner.reset_embeddings(spec).await
+    }
+
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        let sealed = entries
+            .into_iter()
+            .map(|e| self.seal(e))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.inner.add_batch(sealed).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
+        self.inner.get(id).await?.map(|e| self.open(e)).transpose()
+    }
+
+    async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        self.inner.update(self.seal(entry)?).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), anyhow::Error> {
+        self.inner.delete(id).await
+    }
+
+    async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.open_all(self.inner.search_by_user(user_id).await?)
+    }
+
+    async fn search_by_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.open_all(self.inner.search_by_conversation(conversation_id).await?)
+    }
+
+    async fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        user_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner
+            .semantic_search(query_embedding, limit, user_id, conversation_id)
+            .await?
+            .into_iter()
+            .map(|(score, e)| Ok((score, self.open(e)?)))
+            .collect()
+    }
+
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner
+            .semantic_search_filtered(query_embedding, limit, filter)
+            .await?
+            .into_iter()
+            .map(|(score, e)| Ok((score, self.open(e)?)))
+            .collect()
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let page = self.inner.list(filter, order, limit, cursor).await?;
+        Ok(MemoryPage {
+            entries: self.open_all(page.entries)?,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        self.inner.count(filter).await
+    }
+
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        self.inner.delete_by_conversation(conversation_id).await
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        self.inner.delete_by_user(user_id).await
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        self.inner.delete_expired(now).await
+    }
+}
@@ -6,18 +6,25 @@ use crate::core::{Handler, HandlerResponse, Message, Result};
 use async_trait::async_trait;
 use chrono::Utc;
 use crate::embedding::EmbeddingService;
-use crate::memory::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
-use crate::memory::InMemoryVectorStore;
+use crate::memory::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore, MemoryWritePipeline, WriteOutcome};
+use crate::memory::{InMemoryVectorStore, Replication, TtlPolicy};
+use crate::outbox::{OutboxPayload, OutboxRepository};
+use crate::profile::ProfileUpdater;
 use std::sync::Arc;
-use tracing::{error, info, instrument};
+use std::time::Duration;
+use tracing::{error, info, instrument, warn};
+
+/// Longest a save waits for the recent store replication to apply it.
+const REPLICATION_ACK_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Configuration for MemoryHandler.
 #[derive(Clone)]
 pub struct MemoryConfig {
     /// Memory store instance (used by handler and by tests for assertions). Primary store for semantic search and general persistence.
     pub store: Arc<dyn MemoryStore>,
-    /// Optional store for recent messages only. When set, user messages and AI replies are also written here so RecentMessagesStrategy reads from it; semantic search still uses `store`.
-    pub recent_store: Option<Arc<dyn MemoryStore>>,
+    /// Replication of `store`'s change feed into a separate recent store. When set, each save waits until
+    /// the replica has applied it, so RecentMessagesStrategy reads the message just saved.
+    pub recent_replication: Option<Arc<Replication>>,
     /// Optional embedding service: when set, user messages and LLM replies are embedded before saving so they participate in semantic search.
     pub embedding_service: Option<Arc<dyn EmbeddingService>>,
     /// Maximum number of recent messages to include in context
@@ -28,18 +35,31 @@ pub struct MemoryConfig {
     pub save_user_messages: bool,
     /// Whether to save LLM responses
     pub save_llm_responses: bool,
+    /// Optional write pipeline for `store`: token count and importance, low-importance and near-duplicate
+    /// suppression. The recent store mirrors `store`, so it does not receive suppressed messages either.
+    pub write_pipeline: Option<Arc<MemoryWritePipeline>>,
+    /// Sets `expires_at` on new entries by role and chat type; nothing expires by default.
+    pub ttl_policy: TtlPolicy,
+    /// When set, entries whose write or embedding failed are queued here for retry instead of dropped.
+    pub outbox: Option<OutboxRepository>,
+    /// When set, user messages are passed to it for profile fact extraction (in the background).
+    pub profile_updater: Option<Arc<ProfileUpdater>>,
 }
 
 impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
             store: Arc::new(InMemoryVectorStore::new()) as Arc<dyn MemoryStore>,
-            recent_store: None,
+            recent_replication: None,
             embedding_service: None,
             max_recent_messages: 10,
             max_context_tokens: 4096,
             save_user_messages: true,
             save_llm_responses: true,
+            write_pipeline: None,
+            ttl_policy: TtlPolicy::new(),
+            outbox: None,
+            profile_updater: None,
         }
     }
 }
@@ -65,33 +85,152 @@ impl MemoryHandler {
     }
 
     /// Creates a new MemoryHandler with store and embedding service so saved messages get embeddings and participate in semantic search.
-    /// When `recent_store` is set, user messages and AI replies are also written there so recent-message strategies read from it.
     pub fn with_store_and_embedding(
         store: Arc<dyn MemoryStore>,
         embedding_service: Arc<dyn EmbeddingService>,
-        recent_store: Option<Arc<dyn MemoryStore>>,
     ) -> Self {
         Self::new(MemoryConfig {
             store,
-            recent_store,
             embedding_service: Some(embedding_service),
             ..Default::default()
         })
     }
 
+    /// Waits for `replication` (of `store`'s [`MemoryEvents`](crate::memory::MemoryEvents) into the recent
+    /// store) after each save, so the recent store holds the message before the handler returns.
+    ///
+    /// Consistency: the recent store only receives writes through the replication; a save waits at most
+    /// [`REPLICATION_ACK_TIMEOUT`] for it. Erasure and expiry are applied to both stores directly by the
+    /// [`DataEraser`](crate::retention::DataEraser).
+    pub fn with_recent_replication(mut self, replication: Arc<Replication>) -> Self {
+        self.config.recent_replication = Some(replication);
+        self
+    }
+
+    /// Routes writes to the primary store through `pipeline`.
+    pub fn with_write_pipeline(mut self, pipeline: Arc<MemoryWritePipeline>) -> Self {
+        self.config.write_pipeline = Some(pipeline);
+        self
+    }
+
+    /// Gives new entries an expiry according to `policy`.
+    pub fn with_ttl_policy(mut self, policy: TtlPolicy) -> Self {
+        self.config.ttl_policy = policy;
+        self
+    }
+
+    /// Queues failed memory writes in `outbox` for retry.
+    pub fn with_outbox(mut self, outbox: OutboxRepository) -> Self {
+        self.config.outbox = Some(outbox);
+        self
+    }
+
+    /// Passes user messages to `updater` for profile fact extraction.
+    pub fn with_profile_updater(mut self, updater: Arc<ProfileUpdater>) -> Self {
+        self.config.profile_updater = Some(updater);
+        self
+    }
+
+    /// Hands `entry` to the profile updater without waiting for the extraction (an LLM call).
+    fn observe_profile(&self, entry: &MemoryEntry) {
+        let Some(ref updater) = self.config.profile_updater else {
+            return;
+        };
+        let updater = updater.clone();
+        let entry = entry.clone();
+        tokio::spawn(async move {
+            if let Err(e) = updater.observe(entry).await {
+                warn!(error = %e, "Profile fact extraction failed");
+            }
+        });
+    }
+
+    /// Embeds `entry` when an embedding service is set. Returns the embedding error, if any; the
+    /// entry is then saved without embedding.
+    async fn embed_entry(&self, entry: &mut MemoryEntry, what: &str) -> Option<String> {
+        let svc = self.config.embedding_service.as_ref()?;
+        match svc.embed(&entry.content).await {
+            Ok(emb) => {
+                entry.embedding = Some(emb);
+                None
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to embed {}, saving without embedding", what);
+                Some(e.to_string())
+            }
+        }
+    }
+
+    /// Writes `entry` to the store (through the pipeline when set) and waits for the recent store replication.
+    /// With an outbox, a failed write to the store is queued for retry, and a stored entry whose embedding
+    /// failed (`embed_error`) is queued for re-embedding.
+    async fn save_entry(&self, mut entry: MemoryEntry, what: &str, embed_error: Option<String>) {
+        if let Some(ref pipeline) = self.config.write_pipeline {
+            // Annotated up front so a queued copy carries the same tokens and importance.
+            pipeline.annotate(&mut entry).await;
+        }
+        let entry_id = entry.id;
+        let queued = self.config.outbox.as_ref().map(|_| entry.clone());
+        let result = match self.config.write_pipeline {
+            Some(ref pipeline) => pipeline.write(self.config.store.as_ref(), entry).await,
+            None => self.config.store.add(entry).await.map(|_| WriteOutcome::Stored),
+        };
+        let retry_error = match result {
+            Ok(outcome) => {
+                info!(
+                    entry_id = %entry_id,
+                    outcome = ?outcome,
+                    "step: MemoryHandler {} written to memory",
+                    what
+                );
+                // Skipped and merged entries need no embedding of their own.
+                embed_error.filter(|_| matches!(outcome, WriteOutcome::Stored))
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to save {} to memory", what);
+                Some(e.to_string())
+            }
+        };
+        if let Some(ref replication) = self.config.recent_replication {
+            if tokio::time::timeout(REPLICATION_ACK_TIMEOUT, replication.caught_up())
+                .await
+                .is_err()
+            {
+                warn!("Recent store replication did not apply {} in time", what);
+            }
+        }
+        if let (Some(error), Some(entry), Some(outbox)) =
+            (retry_error, queued, self.config.outbox.as_ref())
+        {
+            let embed = entry.embedding.is_none() && self.config.embedding_service.is_some();
+            if let Err(e) = outbox
+                .enqueue(&OutboxPayload::MemoryEntry { entry, embed }, &error)
+                .await
+            {
+                error!(error = %e, "Failed to queue {} in outbox; it is lost", what);
+            }
+        }
+    }
+
     /// Creates a memory entry from a bot message (user role).
     /// pub(crate) for unit tests in memory_handler_test.
     pub(crate) fn message_to_memory_entry(&self, message: &Message) -> MemoryEntry {
         let user_id = Some(message.user.id.to_string());
         let conversation_id = Some(message.chat.id.to_string());
+        let timestamp = Utc::now();
 
         let metadata = MemoryMetadata {
             user_id,
             conversation_id,
             role: MemoryRole::User,
-            timestamp: Utc::now(),
+            timestamp,
             tokens: None,
             importance: None,
+            expires_at: self.config.ttl_policy.expires_at(
+                MemoryRole::User,
+                Some(&message.chat.chat_type),
+                timestamp,
+            ),
         };
 
         MemoryEntry::new(message.content.clone(), metadata)
@@ -102,14 +241,20 @@ impl MemoryHandler {
     pub(crate) fn reply_to_memory_entry(&self, message: &Message, reply_text: &str) -> MemoryEntry {
         let user_id = Some(message.user.id.to_string());
         let conversation_id = Some(message.chat.id.to_string());
+        let timestamp = Utc::now();
 
         let metadata = MemoryMetadata {
             user_id,
             conversation_id,
             role: MemoryRole::Assistant,
-            timestamp: Utc::now(),
+            timestamp,
             tokens: None,
             importance: None,
+            expires_at: self.config.ttl_policy.expires_at(
+                MemoryRole::Assistant,
+                Some(&message.chat.chat_type),
+                timestamp,
+            ),
         };
 
         MemoryEntry::new(reply_text.to_string(), metadata)
@@ -133,32 +278,9 @@ impl Handler for MemoryHandler {
         // Save user message to memory
         if self.config.save_user_messages {
             let mut entry = self.message_to_memory_entry(message);
-            if let Some(ref svc) = self.config.embedding_service {
-                match svc.embed(&entry.content).await {
-                    Ok(emb) => entry.embedding = Some(emb),
-                    Err(e) => {
-                        error!(error = %e, "Failed to embed user message, saving without embedding");
-                    }
-                }
-            }
-
-            if let Err(e) = self.config.store.add(entry.clone()).await {
-                error!(error = %e, "Failed to save user message to memory");
-            } else {
-                info!(
-                    user_id = %user_id,
-                    conversation_id = %conversation_id,
-                    entry_id = %entry.id,
-                    "step: MemoryHandler before done, user message saved to memory"
-                );
-            }
-            if let Some(ref r) = self.config.recent_store {
-                if !std::ptr::addr_eq(r.as_ref() as *const _, self.config.store.as_ref() as *const _) {
-                    if let Err(e) = r.add(entry).await {
-                        error!(error = %e, "Failed to save user message to recent store");
-                    }
-                }
-            }
+            self.observe_profile(&entry);
+            let embed_error = self.embed_entry(&mut entry, "user message").await;
+            self.save_entry(entry, "user message", embed_error).await;
         } else {
             info!(
                 user_id = %user_id,
@@ -189,31 +311,8 @@ impl Handler for MemoryHandler {
         if self.config.save_llm_responses {
             if let HandlerResponse::Reply(text) = response {
                 let mut entry = self.reply_to_memory_entry(message, text);
-                if let Some(ref svc) = self.config.embedding_service {
-                    match svc.embed(&entry.content).await {
-                        Ok(emb) => entry.embedding = Some(emb),
-                        Err(e) => {
-                            error!(error = %e, "Failed to embed LLM reply, saving without embedding");
-                        }
-                    }
-                }
-                if let Err(e) = self.config.store.add(entry.clone()).await {
-                    error!(error = %e, "Failed to save LLM response to memory");
-                } else {
-                    info!(
-                        user_id = %user_id,
-                        conversation_id = %conversation_id,
-                        entry_id = %entry.id,
-                        "step: MemoryHandler after done, LLM reply saved to memory"
-                    );
-                }
-                if let Some(ref r) = self.config.recent_store {
-                    if !std::ptr::addr_eq(r.as_ref() as *const _, self.config.store.as_ref() as *const _) {
-                        if let Err(e) = r.add(entry).await {
-                            error!(error = %e, "Failed to save LLM reply to recent store");
-                        }
-                    }
-                }
+                let embed_error = self.embed_entry(&mut entry, "LLM reply").await;
+                self.save_entry(entry, "LLM reply", embed_error).await;
             } else {
                 info!(
                     user_id = %user_id,
@@ -5,7 +5,10 @@
 use chrono::Utc;
 use crate::core::{Handler, HandlerResponse};
 use crate::core::types::{Chat, Message, User};
-use crate::memory::{InMemoryVectorStore, MemoryRole, MemoryStore};
+use crate::memory::{
+    spawn_replication, InMemoryVectorStore, MemoryEvents, MemoryRole, MemoryStore, MemoryWritePipeline,
+    ObservableMemoryStore,
+};
 use std::sync::Arc;
 
 use super::{MemoryConfig, MemoryHandler};
@@ -112,3 +115,51 @@ async fn test_memory_handler_after_saves_reply_to_memory() {
     assert_eq!(entries[0].content, "AI reply here.");
     assert_eq!(entries[0].metadata.role, MemoryRole::Assistant);
 }
+
+/// **Test: with a write pipeline, fillers are dropped and stored entries carry tokens and importance.**
+#[tokio::test]
+async fn test_memory_handler_write_pipeline_filters_fillers() {
+    let store = Arc::new(InMemoryVectorStore::new()) as Arc<dyn MemoryStore>;
+    let pipeline = Arc::new(MemoryWritePipeline::default().with_min_importance(0.2));
+    let handler = MemoryHandler::with_store(store.clone()).with_write_pipeline(pipeline);
+
+    handler.before(&create_test_message("ok")).await.unwrap();
+    handler
+        .before(&create_test_message("My flight LH 404 leaves Monday at 9am"))
+        .await
+        .unwrap();
+
+    let entries = store.search_by_user("123").await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].metadata.tokens.is_some());
+    assert!(entries[0].metadata.importance.unwrap() >= 0.2);
+}
+
+/// **Test: a separate recent store is written through the replication only, and holds each stored
+/// message when the handler returns.**
+#[tokio::test]
+async fn test_memory_handler_waits_for_recent_store_replication() {
+    let events = MemoryEvents::default();
+    let store = Arc::new(ObservableMemoryStore::new(
+        Arc::new(InMemoryVectorStore::new()),
+        events.clone(),
+    )) as Arc<dyn MemoryStore>;
+    let recent = Arc::new(InMemoryVectorStore::new()) as Arc<dyn MemoryStore>;
+    let replication = Arc::new(spawn_replication(&events, recent.clone()));
+    let pipeline = Arc::new(MemoryWritePipeline::default().with_min_importance(0.2));
+    let handler = MemoryHandler::with_store(store.clone())
+        .with_write_pipeline(pipeline)
+        .with_recent_replication(replication.clone());
+
+    handler.before(&create_test_message("ok")).await.unwrap();
+    handler
+        .before(&create_test_message("My flight LH 404 leaves Monday at 9am"))
+        .await
+        .unwrap();
+
+    let history = recent.search_by_user("123").await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].metadata.tokens.is_some());
+    assert_eq!(events.published(), 1, "each message is published once");
+    replication.abort();
+}
@@ -9,18 +9,23 @@ pub mod components;
 pub mod config;
 pub mod core;
 pub mod embedding;
+pub mod encryption;
 pub mod handlers;
 pub mod mention;
 pub mod memory;
 pub mod memory_core;
 pub mod memory_strategies;
+pub mod outbox;
+pub mod profile;
+pub mod retention;
 pub mod runner;
 pub mod storage;
 pub mod telegram;
 pub mod telegram_impl;
+pub mod tokenizer;
 
 // Re-export CLI (integrated from dbot-cli)
-pub use cli::{load_config, Cli, Commands};
+pub use cli::{load_config, Cli, Commands, OutboxAction};
 
 // Re-export core (from dbot-core)
 pub use core::{
@@ -37,9 +42,14 @@ pub use telegram::{
 };
 
 pub use config::{AppExtensions, BotConfig};
-pub use runner::{run_bot, run_bot_with_memory_stores, run_bot_with_memory_stores_build_only};
+pub use runner::{
+    run_backfill, run_bot, run_bot_with_memory_stores, run_bot_with_memory_stores_build_only,
+    run_outbox, run_reembed, run_reencrypt, OutboxCommandReport,
+};
 
-pub use components::{build_bot_components, create_memory_stores, BotComponents};
+pub use components::{
+    build_bot_components, build_data_eraser, create_memory_stores, BotComponents,
+};
 pub use handlers::{
     AuthHandler, LoggingHandler, MemoryConfig, MemoryHandler, NoOpHandler, PersistenceHandler,
 };
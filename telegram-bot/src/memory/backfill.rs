@@ -0,0 +1,81 @@
+//! Memory backfill: imports the messages table into a memory store, e.g. after switching to a new
+//! store type or losing the memory database.
+
+use anyhow::{Context as _, Result};
+use futures::TryStreamExt;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use super::batch_writer::BatchingMemoryWriter;
+use crate::embedding::EmbeddingService;
+use crate::memory_core::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
+use crate::storage::{MessageOrder, MessageQuery, MessageRecord, MessageRepository};
+
+/// Entries imported by one backfill run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillReport {
+    /// Entries written per memory store (label, count), in the given order.
+    pub memory_entries: Vec<(String, u64)>,
+}
+
+/// Imports every message of `repo` into `store`, oldest first, through a [`BatchingMemoryWriter`]:
+/// `batch_size` entries are embedded with one `embed_batch` call (when `embedding` is set) and
+/// written with one `add_batch` call. Returns the number of entries written.
+///
+/// Entries reuse the message id, and messages already in the store are skipped, so the command can be
+/// re-run after an interruption. Messages the running bot wrote to memory itself carry other ids, so
+/// the backfill is meant for a store that does not hold them yet.
+pub async fn backfill_store(
+    repo: &MessageRepository,
+    store: Arc<dyn MemoryStore>,
+    embedding: Option<Arc<dyn EmbeddingService>>,
+    batch_size: usize,
+) -> Result<u64> {
+    let mut writer = BatchingMemoryWriter::new(store.clone(), batch_size);
+    if let Some(embedding) = embedding {
+        writer = writer.with_embedding(embedding);
+    }
+    let mut messages = Box::pin(repo.stream_messages(
+        MessageQuery::default(),
+        MessageOrder::OldestFirst,
+        batch_size.max(1) as u32,
+    ));
+    let mut written = 0;
+    let mut skipped = 0;
+    while let Some(record) = messages
+        .try_next()
+        .await
+        .context("backfill: reading messages failed")?
+    {
+        let entry = message_to_entry(&record);
+        if store.get(entry.id).await?.is_some() {
+            skipped += 1;
+            continue;
+        }
+        written += writer.push(entry).await? as u64;
+    }
+    written += writer.flush().await? as u64;
+    info!(written, skipped, "Memory backfill finished");
+    Ok(written)
+}
+
+/// Builds the memory entry of a stored message: received messages are user turns, sent ones
+/// assistant turns.
+fn message_to_entry(record: &MessageRecord) -> MemoryEntry {
+    let role = if record.direction == "sent" {
+        MemoryRole::Assistant
+    } else {
+        MemoryRole::User
+    };
+    let metadata = MemoryMetadata::default()
+        .with_user(record.user_id.to_string())
+        .with_conversation(record.chat_id.to_string())
+        .with_role(role)
+        .with_timestamp(record.created_at);
+    let mut entry = MemoryEntry::new(record.content.clone(), metadata);
+    if let Ok(id) = Uuid::parse_str(&record.id) {
+        entry.id = id;
+    }
+    entry
+}
@@ -0,0 +1,167 @@
+//! Buffered writer for bulk imports: collects entries, embeds them with one `embed_batch` call per
+//! batch and writes them with [`MemoryStore::add_batch`].
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use tracing::info;
+
+use crate::embedding::EmbeddingService;
+use crate::memory_core::{MemoryEntry, MemoryStore};
+
+/// Default number of entries per batch.
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Collects entries and flushes them in batches. Entries that already carry an embedding are
+/// written as-is; the others are embedded together when an embedding service is set.
+/// On a failed flush the pending entries are kept, so the caller can retry [`flush`](Self::flush).
+pub struct BatchingMemoryWriter {
+    store: Arc<dyn MemoryStore>,
+    embedding_service: Option<Arc<dyn EmbeddingService>>,
+    batch_size: usize,
+    pending: Vec<MemoryEntry>,
+}
+
+impl BatchingMemoryWriter {
+    /// Creates a writer that flushes every `batch_size` entries (at least 1).
+    pub fn new(store: Arc<dyn MemoryStore>, batch_size: usize) -> Self {
+        Self {
+            store,
+            embedding_service: None,
+            batch_size: batch_size.max(1),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Embeds entries without an embedding before writing them.
+    pub fn with_embedding(mut self, embedding_service: Arc<dyn EmbeddingService>) -> Self {
+        self.embedding_service = Some(embedding_service);
+        self
+    }
+
+    /// Number of entries buffered and not yet written.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Buffers one entry; flushes when the batch is full. Returns the number of entries written.
+    pub async fn push(&mut self, entry: MemoryEntry) -> Result<usize, anyhow::Error> {
+        self.pending.push(entry);
+        if self.pending.len() >= self.batch_size {
+            self.flush().await
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Buffers all entries and flushes the remainder. Returns the number of entries written.
+    pub async fn write_all(
+        &mut self,
+        entries: impl IntoIterator<Item = MemoryEntry>,
+    ) -> Result<usize, anyhow::Error> {
+        let mut written = 0;
+        for entry in entries {
+            written += self.push(entry).await?;
+        }
+        written += self.flush().await?;
+        Ok(written)
+    }
+
+    /// Embeds and writes all buffered entries. Returns the number of entries written.
+    pub async fn flush(&mut self) -> Result<usize, anyhow::Error> {
+        if self.pending.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(embedding_service) = &self.embedding_service {
+            let missing: Vec<usize> = self
+                .pending
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.embedding.is_none())
+                .map(|(i, _)| i)
+                .collect();
+            if !missing.is_empty() {
+                let texts: Vec<String> = missing
+                    .iter()
+                    .map(|&i| self.pending[i].content.clone())
+                    .collect();
+                let embeddings = embedding_service.embed_batch(&texts).await?;
+                if embeddings.len() != texts.len() {
+                    return Err(anyhow!(
+                        "embed_batch returned {} embeddings for {} texts",
+                        embeddings.len(),
+                        texts.len()
+                    ));
+                }
+                for (i, embedding) in missing.into_iter().zip(embeddings) {
+                    self.pending[i].embedding = Some(embedding);
+                }
+            }
+        }
+
+        let batch = std::mem::take(&mut self.pending);
+        let count = batch.len();
+        if let Err(e) = self.store.add_batch(batch.clone()).await {
+            self.pending = batch;
+            return Err(e);
+        }
+        info!(count, "Flushed memory batch");
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryVectorStore;
+    use crate::memory_core::{MemoryFilter, MemoryMetadata};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingEmbedding {
+        batches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingService for CountingEmbedding {
+        async fn embed(&self, _text: &str) -> Result<Vec<f32>, anyhow::Error> {
+            Err(anyhow!("single embed must not be used"))
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, anyhow::Error> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            Ok(texts.iter().map(|t| vec![t.len() as f32, 1.0]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_all_embeds_once_per_batch() {
+        let store = Arc::new(InMemoryVectorStore::new());
+        let embedding = Arc::new(CountingEmbedding {
+            batches: AtomicUsize::new(0),
+        });
+        let mut writer =
+            BatchingMemoryWriter::new(store.clone(), 2).with_embedding(embedding.clone());
+
+        let chat = MemoryMetadata::default().with_conversation("c1");
+        let mut entries: Vec<MemoryEntry> = ["a", "bb", "ccc"]
+            .iter()
+            .map(|content| MemoryEntry::new(content.to_string(), chat.clone()))
+            .collect();
+        entries.push(MemoryEntry::new("pre".to_string(), chat).with_embedding(vec![9.0, 9.0]));
+
+        let written = writer.write_all(entries).await.unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(writer.pending(), 0);
+        assert_eq!(embedding.batches.load(Ordering::SeqCst), 2);
+
+        let stored = store.count(&MemoryFilter::for_conversation("c1")).await.unwrap();
+        assert_eq!(stored, 4);
+        let all = store.search_by_conversation("c1").await.unwrap();
+        assert!(all.iter().all(|e| e.embedding.is_some()));
+        assert!(all
+            .iter()
+            .any(|e| e.content == "pre" && e.embedding.as_deref() == Some(&[9.0, 9.0][..])));
+    }
+}
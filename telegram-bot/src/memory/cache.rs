@@ -0,0 +1,539 @@
+//! [`CachedMemoryStore`]: keeps the newest entries of recently used conversations and users in
+//! memory so context strategies do not re-read them from the backend on every turn.
+//!
+//! Each cached key (a conversation or a user) holds a window with its newest `window` entries.
+//! Reads are answered from a window only when the answer is exact: a newest-first `list` when the
+//! window holds more matching entries than the page, anything else when the window holds every
+//! entry of the key. Other reads go to the inner store. Writes through the wrapper invalidate the
+//! affected keys; writes that bypass it (the inner store directly, another process) stay invisible
+//! until the key is evicted.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::memory_core::{
+    EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryOrder, MemoryPage, MemoryStore,
+    SearchFilter,
+};
+
+/// Default number of entries kept per conversation or user.
+pub const DEFAULT_CACHE_WINDOW: usize = 500;
+
+/// Cache counters and occupancy, from [`CachedMemoryStore::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Reads answered from the cache.
+    pub hits: u64,
+    /// Cacheable reads that went to the inner store.
+    pub misses: u64,
+    /// Conversations currently cached.
+    pub conversations: usize,
+    /// Users currently cached.
+    pub users: usize,
+}
+
+impl CacheStats {
+    /// Share of cacheable reads answered from the cache (0 before the first read).
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum KeyKind {
+    Conversation,
+    User,
+}
+
+/// Newest entries of one key, newest first; `complete` when they are all of the key's entries.
+struct Window {
+    entries: Vec<MemoryEntry>,
+    complete: bool,
+}
+
+impl Window {
+    /// Keeps the newest `size` of `entries` (any order, all of the key or its newest ones).
+    fn new(mut entries: Vec<MemoryEntry>, size: usize, complete: bool) -> Self {
+        entries.sort_by_key(|e| std::cmp::Reverse((e.metadata.timestamp, e.id)));
+        let complete = complete && entries.len() <= size;
+        entries.truncate(size);
+        Self { entries, complete }
+    }
+
+    fn holds(&self, pred: impl Fn(&MemoryEntry) -> bool) -> bool {
+        self.entries.iter().any(pred)
+    }
+
+    /// The page for the query, when this window answers it exactly. Entries of the key missing
+    /// from an incomplete window are older than all of it, so a newest-first page is exact as soon
+    /// as the window has a match beyond the page.
+    fn page(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Option<MemoryPage> {
+        match order {
+            MemoryOrder::NewestFirst => {
+                let matching: Vec<MemoryEntry> = self
+                    .entries
+                    .iter()
+                    .filter(|e| filter.matches(e) && cursor.is_none_or(|c| c.precedes(e, order)))
+                    .take(limit.saturating_add(1))
+                    .cloned()
+                    .collect();
+                let page = MemoryPage::from_sorted(matching, limit);
+                (self.complete || page.next_cursor.is_some()).then_some(page)
+            }
+            MemoryOrder::OldestFirst => self
+                .complete
+                .then(|| MemoryPage::paginate(self.entries.clone(), filter, order, limit, cursor)),
+        }
+    }
+
+    /// Every entry of the key, less those that expired since the window was loaded.
+    fn all(&self) -> Option<Vec<MemoryEntry>> {
+        let now = Utc::now();
+        self.complete.then(|| {
+            self.entries
+                .iter()
+                .filter(|e| !e.metadata.is_expired(now))
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn count(&self, filter: &MemoryFilter) -> Option<u64> {
+        self.complete
+            .then(|| self.entries.iter().filter(|e| filter.matches(e)).count() as u64)
+    }
+}
+
+/// Windows by key; beyond `capacity` the least recently used one is evicted (linear scan, meant
+/// for hundreds of keys).
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    windows: HashMap<String, (u64, Arc<Window>)>,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            windows: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<Arc<Window>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.windows.get_mut(key).map(|(used, window)| {
+            *used = tick;
+            window.clone()
+        })
+    }
+
+    fn insert(&mut self, key: &str, window: Window) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.windows.contains_key(key) && self.windows.len() >= self.capacity {
+            let oldest = self
+                .windows
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.windows.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.windows
+            .insert(key.to_string(), (self.tick, Arc::new(window)));
+    }
+
+    fn remove(&mut self, key: Option<&str>) {
+        if let Some(key) = key {
+            self.windows.remove(key);
+        }
+    }
+
+    /// Drops every window holding an entry that satisfies `pred`.
+    fn remove_holding(&mut self, pred: impl Fn(&MemoryEntry) -> bool) {
+        self.windows.retain(|_, (_, window)| !window.holds(&pred));
+    }
+}
+
+struct CacheState {
+    conversations: Lru,
+    users: Lru,
+    /// Bumped by every write, so a read that raced a write does not store what it read.
+    generation: u64,
+}
+
+impl CacheState {
+    fn lru(&mut self, kind: KeyKind) -> &mut Lru {
+        match kind {
+            KeyKind::Conversation => &mut self.conversations,
+            KeyKind::User => &mut self.users,
+        }
+    }
+}
+
+/// Wrapper around any [`MemoryStore`] that caches the newest entries of the `capacity` most recently
+/// used conversations and users (each kept separately) and counts hits and misses.
+///
+/// Cached: `list`, `count`, `search_by_conversation` and `search_by_user` (answered from the cache
+/// newest first). Vector and keyword search and `get` always go to the inner store.
+pub struct CachedMemoryStore {
+    inner: Arc<dyn MemoryStore>,
+    window: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedMemoryStore {
+    /// Caches up to `capacity` conversations and `capacity` users with [`DEFAULT_CACHE_WINDOW`] entries each.
+    pub fn new(inner: Arc<dyn MemoryStore>, capacity: usize) -> Self {
+        Self {
+            inner,
+            window: DEFAULT_CACHE_WINDOW,
+            state: Mutex::new(CacheState {
+                conversations: Lru::new(capacity),
+                users: Lru::new(capacity),
+                generation: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of newest entries kept per conversation or user (at least 1).
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            conversations: state.conversations.windows.len(),
+            users: state.users.windows.len(),
+        }
+    }
+
+    /// Drops every cached window (e.g. after writing to the inner store directly).
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.generation += 1;
+        state.conversations.windows.clear();
+        state.users.windows.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record<T>(&self, answer: Option<T>) -> Option<T> {
+        let counter = if answer.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        answer
+    }
+
+    /// Cached window and current generation.
+    fn lookup(&self, kind: KeyKind, key: &str) -> (Option<Arc<Window>>, u64) {
+        let mut state = self.lock();
+        let generation = state.generation;
+        (state.lru(kind).get(key), generation)
+    }
+
+    /// Caches `window` unless a write happened since `generation`.
+    fn fill(
+        &self,
+        kind: KeyKind,
+        key: &str,
+        generation: u64,
+        window: Window,
+    ) -> Option<Arc<Window>> {
+        let mut state = self.lock();
+        if state.generation != generation {
+            return None;
+        }
+        state.lru(kind).insert(key, window);
+        state.lru(kind).get(key)
+    }
+
+    /// Fetches the newest `window` entries of the key from the inner store and caches them.
+    async fn load(
+        &self,
+        kind: KeyKind,
+        key: &str,
+        generation: u64,
+    ) -> Result<Option<Arc<Window>>, anyhow::Error> {
+        let filter = match kind {
+            KeyKind::Conversation => MemoryFilter::for_conversation(key),
+            KeyKind::User => MemoryFilter::for_user(key),
+        };
+        let page = self
+            .inner
+            .list(&filter, MemoryOrder::NewestFirst, self.window, None)
+            .await?;
+        let complete = page.next_cursor.is_none();
+        Ok(self.fill(
+            kind,
+            key,
+            generation,
+            Window::new(page.entries, self.window, complete),
+        ))
+    }
+
+    /// Drops the windows of the given conversation and user.
+    fn invalidate_keys(&self, keys: &[(Option<String>, Option<String>)]) {
+        let mut state = self.lock();
+        state.generation += 1;
+        for (conversation_id, user_id) in keys {
+            state.conversations.remove(conversation_id.as_deref());
+            state.users.remove(user_id.as_deref());
+        }
+    }
+
+    /// Drops the windows holding `id`. An entry missing from an incomplete window is older than
+    /// the whole window, so removing it leaves that window exact.
+    fn invalidate_id(&self, id: Uuid) {
+        let mut state = self.lock();
+        state.generation += 1;
+        state.conversations.remove_holding(|e| e.id == id);
+        state.users.remove_holding(|e| e.id == id);
+    }
+
+    fn keys(entry: &MemoryEntry) -> (Option<String>, Option<String>) {
+        (
+            entry.metadata.conversation_id.clone(),
+            entry.metadata.user_id.clone(),
+        )
+    }
+
+    fn search_key(filter: &MemoryFilter) -> Option<(KeyKind, &str)> {
+        match (filter.conversation_id.as_deref(), filter.user_id.as_deref()) {
+            (Some(conversation_id), _) => Some((KeyKind::Conversation, conversation_id)),
+            (None, Some(user_id)) => Some((KeyKind::User, user_id)),
+            (None, None) => None,
+        }
+    }
+
+    /// Complete list of a key's entries: from the cache, or from `fetch` (then cached).
+    async fn search_all(
+        &self,
+        kind: KeyKind,
+        key: &str,
+        fetch: impl std::future::Future<Output = Result<Vec<MemoryEntry>, anyhow::Error>>,
+    ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        let (cached, generation) = self.lookup(kind, key);
+        if let Some(entries) = self.record(cached.and_then(|w| w.all())) {
+            return Ok(entries);
+        }
+        let entries = fetch.await?;
+        self.fill(
+            kind,
+            key,
+            generation,
+            Window::new(entries.clone(), self.window, true),
+        );
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl MemoryStore for CachedMemoryStore {
+    async fn add(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        let keys = Self::keys(&entry);
+        let result = self.inner.add(entry).await;
+        self.invalidate_keys(&[keys]);
+        result
+    }
+
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.inner.flush().await
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        self.inner.embedding_spec().await
+    }
+
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        self.inner.bind_embedding_spec(spec).await
+    }
+
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        let result = self.inner.reset_embeddings(spec).await;
+        self.clear();
+        result
+    }
+
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        let keys: Vec<_> = entries.iter().map(Self::keys).collect();
+        let result = self.inner.add_batch(entries).await;
+        self.invalidate_keys(&keys);
+        result
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
+        self.inner.get(id).await
+    }
+
+    /// Invalidates the windows holding the old entry and those of the new entry's conversation and user.
+    async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        let (id, keys) = (entry.id, Self::keys(&entry));
+        let result = self.inner.update(entry).await;
+        self.invalidate_id(id);
+        self.invalidate_keys(&[keys]);
+        result
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), anyhow::Error> {
+        let result = self.inner.delete(id).await;
+        self.invalidate_id(id);
+        result
+    }
+
+    async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.search_all(KeyKind::User, user_id, self.inner.search_by_user(user_id))
+            .await
+    }
+
+    async fn search_by_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.search_all(
+            KeyKind::Conversation,
+            conversation_id,
+            self.inner.search_by_conversation(conversation_id),
+        )
+        .await
+    }
+
+    async fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        user_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner
+            .semantic_search(query_embedding, limit, user_id, conversation_id)
+            .await
+    }
+
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner
+            .semantic_search_filtered(query_embedding, limit, filter)
+            .await
+    }
+
+    async fn keyword_search(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner.keyword_search(query, limit, filter).await
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let Some((kind, key)) = Self::search_key(filter) else {
+            return self.inner.list(filter, order, limit, cursor).await;
+        };
+        let (cached, generation) = self.lookup(kind, key);
+        let page = match cached {
+            Some(window) => self.record(window.page(filter, order, limit, cursor)),
+            None => {
+                // A window loaded now still counts as a miss.
+                self.record::<()>(None);
+                self.load(kind, key, generation)
+                    .await?
+                    .and_then(|w| w.page(filter, order, limit, cursor))
+            }
+        };
+        match page {
+            Some(page) => Ok(page),
+            None => self.inner.list(filter, order, limit, cursor).await,
+        }
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        let Some((kind, key)) = Self::search_key(filter) else {
+            return self.inner.count(filter).await;
+        };
+        let (cached, _) = self.lookup(kind, key);
+        match self.record(cached.and_then(|w| w.count(filter))) {
+            Some(count) => Ok(count),
+            None => self.inner.count(filter).await,
+        }
+    }
+
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        let result = self.inner.delete_by_conversation(conversation_id).await;
+        let mut state = self.lock();
+        state.generation += 1;
+        state.conversations.remove(Some(conversation_id));
+        state
+            .users
+            .remove_holding(|e| e.metadata.conversation_id.as_deref() == Some(conversation_id));
+        result
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        let result = self.inner.delete_by_user(user_id).await;
+        let mut state = self.lock();
+        state.generation += 1;
+        state.users.remove(Some(user_id));
+        state
+            .conversations
+            .remove_holding(|e| e.metadata.user_id.as_deref() == Some(user_id));
+        result
+    }
+
+    /// Cached windows already hide expired entries; they are dropped so the memory is freed too.
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let result = self.inner.delete_expired(now).await;
+        let mut state = self.lock();
+        state.generation += 1;
+        state
+            .conversations
+            .remove_holding(|e| e.metadata.is_expired(now));
+        state.users.remove_holding(|e| e.metadata.is_expired(now));
+        result
+    }
+}
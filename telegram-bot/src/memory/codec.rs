@@ -0,0 +1,81 @@
+//! Little-endian binary helpers shared by the on-disk formats of the memory module
+//! (HNSW index files and in-memory store snapshots).
+
+use std::io::{self, Read, Write};
+
+/// Length marker for an absent optional string or vector.
+const NONE_LEN: u32 = u32::MAX;
+
+pub(crate) fn write_u32(out: &mut impl Write, v: u32) -> io::Result<()> {
+    out.write_all(&v.to_le_bytes())
+}
+
+pub(crate) fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn write_str(out: &mut impl Write, s: &str) -> io::Result<()> {
+    write_u32(out, s.len() as u32)?;
+    out.write_all(s.as_bytes())
+}
+
+pub(crate) fn write_opt_str(out: &mut impl Write, s: Option<&str>) -> io::Result<()> {
+    match s {
+        Some(s) => write_str(out, s),
+        None => write_u32(out, NONE_LEN),
+    }
+}
+
+pub(crate) fn read_opt_str(input: &mut impl Read) -> io::Result<Option<String>> {
+    let len = read_u32(input)?;
+    if len == NONE_LEN {
+        return Ok(None);
+    }
+    let mut buf = vec![0u8; len as usize];
+    input.read_exact(&mut buf)?;
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub(crate) fn read_str(input: &mut impl Read) -> io::Result<String> {
+    read_opt_str(input)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing required string"))
+}
+
+pub(crate) fn write_f32s(out: &mut impl Write, values: &[f32]) -> io::Result<()> {
+    for x in values {
+        out.write_all(&x.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_f32s(input: &mut impl Read, len: usize) -> io::Result<Vec<f32>> {
+    let mut values = Vec::with_capacity(len);
+    let mut buf = [0u8; 4];
+    for _ in 0..len {
+        input.read_exact(&mut buf)?;
+        values.push(f32::from_le_bytes(buf));
+    }
+    Ok(values)
+}
+
+pub(crate) fn write_opt_f32s(out: &mut impl Write, values: Option<&[f32]>) -> io::Result<()> {
+    match values {
+        Some(values) => {
+            write_u32(out, values.len() as u32)?;
+            write_f32s(out, values)
+        }
+        None => write_u32(out, NONE_LEN),
+    }
+}
+
+pub(crate) fn read_opt_f32s(input: &mut impl Read) -> io::Result<Option<Vec<f32>>> {
+    let len = read_u32(input)?;
+    if len == NONE_LEN {
+        return Ok(None);
+    }
+    read_f32s(input, len as usize).map(Some)
+}
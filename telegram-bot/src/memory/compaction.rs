@@ -0,0 +1,443 @@
+//! Memory compaction: old entries of a conversation are grouped into time windows, each window is
+//! summarized into one `System`-role entry (with an embedding), and the originals are moved to an
+//! archive store or deleted.
+//!
+//! The summarizer is pluggable ([`MemorySummarizer`]); the LLM application installs one backed by
+//! its LLM client. Summaries keep semantic search and [`ConversationSummaryStrategy`](crate::memory_strategies::ConversationSummaryStrategy)
+//! useful for older history without thousands of raw rows.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{Context as _, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::{Builder, Uuid};
+
+use crate::embedding::EmbeddingService;
+use crate::memory_core::{
+    MemoryEntry, MemoryFilter, MemoryMetadata, MemoryOrder, MemoryRole, MemoryStore,
+};
+use crate::tokenizer::{EstimateTokenizer, Tokenizer};
+
+use super::config::MemoryConfig;
+use super::importance::NEUTRAL_IMPORTANCE;
+
+/// Page size when listing old entries.
+const COMPACTION_PAGE_SIZE: usize = 500;
+/// Most entries summarized at once; busier windows are split into several summaries.
+const MAX_ENTRIES_PER_SUMMARY: usize = 200;
+
+/// Turns a window of entries into a short summary.
+#[async_trait]
+pub trait MemorySummarizer: Send + Sync {
+    /// Summary of `entries` (one conversation, oldest first).
+    async fn summarize(&self, entries: &[MemoryEntry]) -> Result<String>;
+}
+
+/// Which entries are compacted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactionPolicy {
+    /// Only windows that ended at least this long ago are compacted.
+    pub older_than: Duration,
+    /// Window length; windows are aligned to the Unix epoch so reruns see the same windows.
+    pub window: Duration,
+    /// Windows with fewer entries are left as they are.
+    pub min_entries: usize,
+}
+
+impl CompactionPolicy {
+    pub fn new(older_than: Duration, window: Duration, min_entries: usize) -> Self {
+        Self {
+            older_than,
+            window,
+            min_entries,
+        }
+    }
+
+    /// Builds a policy from a [`MemoryConfig`]; `None` when compaction is disabled.
+    pub fn from_config(config: &dyn MemoryConfig) -> Option<Self> {
+        let days = config.compaction_after_days()?;
+        Some(Self::new(
+            Duration::days(days as i64),
+            Duration::hours(config.compaction_window_hours().max(1) as i64),
+            config.compaction_min_entries(),
+        ))
+    }
+
+    /// Start of the window containing `t`.
+    fn window_start(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        let len = self.window.num_seconds().max(1);
+        let secs = t.timestamp();
+        DateTime::from_timestamp(secs - secs.rem_euclid(len), 0).unwrap_or(t)
+    }
+}
+
+/// Result of one compaction run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompactionReport {
+    /// Summary entries written.
+    pub summaries: u64,
+    /// Original entries removed from the store.
+    pub compacted: u64,
+    /// Original entries copied to the archive store (0 when originals are deleted).
+    pub archived: u64,
+}
+
+impl CompactionReport {
+    fn add(&mut self, other: &CompactionReport) {
+        self.summaries += other.summaries;
+        self.compacted += other.compacted;
+        self.archived += other.archived;
+    }
+}
+
+/// Summarizes old windows of every conversation in `store`.
+///
+/// `System`-role entries (summaries from earlier runs) are never compacted again. A window whose
+/// summary or embedding fails is kept as it is and retried on the next run. Conversations are
+/// discovered by paging the store itself.
+///
+/// A window is archived, then its summary written, then its originals deleted. The summary id is
+/// derived from the ids of the window's entries and every write is an upsert, so a run interrupted
+/// before the deletes finish is completed by the next one without a second summary.
+///
+/// Summary token counts come from [`EstimateTokenizer`] until the model's tokenizer is set
+/// ([`set_tokenizer`](Self::set_tokenizer)).
+pub struct MemoryCompactor {
+    store: Arc<dyn MemoryStore>,
+    embedding_service: Arc<dyn EmbeddingService>,
+    policy: CompactionPolicy,
+    archive_store: Option<Arc<dyn MemoryStore>>,
+    summarizer: RwLock<Option<Arc<dyn MemorySummarizer>>>,
+    tokenizer: RwLock<Arc<dyn Tokenizer>>,
+}
+
+impl MemoryCompactor {
+    /// Compactor without a summarizer (see [`set_summarizer`](Self::set_summarizer)) that deletes originals.
+    pub fn new(
+        store: Arc<dyn MemoryStore>,
+        embedding_service: Arc<dyn EmbeddingService>,
+        policy: CompactionPolicy,
+    ) -> Self {
+        Self {
+            store,
+            embedding_service,
+            policy,
+            archive_store: None,
+            summarizer: RwLock::new(None),
+            tokenizer: RwLock::new(Arc::new(EstimateTokenizer)),
+        }
+    }
+
+    /// Moves originals to `archive` instead of deleting them.
+    pub fn with_archive_store(mut self, archive: Arc<dyn MemoryStore>) -> Self {
+        self.archive_store = Some(archive);
+        self
+    }
+
+    pub fn with_summarizer(self, summarizer: Arc<dyn MemorySummarizer>) -> Self {
+        self.set_summarizer(summarizer);
+        self
+    }
+
+    pub fn set_summarizer(&self, summarizer: Arc<dyn MemorySummarizer>) {
+        *self.summarizer.write().unwrap_or_else(|e| e.into_inner()) = Some(summarizer);
+    }
+
+    pub fn with_tokenizer(self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.set_tokenizer(tokenizer);
+        self
+    }
+
+    pub fn set_tokenizer(&self, tokenizer: Arc<dyn Tokenizer>) {
+        *self.tokenizer.write().unwrap_or_else(|e| e.into_inner()) = tokenizer;
+    }
+
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        self.tokenizer.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn has_summarizer(&self) -> bool {
+        self.summarizer().is_some()
+    }
+
+    fn summarizer(&self) -> Option<Arc<dyn MemorySummarizer>> {
+        self.summarizer
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn policy(&self) -> &CompactionPolicy {
+        &self.policy
+    }
+
+    /// Store receiving archived originals, if any.
+    pub fn archive_store(&self) -> Option<&Arc<dyn MemoryStore>> {
+        self.archive_store.as_ref()
+    }
+
+    /// Compacts every conversation of the store that has entries old enough.
+    pub async fn compact_all(&self) -> Result<CompactionReport> {
+        let now = Utc::now();
+        let conversation_ids = self
+            .conversations_before(self.policy.window_start(now - self.policy.older_than))
+            .await
+            .context("compaction: listing conversations failed")?;
+        let mut report = CompactionReport::default();
+        for conversation_id in conversation_ids {
+            match self.compact_conversation(&conversation_id, now).await {
+                Ok(r) => report.add(&r),
+                Err(e) => {
+                    warn!(error = %e, conversation_id = %conversation_id, "compaction: conversation failed")
+                }
+            }
+        }
+        info!(report = ?report, "Memory compaction finished");
+        Ok(report)
+    }
+
+    /// Conversations with non-summary entries dated before `cutoff`.
+    async fn conversations_before(&self, cutoff: DateTime<Utc>) -> Result<BTreeSet<String>> {
+        let filter = MemoryFilter::default().until(cutoff);
+        let mut conversation_ids = BTreeSet::new();
+        let mut cursor = None;
+        loop {
+            let page = self
+                .store
+                .list(
+                    &filter,
+                    MemoryOrder::OldestFirst,
+                    COMPACTION_PAGE_SIZE,
+                    cursor.as_ref(),
+                )
+                .await?;
+            conversation_ids.extend(
+                page.entries
+                    .into_iter()
+                    .filter(|e| e.metadata.role != MemoryRole::System)
+                    .filter_map(|e| e.metadata.conversation_id),
+            );
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(conversation_ids)
+    }
+
+    /// Compacts the windows of one conversation that ended at least `older_than` before `now`.
+    pub async fn compact_conversation(
+        &self,
+        conversation_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<CompactionReport> {
+        let summarizer = self
+            .summarizer()
+            .ok_or_else(|| anyhow::anyhow!("compaction: no summarizer installed"))?;
+        let cutoff = self.policy.window_start(now - self.policy.older_than);
+        let filter = MemoryFilter::for_conversation(conversation_id).until(cutoff);
+        let mut report = CompactionReport::default();
+        let mut window: Vec<MemoryEntry> = Vec::new();
+        let mut window_start = None;
+        let mut cursor = None;
+        loop {
+            let page = self
+                .store
+                .list(
+                    &filter,
+                    MemoryOrder::OldestFirst,
+                    COMPACTION_PAGE_SIZE,
+                    cursor.as_ref(),
+                )
+                .await?;
+            for entry in page.entries {
+                if entry.metadata.role == MemoryRole::System {
+                    continue;
+                }
+                let start = self.policy.window_start(entry.metadata.timestamp);
+                if window_start != Some(start) || window.len() >= MAX_ENTRIES_PER_SUMMARY {
+                    let done = std::mem::take(&mut window);
+                    self.compact_window(summarizer.as_ref(), conversation_id, done, &mut report)
+                        .await?;
+                    window_start = Some(start);
+                }
+                window.push(entry);
+            }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        self.compact_window(summarizer.as_ref(), conversation_id, window, &mut report)
+            .await?;
+        Ok(report)
+    }
+
+    /// Summarizes `entries`, archives them, writes the summary, then deletes them. Windows below
+    /// `min_entries` and failed summaries leave the entries untouched; a summary already written by
+    /// an interrupted run is reused.
+    async fn compact_window(
+        &self,
+        summarizer: &dyn MemorySummarizer,
+        conversation_id: &str,
+        entries: Vec<MemoryEntry>,
+        report: &mut CompactionReport,
+    ) -> Result<()> {
+        if entries.len() < self.policy.min_entries.max(1) {
+            return Ok(());
+        }
+        let summary_id = Self::summary_id(&entries);
+        let summary = if self.store.get(summary_id).await?.is_some() {
+            None
+        } else {
+            match self
+                .summarize_window(summarizer, conversation_id, &entries, summary_id)
+                .await?
+            {
+                Some(summary) => Some(summary),
+                None => return Ok(()),
+            }
+        };
+
+        let count = entries.len() as u64;
+        if let Some(ref archive) = self.archive_store {
+            for entry in &entries {
+                archive.update(entry.clone()).await?;
+            }
+            report.archived += count;
+        }
+        if let Some(summary) = summary {
+            self.store.update(summary).await?;
+            report.summaries += 1;
+        }
+        for entry in &entries {
+            self.store.delete(entry.id).await?;
+        }
+        report.compacted += count;
+        info!(
+            conversation_id = conversation_id,
+            compacted = count,
+            "Compacted memory window into a summary"
+        );
+        Ok(())
+    }
+
+    /// Id of the summary of `entries`: a hash of their ids, so reruns over the same window name the
+    /// same summary. Version 8, so it never equals a (version 4) entry id.
+    fn summary_id(entries: &[MemoryEntry]) -> Uuid {
+        let mut ids: Vec<u128> = entries.iter().map(|e| e.id.as_u128()).collect();
+        ids.sort_unstable();
+        // FNV-1a (128-bit), stable across builds unlike `DefaultHasher`.
+        let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+        for byte in ids.iter().flat_map(|id| id.to_be_bytes()) {
+            hash ^= byte as u128;
+            hash = hash.wrapping_mul(0x0000000001000000000000000000013b);
+        }
+        Builder::from_custom_bytes(hash.to_be_bytes()).into_uuid()
+    }
+
+    /// Summary entry of a window with id `summary_id`; `None` when the summarizer or the embedding
+    /// fails (logged, the window is retried on the next run).
+    async fn summarize_window(
+        &self,
+        summarizer: &dyn MemorySummarizer,
+        conversation_id: &str,
+        entries: &[MemoryEntry],
+        summary_id: Uuid,
+    ) -> Result<Option<MemoryEntry>> {
+        let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+            return Ok(None);
+        };
+        let text = match summarizer.summarize(entries).await {
+            Ok(text) if !text.trim().is_empty() => text,
+            Ok(_) => {
+                warn!(
+                    conversation_id = conversation_id,
+                    "compaction: empty summary, window kept"
+                );
+                return Ok(None);
+            }
+            Err(e) => {
+                warn!(error = %e, conversation_id = conversation_id, "compaction: summarizer failed, window kept");
+                return Ok(None);
+            }
+        };
+        let content = format!(
+            "Summary of {} messages ({} – {} UTC): {}",
+            entries.len(),
+            first.metadata.timestamp.format("%Y-%m-%d %H:%M"),
+            last.metadata.timestamp.format("%Y-%m-%d %H:%M"),
+            text.trim()
+        );
+        let embedding = match self.embedding_service.embed(&content).await {
+            Ok(embedding) => embedding,
+            Err(e) => {
+                warn!(error = %e, conversation_id = conversation_id, "compaction: embedding summary failed, window kept");
+                return Ok(None);
+            }
+        };
+
+        let user_id = first.metadata.user_id.clone().filter(|u| {
+            entries
+                .iter()
+                .all(|e| e.metadata.user_id.as_deref() == Some(u.as_str()))
+        });
+        let importance = entries
+            .iter()
+            .filter_map(|e| e.metadata.importance)
+            .reduce(f32::max)
+            .unwrap_or(NEUTRAL_IMPORTANCE);
+        // The summary expires with the last original to expire, and never when one of them never does.
+        let expires_at = entries
+            .iter()
+            .map(|e| e.metadata.expires_at)
+            .try_fold(None, |latest: Option<DateTime<Utc>>, e| {
+                e.map(|t| Some(latest.map_or(t, |l| l.max(t))))
+            })
+            .flatten();
+        let tokens = self.tokenizer().count_tokens(&content) as u32;
+        let mut summary = MemoryEntry::new(
+            content,
+            MemoryMetadata {
+                user_id,
+                conversation_id: Some(conversation_id.to_string()),
+                role: MemoryRole::System,
+                timestamp: last.metadata.timestamp,
+                tokens: Some(tokens),
+                importance: Some(importance),
+                expires_at,
+            },
+        );
+        summary.id = summary_id;
+        summary.embedding = Some(embedding);
+        Ok(Some(summary))
+    }
+}
+
+/// Spawns a task that runs [`MemoryCompactor::compact_all`] every `interval` (first run immediately).
+/// Errors are logged and the next run still happens. Abort the returned handle to stop the job.
+pub fn spawn_compaction_job(
+    compactor: Arc<MemoryCompactor>,
+    interval: std::time::Duration,
+) -> JoinHandle<()> {
+    info!(
+        policy = ?compactor.policy(),
+        archive = compactor.archive_store().is_some(),
+        interval_secs = interval.as_secs(),
+        "Starting memory compaction job"
+    );
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = compactor.compact_all().await {
+                error!(error = %e, "Memory compaction failed");
+            }
+        }
+    })
+}
@@ -3,15 +3,70 @@
 use anyhow::Result;
 use std::env;
 
+use crate::memory::cache::DEFAULT_CACHE_WINDOW;
+use crate::memory::ttl::{parse_source_ttls, TtlPolicy};
+use crate::memory::write_pipeline::DEFAULT_DEDUP_THRESHOLD;
+use crate::memory_strategies::HybridWeights;
+
+/// Default MEMORY_MIN_IMPORTANCE: nothing is dropped. Filtering is opt-in because without a
+/// separate recent store the history is read from the primary store, which would then miss the
+/// dropped acknowledgements; 0.1 drops "ok", "thanks" and greetings (heuristic score 0.05).
+const DEFAULT_MIN_IMPORTANCE: f32 = 0.0;
+/// Default MEMORY_COMPACTION_MIN_ENTRIES: quieter windows are not worth a summary.
+const DEFAULT_COMPACTION_MIN_ENTRIES: usize = 10;
+
 /// Memory storage and RAG strategy configuration interface.
 pub trait MemoryConfig: Send + Sync {
     fn store_type(&self) -> &str;
     fn sqlite_path(&self) -> &str;
+    /// Whether the SQLite store keeps an HNSW index (persisted next to the database) for semantic search.
+    fn sqlite_hnsw(&self) -> bool;
     fn recent_use_sqlite(&self) -> bool;
     fn lance_path(&self) -> Option<&str>;
+    /// Snapshot file for the in-memory store (`MEMORY_STORE_TYPE=memory`); `None` keeps it ephemeral.
+    fn snapshot_path(&self) -> Option<&str>;
+    /// Autosave interval for the snapshot in seconds; 0 saves only at shutdown.
+    fn snapshot_interval_secs(&self) -> u64;
     fn recent_limit(&self) -> u32;
     fn relevant_top_k(&self) -> u32;
     fn semantic_min_score(&self) -> f32;
+    /// Half-life in hours of the recency decay applied to semantic search scores; `None` disables it.
+    fn semantic_decay_half_life_hours(&self) -> Option<u32>;
+    /// MMR trade-off of semantic search between relevance (1) and diversity (0); `None` disables MMR.
+    fn semantic_mmr_lambda(&self) -> Option<f32>;
+    /// Whether context retrieval uses [`HybridSearchStrategy`](crate::memory::HybridSearchStrategy) instead of pure vector search.
+    fn hybrid_search(&self) -> bool;
+    /// Fusion weights of the vector and keyword rankings for hybrid search.
+    fn hybrid_weights(&self) -> HybridWeights;
+    /// Importance scorer for memory writes: "heuristic" or "llm" (applications that provide an LLM scorer).
+    fn importance_scorer(&self) -> &str;
+    /// Entries scoring below this importance are not written to the primary store (0 by default).
+    fn min_importance(&self) -> f32;
+    /// Similarity at or above which a write is merged into a recent entry; `None` (the default)
+    /// disables merging.
+    fn dedup_threshold(&self) -> Option<f32>;
+    /// Weight of importance in retrieval ranking (0 = similarity only, 1 = fully scaled by importance).
+    fn importance_weight(&self) -> f32;
+    /// Entries older than this many days are summarized by memory compaction; `None` disables it.
+    fn compaction_after_days(&self) -> Option<u32>;
+    /// Length of a compaction window in hours.
+    fn compaction_window_hours(&self) -> u32;
+    /// Windows with fewer entries are not compacted.
+    fn compaction_min_entries(&self) -> usize;
+    /// SQLite file receiving compacted originals; `None` deletes them.
+    fn compaction_archive_path(&self) -> Option<&str>;
+    /// Interval between compaction runs, in seconds.
+    fn compaction_interval_secs(&self) -> u64;
+    /// Number of conversation summaries included in the context.
+    fn summary_limit(&self) -> usize;
+    /// Conversations (and, separately, users) kept in the read cache; 0 disables the cache.
+    fn cache_capacity(&self) -> usize;
+    /// Newest entries cached per conversation or user.
+    fn cache_window(&self) -> usize;
+    /// TTLs of new entries by role and source (chat type); nothing expires by default.
+    fn ttl_policy(&self) -> TtlPolicy;
+    /// Interval between expiry sweeps, in seconds.
+    fn ttl_sweep_interval_secs(&self) -> u64;
 }
 
 /// Memory config loaded from environment variables.
@@ -19,11 +74,32 @@ pub trait MemoryConfig: Send + Sync {
 pub struct EnvMemoryConfig {
     pub memory_store_type: String,
     pub memory_sqlite_path: String,
+    pub memory_sqlite_hnsw: bool,
     pub memory_recent_use_sqlite: bool,
     pub memory_lance_path: Option<String>,
+    pub memory_snapshot_path: Option<String>,
+    pub memory_snapshot_interval_secs: u64,
     pub memory_recent_limit: u32,
     pub memory_relevant_top_k: u32,
     pub memory_semantic_min_score: f32,
+    pub memory_semantic_decay_half_life_hours: Option<u32>,
+    pub memory_semantic_mmr_lambda: Option<f32>,
+    pub memory_hybrid_search: bool,
+    pub memory_hybrid_weights: HybridWeights,
+    pub memory_importance_scorer: String,
+    pub memory_min_importance: f32,
+    pub memory_dedup_threshold: Option<f32>,
+    pub memory_importance_weight: f32,
+    pub memory_compaction_after_days: Option<u32>,
+    pub memory_compaction_window_hours: u32,
+    pub memory_compaction_min_entries: usize,
+    pub memory_compaction_archive_path: Option<String>,
+    pub memory_compaction_interval_secs: u64,
+    pub memory_summary_limit: usize,
+    pub memory_cache_capacity: usize,
+    pub memory_cache_window: usize,
+    pub memory_ttl: TtlPolicy,
+    pub memory_ttl_sweep_interval_secs: u64,
 }
 
 impl MemoryConfig for EnvMemoryConfig {
@@ -33,12 +109,21 @@ impl MemoryConfig for EnvMemoryConfig {
     fn sqlite_path(&self) -> &str {
         &self.memory_sqlite_path
     }
+    fn sqlite_hnsw(&self) -> bool {
+        self.memory_sqlite_hnsw
+    }
     fn recent_use_sqlite(&self) -> bool {
         self.memory_recent_use_sqlite
     }
     fn lance_path(&self) -> Option<&str> {
         self.memory_lance_path.as_deref()
     }
+    fn snapshot_path(&self) -> Option<&str> {
+        self.memory_snapshot_path.as_deref()
+    }
+    fn snapshot_interval_secs(&self) -> u64 {
+        self.memory_snapshot_interval_secs
+    }
     fn recent_limit(&self) -> u32 {
         self.memory_recent_limit
     }
@@ -48,6 +133,60 @@ impl MemoryConfig for EnvMemoryConfig {
     fn semantic_min_score(&self) -> f32 {
         self.memory_semantic_min_score
     }
+    fn semantic_decay_half_life_hours(&self) -> Option<u32> {
+        self.memory_semantic_decay_half_life_hours
+    }
+    fn semantic_mmr_lambda(&self) -> Option<f32> {
+        self.memory_semantic_mmr_lambda
+    }
+    fn hybrid_search(&self) -> bool {
+        self.memory_hybrid_search
+    }
+    fn hybrid_weights(&self) -> HybridWeights {
+        self.memory_hybrid_weights
+    }
+    fn importance_scorer(&self) -> &str {
+        &self.memory_importance_scorer
+    }
+    fn min_importance(&self) -> f32 {
+        self.memory_min_importance
+    }
+    fn dedup_threshold(&self) -> Option<f32> {
+        self.memory_dedup_threshold
+    }
+    fn importance_weight(&self) -> f32 {
+        self.memory_importance_weight
+    }
+    fn compaction_after_days(&self) -> Option<u32> {
+        self.memory_compaction_after_days
+    }
+    fn compaction_window_hours(&self) -> u32 {
+        self.memory_compaction_window_hours
+    }
+    fn compaction_min_entries(&self) -> usize {
+        self.memory_compaction_min_entries
+    }
+    fn compaction_archive_path(&self) -> Option<&str> {
+        self.memory_compaction_archive_path.as_deref()
+    }
+    fn compaction_interval_secs(&self) -> u64 {
+        self.memory_compaction_interval_secs
+    }
+    fn summary_limit(&self) -> usize {
+        self.memory_summary_limit
+    }
+    fn cache_capacity(&self) -> usize {
+        self.memory_cache_capacity
+    }
+    fn cache_window(&self) -> usize {
+        self.memory_cache_window
+    }
+    fn ttl_policy(&self) -> TtlPolicy {
+        self.memory_ttl.clone()
+    }
+    fn ttl_sweep_interval_secs(&self) -> u64 {
+        self.memory_ttl_sweep_interval_secs
+    }
 }
 
 impl EnvMemoryConfig {
@@ -57,6 +196,13 @@ impl EnvMemoryConfig {
             env::var("MEMORY_STORE_TYPE").unwrap_or_else(|_| "memory".to_string());
         let memory_sqlite_path =
             env::var("MEMORY_SQLITE_PATH").unwrap_or_else(|_| "./data/memory.db".to_string());
+        let memory_sqlite_hnsw = env::var("MEMORY_SQLITE_HNSW")
+            .ok()
+            .and_then(|s| match s.to_lowercase().as_str() {
+                "1" | "true" | "yes" => Some(true),
+                _ => s.parse().ok(),
+            })
+            .unwrap_or(false);
         let memory_recent_use_sqlite = env::var("MEMORY_RECENT_USE_SQLITE")
             .ok()
             .and_then(|s| match s.to_lowercase().as_str() {
@@ -67,6 +213,13 @@ impl EnvMemoryConfig {
         let memory_lance_path = env::var("MEMORY_LANCE_PATH")
             .or_else(|_| env::var("LANCE_DB_PATH"))
             .ok();
+        let memory_snapshot_path = env::var("MEMORY_SNAPSHOT_PATH")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+        let memory_snapshot_interval_secs = env::var("MEMORY_SNAPSHOT_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
         let memory_recent_limit = env::var("MEMORY_RECENT_LIMIT")
             .ok()
             .and_then(|s| s.parse().ok())
@@ -79,14 +232,135 @@ impl EnvMemoryConfig {
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(0.0);
+        let memory_semantic_decay_half_life_hours = env::var("MEMORY_SEMANTIC_DECAY_HALF_LIFE")
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .filter(|h| *h > 0);
+        let memory_semantic_mmr_lambda = env::var("MEMORY_SEMANTIC_MMR_LAMBDA")
+            .ok()
+            .and_then(|s| s.trim().parse::<f32>().ok())
+            .map(|l| l.max(0.0))
+            .filter(|l| *l < 1.0);
+        let memory_hybrid_search = env::var("MEMORY_HYBRID_SEARCH")
+            .ok()
+            .and_then(|s| match s.to_lowercase().as_str() {
+                "1" | "true" | "yes" => Some(true),
+                _ => s.parse().ok(),
+            })
+            .unwrap_or(false);
+        let defaults = HybridWeights::default();
+        let memory_hybrid_weights = HybridWeights {
+            vector: env::var("MEMORY_HYBRID_VECTOR_WEIGHT")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.vector),
+            keyword: env::var("MEMORY_HYBRID_KEYWORD_WEIGHT")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.keyword),
+        };
+        let memory_importance_scorer = env::var("MEMORY_IMPORTANCE_SCORER")
+            .map(|s| s.trim().to_lowercase())
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "heuristic".to_string());
+        let memory_min_importance = env::var("MEMORY_MIN_IMPORTANCE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MIN_IMPORTANCE);
+        let memory_dedup_threshold = match env::var("MEMORY_DEDUP_THRESHOLD") {
+            Ok(s) => match s.trim().to_lowercase().as_str() {
+                "" | "0" | "off" | "false" | "no" => None,
+                other => Some(other.parse().unwrap_or(DEFAULT_DEDUP_THRESHOLD)),
+            },
+            Err(_) => None,
+        };
+        let memory_importance_weight = env::var("MEMORY_IMPORTANCE_WEIGHT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0.0);
+        let memory_compaction_after_days = env::var("MEMORY_COMPACTION_AFTER_DAYS")
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .filter(|d| *d > 0);
+        let memory_compaction_window_hours = env::var("MEMORY_COMPACTION_WINDOW_HOURS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24);
+        let memory_compaction_min_entries = env::var("MEMORY_COMPACTION_MIN_ENTRIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_COMPACTION_MIN_ENTRIES);
+        let memory_compaction_archive_path = env::var("MEMORY_COMPACTION_ARCHIVE_PATH")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+        let memory_compaction_interval_secs = env::var("MEMORY_COMPACTION_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(3600);
+        let memory_summary_limit = env::var("MEMORY_SUMMARY_LIMIT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3);
+        let memory_cache_capacity = env::var("MEMORY_CACHE_CAPACITY")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let memory_cache_window = env::var("MEMORY_CACHE_WINDOW")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|w| *w > 0)
+            .unwrap_or(DEFAULT_CACHE_WINDOW);
+        let ttl_hours = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse::<u32>().ok())
+        };
+        let memory_ttl = TtlPolicy::from_hours(
+            ttl_hours("MEMORY_TTL_USER_HOURS"),
+            ttl_hours("MEMORY_TTL_ASSISTANT_HOURS"),
+            ttl_hours("MEMORY_TTL_SYSTEM_HOURS"),
+            match env::var("MEMORY_TTL_SOURCES") {
+                Ok(s) => parse_source_ttls(&s)?,
+                Err(_) => Default::default(),
+            },
+        );
+        let memory_ttl_sweep_interval_secs = env::var("MEMORY_TTL_SWEEP_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(3600);
         Ok(Self {
             memory_store_type,
             memory_sqlite_path,
+            memory_sqlite_hnsw,
             memory_recent_use_sqlite,
             memory_lance_path,
+            memory_snapshot_path,
+            memory_snapshot_interval_secs,
             memory_recent_limit,
             memory_relevant_top_k,
             memory_semantic_min_score,
+            memory_semantic_decay_half_life_hours,
+            memory_semantic_mmr_lambda,
+            memory_hybrid_search,
+            memory_hybrid_weights,
+            memory_importance_scorer,
+            memory_min_importance,
+            memory_dedup_threshold,
+            memory_importance_weight,
+            memory_compaction_after_days,
+            memory_compaction_window_hours,
+            memory_compaction_min_entries,
+            memory_compaction_archive_path,
+            memory_compaction_interval_secs,
+            memory_summary_limit,
+            memory_cache_capacity,
+            memory_cache_window,
+            memory_ttl,
+            memory_ttl_sweep_interval_secs,
         })
     }
 }
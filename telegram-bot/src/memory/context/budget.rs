@@ -0,0 +1,123 @@
+//! Token budget for [`ContextBuilder`](super::ContextBuilder): per-section priority and share.
+
+use super::types::ContextSection;
+
+/// Priority and share of one context section.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectionBudget {
+    /// Lower values are served first, both for the reserved share and for spare tokens.
+    pub priority: u8,
+    /// Fraction of the token limit reserved for the section (0.0..=1.0).
+    pub share: f32,
+}
+
+/// How the token limit is split between context sections.
+///
+/// Each section first gets up to `share * limit` tokens. Tokens not used by a section are then given
+/// to sections that need more, in priority order. Defaults, highest priority first: system (0.25),
+/// preferences (0.10), recent (0.40), semantic (0.25).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextBudget {
+    sections: [SectionBudget; 4],
+}
+
+impl Default for ContextBudget {
+    fn default() -> Self {
+        Self {
+            sections: [
+                SectionBudget {
+                    priority: 0,
+                    share: 0.25,
+                },
+                SectionBudget {
+                    priority: 1,
+                    share: 0.10,
+                },
+                SectionBudget {
+                    priority: 2,
+                    share: 0.40,
+                },
+                SectionBudget {
+                    priority: 3,
+                    share: 0.25,
+                },
+            ],
+        }
+    }
+}
+
+impl ContextBudget {
+    /// Sets the priority and share of `section`; the share is clamped to 0.0..=1.0.
+    pub fn with_section(mut self, section: ContextSection, priority: u8, share: f32) -> Self {
+        let share = if share.is_finite() {
+            share.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        self.sections[section.index()] = SectionBudget { priority, share };
+        self
+    }
+
+    pub fn section(&self, section: ContextSection) -> SectionBudget {
+        self.sections[section.index()]
+    }
+
+    /// Sections by priority; ties keep the [`ContextSection::ALL`] order.
+    fn by_priority(&self) -> [ContextSection; 4] {
+        let mut order = ContextSection::ALL;
+        order.sort_by_key(|s| self.section(*s).priority);
+        order
+    }
+
+    /// Tokens allotted to each section (indexed like [`ContextSection::ALL`]) given the tokens each
+    /// one would need (`demand`). The allotments never add up to more than `limit`.
+    pub(crate) fn allocate(&self, limit: usize, demand: [usize; 4]) -> [usize; 4] {
+        let mut allotted = [0usize; 4];
+        let mut remaining = limit;
+        for section in self.by_priority() {
+            let i = section.index();
+            let reserved = (limit as f64 * self.sections[i].share as f64).floor() as usize;
+            allotted[i] = demand[i].min(reserved).min(remaining);
+            remaining -= allotted[i];
+        }
+        for section in self.by_priority() {
+            let i = section.index();
+            let extra = (demand[i] - allotted[i]).min(remaining);
+            allotted[i] += extra;
+            remaining -= extra;
+        }
+        allotted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spare_tokens_go_to_sections_in_priority_order() {
+        let budget = ContextBudget::default();
+        // The 250 tokens system and preferences leave unused go to recent before semantic.
+        assert_eq!(
+            budget.allocate(1000, [100, 0, 900, 400]),
+            [100, 0, 650, 250]
+        );
+        // Everything fits.
+        assert_eq!(budget.allocate(1000, [10, 20, 30, 40]), [10, 20, 30, 40]);
+
+        let semantic_first = budget.with_section(ContextSection::Semantic, 0, 0.25);
+        assert_eq!(
+            semantic_first.allocate(1000, [100, 0, 900, 400]),
+            [100, 0, 500, 400]
+        );
+    }
+
+    #[test]
+    fn shares_above_one_never_exceed_the_limit() {
+        let budget = ContextBudget::default()
+            .with_section(ContextSection::Recent, 2, 1.0)
+            .with_section(ContextSection::Semantic, 3, 1.0);
+        let allotted = budget.allocate(100, [0, 0, 80, 80]);
+        assert_eq!(allotted, [0, 0, 80, 20]);
+    }
+}
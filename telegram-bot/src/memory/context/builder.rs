@@ -1,19 +1,27 @@
 //! Context builder for assembling AI conversation context from strategies.
 
-use super::types::{Context, ContextMetadata};
-use super::utils::estimate_tokens;
-use crate::memory::{MessageCategory, MemoryStore, StrategyResult};
+use super::budget::ContextBudget;
+use super::types::{Context, ContextMetadata, ContextSection, DroppedItem};
+use crate::memory::{ContextItem, MessageCategory, MemoryStore, StrategyResult};
 use crate::memory::{ContextStrategy, StoreKind};
+use crate::tokenizer::{EstimateTokenizer, Tokenizer};
 use std::sync::Arc;
 use chrono::Utc;
-use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+use tracing::{debug, error, info, instrument, warn};
 
 /// Builder for constructing AI conversation context.
+///
+/// The built context fits `token_limit`: sections over their [`ContextBudget`] allotment are trimmed,
+/// recent messages oldest first and semantic results lowest score first; the system message and
+/// preferences are kept whole or dropped.
 pub struct ContextBuilder {
     store: Arc<dyn MemoryStore>,
     pub(crate) recent_store: Option<Arc<dyn MemoryStore>>,
     pub(crate) strategies: Vec<Box<dyn ContextStrategy>>,
     pub(crate) token_limit: usize,
+    pub(crate) budget: ContextBudget,
+    tokenizer: Arc<dyn Tokenizer>,
     pub(crate) user_id: Option<String>,
     pub(crate) conversation_id: Option<String>,
     pub(crate) query: Option<String>,
@@ -27,6 +35,8 @@ impl ContextBuilder {
             recent_store: None,
             strategies: Vec::new(),
             token_limit: 4096,
+            budget: ContextBudget::default(),
+            tokenizer: Arc::new(EstimateTokenizer),
             user_id: None,
             conversation_id: None,
             query: None,
@@ -49,6 +59,18 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets how the token limit is split between sections.
+    pub fn with_budget(mut self, budget: ContextBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Counts tokens with `tokenizer` (default: [`EstimateTokenizer`]).
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
     pub fn for_user(mut self, user_id: &str) -> Self {
         self.user_id = Some(user_id.to_string());
         self
@@ -115,9 +137,23 @@ impl ContextBuilder {
             );
         }
 
+        let mut system_message = self.system_message.clone();
+        let dropped = self.enforce_budget(
+            &mut system_message,
+            &mut preferences,
+            &mut recent_messages,
+            &mut semantic_messages,
+        );
+
         let message_count = recent_messages.len() + semantic_messages.len();
-        let total_tokens =
-            self.calculate_total_tokens(&recent_messages, &semantic_messages, &preferences);
+        let total_tokens = system_message
+            .iter()
+            .chain(preferences.iter())
+            .map(String::as_str)
+            .chain(recent_messages.iter().map(|m| m.content.as_str()))
+            .chain(semantic_messages.iter().map(|m| m.content.as_str()))
+            .map(|s| self.tokenizer.count_tokens(s))
+            .sum();
 
         let metadata = ContextMetadata {
             user_id: self.user_id.clone(),
@@ -125,6 +161,8 @@ impl ContextBuilder {
             total_tokens,
             message_count,
             created_at: Utc::now(),
+            token_limit: self.token_limit,
+            dropped,
         };
 
         debug!(
@@ -136,7 +174,7 @@ impl ContextBuilder {
         log_context_detail(&recent_messages, &semantic_messages, &preferences);
 
         Ok(Context {
-            system_message: self.system_message.clone(),
+            system_message,
             recent_messages,
             semantic_messages,
             user_preferences: preferences,
@@ -144,25 +182,99 @@ impl ContextBuilder {
         })
     }
 
-    fn calculate_total_tokens(
+    /// Trims each section to its allotment of `token_limit` and returns the dropped items.
+    fn enforce_budget(
         &self,
-        recent_messages: &[String],
-        semantic_messages: &[String],
-        preferences: &Option<String>,
-    ) -> usize {
-        self.system_message
-            .iter()
-            .chain(recent_messages.iter())
-            .chain(semantic_messages.iter())
-            .chain(preferences.iter())
-            .map(|s| estimate_tokens(s))
-            .sum()
+        system_message: &mut Option<String>,
+        preferences: &mut Option<String>,
+        recent_messages: &mut Vec<ContextItem>,
+        semantic_messages: &mut Vec<ContextItem>,
+    ) -> Vec<DroppedItem> {
+        let count = |text: &str| self.tokenizer.count_tokens(text);
+        let tokens = |items: &[ContextItem]| items.iter().map(|m| count(&m.content)).sum::<usize>();
+        let demand = [
+            system_message.as_deref().map_or(0, count),
+            preferences.as_deref().map_or(0, count),
+            tokens(recent_messages),
+            tokens(semantic_messages),
+        ];
+        let allotted = self.budget.allocate(self.token_limit, demand);
+
+        let mut dropped = Vec::new();
+        for section in ContextSection::ALL {
+            let allowance = allotted[section.index()];
+            match section {
+                // Kept whole or dropped.
+                ContextSection::System | ContextSection::Preferences => {
+                    let item = match section {
+                        ContextSection::System => &mut *system_message,
+                        _ => &mut *preferences,
+                    };
+                    if demand[section.index()] > allowance {
+                        if let Some(content) = item.take() {
+                            dropped.push(dropped_item(
+                                section,
+                                content,
+                                None,
+                                demand[section.index()],
+                            ));
+                        }
+                    }
+                }
+                // Oldest first: recent messages are in chronological order.
+                ContextSection::Recent => {
+                    let mut used = demand[section.index()];
+                    while used > allowance && !recent_messages.is_empty() {
+                        let item = recent_messages.remove(0);
+                        let tokens = count(&item.content);
+                        used -= tokens;
+                        dropped.push(dropped_item(section, item.content, item.entry_id, tokens));
+                    }
+                }
+                // Lowest score first: semantic results are ranked best first.
+                ContextSection::Semantic => {
+                    let mut used = demand[section.index()];
+                    while used > allowance {
+                        let Some(item) = semantic_messages.pop() else {
+                            break;
+                        };
+                        let tokens = count(&item.content);
+                        used -= tokens;
+                        dropped.push(dropped_item(section, item.content, item.entry_id, tokens));
+                    }
+                }
+            }
+        }
+        if !dropped.is_empty() {
+            warn!(
+                token_limit = self.token_limit,
+                dropped_count = dropped.len(),
+                dropped_tokens = dropped.iter().map(|d| d.tokens).sum::<usize>(),
+                "Context over token budget, dropped items"
+            );
+        }
+        dropped
+    }
+}
+
+fn dropped_item(
+    section: ContextSection,
+    content: String,
+    entry_id: Option<Uuid>,
+    tokens: usize,
+) -> DroppedItem {
+    debug!(section = ?section, entry_id = ?entry_id, tokens, "Context budget: dropped item");
+    DroppedItem {
+        section,
+        content,
+        entry_id,
+        tokens,
     }
 }
 
 fn log_context_detail(
-    recent_messages: &[String],
-    semantic_messages: &[String],
+    recent_messages: &[ContextItem],
+    semantic_messages: &[ContextItem],
     preferences: &Option<String>,
 ) {
     info!(
@@ -170,14 +282,14 @@ fn log_context_detail(
         "context_detail: recent messages"
     );
     for (i, msg) in recent_messages.iter().enumerate() {
-        info!(index = i, content = %msg, "recent messages");
+        info!(index = i, content = %msg.to_line(), "recent messages");
     }
     info!(
         count = semantic_messages.len(),
         "context_detail: semantic search"
     );
     for (i, msg) in semantic_messages.iter().enumerate() {
-        info!(index = i, content = %msg, "semantic search");
+        info!(index = i, content = %msg.to_line(), "semantic search");
     }
     if let Some(prefs) = preferences {
         info!(preferences = %prefs, "context_detail: user preferences");
@@ -190,13 +302,13 @@ fn apply_strategy_result(
     strategy_name: &str,
     strategy_index: usize,
     result: StrategyResult,
-    recent_messages: &mut Vec<String>,
-    semantic_messages: &mut Vec<String>,
+    recent_messages: &mut Vec<ContextItem>,
+    semantic_messages: &mut Vec<ContextItem>,
     preferences: &mut Option<String>,
 ) {
     match result {
         StrategyResult::Messages { category, messages } => {
-            let total_len: usize = messages.iter().map(|m| m.len()).sum();
+            let total_len: usize = messages.iter().map(|m| m.content.len()).sum();
             let label = match category {
                 MessageCategory::Recent => "recent messages",
                 MessageCategory::Semantic => "semantic search",
@@ -210,7 +322,13 @@ fn apply_strategy_result(
                 "Strategy returned messages"
             );
             for (i, msg) in messages.iter().enumerate() {
-                info!(strategy_name, index = i, content = %msg, label, "strategy message");
+                info!(
+                    strategy_name,
+                    index = i,
+                    content = %msg.to_line(),
+                    label,
+                    "strategy message"
+                );
             }
             match category {
                 MessageCategory::Recent => recent_messages.extend(messages),
@@ -1,14 +1,16 @@
 //! # Context Builder
 //!
 //! This module provides the `ContextBuilder` for constructing AI conversation context
-//! from memory store using various strategies.
+//! from memory store using various strategies, within a per-section token budget ([`ContextBudget`]).
 
+mod budget;
 mod builder;
 mod types;
 mod utils;
 
+pub use budget::{ContextBudget, SectionBudget};
 pub use builder::ContextBuilder;
-pub use types::{Context, ContextMetadata};
+pub use types::{Context, ContextMetadata, ContextSection, DroppedItem};
 pub use utils::estimate_tokens;
 
 #[cfg(test)]
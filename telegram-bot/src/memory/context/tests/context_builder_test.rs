@@ -1,5 +1,8 @@
 use crate::memory::context::*;
-use crate::memory::{ContextStrategy, MemoryEntry, MemoryStore, MessageCategory, StrategyResult};
+use crate::memory::{
+    ContextItem, ContextStrategy, MemoryEntry, MemoryRole, MemoryStore, MessageCategory,
+    StrategyResult,
+};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -77,6 +80,17 @@ impl MemoryStore for MockStore {
 
 struct MockStrategy;
 
+fn item(role: MemoryRole, content: &str) -> ContextItem {
+    ContextItem {
+        role,
+        content: content.to_string(),
+        author: None,
+        timestamp: chrono::Utc::now(),
+        entry_id: Some(Uuid::new_v4()),
+        score: None,
+    }
+}
+
 #[async_trait::async_trait]
 impl ContextStrategy for MockStrategy {
     fn name(&self) -> &str {
@@ -93,8 +107,8 @@ impl ContextStrategy for MockStrategy {
         Ok(StrategyResult::Messages {
             category: MessageCategory::Recent,
             messages: vec![
-                "User: Hello".to_string(),
-                "Assistant: Hi there!".to_string(),
+                item(MemoryRole::User, "Hello"),
+                item(MemoryRole::Assistant, "Hi there!"),
             ],
         })
     }
@@ -168,8 +182,10 @@ async fn test_context_builder_build_aggregates_strategy_result() {
         .expect("build should succeed");
 
     assert_eq!(context.recent_messages.len(), 2);
-    assert!(context.recent_messages[0].contains("Hello"));
-    assert!(context.recent_messages[1].contains("Hi there"));
+    assert_eq!(context.recent_messages[0].role, MemoryRole::User);
+    assert_eq!(context.recent_messages[0].content, "Hello");
+    assert_eq!(context.recent_messages[1].role, MemoryRole::Assistant);
+    assert_eq!(context.recent_messages[1].content, "Hi there!");
     assert!(context.semantic_messages.is_empty());
     assert_eq!(context.system_message.as_deref(), Some("System"));
     assert_eq!(context.metadata.user_id.as_deref(), Some("u1"));
@@ -177,3 +193,102 @@ async fn test_context_builder_build_aggregates_strategy_result() {
     assert_eq!(context.metadata.message_count, 2);
     assert!(context.metadata.total_tokens > 0);
 }
+
+/// Strategy returning fixed messages of one category.
+struct FixedStrategy(MessageCategory, Vec<ContextItem>);
+
+#[async_trait::async_trait]
+impl ContextStrategy for FixedStrategy {
+    fn name(&self) -> &str {
+        "FixedStrategy"
+    }
+
+    async fn build_context(
+        &self,
+        _store: &dyn MemoryStore,
+        _user_id: &Option<String>,
+        _conversation_id: &Option<String>,
+        _query: &Option<String>,
+    ) -> Result<StrategyResult, anyhow::Error> {
+        Ok(StrategyResult::Messages {
+            category: self.0,
+            messages: self.1.clone(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_context_builder_build_trims_sections_to_budget() {
+    let store = Arc::new(MockStore::new()) as Arc<dyn MemoryStore>;
+    // 10 tokens each (40 chars).
+    let msg = |c: char| c.to_string().repeat(40);
+    let items = |cs: &[char]| -> Vec<ContextItem> {
+        cs.iter()
+            .map(|c| item(MemoryRole::User, &msg(*c)))
+            .collect()
+    };
+    let recent = items(&['a', 'b', 'c', 'd']);
+    let semantic = items(&['x', 'y', 'z']);
+
+    let context = ContextBuilder::new(store)
+        .with_strategy(Box::new(FixedStrategy(
+            MessageCategory::Recent,
+            recent.clone(),
+        )))
+        .with_strategy(Box::new(FixedStrategy(
+            MessageCategory::Semantic,
+            semantic.clone(),
+        )))
+        .with_token_limit(50)
+        .with_budget(
+            ContextBudget::default()
+                .with_section(ContextSection::Recent, 2, 0.6)
+                .with_section(ContextSection::Semantic, 3, 0.4),
+        )
+        .build()
+        .await
+        .expect("build should succeed");
+
+    // Recent keeps its newest 3 (30 tokens), semantic its best 2 (20 tokens).
+    assert_eq!(context.recent_messages, recent[1..]);
+    assert_eq!(context.semantic_messages, semantic[..2]);
+    assert_eq!(context.metadata.total_tokens, 50);
+    assert_eq!(context.metadata.token_limit, 50);
+    let dropped: Vec<(ContextSection, String, Option<Uuid>)> = context
+        .metadata
+        .dropped
+        .iter()
+        .map(|d| (d.section, d.content.clone(), d.entry_id))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            (ContextSection::Recent, msg('a'), recent[0].entry_id),
+            (ContextSection::Semantic, msg('z'), semantic[2].entry_id),
+        ]
+    );
+    assert_eq!(context.dropped_tokens(), 20);
+}
+
+#[tokio::test]
+async fn test_context_builder_build_gives_unused_share_to_other_sections() {
+    let store = Arc::new(MockStore::new()) as Arc<dyn MemoryStore>;
+
+    // No semantic results: recent may use the whole limit.
+    let context = ContextBuilder::new(store)
+        .with_strategy(Box::new(FixedStrategy(
+            MessageCategory::Recent,
+            vec![
+                item(MemoryRole::User, "Hello"),
+                item(MemoryRole::Assistant, "Hi there!"),
+                item(MemoryRole::User, "Bye"),
+            ],
+        )))
+        .with_token_limit(6)
+        .build()
+        .await
+        .expect("build should succeed");
+
+    assert_eq!(context.recent_messages.len(), 3);
+    assert!(context.metadata.dropped.is_empty());
+}
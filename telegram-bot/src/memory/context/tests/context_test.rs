@@ -1,10 +1,22 @@
 use crate::memory::context::*;
-use chrono::Utc;
+use crate::memory::{ContextItem, MemoryRole};
+use chrono::{TimeZone, Utc};
+
+fn item(role: MemoryRole, content: &str) -> ContextItem {
+    ContextItem {
+        role,
+        content: content.to_string(),
+        author: (role == MemoryRole::User).then(|| "user1".to_string()),
+        timestamp: Utc.with_ymd_and_hms(2026, 5, 1, 10, 0, 0).unwrap(),
+        entry_id: None,
+        score: None,
+    }
+}
 
 fn make_context(
     system_message: Option<String>,
-    recent_messages: Vec<String>,
-    semantic_messages: Vec<String>,
+    recent_messages: Vec<ContextItem>,
+    semantic_messages: Vec<ContextItem>,
     user_preferences: Option<String>,
     total_tokens: usize,
 ) -> Context {
@@ -20,6 +32,8 @@ fn make_context(
             total_tokens,
             message_count,
             created_at: Utc::now(),
+            token_limit: 4096,
+            dropped: Vec::new(),
         },
     }
 }
@@ -28,7 +42,10 @@ fn make_context(
 fn test_format_for_model_with_system() {
     let ctx = make_context(
         Some("You are helpful.".to_string()),
-        vec!["User: Hi".to_string(), "Assistant: Hello".to_string()],
+        vec![
+            item(MemoryRole::User, "Hi"),
+            item(MemoryRole::Assistant, "Hello"),
+        ],
         vec![],
         None,
         10,
@@ -44,7 +61,7 @@ fn test_format_for_model_with_system() {
 fn test_format_for_model_without_system() {
     let ctx = make_context(
         Some("You are helpful.".to_string()),
-        vec!["User: Hi".to_string()],
+        vec![item(MemoryRole::User, "Hi")],
         vec![],
         None,
         5,
@@ -58,7 +75,7 @@ fn test_format_for_model_without_system() {
 fn test_format_for_model_with_preferences() {
     let ctx = make_context(
         None,
-        vec!["User: Hi".to_string()],
+        vec![item(MemoryRole::User, "Hi")],
         vec![],
         Some("Pref: English".to_string()),
         5,
@@ -90,8 +107,14 @@ fn test_exceeds_limit_equal() {
 fn test_format_for_model_distinguishes_recent_and_semantic() {
     let ctx = make_context(
         None,
-        vec!["User: Hi".to_string(), "Assistant: Hello".to_string()],
-        vec!["User: What do cats eat?".to_string(), "Assistant: Cats eat fish.".to_string()],
+        vec![
+            item(MemoryRole::User, "Hi"),
+            item(MemoryRole::Assistant, "Hello"),
+        ],
+        vec![
+            item(MemoryRole::User, "What do cats eat?"),
+            item(MemoryRole::Assistant, "Cats eat fish."),
+        ],
         None,
         20,
     );
@@ -108,41 +131,104 @@ fn test_format_for_model_distinguishes_recent_and_semantic() {
 fn test_is_empty() {
     let empty = make_context(None, vec![], vec![], None, 0);
     assert!(empty.is_empty());
-    let with_recent = make_context(None, vec!["x".to_string()], vec![], None, 5);
+    let with_recent = make_context(None, vec![item(MemoryRole::User, "x")], vec![], None, 5);
     assert!(!with_recent.is_empty());
-    let with_semantic = make_context(None, vec![], vec!["y".to_string()], None, 5);
+    let with_semantic = make_context(None, vec![], vec![item(MemoryRole::User, "y")], None, 5);
     assert!(!with_semantic.is_empty());
 }
 
 #[test]
 fn test_to_messages_returns_chat_messages_with_different_roles() {
-    use prompt::{MessageRole, SECTION_RECENT};
+    use prompt::{MessageRole, REFERENCE_OPEN, SECTION_SEMANTIC, SECTION_SUMMARY};
 
     let ctx = make_context(
         Some("You are helpful.".to_string()),
         vec![
-            "User: Hi".to_string(),
-            "Assistant: Hello".to_string(),
-            "User: What do cats eat?".to_string(),
+            item(MemoryRole::System, "Summary of 3 messages: greetings"),
+            item(MemoryRole::User, "Hi"),
+            item(MemoryRole::Assistant, "Hello"),
+            item(MemoryRole::User, "What do cats eat?"),
+        ],
+        vec![
+            item(MemoryRole::User, "What do dogs eat?"),
+            item(MemoryRole::Assistant, "Dogs eat dog food."),
         ],
-        vec!["User: What do dogs eat?".to_string(), "Assistant: Dogs eat dog food.".to_string()],
         Some("Pref: tea".to_string()),
         50,
     );
     let msgs = ctx.to_messages(true, "What about cats?");
-    assert!(msgs.len() >= 3);
-    let first = &msgs[0];
-    assert!(matches!(first.role, MessageRole::System));
-    assert_eq!(first.content, "You are helpful.");
-    assert!(matches!(msgs[1].role, MessageRole::User));
-    assert!(msgs[1].content.contains(SECTION_RECENT));
-    assert!(msgs[1].content.contains("Hi"));
-    assert!(msgs[1].content.contains("Hello"));
-    assert!(msgs[1].content.contains("What do cats eat?"));
-    assert!(msgs[1].content.contains("User Preferences: Pref: tea"));
-    assert!(msgs[1].content.contains("What do dogs eat?"));
-    assert!(msgs[1].content.contains("Dogs eat dog food."));
-    let last = msgs.last().unwrap();
-    assert!(matches!(last.role, MessageRole::User));
-    assert_eq!(last.content, "What about cats?");
+    assert_eq!(msgs.len(), 6);
+    assert!(matches!(msgs[0].role, MessageRole::System));
+    assert_eq!(msgs[0].content, "You are helpful.");
+
+    // Background: preferences, summaries and retrieved memories, delimited as reference.
+    let reference = &msgs[1];
+    assert!(matches!(reference.role, MessageRole::User));
+    assert!(reference.content.starts_with(REFERENCE_OPEN));
+    assert!(reference.content.contains("User Preferences: Pref: tea"));
+    assert!(reference.content.contains(SECTION_SUMMARY));
+    assert!(reference
+        .content
+        .contains("Summary of 3 messages: greetings"));
+    assert!(reference.content.contains(SECTION_SEMANTIC));
+    assert!(reference
+        .content
+        .contains("[2026-05-01 10:00] User (user1): What do dogs eat?"));
+    assert!(reference
+        .content
+        .contains("[2026-05-01 10:00] Assistant: Dogs eat dog food."));
+    assert!(!reference.content.contains("Hello"));
+
+    // Recent history as real turns, then the question.
+    assert!(matches!(msgs[2].role, MessageRole::User));
+    assert_eq!(msgs[2].content, "Hi");
+    assert!(matches!(msgs[3].role, MessageRole::Assistant));
+    assert_eq!(msgs[3].content, "Hello");
+    assert!(matches!(msgs[4].role, MessageRole::User));
+    assert_eq!(msgs[4].content, "What do cats eat?");
+    assert!(matches!(msgs[5].role, MessageRole::User));
+    assert_eq!(msgs[5].content, "What about cats?");
+}
+
+#[test]
+fn test_to_messages_prefixes_turns_of_other_users() {
+    let mut other = item(MemoryRole::User, "I prefer window seats");
+    other.author = Some("user2".to_string());
+    let ctx = make_context(
+        None,
+        vec![
+            other,
+            item(MemoryRole::Assistant, "Noted"),
+            item(MemoryRole::User, "Book me a seat"),
+        ],
+        vec![],
+        None,
+        10,
+    );
+    let msgs = ctx.to_messages(true, "Which one?");
+    assert_eq!(msgs[0].content, "User (user2): I prefer window seats");
+    assert_eq!(msgs[1].content, "Noted");
+    assert_eq!(msgs[2].content, "Book me a seat");
+}
+
+#[test]
+fn test_to_messages_without_background_has_no_reference_block() {
+    use prompt::MessageRole;
+
+    let ctx = make_context(
+        None,
+        vec![
+            item(MemoryRole::User, "Hi"),
+            item(MemoryRole::Assistant, "Hello"),
+        ],
+        vec![],
+        None,
+        5,
+    );
+    let msgs = ctx.to_messages(true, "How are you?");
+    assert_eq!(msgs.len(), 3);
+    assert!(matches!(msgs[0].role, MessageRole::User));
+    assert_eq!(msgs[0].content, "Hi");
+    assert!(matches!(msgs[1].role, MessageRole::Assistant));
+    assert_eq!(msgs[2].content, "How are you?");
 }
@@ -1,13 +1,19 @@
 //! Context and metadata types for AI conversation.
 
 use chrono::{DateTime, Utc};
+use prompt::ChatMessage;
+use uuid::Uuid;
+
+use crate::memory_core::{ContextItem, MemoryRole};
 
 /// Represents a constructed context for AI conversation.
 #[derive(Debug, Clone)]
 pub struct Context {
     pub system_message: Option<String>,
-    pub recent_messages: Vec<String>,
-    pub semantic_messages: Vec<String>,
+    /// Conversation history, oldest first; `System` items are summaries of older history.
+    pub recent_messages: Vec<ContextItem>,
+    /// Retrieved memories, best first.
+    pub semantic_messages: Vec<ContextItem>,
     pub user_preferences: Option<String>,
     pub metadata: ContextMetadata,
 }
@@ -17,9 +23,46 @@ pub struct Context {
 pub struct ContextMetadata {
     pub user_id: Option<String>,
     pub conversation_id: Option<String>,
+    /// Tokens of the context after the budget was enforced.
     pub total_tokens: usize,
     pub message_count: usize,
     pub created_at: DateTime<Utc>,
+    /// Token limit the context was built for.
+    pub token_limit: usize,
+    /// Items left out to stay within `token_limit`, in the order they were dropped.
+    pub dropped: Vec<DroppedItem>,
+}
+
+/// A section of the context; each has its own share of the token budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContextSection {
+    System,
+    Preferences,
+    Recent,
+    Semantic,
+}
+
+impl ContextSection {
+    pub const ALL: [ContextSection; 4] = [
+        ContextSection::System,
+        ContextSection::Preferences,
+        ContextSection::Recent,
+        ContextSection::Semantic,
+    ];
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A context item dropped by the token budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedItem {
+    pub section: ContextSection,
+    pub content: String,
+    /// Memory entry of a dropped recent or semantic item.
+    pub entry_id: Option<Uuid>,
+    pub tokens: usize,
 }
 
 impl Context {
@@ -28,22 +71,39 @@ impl Context {
             include_system,
             self.system_message.as_deref(),
             self.user_preferences.as_deref(),
-            &self.recent_messages,
-            &self.semantic_messages,
+            self.recent_messages.iter().map(ContextItem::to_line),
+            self.semantic_messages.iter().map(ContextItem::to_line),
         )
     }
 
-    pub fn to_messages(
-        &self,
-        include_system: bool,
-        current_question: &str,
-    ) -> Vec<prompt::ChatMessage> {
-        prompt::format_for_model_as_messages_with_roles(
+    /// Chat messages for the LLM: recent history as genuine user/assistant turns, preceded by a
+    /// delimited reference block with preferences, summaries and retrieved memories (dated, with
+    /// their author), and followed by `current_question`. In group chats, user turns from someone
+    /// other than `metadata.user_id` are prefixed with their author ("User (42): ...").
+    pub fn to_messages(&self, include_system: bool, current_question: &str) -> Vec<ChatMessage> {
+        let (summaries, turns): (Vec<&ContextItem>, Vec<&ContextItem>) = self
+            .recent_messages
+            .iter()
+            .partition(|item| item.role == MemoryRole::System);
+        let history = turns
+            .into_iter()
+            .map(|item| match item.role {
+                MemoryRole::Assistant => ChatMessage::assistant(item.content.as_str()),
+                _ => match item.author.as_deref() {
+                    Some(author) if Some(author) != self.metadata.user_id.as_deref() => {
+                        ChatMessage::user(format!("User ({}): {}", author, item.content))
+                    }
+                    _ => ChatMessage::user(item.content.as_str()),
+                },
+            })
+            .collect();
+        prompt::format_context_as_messages(
             include_system,
             self.system_message.as_deref(),
             self.user_preferences.as_deref(),
-            &self.recent_messages,
-            &self.semantic_messages,
+            summaries.iter().map(|item| item.content.as_str()),
+            history,
+            self.semantic_messages.iter().map(reference_line),
             current_question,
         )
     }
@@ -55,4 +115,33 @@ impl Context {
     pub fn exceeds_limit(&self, limit: usize) -> bool {
         self.metadata.total_tokens > limit
     }
+
+    /// Tokens of the items dropped by the token budget.
+    pub fn dropped_tokens(&self) -> usize {
+        self.metadata.dropped.iter().map(|d| d.tokens).sum()
+    }
+}
+
+/// "[2026-05-01 10:00] User (42): content" line of a retrieved memory.
+fn reference_line(item: &ContextItem) -> String {
+    let role = match item.role {
+        MemoryRole::User => "User",
+        MemoryRole::Assistant => "Assistant",
+        MemoryRole::System => "Summary",
+    };
+    match item.author.as_deref() {
+        Some(author) => format!(
+            "[{}] {} ({}): {}",
+            item.timestamp.format("%Y-%m-%d %H:%M"),
+            role,
+            author,
+            item.content
+        ),
+        None => format!(
+            "[{}] {}: {}",
+            item.timestamp.format("%Y-%m-%d %H:%M"),
+            role,
+            item.content
+        ),
+    }
 }
@@ -0,0 +1,343 @@
+//! Change feed for memory stores: [`ObservableMemoryStore`] wraps any [`MemoryStore`] and publishes
+//! a [`MemoryEvent`] on a [`MemoryEvents`] broadcast after every successful write.
+//!
+//! Subscribers (secondary indexes, caches, replicas, audit logs) call [`MemoryEvents::subscribe`]
+//! and receive events in write order. Delivery is in-process and best effort: a subscriber that
+//! falls more than the channel capacity behind skips the oldest events and is told how many it
+//! missed. Writes that bypass the wrapper are not published. [`spawn_replication`] is the built-in
+//! subscriber that mirrors the feed into another store (e.g. the recent store) and acknowledges the
+//! events it applied, so writers can wait for the replica with [`Replication::caught_up`].
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::memory_core::{
+    EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryOrder, MemoryPage, MemoryStore,
+    SearchFilter,
+};
+
+/// Default number of events buffered per subscriber before the oldest are dropped.
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// One write to an observed store. Bulk deletes carry their selector and the number of entries removed.
+#[derive(Debug, Clone)]
+pub enum MemoryEvent {
+    /// An entry was added (one event per entry of a batch).
+    Added(MemoryEntry),
+    /// An entry was replaced.
+    Updated(MemoryEntry),
+    /// The entry with this id was deleted.
+    Deleted(Uuid),
+    /// All entries of a conversation were deleted.
+    ConversationDeleted { conversation_id: String, count: u64 },
+    /// All entries of a user were deleted.
+    UserDeleted { user_id: String, count: u64 },
+    /// Entries that expired at or before `now` were deleted.
+    ExpiredDeleted { now: DateTime<Utc>, count: u64 },
+}
+
+/// Broadcast channel of [`MemoryEvent`]s. Cloning shares the channel.
+#[derive(Debug, Clone)]
+pub struct MemoryEvents {
+    sender: broadcast::Sender<MemoryEvent>,
+    /// Number of events sent to at least one subscriber; the lock keeps it in channel order.
+    published: Arc<Mutex<u64>>,
+}
+
+impl Default for MemoryEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+impl MemoryEvents {
+    /// Channel buffering up to `capacity` events per subscriber (at least 1).
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            published: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Receives every event published after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<MemoryEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Subscribes and returns the number of events published before the subscription.
+    fn subscribe_counted(&self) -> (broadcast::Receiver<MemoryEvent>, u64) {
+        let published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        (self.sender.subscribe(), *published)
+    }
+
+    /// Number of events published so far (events nobody subscribed to are not counted).
+    pub fn published(&self) -> u64 {
+        *self.published.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Number of live subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Publishes `event`; it is dropped when nobody subscribes.
+    pub fn publish(&self, event: MemoryEvent) {
+        let mut published = self.published.lock().unwrap_or_else(|e| e.into_inner());
+        if self.sender.send(event).is_ok() {
+            *published += 1;
+        }
+    }
+
+    /// Builds and publishes the event only when someone subscribes.
+    fn publish_with(&self, event: impl FnOnce() -> MemoryEvent) {
+        if self.subscriber_count() > 0 {
+            self.publish(event());
+        }
+    }
+}
+
+/// [`MemoryStore`] wrapper that publishes every successful write of the inner store on a [`MemoryEvents`] channel.
+/// Reads pass through unchanged.
+pub struct ObservableMemoryStore {
+    inner: Arc<dyn MemoryStore>,
+    events: MemoryEvents,
+}
+
+impl ObservableMemoryStore {
+    /// Wraps `inner`, publishing on `events`.
+    pub fn new(inner: Arc<dyn MemoryStore>, events: MemoryEvents) -> Self {
+        Self { inner, events }
+    }
+
+    /// The channel this store publishes on.
+    pub fn events(&self) -> &MemoryEvents {
+        &self.events
+    }
+}
+
+#[async_trait]
+impl MemoryStore for ObservableMemoryStore {
+    async fn add(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        let event = (self.events.subscriber_count() > 0).then(|| entry.clone());
+        self.inner.add(entry).await?;
+        if let Some(entry) = event {
+            self.events.publish(MemoryEvent::Added(entry));
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.inner.flush().await
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        self.inner.embedding_spec().await
+    }
+
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        self.inner.bind_embedding_spec(spec).await
+    }
+
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        self.inner.reset_embeddings(spec).await
+    }
+
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        let events = (self.events.subscriber_count() > 0).then(|| entries.clone());
+        self.inner.add_batch(entries).await?;
+        for entry in events.into_iter().flatten() {
+            self.events.publish(MemoryEvent::Added(entry));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
+        self.inner.get(id).await
+    }
+
+    async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        let event = (self.events.subscriber_count() > 0).then(|| entry.clone());
+        self.inner.update(entry).await?;
+        if let Some(entry) = event {
+            self.events.publish(MemoryEvent::Updated(entry));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), anyhow::Error> {
+        self.inner.delete(id).await?;
+        self.events.publish_with(|| MemoryEvent::Deleted(id));
+        Ok(())
+    }
+
+    async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.inner.search_by_user(user_id).await
+    }
+
+    async fn search_by_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.inner.search_by_conversation(conversation_id).await
+    }
+
+    async fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        user_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner
+            .semantic_search(query_embedding, limit, user_id, conversation_id)
+            .await
+    }
+
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner
+            .semantic_search_filtered(query_embedding, limit, filter)
+            .await
+    }
+
+    async fn keyword_search(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.inner.keyword_search(query, limit, filter).await
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        self.inner.list(filter, order, limit, cursor).await
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        self.inner.count(filter).await
+    }
+
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        let count = self.inner.delete_by_conversation(conversation_id).await?;
+        self.events
+            .publish_with(|| MemoryEvent::ConversationDeleted {
+                conversation_id: conversation_id.to_string(),
+                count,
+            });
+        Ok(count)
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        let count = self.inner.delete_by_user(user_id).await?;
+        self.events.publish_with(|| MemoryEvent::UserDeleted {
+            user_id: user_id.to_string(),
+            count,
+        });
+        Ok(count)
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let count = self.inner.delete_expired(now).await?;
+        self.events
+            .publish_with(|| MemoryEvent::ExpiredDeleted { now, count });
+        Ok(count)
+    }
+}
+
+/// Running [`spawn_replication`] task. It acknowledges every event it handled, so writers that need
+/// the target current wait with [`caught_up`](Self::caught_up) instead of writing it themselves.
+#[derive(Debug)]
+pub struct Replication {
+    /// Published-event counter of the replicated channel (not a sender, so the task still ends
+    /// when the channel is dropped).
+    published: Arc<Mutex<u64>>,
+    applied: watch::Receiver<u64>,
+    task: JoinHandle<()>,
+}
+
+impl Replication {
+    /// Waits until the target has handled every event published before this call. Returns at once
+    /// when the task has ended.
+    pub async fn caught_up(&self) {
+        let published = *self.published.lock().unwrap_or_else(|e| e.into_inner());
+        let mut applied = self.applied.clone();
+        let _ = applied.wait_for(|applied| *applied >= published).await;
+    }
+
+    /// Stops the replication.
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
+/// Spawns a subscriber that applies every event of `events` to `target`, so `target` mirrors the
+/// writes made through the observed store from now on. Added and updated entries are upserted.
+///
+/// Replication is asynchronous and best effort: failed writes are logged and not retried, and a
+/// subscriber that lags behind loses the skipped events (logged with their number); the affected
+/// entries stay stale in `target` until rewritten. Both count as handled for
+/// [`Replication::caught_up`]. The task ends when every sender is dropped.
+pub fn spawn_replication(events: &MemoryEvents, target: Arc<dyn MemoryStore>) -> Replication {
+    let (mut receiver, published) = events.subscribe_counted();
+    let (ack, applied) = watch::channel(published);
+    info!("Starting memory replication");
+    let task = tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Err(e) = apply_event(target.as_ref(), event).await {
+                        error!(error = %e, "Memory replication write failed");
+                    }
+                    ack.send_modify(|applied| *applied += 1);
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(
+                        skipped,
+                        "Memory replication fell behind; events were skipped"
+                    );
+                    ack.send_modify(|applied| *applied += skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+    Replication {
+        published: events.published.clone(),
+        applied,
+        task,
+    }
+}
+
+async fn apply_event(target: &dyn MemoryStore, event: MemoryEvent) -> Result<(), anyhow::Error> {
+    match event {
+        MemoryEvent::Added(entry) | MemoryEvent::Updated(entry) => target.update(entry).await,
+        MemoryEvent::Deleted(id) => target.delete(id).await,
+        MemoryEvent::ConversationDeleted {
+            conversation_id, ..
+        } => target
+            .delete_by_conversation(&conversation_id)
+            .await
+            .map(|_| ()),
+        MemoryEvent::UserDeleted { user_id, .. } => {
+            target.delete_by_user(&user_id).await.map(|_| ())
+        }
+        MemoryEvent::ExpiredDeleted { now, .. } => target.delete_expired(now).await.map(|_| ()),
+    }
+}
@@ -0,0 +1,647 @@
+//! HNSW (hierarchical navigable small world) index over memory embeddings.
+//!
+//! Used by [`SQLiteVectorStore`](super::SQLiteVectorStore) for approximate nearest-neighbour search.
+//! Vectors are L2-normalized on insert, so the returned score equals cosine similarity. Deletes are
+//! tombstones (the node keeps routing the graph but is never returned); the graph is rebuilt from
+//! live nodes once tombstones outnumber them. Each node keeps its user and conversation id so
+//! filtered searches only collect matching nodes while still walking the whole graph.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::RwLock;
+
+use anyhow::{anyhow, bail};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::codec::{read_f32s, read_opt_str, read_u32, write_f32s, write_opt_str, write_u32};
+
+/// File header of a persisted index.
+const MAGIC: &[u8; 6] = b"HNSW01";
+
+/// Rebuild is considered only above this many nodes.
+const COMPACT_MIN_NODES: usize = 64;
+
+/// Persist the index after this many unsaved mutations.
+const SAVE_EVERY: usize = 256;
+
+/// Graph construction and search parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HnswParams {
+    /// Neighbours per node on upper layers (layer 0 keeps up to `2 * m`).
+    pub m: usize,
+    /// Beam width while inserting.
+    pub ef_construction: usize,
+    /// Beam width while searching (raised to `k` when smaller).
+    pub ef_search: usize,
+}
+
+impl Default for HnswParams {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+        }
+    }
+}
+
+/// One indexed embedding.
+#[derive(Debug, Clone)]
+pub struct HnswNode {
+    pub id: Uuid,
+    pub user_id: Option<String>,
+    pub conversation_id: Option<String>,
+    vector: Vec<f32>,
+    /// `neighbors[layer]` for layers `0..=level`.
+    neighbors: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+/// Distance with a total order, for the search heaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dist(f32);
+
+impl Eq for Dist {}
+
+impl PartialOrd for Dist {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Dist {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// In-memory HNSW graph.
+#[derive(Debug, Clone)]
+pub struct HnswIndex {
+    params: HnswParams,
+    dim: Option<usize>,
+    nodes: Vec<HnswNode>,
+    by_id: HashMap<Uuid, u32>,
+    entry_point: Option<u32>,
+    deleted: usize,
+    rng: u64,
+}
+
+impl HnswIndex {
+    pub fn new(params: HnswParams) -> Self {
+        Self {
+            params: HnswParams {
+                m: params.m.max(2),
+                ef_construction: params.ef_construction.max(1),
+                ef_search: params.ef_search.max(1),
+            },
+            dim: None,
+            nodes: Vec::new(),
+            by_id: HashMap::new(),
+            entry_point: None,
+            deleted: 0,
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn params(&self) -> HnswParams {
+        self.params
+    }
+
+    /// Number of live (not deleted) entries.
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    pub fn contains(&self, id: &Uuid) -> bool {
+        self.by_id.contains_key(id)
+    }
+
+    /// Ids of all live entries.
+    pub fn ids(&self) -> impl Iterator<Item = &Uuid> {
+        self.by_id.keys()
+    }
+
+    /// Dimension fixed by the first insert; `None` while the index has never held a vector.
+    pub fn dim(&self) -> Option<usize> {
+        self.dim
+    }
+
+    /// Inserts or replaces the embedding for `id`.
+    pub fn insert(
+        &mut self,
+        id: Uuid,
+        vector: &[f32],
+        user_id: Option<String>,
+        conversation_id: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        // Drop the previous version first; a rejected vector must not leave a stale one behind.
+        self.remove(&id);
+        if vector.is_empty() {
+            bail!("cannot index an empty vector");
+        }
+        match self.dim {
+            Some(dim) if dim != vector.len() => {
+                bail!("vector dimension {} does not match index dimension {}", vector.len(), dim)
+            }
+            _ => self.dim = Some(vector.len()),
+        }
+
+        let vector = normalize(vector);
+        let level = self.random_level();
+        let idx = self.nodes.len() as u32;
+        self.nodes.push(HnswNode {
+            id,
+            user_id,
+            conversation_id,
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.by_id.insert(id, idx);
+
+        let Some(entry_point) = self.entry_point else {
+            self.entry_point = Some(idx);
+            return Ok(());
+        };
+        let top = self.level_of(entry_point);
+        let query = self.nodes[idx as usize].vector.clone();
+
+        let mut current = entry_point;
+        for layer in (level + 1..=top).rev() {
+            current = self.greedy_closest(&query, current, layer);
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, current, self.params.ef_construction, layer, |_| true);
+            let selected: Vec<u32> = found
+                .iter()
+                .map(|&(_, n)| n)
+                .filter(|&n| n != idx)
+                .take(self.params.m)
+                .collect();
+            for &neighbor in &selected {
+                self.connect(neighbor, idx, layer);
+            }
+            self.nodes[idx as usize].neighbors[layer] = selected;
+            if let Some(&(_, closest)) = found.first() {
+                current = closest;
+            }
+        }
+        if level > top {
+            self.entry_point = Some(idx);
+        }
+        Ok(())
+    }
+
+    /// Tombstones the entry; returns false if it was not indexed.
+    pub fn remove(&mut self, id: &Uuid) -> bool {
+        let Some(idx) = self.by_id.remove(id) else {
+            return false;
+        };
+        self.nodes[idx as usize].deleted = true;
+        self.deleted += 1;
+        if self.nodes.len() >= COMPACT_MIN_NODES && self.deleted > self.by_id.len() {
+            self.compact();
+        }
+        true
+    }
+
+    /// Tombstones every live entry matching `predicate`; returns how many were removed.
+    pub fn remove_where(&mut self, predicate: impl Fn(&HnswNode) -> bool) -> usize {
+        let ids: Vec<Uuid> = self
+            .nodes
+            .iter()
+            .filter(|n| !n.deleted && predicate(n))
+            .map(|n| n.id)
+            .collect();
+        for id in &ids {
+            self.remove(id);
+        }
+        ids.len()
+    }
+
+    /// Rebuilds the graph from live nodes, dropping tombstones.
+    pub fn compact(&mut self) {
+        let live: Vec<HnswNode> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .filter(|n| !n.deleted)
+            .collect();
+        info!(live = live.len(), tombstones = self.deleted, "Compacting HNSW index");
+        let mut rebuilt = HnswIndex::new(self.params);
+        rebuilt.rng = self.rng;
+        for node in live {
+            // Same dimension as before; insert cannot fail.
+            let _ = rebuilt.insert(node.id, &node.vector, node.user_id, node.conversation_id);
+        }
+        *self = rebuilt;
+    }
+
+    /// Returns up to `k` live entries accepted by `accept`, most similar first, as (id, cosine similarity).
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        accept: impl Fn(&HnswNode) -> bool,
+    ) -> Vec<(Uuid, f32)> {
+        let Some(entry_point) = self.entry_point else {
+            return Vec::new();
+        };
+        if k == 0 || self.dim != Some(query.len()) {
+            return Vec::new();
+        }
+        let query = normalize(query);
+        let mut current = entry_point;
+        for layer in (1..=self.level_of(entry_point)).rev() {
+            current = self.greedy_closest(&query, current, layer);
+        }
+        let ef = self.params.ef_search.max(k);
+        self.search_layer(&query, current, ef, 0, |n| !n.deleted && accept(n))
+            .into_iter()
+            .take(k)
+            .map(|(d, idx)| (self.nodes[idx as usize].id, 1.0 - d.0))
+            .collect()
+    }
+
+    fn level_of(&self, idx: u32) -> usize {
+        self.nodes[idx as usize].neighbors.len() - 1
+    }
+
+    fn distance(&self, query: &[f32], idx: u32) -> Dist {
+        let v = &self.nodes[idx as usize].vector;
+        Dist(1.0 - query.iter().zip(v).map(|(a, b)| a * b).sum::<f32>())
+    }
+
+    fn greedy_closest(&self, query: &[f32], start: u32, layer: usize) -> u32 {
+        let mut current = start;
+        let mut best = self.distance(query, current);
+        loop {
+            let mut improved = false;
+            for &n in self.neighbors(current, layer) {
+                let d = self.distance(query, n);
+                if d < best {
+                    best = d;
+                    current = n;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return current;
+            }
+        }
+    }
+
+    fn neighbors(&self, idx: u32, layer: usize) -> &[u32] {
+        self.nodes[idx as usize]
+            .neighbors
+            .get(layer)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Beam search on one layer. Every reachable node is explored while fewer than `ef` accepted
+    /// nodes are known, so selective filters degrade to a full walk rather than missing results.
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry: u32,
+        ef: usize,
+        layer: usize,
+        accept: impl Fn(&HnswNode) -> bool,
+    ) -> Vec<(Dist, u32)> {
+        let mut visited: HashSet<u32> = HashSet::from([entry]);
+        let entry_dist = self.distance(query, entry);
+        let mut candidates = BinaryHeap::from([Reverse((entry_dist, entry))]);
+        let mut results: BinaryHeap<(Dist, u32)> = BinaryHeap::new();
+        if accept(&self.nodes[entry as usize]) {
+            results.push((entry_dist, entry));
+        }
+
+        while let Some(Reverse((dist, idx))) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|&(worst, _)| dist > worst) {
+                break;
+            }
+            for &n in self.neighbors(idx, layer) {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let d = self.distance(query, n);
+                let full = results.len() >= ef;
+                if full && results.peek().is_some_and(|&(worst, _)| d >= worst) {
+                    continue;
+                }
+                candidates.push(Reverse((d, n)));
+                if accept(&self.nodes[n as usize]) {
+                    results.push((d, n));
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Adds `to` to the neighbour list of `from`, keeping only the closest when over capacity.
+    fn connect(&mut self, from: u32, to: u32, layer: usize) {
+        let cap = if layer == 0 { self.params.m * 2 } else { self.params.m };
+        let node = &self.nodes[from as usize];
+        if layer >= node.neighbors.len() || node.neighbors[layer].contains(&to) {
+            return;
+        }
+        let mut list = node.neighbors[layer].clone();
+        list.push(to);
+        if list.len() > cap {
+            let base = node.vector.clone();
+            list.sort_by_key(|&n| self.distance(&base, n));
+            list.truncate(cap);
+        }
+        self.nodes[from as usize].neighbors[layer] = list;
+    }
+
+    /// Geometric level with mean 1 / ln(m), from a xorshift generator (deterministic per index).
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let uniform = ((self.rng >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let level = -uniform.ln() / (self.params.m as f64).ln();
+        (level as usize).min(16)
+    }
+
+    /// Writes the index to `path` atomically (temp file + rename).
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let tmp = path.with_extension("tmp");
+        let mut out = io::BufWriter::new(fs::File::create(&tmp)?);
+        out.write_all(MAGIC)?;
+        for v in [self.params.m, self.params.ef_construction, self.params.ef_search, self.dim.unwrap_or(0)] {
+            write_u32(&mut out, v as u32)?;
+        }
+        write_u32(&mut out, self.entry_point.unwrap_or(u32::MAX))?;
+        out.write_all(&self.rng.to_le_bytes())?;
+        write_u32(&mut out, self.nodes.len() as u32)?;
+        for node in &self.nodes {
+            out.write_all(node.id.as_bytes())?;
+            out.write_all(&[node.deleted as u8])?;
+            write_opt_str(&mut out, node.user_id.as_deref())?;
+            write_opt_str(&mut out, node.conversation_id.as_deref())?;
+            write_f32s(&mut out, &node.vector)?;
+            write_u32(&mut out, node.neighbors.len() as u32)?;
+            for layer in &node.neighbors {
+                write_u32(&mut out, layer.len() as u32)?;
+                for &n in layer {
+                    write_u32(&mut out, n)?;
+                }
+            }
+        }
+        out.into_inner().map_err(|e| anyhow!("flush HNSW index: {}", e))?.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Reads an index written by [`save`](Self::save).
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let mut input = io::BufReader::new(fs::File::open(path)?);
+        let mut magic = [0u8; 6];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("{} is not an HNSW index file", path.display());
+        }
+        let m = read_u32(&mut input)? as usize;
+        let ef_construction = read_u32(&mut input)? as usize;
+        let ef_search = read_u32(&mut input)? as usize;
+        let dim = read_u32(&mut input)? as usize;
+        let entry_point = read_u32(&mut input)?;
+        let mut rng = [0u8; 8];
+        input.read_exact(&mut rng)?;
+        let count = read_u32(&mut input)?;
+
+        let mut index = HnswIndex::new(HnswParams { m, ef_construction, ef_search });
+        index.dim = (dim > 0).then_some(dim);
+        index.entry_point = (entry_point != u32::MAX).then_some(entry_point);
+        index.rng = u64::from_le_bytes(rng);
+        for idx in 0..count {
+            let mut id = [0u8; 16];
+            input.read_exact(&mut id)?;
+            let mut deleted = [0u8; 1];
+            input.read_exact(&mut deleted)?;
+            let user_id = read_opt_str(&mut input)?;
+            let conversation_id = read_opt_str(&mut input)?;
+            let vector = read_f32s(&mut input, dim)?;
+            let layers = read_u32(&mut input)? as usize;
+            let mut neighbors = Vec::with_capacity(layers);
+            for _ in 0..layers {
+                let len = read_u32(&mut input)? as usize;
+                let mut layer = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let n = read_u32(&mut input)?;
+                    if n >= count {
+                        bail!("HNSW index file has a neighbour out of range");
+                    }
+                    layer.push(n);
+                }
+                neighbors.push(layer);
+            }
+            if neighbors.is_empty() {
+                bail!("HNSW index file has a node without layers");
+            }
+            let node = HnswNode {
+                id: Uuid::from_bytes(id),
+                user_id,
+                conversation_id,
+                vector,
+                neighbors,
+                deleted: deleted[0] != 0,
+            };
+            if node.deleted {
+                index.deleted += 1;
+            } else {
+                index.by_id.insert(node.id, idx);
+            }
+            index.nodes.push(node);
+        }
+        if index.entry_point.is_some_and(|e| e >= count) {
+            bail!("HNSW index file has an invalid entry point");
+        }
+        Ok(index)
+    }
+}
+
+/// [`HnswIndex`] bound to a file: saved every [`SAVE_EVERY`] mutations and when dropped.
+#[derive(Debug)]
+pub struct PersistentHnsw {
+    index: RwLock<HnswIndex>,
+    path: PathBuf,
+    unsaved: AtomicUsize,
+}
+
+impl PersistentHnsw {
+    /// Loads the index from `path`, or starts an empty one when the file is missing or unreadable.
+    /// Returns whether the file was loaded; callers rebuild from their data when it was not.
+    pub fn open(path: impl Into<PathBuf>, params: HnswParams) -> (Self, bool) {
+        let path = path.into();
+        let (index, loaded) = if path.exists() {
+            match HnswIndex::load(&path) {
+                Ok(index) => (index, true),
+                Err(e) => {
+                    warn!(error = %e, path = %path.display(), "Failed to load HNSW index; rebuilding");
+                    (HnswIndex::new(params), false)
+                }
+            }
+        } else {
+            (HnswIndex::new(params), false)
+        };
+        let persistent = Self {
+            index: RwLock::new(index),
+            path,
+            unsaved: AtomicUsize::new(0),
+        };
+        (persistent, loaded)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs `f` with shared access.
+    pub fn read<T>(&self, f: impl FnOnce(&HnswIndex) -> T) -> T {
+        let index = self.index.read().unwrap_or_else(|e| e.into_inner());
+        f(&index)
+    }
+
+    /// Runs `f` with exclusive access, counting `mutations` towards the next save.
+    pub fn write<T>(&self, mutations: usize, f: impl FnOnce(&mut HnswIndex) -> T) -> T {
+        let result = {
+            let mut index = self.index.write().unwrap_or_else(|e| e.into_inner());
+            f(&mut index)
+        };
+        if mutations > 0
+            && self.unsaved.fetch_add(mutations, AtomicOrdering::SeqCst) + mutations >= SAVE_EVERY
+        {
+            if let Err(e) = self.save() {
+                warn!(error = %e, path = %self.path.display(), "Failed to save HNSW index");
+            }
+        }
+        result
+    }
+
+    /// Writes the index to disk now.
+    pub fn save(&self) -> Result<(), anyhow::Error> {
+        self.unsaved.store(0, AtomicOrdering::SeqCst);
+        let index = self.index.read().unwrap_or_else(|e| e.into_inner());
+        index.save(&self.path)
+    }
+}
+
+impl Drop for PersistentHnsw {
+    fn drop(&mut self) {
+        if self.unsaved.load(AtomicOrdering::SeqCst) > 0 {
+            if let Err(e) = self.save() {
+                warn!(error = %e, path = %self.path.display(), "Failed to save HNSW index on drop");
+            }
+        }
+    }
+}
+
+fn normalize(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        v.to_vec()
+    } else {
+        v.iter().map(|x| x / norm).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors.
+    fn vectors(count: usize, dim: usize) -> Vec<Vec<f32>> {
+        let mut state: u64 = 42;
+        (0..count)
+            .map(|_| {
+                (0..dim)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact_top(query: &[f32], data: &[(Uuid, Vec<f32>)], k: usize) -> Vec<Uuid> {
+        let q = normalize(query);
+        let mut scored: Vec<(f32, Uuid)> = data
+            .iter()
+            .map(|(id, v)| (normalize(v).iter().zip(&q).map(|(a, b)| a * b).sum(), *id))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+
+    #[test]
+    fn test_recall_against_exact_search() {
+        let data: Vec<(Uuid, Vec<f32>)> = vectors(500, 16)
+            .into_iter()
+            .map(|v| (Uuid::new_v4(), v))
+            .collect();
+        let mut index = HnswIndex::new(HnswParams::default());
+        for (id, v) in &data {
+            index.insert(*id, v, None, None).unwrap();
+        }
+
+        let mut hits = 0;
+        let queries = vectors(520, 16).split_off(500);
+        for query in &queries {
+            let expected = exact_top(query, &data, 10);
+            let found: Vec<Uuid> = index.search(query, 10, |_| true).into_iter().map(|(id, _)| id).collect();
+            hits += expected.iter().filter(|id| found.contains(id)).count();
+        }
+        let recall = hits as f32 / (queries.len() * 10) as f32;
+        assert!(recall >= 0.9, "recall {} too low", recall);
+    }
+
+    #[test]
+    fn test_filter_remove_and_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.hnsw");
+        let mut index = HnswIndex::new(HnswParams::default());
+        let mut ids = Vec::new();
+        for (i, v) in vectors(100, 8).into_iter().enumerate() {
+            let id = Uuid::new_v4();
+            let conversation = if i % 10 == 0 { "rare" } else { "common" };
+            index.insert(id, &v, Some("u1".to_string()), Some(conversation.to_string())).unwrap();
+            ids.push((id, v));
+        }
+
+        let rare = index.search(&ids[0].1, 20, |n| n.conversation_id.as_deref() == Some("rare"));
+        assert_eq!(rare.len(), 10);
+        assert_eq!(rare[0].0, ids[0].0);
+
+        assert!(index.remove(&ids[0].0));
+        assert!(!index.contains(&ids[0].0));
+        assert_eq!(index.remove_where(|n| n.conversation_id.as_deref() == Some("rare")), 9);
+        assert!(index.search(&ids[0].1, 20, |n| n.conversation_id.as_deref() == Some("rare")).is_empty());
+        assert_eq!(index.len(), 90);
+
+        index.save(&path).unwrap();
+        let mut loaded = HnswIndex::load(&path).unwrap();
+        assert_eq!(loaded.len(), 90);
+        assert_eq!(loaded.dim(), Some(8));
+        assert_eq!(
+            loaded.search(&ids[1].1, 5, |_| true),
+            index.search(&ids[1].1, 5, |_| true)
+        );
+        assert!(loaded.insert(Uuid::new_v4(), &[1.0; 3], None, None).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Importance scoring of memory entries: how useful an entry is to recall later, in `[0, 1]`.
+
+use async_trait::async_trait;
+
+use crate::memory_core::{MemoryEntry, MemoryRole};
+
+use super::estimate_tokens;
+
+/// Importance assumed for entries that were never scored (written before scoring existed).
+pub const NEUTRAL_IMPORTANCE: f32 = 0.5;
+
+/// Scores an entry before it is written; the result is stored in `MemoryMetadata.importance`.
+#[async_trait]
+pub trait ImportanceScorer: Send + Sync {
+    /// Importance in `[0, 1]`; values outside are clamped by the caller.
+    async fn score(&self, entry: &MemoryEntry) -> Result<f32, anyhow::Error>;
+}
+
+/// Acknowledgements and greetings that carry nothing worth recalling.
+const FILLERS: &[&str] = &[
+    "ok", "okay", "k", "kk", "thanks", "thank you", "thx", "ty", "lol", "haha", "yes", "no",
+    "yeah", "yep", "nope", "sure", "cool", "nice", "great", "hi", "hello", "hey", "bye",
+    "good night", "got it", "np", "好", "好的", "谢谢", "嗯", "哈哈",
+];
+
+/// Phrases that usually introduce a durable fact or preference about the user.
+const FACT_MARKERS: &[&str] = &[
+    "i am", "i'm", "my ", "i like", "i love", "i hate", "i prefer", "i live", "i work",
+    "call me", "remember", "don't forget", "always", "never", "我是", "我的", "我喜欢", "记住",
+];
+
+/// Rule-based scorer: fillers score near zero; length, stated facts or preferences, concrete
+/// details (numbers, identifiers, links) and questions raise the score.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicImportanceScorer;
+
+impl HeuristicImportanceScorer {
+    pub fn score_text(content: &str, role: MemoryRole) -> f32 {
+        let lower = content.trim().to_lowercase();
+        let bare: String = lower
+            .chars()
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+            .collect();
+        let bare = bare.split_whitespace().collect::<Vec<_>>().join(" ");
+        if bare.is_empty() || FILLERS.contains(&bare.as_str()) {
+            return 0.05;
+        }
+
+        let mut score = 0.3;
+        score += (estimate_tokens(content) as f32 / 50.0).min(1.0) * 0.2;
+        if FACT_MARKERS.iter().any(|m| lower.contains(m)) {
+            score += 0.2;
+        }
+        if lower.chars().any(|c| c.is_ascii_digit())
+            || lower.contains('_')
+            || lower.contains('@')
+            || lower.contains("://")
+        {
+            score += 0.1;
+        }
+        if lower.contains('?') || lower.contains('？') {
+            score += 0.1;
+        }
+        match role {
+            MemoryRole::System => score += 0.2,
+            MemoryRole::Assistant => score -= 0.1,
+            MemoryRole::User => {}
+        }
+        score.clamp(0.0, 1.0)
+    }
+}
+
+#[async_trait]
+impl ImportanceScorer for HeuristicImportanceScorer {
+    async fn score(&self, entry: &MemoryEntry) -> Result<f32, anyhow::Error> {
+        Ok(Self::score_text(&entry.content, entry.metadata.role))
+    }
+}
+
+/// Scales `score` by the entry's importance: `score * (1 - weight + weight * importance)`.
+/// `weight` 0 leaves scores unchanged; unscored entries count as [`NEUTRAL_IMPORTANCE`].
+pub fn importance_weighted(score: f32, entry: &MemoryEntry, weight: f32) -> f32 {
+    let importance = entry
+        .metadata
+        .importance
+        .unwrap_or(NEUTRAL_IMPORTANCE)
+        .clamp(0.0, 1.0);
+    score * (1.0 - weight + weight * importance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_scores_fillers_low_and_facts_high() {
+        let thanks = HeuristicImportanceScorer::score_text("Thanks!", MemoryRole::User);
+        let chat = HeuristicImportanceScorer::score_text("what a day", MemoryRole::User);
+        let fact = HeuristicImportanceScorer::score_text(
+            "My order number is 4471 and I prefer delivery after 6pm",
+            MemoryRole::User,
+        );
+        assert!(thanks < 0.1, "filler scored {}", thanks);
+        assert!(chat > thanks && fact > chat, "{} {} {}", thanks, chat, fact);
+    }
+}
@@ -1,7 +1,13 @@
 //! In-memory implementation of the MemoryStore trait.
 
-use super::{MemoryEntry, MemoryStore};
+use super::snapshot::{read_snapshot, write_snapshot};
+use super::{
+    EmbeddingGuard, EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryOrder,
+    MemoryPage, MemoryStore, SearchFilter,
+};
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
@@ -10,15 +16,67 @@ use uuid::Uuid;
 type EntryMap = HashMap<Uuid, MemoryEntry>;
 
 /// In-memory vector store for testing and development.
+///
+/// Contents can be saved to and loaded from a snapshot file; with [`with_snapshot`](Self::with_snapshot)
+/// the store is restored at startup and [`MemoryStore::flush`] writes it back.
 #[derive(Debug, Clone)]
 pub struct InMemoryVectorStore {
     entries: Arc<RwLock<EntryMap>>,
+    snapshot_path: Option<Arc<PathBuf>>,
+    embedding_guard: Arc<EmbeddingGuard>,
 }
 
 impl InMemoryVectorStore {
     pub fn new() -> Self {
         Self {
             entries: Arc::new(RwLock::new(EntryMap::new())),
+            snapshot_path: None,
+            embedding_guard: Arc::new(EmbeddingGuard::default()),
+        }
+    }
+
+    /// Binds the store to a snapshot file: loads it when it exists (an incompatible or corrupt file
+    /// is an error, not silently discarded) and saves there on `flush` and autosave.
+    pub async fn with_snapshot(mut self, path: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        let path = path.into();
+        if tokio::fs::try_exists(&path).await? {
+            let count = self.load_snapshot(&path).await?;
+            info!(path = %path.display(), count, "Loaded in-memory store snapshot");
+        } else {
+            info!(path = %path.display(), "No in-memory store snapshot yet; starting empty");
+        }
+        self.snapshot_path = Some(Arc::new(path));
+        Ok(self)
+    }
+
+    /// Writes all entries to `path`; returns how many were saved.
+    pub async fn save_snapshot(&self, path: &Path) -> Result<usize, anyhow::Error> {
+        let entries: Vec<MemoryEntry> = self.entries.read().await.values().cloned().collect();
+        write_snapshot(path, &entries).await?;
+        Ok(entries.len())
+    }
+
+    /// Replaces the contents with the snapshot at `path`; returns how many entries were loaded.
+    /// The embedding dimension is inferred again from the loaded vectors.
+    pub async fn load_snapshot(&self, path: &Path) -> Result<usize, anyhow::Error> {
+        let loaded = read_snapshot(path).await?;
+        let count = loaded.len();
+        self.embedding_guard.set(
+            loaded
+                .iter()
+                .find_map(|e| e.embedding.as_ref())
+                .map(|v| EmbeddingSpec::inferred(v.len())),
+        );
+        let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
+        *entries = loaded.into_iter().map(|e| (e.id, e)).collect();
+        Ok(count)
+    }
+
+    /// Saves to the bound snapshot path; `None` when the store has no snapshot path.
+    pub async fn save_to_snapshot_path(&self) -> Result<Option<usize>, anyhow::Error> {
+        match &self.snapshot_path {
+            Some(path) => self.save_snapshot(path).await.map(Some),
+            None => Ok(None),
         }
     }
 
@@ -58,6 +116,36 @@ impl Default for InMemoryVectorStore {
 
 #[async_trait::async_trait]
 impl MemoryStore for InMemoryVectorStore {
+    /// Saves the snapshot when the store is bound to a snapshot path.
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        if let Some(count) = self.save_to_snapshot_path().await? {
+            info!(count, "In-memory store snapshot saved");
+        }
+        Ok(())
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        Ok(self.embedding_guard.spec())
+    }
+
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        self.embedding_guard.bind(spec)?;
+        Ok(())
+    }
+
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
+        let mut cleared = 0;
+        for entry in entries.values_mut() {
+            if entry.embedding.take().is_some() {
+                cleared += 1;
+            }
+        }
+        self.embedding_guard.set(Some(spec.clone()));
+        info!(cleared, spec = %spec, "In-memory store embeddings reset");
+        Ok(cleared)
+    }
+
     async fn add(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
         if entry.embedding.is_some() {
             info!(
@@ -74,6 +162,8 @@ impl MemoryStore for InMemoryVectorStore {
             has_embedding = entry.embedding.is_some(),
             "Writing entry to in-memory vector store"
         );
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
         let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
         entries.insert(entry.id, entry.clone());
         drop(entries);
@@ -86,16 +176,31 @@ impl MemoryStore for InMemoryVectorStore {
         Ok(())
     }
 
+    async fn add_batch(&self, batch: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        let count = batch.len();
+        self.embedding_guard.check_entries(&batch)?;
+        let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
+        entries.extend(batch.into_iter().map(|e| (e.id, e)));
+        drop(entries);
+        info!(count, "Batch written to in-memory vector store");
+        Ok(())
+    }
+
     async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
         info!(id = %id, "Querying in-memory vector store by id");
         let entries: tokio::sync::RwLockReadGuard<'_, EntryMap> = self.entries.read().await;
-        let result = entries.get(&id).cloned();
+        let result = entries
+            .get(&id)
+            .filter(|e| !e.metadata.is_expired(Utc::now()))
+            .cloned();
         let found = result.is_some();
         info!(id = %id, found, "In-memory vector store get returned");
         Ok(result)
     }
 
     async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
         let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
         entries.insert(entry.id, entry);
         Ok(())
@@ -109,10 +214,12 @@ impl MemoryStore for InMemoryVectorStore {
 
     async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
         info!(user_id = %user_id, "Querying in-memory vector store by user");
+        let now = Utc::now();
         let entries: tokio::sync::RwLockReadGuard<'_, EntryMap> = self.entries.read().await;
         let results: Vec<MemoryEntry> = entries
             .values()
             .filter(|e| e.metadata.user_id.as_deref() == Some(user_id))
+            .filter(|e| !e.metadata.is_expired(now))
             .cloned()
             .collect();
         info!(user_id = %user_id, count = results.len(), "In-memory vector store search_by_user returned");
@@ -124,10 +231,12 @@ impl MemoryStore for InMemoryVectorStore {
         conversation_id: &str,
     ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
         info!(conversation_id = %conversation_id, "Querying in-memory vector store by conversation");
+        let now = Utc::now();
         let entries: tokio::sync::RwLockReadGuard<'_, EntryMap> = self.entries.read().await;
         let results: Vec<MemoryEntry> = entries
             .values()
             .filter(|e| e.metadata.conversation_id.as_deref() == Some(conversation_id))
+            .filter(|e| !e.metadata.is_expired(now))
             .cloned()
             .collect();
         info!(conversation_id = %conversation_id, count = results.len(), "In-memory vector store search_by_conversation returned");
@@ -141,19 +250,26 @@ impl MemoryStore for InMemoryVectorStore {
         user_id: Option<&str>,
         conversation_id: Option<&str>,
     ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
-        info!(dimension = query_embedding.len(), limit = limit, user_id = ?user_id, conversation_id = ?conversation_id, "step: embedding InMemory semantic search");
+        self.semantic_search_filtered(
+            query_embedding,
+            limit,
+            &SearchFilter::scoped(user_id, conversation_id),
+        )
+        .await
+    }
+
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        info!(dimension = query_embedding.len(), limit = limit, filter = ?filter, "step: embedding InMemory semantic search");
+        self.embedding_guard.check_query(query_embedding)?;
         let entries: tokio::sync::RwLockReadGuard<'_, EntryMap> = self.entries.read().await;
         let mut similarities: Vec<(f32, MemoryEntry)> = entries
             .values()
-            .filter(|entry| {
-                let match_user = user_id
-                    .map(|u| entry.metadata.user_id.as_deref() == Some(u))
-                    .unwrap_or(true);
-                let match_conv = conversation_id
-                    .map(|c| entry.metadata.conversation_id.as_deref() == Some(c))
-                    .unwrap_or(true);
-                match_user && match_conv
-            })
+            .filter(|entry| filter.matches(entry))
             .filter_map(|entry| {
                 entry.embedding.as_ref().map(|embedding| {
                     let similarity = Self::cosine_similarity(query_embedding, embedding);
@@ -166,24 +282,64 @@ impl MemoryStore for InMemoryVectorStore {
         info!(limit = limit, count = results.len(), "step: embedding InMemory semantic search done");
         Ok(results)
     }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let entries: tokio::sync::RwLockReadGuard<'_, EntryMap> = self.entries.read().await;
+        let candidates: Vec<MemoryEntry> = entries
+            .values()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect();
+        drop(entries);
+        Ok(MemoryPage::paginate(candidates, filter, order, limit, cursor))
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        let entries: tokio::sync::RwLockReadGuard<'_, EntryMap> = self.entries.read().await;
+        Ok(entries.values().filter(|e| filter.matches(e)).count() as u64)
+    }
+
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, e| e.metadata.conversation_id.as_deref() != Some(conversation_id));
+        let deleted = (before - entries.len()) as u64;
+        info!(conversation_id = %conversation_id, deleted, "In-memory vector store delete_by_conversation");
+        Ok(deleted)
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, e| e.metadata.user_id.as_deref() != Some(user_id));
+        let deleted = (before - entries.len()) as u64;
+        info!(user_id = %user_id, deleted, "In-memory vector store delete_by_user");
+        Ok(deleted)
+    }
+
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let mut entries: tokio::sync::RwLockWriteGuard<'_, EntryMap> = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, e| !e.metadata.is_expired(now));
+        let deleted = (before - entries.len()) as u64;
+        info!(deleted, "In-memory vector store delete_expired");
+        Ok(deleted)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::memory::{MemoryMetadata, MemoryRole};
-    use chrono::Utc;
+    use crate::memory::MemoryMetadata;
 
     fn create_test_entry(content: &str, user_id: &str) -> MemoryEntry {
-        let metadata = MemoryMetadata {
-            user_id: Some(user_id.to_string()),
-            conversation_id: None,
-            role: MemoryRole::User,
-            timestamp: Utc::now(),
-            tokens: None,
-            importance: None,
-        };
-        MemoryEntry::new(content.to_string(), metadata)
+        MemoryEntry::new(content.to_string(), MemoryMetadata::default().with_user(user_id))
     }
 
     #[tokio::test]
@@ -196,6 +352,25 @@ mod tests {
         assert_eq!(found.unwrap().content, "Test");
     }
 
+    #[tokio::test]
+    async fn test_snapshot_flush_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join("memory.snap");
+
+        let store = InMemoryVectorStore::new().with_snapshot(&path).await.unwrap();
+        assert!(store.is_empty().await);
+        let entry = create_test_entry("Remember me", "user123");
+        store.add(entry.clone()).await.unwrap();
+        store.flush().await.unwrap();
+
+        let restored = InMemoryVectorStore::new().with_snapshot(&path).await.unwrap();
+        assert_eq!(restored.len().await, 1);
+        assert_eq!(restored.get(entry.id).await.unwrap().unwrap().content, "Remember me");
+
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(InMemoryVectorStore::new().with_snapshot(&path).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_get_nonexistent() {
         let store = InMemoryVectorStore::new();
@@ -203,6 +378,29 @@ mod tests {
         assert!(found.is_none());
     }
 
+    #[tokio::test]
+    async fn test_list_count_and_delete_by_user() {
+        let store = InMemoryVectorStore::new();
+        for content in ["a", "b", "c"] {
+            store.add(create_test_entry(content, "user123")).await.unwrap();
+        }
+        store.add(create_test_entry("other", "user456")).await.unwrap();
+
+        let filter = MemoryFilter::for_user("user123");
+        assert_eq!(store.count(&filter).await.unwrap(), 3);
+        let page = store.list(&filter, MemoryOrder::NewestFirst, 2, None).await.unwrap();
+        assert_eq!(page.entries.len(), 2);
+        let rest = store
+            .list(&filter, MemoryOrder::NewestFirst, 2, page.next_cursor.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(rest.entries.len(), 1);
+        assert!(rest.next_cursor.is_none());
+
+        assert_eq!(store.delete_by_user("user123").await.unwrap(), 3);
+        assert_eq!(store.len().await, 1);
+    }
+
     #[tokio::test]
     async fn test_len_and_is_empty() {
         let store = InMemoryVectorStore::new();
@@ -2,17 +2,50 @@
 //!
 //! Abstraction (MemoryStore, types), in-memory, SQLite, and Lance implementations.
 
+pub mod backfill;
+pub mod batch_writer;
+pub mod cache;
+pub(crate) mod codec;
+pub mod compaction;
 pub mod config;
 pub mod context;
+pub mod events;
+pub mod hnsw;
+pub mod importance;
 pub mod inmemory;
+pub mod reembed;
+pub mod snapshot;
 pub mod sqlite;
+pub mod ttl;
+pub mod write_pipeline;
 
 pub use crate::memory_core::*;
 pub use crate::memory_strategies::{
-    ContextStrategy, RecentMessagesStrategy, SemanticSearchStrategy, StoreKind,
-    UserPreferencesStrategy,
+    ContextStrategy, ConversationSummaryStrategy, HybridSearchStrategy, HybridWeights,
+    RecentMessagesStrategy, SemanticSearchStrategy, StoreKind, UserPreferencesStrategy,
+};
+pub use backfill::{backfill_store, BackfillReport};
+pub use batch_writer::BatchingMemoryWriter;
+pub use cache::{CacheStats, CachedMemoryStore, DEFAULT_CACHE_WINDOW};
+pub use compaction::{
+    spawn_compaction_job, CompactionPolicy, CompactionReport, MemoryCompactor, MemorySummarizer,
 };
 pub use config::{EnvMemoryConfig, MemoryConfig};
-pub use context::{Context, ContextBuilder, estimate_tokens};
+pub use context::{
+    Context, ContextBudget, ContextBuilder, ContextSection, DroppedItem, SectionBudget,
+    estimate_tokens,
+};
+pub use events::{
+    spawn_replication, MemoryEvent, MemoryEvents, ObservableMemoryStore, Replication,
+    DEFAULT_EVENT_CAPACITY,
+};
+pub use hnsw::{HnswIndex, HnswParams};
+pub use importance::{
+    importance_weighted, HeuristicImportanceScorer, ImportanceScorer, NEUTRAL_IMPORTANCE,
+};
 pub use inmemory::InMemoryVectorStore;
+pub use reembed::{reembed_all, ReembedReport};
+pub use snapshot::{spawn_snapshot_autosave, SnapshotError, SNAPSHOT_VERSION};
 pub use sqlite::SQLiteVectorStore;
+pub use ttl::TtlPolicy;
+pub use write_pipeline::{MemoryWritePipeline, WriteOutcome, DEFAULT_DEDUP_WINDOW};
@@ -0,0 +1,111 @@
+//! Re-embed migration: rewrites stored vectors with the configured embedding model after a model
+//! or dimension change.
+
+use anyhow::{Context as _, Result};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::embedding::EmbeddingService;
+use crate::memory_core::{EmbeddingSpec, MemoryFilter, MemoryOrder, MemoryStore};
+
+/// Entries re-embedded by one [`reembed_all`] run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReembedReport {
+    /// Model and dimension all stores now hold.
+    pub embedding: EmbeddingSpec,
+    /// Entries given a new vector per memory store (label, count), in the given order.
+    pub memory_entries: Vec<(String, u64)>,
+}
+
+/// Re-embeds every memory entry of `stores` with `embedding`, which must produce `spec` vectors.
+///
+/// A store whose recorded spec differs from `spec` first drops its vectors
+/// ([`MemoryStore::reset_embeddings`]); then every entry of the store without a vector (including
+/// summaries and imported entries of chats not in the messages table) is embedded and updated,
+/// `batch_size` entries per embedding call. Entries that already have a vector are skipped, so the
+/// command can be re-run after an interruption. Pass stores wrapped in `EncryptedMemoryStore` when
+/// encryption is enabled, so plaintext is embedded.
+pub async fn reembed_all(
+    stores: &[(String, Arc<dyn MemoryStore>)],
+    embedding: &dyn EmbeddingService,
+    spec: &EmbeddingSpec,
+    batch_size: usize,
+) -> Result<ReembedReport> {
+    info!(embedding = %spec, "Re-embedding memory stores");
+    let batch_size = batch_size.max(1);
+    let mut report = ReembedReport {
+        embedding: spec.clone(),
+        memory_entries: Vec::new(),
+    };
+
+    for (name, store) in stores {
+        if store.embedding_spec().await?.as_ref() != Some(spec) {
+            let reset = store
+                .reset_embeddings(spec)
+                .await
+                .with_context(|| format!("reembed: resetting store {} failed", name))?;
+            info!(store = %name, entries = reset, "Dropped vectors of the previous embedding model");
+        }
+        let embedded = reembed_store(store.as_ref(), embedding, spec, batch_size)
+            .await
+            .with_context(|| format!("reembed: store {} failed", name))?;
+        info!(store = %name, embedded = embedded, "Memory store re-embedded");
+        report.memory_entries.push((name.clone(), embedded));
+    }
+    info!(report = ?report, "Re-embedding finished");
+    Ok(report)
+}
+
+/// Embeds the entries of `store` that have no vector, paging over the whole store. Returns how
+/// many were updated.
+async fn reembed_store(
+    store: &dyn MemoryStore,
+    embedding: &dyn EmbeddingService,
+    spec: &EmbeddingSpec,
+    batch_size: usize,
+) -> Result<u64> {
+    let filter = MemoryFilter::default();
+    let mut embedded = 0;
+    let mut cursor = None;
+    loop {
+        let page = store
+            .list(
+                &filter,
+                MemoryOrder::OldestFirst,
+                batch_size,
+                cursor.as_ref(),
+            )
+            .await?;
+        let pending: Vec<_> = page
+            .entries
+            .into_iter()
+            .filter(|e| e.embedding.is_none())
+            .collect();
+        if !pending.is_empty() {
+            let texts: Vec<String> = pending.iter().map(|e| e.content.clone()).collect();
+            let vectors = embedding.embed_batch(&texts).await?;
+            anyhow::ensure!(
+                vectors.len() == pending.len(),
+                "embedding service returned {} vectors for {} texts",
+                vectors.len(),
+                pending.len()
+            );
+            for (mut entry, vector) in pending.into_iter().zip(vectors) {
+                anyhow::ensure!(
+                    vector.len() == spec.dimension,
+                    "embedding service returned {}-dimensional vectors but {} is configured",
+                    vector.len(),
+                    spec
+                );
+                entry.embedding = Some(vector);
+                store.update(entry).await?;
+                embedded += 1;
+            }
+        }
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(embedded)
+}
@@ -0,0 +1,355 @@
+//! Snapshot file format for [`InMemoryVectorStore`](super::InMemoryVectorStore) and the autosave task.
+//!
+//! Layout (little-endian): magic `DBMEMSNP`, format version (u32), entry count (u64), then per entry:
+//! id (16 bytes), content, user_id, conversation_id, role (u8), timestamp (i64 seconds + u32 nanos),
+//! tokens, importance, expires_at (since version 2), embedding. Optional fields carry a presence
+//! flag or a `u32::MAX` length. Version 1 files are still read; their entries never expire.
+//! Files are written to a temp file, synced and renamed (then the directory is synced), so a crash
+//! never leaves a half-written snapshot.
+
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::codec::{read_opt_f32s, read_opt_str, read_str, read_u32, write_opt_f32s, write_opt_str, write_str, write_u32};
+use super::InMemoryVectorStore;
+use crate::memory_core::{MemoryEntry, MemoryMetadata, MemoryRole};
+
+const MAGIC: &[u8; 8] = b"DBMEMSNP";
+
+/// Current snapshot format version; bump on any layout change.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Errors reading or writing a snapshot.
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("{0} is not a memory snapshot")]
+    NotASnapshot(PathBuf),
+    #[error("snapshot {path} has format version {found}, this build reads versions 1 to {expected}")]
+    IncompatibleVersion {
+        path: PathBuf,
+        found: u32,
+        expected: u32,
+    },
+    #[error("snapshot {path} is corrupt: {source}")]
+    Corrupt {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("snapshot I/O on {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Writes `entries` to `path` atomically.
+pub async fn write_snapshot(path: &Path, entries: &[MemoryEntry]) -> Result<(), SnapshotError> {
+    let io_err = |source| SnapshotError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut buf = Vec::new();
+    encode(&mut buf, entries).map_err(io_err)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await.map_err(io_err)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = tokio::fs::File::create(&tmp).await.map_err(io_err)?;
+    file.write_all(&buf).await.map_err(io_err)?;
+    file.sync_all().await.map_err(io_err)?;
+    drop(file);
+    tokio::fs::rename(&tmp, path).await.map_err(io_err)?;
+    sync_parent_dir(path).await.map_err(io_err)
+}
+
+/// Syncs the directory containing `path` so a completed rename survives a crash. Directories cannot
+/// be opened for syncing on Windows, where this is a no-op.
+async fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            Some(parent) => parent,
+            None => Path::new("."),
+        };
+        tokio::fs::File::open(parent).await?.sync_all().await?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Reads all entries from the snapshot at `path`.
+pub async fn read_snapshot(path: &Path) -> Result<Vec<MemoryEntry>, SnapshotError> {
+    let buf = tokio::fs::read(path).await.map_err(|source| SnapshotError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut input = buf.as_slice();
+
+    let mut magic = [0u8; 8];
+    if input.read_exact(&mut magic).is_err() || &magic != MAGIC {
+        return Err(SnapshotError::NotASnapshot(path.to_path_buf()));
+    }
+    let corrupt = |source| SnapshotError::Corrupt {
+        path: path.to_path_buf(),
+        source,
+    };
+    let version = read_u32(&mut input).map_err(corrupt)?;
+    if !(1..=SNAPSHOT_VERSION).contains(&version) {
+        return Err(SnapshotError::IncompatibleVersion {
+            path: path.to_path_buf(),
+            found: version,
+            expected: SNAPSHOT_VERSION,
+        });
+    }
+    decode(&mut input, version).map_err(corrupt)
+}
+
+fn encode(out: &mut impl Write, entries: &[MemoryEntry]) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    write_u32(out, SNAPSHOT_VERSION)?;
+    out.write_all(&(entries.len() as u64).to_le_bytes())?;
+    for entry in entries {
+        let m = &entry.metadata;
+        out.write_all(entry.id.as_bytes())?;
+        write_str(out, &entry.content)?;
+        write_opt_str(out, m.user_id.as_deref())?;
+        write_opt_str(out, m.conversation_id.as_deref())?;
+        out.write_all(&[role_to_u8(m.role)])?;
+        write_timestamp(out, &m.timestamp)?;
+        match m.tokens {
+            Some(tokens) => {
+                out.write_all(&[1])?;
+                write_u32(out, tokens)?;
+            }
+            None => out.write_all(&[0])?,
+        }
+        match m.importance {
+            Some(importance) => {
+                out.write_all(&[1])?;
+                out.write_all(&importance.to_le_bytes())?;
+            }
+            None => out.write_all(&[0])?,
+        }
+        match &m.expires_at {
+            Some(expires_at) => {
+                out.write_all(&[1])?;
+                write_timestamp(out, expires_at)?;
+            }
+            None => out.write_all(&[0])?,
+        }
+        write_opt_f32s(out, entry.embedding.as_deref())?;
+    }
+    Ok(())
+}
+
+fn decode(input: &mut impl Read, version: u32) -> io::Result<Vec<MemoryEntry>> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut count = [0u8; 8];
+    input.read_exact(&mut count)?;
+    let count = u64::from_le_bytes(count);
+    let mut entries = Vec::with_capacity(count.min(1 << 20) as usize);
+    for _ in 0..count {
+        let mut id = [0u8; 16];
+        input.read_exact(&mut id)?;
+        let content = read_str(input)?;
+        let user_id = read_opt_str(input)?;
+        let conversation_id = read_opt_str(input)?;
+        let role = role_from_u8(read_u8(input)?).ok_or_else(|| invalid("unknown role"))?;
+        let timestamp = read_timestamp(input)?;
+        let tokens = match read_u8(input)? {
+            0 => None,
+            _ => Some(read_u32(input)?),
+        };
+        let importance = match read_u8(input)? {
+            0 => None,
+            _ => {
+                let mut buf = [0u8; 4];
+                input.read_exact(&mut buf)?;
+                Some(f32::from_le_bytes(buf))
+            }
+        };
+        let expires_at = match version {
+            1 => None,
+            _ => match read_u8(input)? {
+                0 => None,
+                _ => Some(read_timestamp(input)?),
+            },
+        };
+        let embedding = read_opt_f32s(input)?;
+        entries.push(MemoryEntry {
+            id: Uuid::from_bytes(id),
+            content,
+            embedding,
+            metadata: MemoryMetadata {
+                user_id,
+                conversation_id,
+                role,
+                timestamp,
+                tokens,
+                importance,
+                expires_at,
+            },
+        });
+    }
+    let mut trailing = [0u8; 1];
+    if input.read(&mut trailing)? != 0 {
+        return Err(invalid("trailing bytes after last entry"));
+    }
+    Ok(entries)
+}
+
+fn write_timestamp(out: &mut impl Write, t: &DateTime<Utc>) -> io::Result<()> {
+    out.write_all(&t.timestamp().to_le_bytes())?;
+    write_u32(out, t.timestamp_subsec_nanos())
+}
+
+fn read_timestamp(input: &mut impl Read) -> io::Result<DateTime<Utc>> {
+    let mut secs = [0u8; 8];
+    input.read_exact(&mut secs)?;
+    let nanos = read_u32(input)?;
+    DateTime::from_timestamp(i64::from_le_bytes(secs), nanos)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp out of range"))
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    input.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn role_to_u8(role: MemoryRole) -> u8 {
+    match role {
+        MemoryRole::User => 0,
+        MemoryRole::Assistant => 1,
+        MemoryRole::System => 2,
+    }
+}
+
+fn role_from_u8(value: u8) -> Option<MemoryRole> {
+    match value {
+        0 => Some(MemoryRole::User),
+        1 => Some(MemoryRole::Assistant),
+        2 => Some(MemoryRole::System),
+        _ => None,
+    }
+}
+
+/// Spawns a task that saves `store` to its snapshot path every `interval`. Failures are logged.
+pub fn spawn_snapshot_autosave(store: InMemoryVectorStore, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately; the store was just loaded.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match store.save_to_snapshot_path().await {
+                Ok(Some(count)) => info!(count, "Memory snapshot autosaved"),
+                Ok(None) => return,
+                Err(e) => error!(error = %e, "Memory snapshot autosave failed"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_round_trip_and_version_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.snap");
+
+        let mut with_all = MemoryEntry::new(
+            "hello".to_string(),
+            MemoryMetadata {
+                user_id: Some("u1".to_string()),
+                conversation_id: Some("c1".to_string()),
+                role: MemoryRole::Assistant,
+                timestamp: Utc::now(),
+                tokens: Some(3),
+                importance: Some(0.75),
+                expires_at: Some(Utc::now() + chrono::Duration::days(1)),
+            },
+        );
+        with_all.embedding = Some(vec![0.1, -0.2, 0.3]);
+        let bare = MemoryEntry::new(
+            String::new(),
+            MemoryMetadata {
+                user_id: None,
+                conversation_id: None,
+                role: MemoryRole::System,
+                timestamp: Utc::now(),
+                tokens: None,
+                importance: None,
+                expires_at: None,
+            },
+        );
+
+        write_snapshot(&path, &[with_all.clone(), bare.clone()]).await.unwrap();
+        let loaded = read_snapshot(&path).await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].id, with_all.id);
+        assert_eq!(loaded[0].embedding, with_all.embedding);
+        assert_eq!(loaded[0].metadata.timestamp, with_all.metadata.timestamp);
+        assert_eq!(loaded[0].metadata.importance, Some(0.75));
+        assert_eq!(loaded[0].metadata.expires_at, with_all.metadata.expires_at);
+        assert_eq!(loaded[1].metadata.expires_at, None);
+        assert_eq!(loaded[1].metadata.user_id, None);
+        assert_eq!(loaded[1].metadata.role, MemoryRole::System);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[8..12].copy_from_slice(&99u32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            read_snapshot(&path).await,
+            Err(SnapshotError::IncompatibleVersion { found: 99, .. })
+        ));
+
+        std::fs::write(&path, b"not a snapshot").unwrap();
+        assert!(matches!(
+            read_snapshot(&path).await,
+            Err(SnapshotError::NotASnapshot(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reads_version_1_without_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.snap");
+        let id = Uuid::new_v4();
+        let timestamp = Utc::now();
+
+        let mut v1 = Vec::new();
+        v1.extend_from_slice(MAGIC);
+        write_u32(&mut v1, 1).unwrap();
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.extend_from_slice(id.as_bytes());
+        write_str(&mut v1, "old").unwrap();
+        write_opt_str(&mut v1, Some("u1")).unwrap();
+        write_opt_str(&mut v1, None).unwrap();
+        v1.push(role_to_u8(MemoryRole::User));
+        write_timestamp(&mut v1, &timestamp).unwrap();
+        v1.extend_from_slice(&[0, 0]);
+        write_opt_f32s(&mut v1, Some(&[0.5, 0.5])).unwrap();
+        std::fs::write(&path, &v1).unwrap();
+
+        let loaded = read_snapshot(&path).await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, id);
+        assert_eq!(loaded[0].metadata.timestamp, timestamp);
+        assert_eq!(loaded[0].metadata.expires_at, None);
+        assert_eq!(loaded[0].embedding, Some(vec![0.5, 0.5]));
+    }
+}
@@ -1,16 +1,63 @@
 //! SQLite implementation of the MemoryStore trait.
 
-use super::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
+use super::hnsw::{HnswIndex, HnswParams, PersistentHnsw};
+use super::{
+    EmbeddingGuard, EmbeddingSpec, MemoryCursor, MemoryEntry, MemoryFilter, MemoryMetadata, MemoryOrder, MemoryPage, MemoryRole,
+    MemoryStore, SearchFilter,
+};
+use crate::memory_core::keyword::tokenize;
 use chrono::{DateTime, Utc};
-use sqlx::{sqlite::SqliteConnectOptions, Row, SqlitePool};
+use futures::TryStreamExt;
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::str::FromStr;
-use tracing::info;
+use std::sync::Arc;
+use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Plain insert; a duplicate id is an error.
+const INSERT_SQL: &str = r#"
+    INSERT INTO memory_entries (
+        id, content, user_id, conversation_id, role, timestamp,
+        tokens, importance, embedding, expires_at, fts_text
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+"#;
+
+/// `update` is an upsert, like the other stores: a missing id is inserted.
+const UPSERT_SQL: &str = r#"
+    INSERT INTO memory_entries (
+        id, content, user_id, conversation_id, role, timestamp,
+        tokens, importance, embedding, expires_at, fts_text
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+    ON CONFLICT(id) DO UPDATE SET
+        content = excluded.content, user_id = excluded.user_id,
+        conversation_id = excluded.conversation_id, role = excluded.role,
+        timestamp = excluded.timestamp, tokens = excluded.tokens,
+        importance = excluded.importance, embedding = excluded.embedding,
+        expires_at = excluded.expires_at, fts_text = excluded.fts_text
+"#;
+
+/// `memory_meta` keys recording the embedding model and dimension of stored vectors.
+const META_EMBEDDING_MODEL: &str = "embedding_model";
+const META_EMBEDDING_DIM: &str = "embedding_dim";
+
+/// Candidate multiplier for ANN search when the filter has predicates the index cannot check
+/// (role, time range, importance); those are applied in SQL on the candidates.
+const ANN_OVERFETCH: usize = 4;
+
 /// SQLite-based vector store for persistent memory storage.
+///
+/// Semantic search is exact (every embedding scored in process) unless an HNSW index is attached
+/// with [`with_hnsw`](Self::with_hnsw); exact search stays available via [`semantic_search_exact`](Self::semantic_search_exact).
+/// The embedding model and dimension are kept in the `memory_meta` table; vectors of another
+/// dimension are rejected.
 #[derive(Clone)]
 pub struct SQLiteVectorStore {
     pool: SqlitePool,
+    ann: Option<Arc<PersistentHnsw>>,
+    embedding_guard: Arc<EmbeddingGuard>,
 }
 
 impl SQLiteVectorStore {
@@ -19,8 +66,15 @@ impl SQLiteVectorStore {
             .create_if_missing(true)
             .filename(database_url);
         let pool = SqlitePool::connect_with(options).await?;
-        let store = Self { pool };
+        let store = Self {
+            pool,
+            ann: None,
+            embedding_guard: Arc::new(EmbeddingGuard::default()),
+        };
         store.init_schema().await?;
+        store
+            .embedding_guard
+            .set(store.load_embedding_spec().await?);
         Ok(store)
     }
 
@@ -36,18 +90,391 @@ impl SQLiteVectorStore {
                 timestamp TEXT NOT NULL,
                 tokens INTEGER,
                 importance REAL,
-                embedding BLOB
+                embedding BLOB,
+                expires_at TEXT
             );
             CREATE INDEX IF NOT EXISTS idx_user_id ON memory_entries(user_id);
             CREATE INDEX IF NOT EXISTS idx_conversation_id ON memory_entries(conversation_id);
             CREATE INDEX IF NOT EXISTS idx_timestamp ON memory_entries(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_conversation_timestamp ON memory_entries(conversation_id, timestamp);
+            CREATE TABLE IF NOT EXISTS memory_meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        self.migrate_expires_at().await?;
+        self.init_fts().await
+    }
+
+    /// Adds the `expires_at` column to tables created before entries could expire.
+    async fn migrate_expires_at(&self) -> Result<(), anyhow::Error> {
+        let (present,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM pragma_table_info('memory_entries') WHERE name = 'expires_at'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if present == 0 {
+            sqlx::query("ALTER TABLE memory_entries ADD COLUMN expires_at TEXT")
+                .execute(&self.pool)
+                .await?;
+            info!("Added expires_at column to memory_entries");
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_expires_at ON memory_entries(expires_at)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Creates the FTS5 index over `fts_text` (external content, kept in sync by triggers) and
+    /// fills it from existing rows when it is new.
+    ///
+    /// `fts_text` is `content` run through [`tokenize`] and joined by spaces, so CJK characters
+    /// reach FTS5 as separate tokens; with `content` indexed directly, unicode61 would read a run
+    /// of CJK as one token and a query for a word inside it would never match. An index built by
+    /// older versions over `content` is dropped and rebuilt.
+    async fn init_fts(&self) -> Result<(), anyhow::Error> {
+        let existing: Option<String> = sqlx::query_scalar(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'memory_fts'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let current = existing.as_deref().is_some_and(|sql| sql.contains("fts_text"));
+        if existing.is_some() && !current {
+            sqlx::query(
+                r#"
+                DROP TRIGGER IF EXISTS memory_fts_insert;
+                DROP TRIGGER IF EXISTS memory_fts_delete;
+                DROP TRIGGER IF EXISTS memory_fts_update;
+                DROP TABLE memory_fts;
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+            info!("Dropped content-based memory_fts index for rebuild over fts_text");
+        }
+        if !current {
+            self.backfill_fts_text().await?;
+        }
+        sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS memory_fts USING fts5(
+                fts_text, content = 'memory_entries', content_rowid = 'rowid'
+            );
+            CREATE TRIGGER IF NOT EXISTS memory_fts_insert AFTER INSERT ON memory_entries BEGIN
+                INSERT INTO memory_fts(rowid, fts_text) VALUES (new.rowid, new.fts_text);
+            END;
+            CREATE TRIGGER IF NOT EXISTS memory_fts_delete AFTER DELETE ON memory_entries BEGIN
+                INSERT INTO memory_fts(memory_fts, rowid, fts_text) VALUES ('delete', old.rowid, old.fts_text);
+            END;
+            CREATE TRIGGER IF NOT EXISTS memory_fts_update AFTER UPDATE OF fts_text ON memory_entries
+            WHEN old.fts_text IS NOT new.fts_text BEGIN
+                INSERT INTO memory_fts(memory_fts, rowid, fts_text) VALUES ('delete', old.rowid, old.fts_text);
+                INSERT INTO memory_fts(rowid, fts_text) VALUES (new.rowid, new.fts_text);
+            END;
             "#,
         )
         .execute(&self.pool)
         .await?;
+        if !current {
+            sqlx::query("INSERT INTO memory_fts(memory_fts) VALUES ('rebuild')")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds the `fts_text` column to tables created before it existed and fills it for rows that
+    /// lack it. Runs before the FTS triggers exist, so the updates do not touch the index.
+    async fn backfill_fts_text(&self) -> Result<(), anyhow::Error> {
+        let (present,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM pragma_table_info('memory_entries') WHERE name = 'fts_text'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if present == 0 {
+            sqlx::query("ALTER TABLE memory_entries ADD COLUMN fts_text TEXT")
+                .execute(&self.pool)
+                .await?;
+            info!("Added fts_text column to memory_entries");
+        }
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as("SELECT rowid, content FROM memory_entries WHERE fts_text IS NULL")
+                .fetch_all(&self.pool)
+                .await?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        for (rowid, content) in &rows {
+            sqlx::query("UPDATE memory_entries SET fts_text = ?1 WHERE rowid = ?2")
+                .bind(Self::fts_text(content))
+                .bind(rowid)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        info!(count = rows.len(), "Filled fts_text for existing memory entries");
+        Ok(())
+    }
+
+    /// Text indexed by `memory_fts`: the [`tokenize`] tokens of `content`, space separated.
+    fn fts_text(content: &str) -> String {
+        tokenize(content).join(" ")
+    }
+
+    /// Recorded embedding spec, or one inferred from a stored vector when none was recorded
+    /// (databases written before the spec existed).
+    async fn load_embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        let meta: HashMap<String, String> =
+            sqlx::query_as::<_, (String, String)>("SELECT key, value FROM memory_meta WHERE key IN (?1, ?2)")
+                .bind(META_EMBEDDING_MODEL)
+                .bind(META_EMBEDDING_DIM)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .collect();
+        if let Some(dim) = meta.get(META_EMBEDDING_DIM) {
+            let dimension = dim
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid {} in memory_meta: {:?} ({})", META_EMBEDDING_DIM, dim, e))?;
+            let model = meta.get(META_EMBEDDING_MODEL).cloned().unwrap_or_default();
+            return Ok(Some(EmbeddingSpec::new(model, dimension)));
+        }
+        let bytes: Option<i64> = sqlx::query_scalar(
+            "SELECT length(embedding) FROM memory_entries WHERE embedding IS NOT NULL LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(bytes.map(|b| EmbeddingSpec::inferred(b as usize / 4)))
+    }
+
+    async fn save_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (key, value) in [
+            (META_EMBEDDING_MODEL, spec.model.clone()),
+            (META_EMBEDDING_DIM, spec.dimension.to_string()),
+        ] {
+            sqlx::query(
+                "INSERT INTO memory_meta (key, value) VALUES (?1, ?2) \
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            )
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Attaches an HNSW index persisted at `index_path`. The file is loaded when present and
+    /// reconciled with the table (missing rows indexed, deleted rows dropped); otherwise the index
+    /// is built from all stored embeddings. From then on add, update and delete keep it in sync.
+    pub async fn with_hnsw(
+        mut self,
+        index_path: impl Into<PathBuf>,
+        params: HnswParams,
+    ) -> Result<Self, anyhow::Error> {
+        let (ann, loaded) = PersistentHnsw::open(index_path, params);
+        if loaded {
+            self.reconcile_hnsw(&ann).await?;
+        } else {
+            self.rebuild_hnsw(&ann).await?;
+        }
+        ann.save()?;
+        info!(
+            path = %ann.path().display(),
+            entries = ann.read(|index| index.len()),
+            loaded,
+            "SQLite vector store HNSW index ready"
+        );
+        self.ann = Some(Arc::new(ann));
+        Ok(self)
+    }
+
+    /// Indexes every stored embedding into an empty index, streaming rows.
+    async fn rebuild_hnsw(&self, ann: &PersistentHnsw) -> Result<(), anyhow::Error> {
+        let mut rows = sqlx::query("SELECT * FROM memory_entries WHERE embedding IS NOT NULL")
+            .fetch(&self.pool);
+        let mut count = 0usize;
+        while let Some(row) = rows.try_next().await? {
+            let entry = Self::row_to_entry(&row)?;
+            ann.write(0, |index| Self::index_entry(index, &entry));
+            count += 1;
+        }
+        info!(count, "Rebuilt HNSW index from SQLite");
         Ok(())
     }
 
+    /// Brings a loaded index in line with the table after an unclean shutdown.
+    async fn reconcile_hnsw(&self, ann: &PersistentHnsw) -> Result<(), anyhow::Error> {
+        let stored: HashSet<Uuid> =
+            sqlx::query_scalar::<_, String>("SELECT id FROM memory_entries WHERE embedding IS NOT NULL")
+                .fetch_all(&self.pool)
+                .await?
+                .iter()
+                .filter_map(|id| Uuid::from_str(id).ok())
+                .collect();
+        let (stale, missing): (Vec<Uuid>, Vec<Uuid>) = ann.read(|index| {
+            (
+                index.ids().filter(|id| !stored.contains(*id)).copied().collect(),
+                stored.iter().filter(|id| !index.contains(id)).copied().collect(),
+            )
+        });
+        ann.write(0, |index| {
+            for id in &stale {
+                index.remove(id);
+            }
+        });
+        for id in &missing {
+            if let Some(entry) = self.get(*id).await? {
+                ann.write(0, |index| Self::index_entry(index, &entry));
+            }
+        }
+        info!(stale = stale.len(), missing = missing.len(), "Reconciled HNSW index with SQLite");
+        Ok(())
+    }
+
+    /// Indexes (or unindexes, when it has no embedding) one entry; dimension mismatches are logged and skipped.
+    fn index_entry(index: &mut HnswIndex, entry: &MemoryEntry) {
+        match &entry.embedding {
+            Some(embedding) => {
+                if let Err(e) = index.insert(
+                    entry.id,
+                    embedding,
+                    entry.metadata.user_id.clone(),
+                    entry.metadata.conversation_id.clone(),
+                ) {
+                    warn!(id = %entry.id, error = %e, "Entry not added to HNSW index");
+                }
+            }
+            None => {
+                index.remove(&entry.id);
+            }
+        }
+    }
+
+    /// Updates the attached index, if any, after a write.
+    fn sync_ann(&self, entries: &[MemoryEntry]) {
+        if let Some(ann) = &self.ann {
+            ann.write(entries.len(), |index| {
+                for entry in entries {
+                    Self::index_entry(index, entry);
+                }
+            });
+        }
+    }
+
+    /// Writes the attached HNSW index to disk now (it is otherwise saved periodically and on drop).
+    pub fn save_hnsw(&self) -> Result<(), anyhow::Error> {
+        match &self.ann {
+            Some(ann) => ann.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Semantic search scoring every matching embedding; always exact, with or without an index.
+    pub async fn semantic_search_exact(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.embedding_guard.check_query(query_embedding)?;
+        let mut builder =
+            QueryBuilder::<Sqlite>::new("SELECT * FROM memory_entries WHERE embedding IS NOT NULL");
+        Self::push_filter(&mut builder, &filter.metadata);
+        if !filter.exclude_ids.is_empty() {
+            builder.push(" AND id NOT IN (");
+            let mut ids = builder.separated(", ");
+            for id in &filter.exclude_ids {
+                ids.push_bind(id.to_string());
+            }
+            builder.push(")");
+        }
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let mut similarities: Vec<(f32, MemoryEntry)> = Vec::with_capacity(rows.len());
+        for row in rows {
+            let entry = Self::row_to_entry(&row)?;
+            if let Some(embedding) = &entry.embedding {
+                let similarity = Self::cosine_similarity(query_embedding, embedding);
+                similarities.push((similarity, entry));
+            }
+        }
+        similarities.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(similarities.into_iter().take(limit).collect())
+    }
+
+    /// ANN search through the index. User, conversation and excluded ids are checked during the
+    /// graph walk; remaining predicates are applied in SQL on the candidates. Returns `None` when
+    /// the index cannot answer (dimension mismatch, or filtered candidates ran short) so the caller
+    /// falls back to exact search.
+    async fn semantic_search_ann(
+        &self,
+        ann: &PersistentHnsw,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Option<Vec<(f32, MemoryEntry)>>, anyhow::Error> {
+        let m = &filter.metadata;
+        let post_filtered = m.role.is_some()
+            || m.since.is_some()
+            || m.until.is_some()
+            || m.min_importance.is_some()
+            || m.non_empty;
+        let fetch = if post_filtered {
+            limit.saturating_mul(ANN_OVERFETCH)
+        } else {
+            limit
+        };
+        let excluded: HashSet<Uuid> = filter.exclude_ids.iter().copied().collect();
+        let candidates = ann.read(|index| {
+            if index.dim() != Some(query_embedding.len()) {
+                return None;
+            }
+            Some(index.search(query_embedding, fetch, |node| {
+                m.user_id.as_deref().is_none_or(|u| node.user_id.as_deref() == Some(u))
+                    && m
+                        .conversation_id
+                        .as_deref()
+                        .is_none_or(|c| node.conversation_id.as_deref() == Some(c))
+                    && !excluded.contains(&node.id)
+            }))
+        });
+        let Some(candidates) = candidates else {
+            return Ok(None);
+        };
+        if candidates.is_empty() {
+            return Ok(Some(Vec::new()));
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM memory_entries WHERE id IN (");
+        let mut ids = builder.separated(", ");
+        for (id, _) in &candidates {
+            ids.push_bind(id.to_string());
+        }
+        builder.push(")");
+        Self::push_filter(&mut builder, m);
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let mut by_id: HashMap<Uuid, MemoryEntry> = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let entry = Self::row_to_entry(&row)?;
+            by_id.insert(entry.id, entry);
+        }
+        let results: Vec<(f32, MemoryEntry)> = candidates
+            .iter()
+            .filter_map(|(id, score)| by_id.remove(id).map(|e| (*score, e)))
+            .take(limit)
+            .collect();
+        if results.len() < limit && candidates.len() == fetch {
+            return Ok(None);
+        }
+        Ok(Some(results))
+    }
+
     fn row_to_entry(row: &sqlx::sqlite::SqliteRow) -> Result<MemoryEntry, sqlx::Error> {
         let id: String = row.try_get("id")?;
         let content: String = row.try_get("content")?;
@@ -58,6 +485,7 @@ impl SQLiteVectorStore {
         let tokens: Option<i64> = row.try_get("tokens")?;
         let importance: Option<f64> = row.try_get("importance")?;
         let embedding_blob: Option<Vec<u8>> = row.try_get("embedding")?;
+        let expires_at_str: Option<String> = row.try_get("expires_at")?;
 
         let id = Uuid::from_str(&id).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
         let role = match role_str.as_str() {
@@ -74,6 +502,10 @@ impl SQLiteVectorStore {
         let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
             .map_err(|e| sqlx::Error::Decode(Box::new(e)))?
             .with_timezone(&Utc);
+        let expires_at = expires_at_str
+            .map(|s| DateTime::parse_from_rfc3339(&s).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
         let embedding = embedding_blob.map(|blob| {
             blob.chunks_exact(4)
                 .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
@@ -86,6 +518,7 @@ impl SQLiteVectorStore {
             timestamp,
             tokens: tokens.map(|t| t as u32),
             importance: importance.map(|i| i as f32),
+            expires_at,
         };
         Ok(MemoryEntry {
             id,
@@ -95,6 +528,77 @@ impl SQLiteVectorStore {
         })
     }
 
+    /// INSERT statement for one entry, bound and ready to execute on a pool or transaction.
+    fn insert_query(entry: &MemoryEntry) -> sqlx::query::Query<'_, Sqlite, SqliteArguments<'_>> {
+        Self::bind_entry(sqlx::query(INSERT_SQL), entry)
+    }
+
+    /// Binds the ten entry columns in table order, then `fts_text`, to `query`.
+    fn bind_entry<'q>(
+        query: sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>,
+        entry: &'q MemoryEntry,
+    ) -> sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>> {
+        let embedding_blob: Option<Vec<u8>> = entry.embedding.as_ref().map(|embedding| {
+            embedding
+                .iter()
+                .flat_map(|f| f.to_le_bytes().to_vec())
+                .collect()
+        });
+        query
+            .bind(entry.id.to_string())
+            .bind(&entry.content)
+            .bind(&entry.metadata.user_id)
+            .bind(&entry.metadata.conversation_id)
+            .bind(Self::role_to_str(entry.metadata.role))
+            .bind(entry.metadata.timestamp.to_rfc3339())
+            .bind(entry.metadata.tokens.map(|t| t as i64))
+            .bind(entry.metadata.importance.map(|i| i as f64))
+            .bind(embedding_blob)
+            .bind(entry.metadata.expires_at.map(|t| t.to_rfc3339()))
+            .bind(Self::fts_text(&entry.content))
+    }
+
+    fn role_to_str(role: MemoryRole) -> &'static str {
+        match role {
+            MemoryRole::User => "User",
+            MemoryRole::Assistant => "Assistant",
+            MemoryRole::System => "System",
+        }
+    }
+
+    /// Appends `AND ...` conditions for the fields set in `filter`, plus one hiding expired entries.
+    fn push_filter(builder: &mut QueryBuilder<'_, Sqlite>, filter: &MemoryFilter) {
+        builder
+            .push(" AND (expires_at IS NULL OR expires_at > ")
+            .push_bind(Utc::now().to_rfc3339())
+            .push(")");
+        if let Some(user_id) = &filter.user_id {
+            builder.push(" AND user_id = ").push_bind(user_id.clone());
+        }
+        if let Some(conversation_id) = &filter.conversation_id {
+            builder
+                .push(" AND conversation_id = ")
+                .push_bind(conversation_id.clone());
+        }
+        if let Some(role) = filter.role {
+            builder.push(" AND role = ").push_bind(Self::role_to_str(role));
+        }
+        if let Some(since) = filter.since {
+            builder.push(" AND timestamp >= ").push_bind(since.to_rfc3339());
+        }
+        if let Some(until) = filter.until {
+            builder.push(" AND timestamp < ").push_bind(until.to_rfc3339());
+        }
+        if let Some(min_importance) = filter.min_importance {
+            builder
+                .push(" AND importance >= ")
+                .push_bind(min_importance as f64);
+        }
+        if filter.non_empty {
+            builder.push(" AND content != ''");
+        }
+    }
+
     fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         if a.is_empty() || b.is_empty() {
             return 0.0;
@@ -127,37 +631,10 @@ impl MemoryStore for SQLiteVectorStore {
             has_embedding = entry.embedding.is_some(),
             "Writing entry to SQLite vector store"
         );
-        let role_str = match entry.metadata.role {
-            MemoryRole::User => "User",
-            MemoryRole::Assistant => "Assistant",
-            MemoryRole::System => "System",
-        };
-        let timestamp_str = entry.metadata.timestamp.to_rfc3339();
-        let embedding_blob: Option<Vec<u8>> = entry.embedding.map(|embedding| {
-            embedding
-                .iter()
-                .flat_map(|f| f.to_le_bytes().to_vec())
-                .collect()
-        });
-        sqlx::query(
-            r#"
-            INSERT INTO memory_entries (
-                id, content, user_id, conversation_id, role, timestamp,
-                tokens, importance, embedding
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-            "#,
-        )
-        .bind(entry.id.to_string())
-        .bind(&entry.content)
-        .bind(&entry.metadata.user_id)
-        .bind(&entry.metadata.conversation_id)
-        .bind(role_str)
-        .bind(&timestamp_str)
-        .bind(entry.metadata.tokens.map(|t| t as i64))
-        .bind(entry.metadata.importance.map(|i| i as f64))
-        .bind(embedding_blob)
-        .execute(&self.pool)
-        .await?;
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
+        Self::insert_query(&entry).execute(&self.pool).await?;
+        self.sync_ann(std::slice::from_ref(&entry));
         info!(
             id = %entry.id,
             user_id = ?entry.metadata.user_id,
@@ -167,11 +644,63 @@ impl MemoryStore for SQLiteVectorStore {
         Ok(())
     }
 
+    /// Saves the HNSW index when one is attached.
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.save_hnsw()
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        Ok(self.embedding_guard.spec())
+    }
+
+    /// Persists the spec in `memory_meta` the first time it is bound.
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        if self.embedding_guard.bind(spec)? {
+            self.save_embedding_spec(spec).await?;
+            info!(spec = %spec, "SQLite vector store embedding spec recorded");
+        }
+        Ok(())
+    }
+
+    /// Clears every embedding and the HNSW index, then records `spec`.
+    async fn reset_embeddings(&self, spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query("UPDATE memory_entries SET embedding = NULL WHERE embedding IS NOT NULL")
+            .execute(&self.pool)
+            .await?;
+        self.save_embedding_spec(spec).await?;
+        self.embedding_guard.set(Some(spec.clone()));
+        if let Some(ann) = &self.ann {
+            ann.write(0, |index| *index = HnswIndex::new(index.params()));
+            ann.save()?;
+        }
+        info!(cleared = result.rows_affected(), spec = %spec, "SQLite vector store embeddings reset");
+        Ok(result.rows_affected())
+    }
+
+    /// Inserts all entries in a single transaction.
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        self.embedding_guard.check_entries(&entries)?;
+        let mut tx = self.pool.begin().await?;
+        for entry in &entries {
+            Self::insert_query(entry).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        self.sync_ann(&entries);
+        info!(count = entries.len(), "Batch written to SQLite vector store");
+        Ok(())
+    }
+
     async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
         info!(id = %id, "Querying SQLite vector store by id");
-        let row = sqlx::query("SELECT * FROM memory_entries WHERE id = ?1")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
+        let row = sqlx::query(
+            "SELECT * FROM memory_entries WHERE id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+        )
+        .bind(id.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .fetch_optional(&self.pool)
             .await?;
         let found = row.is_some();
         info!(id = %id, found, "SQLite vector store get returned");
@@ -182,37 +711,12 @@ impl MemoryStore for SQLiteVectorStore {
     }
 
     async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
-        let role_str = match entry.metadata.role {
-            MemoryRole::User => "User",
-            MemoryRole::Assistant => "Assistant",
-            MemoryRole::System => "System",
-        };
-        let timestamp_str = entry.metadata.timestamp.to_rfc3339();
-        let embedding_blob: Option<Vec<u8>> = entry.embedding.map(|embedding| {
-            embedding
-                .iter()
-                .flat_map(|f| f.to_le_bytes().to_vec())
-                .collect()
-        });
-        sqlx::query(
-            r#"
-            UPDATE memory_entries SET
-                content = ?1, user_id = ?2, conversation_id = ?3, role = ?4,
-                timestamp = ?5, tokens = ?6, importance = ?7, embedding = ?8
-            WHERE id = ?9
-            "#,
-        )
-        .bind(&entry.content)
-        .bind(&entry.metadata.user_id)
-        .bind(&entry.metadata.conversation_id)
-        .bind(role_str)
-        .bind(&timestamp_str)
-        .bind(entry.metadata.tokens.map(|t| t as i64))
-        .bind(entry.metadata.importance.map(|i| i as f64))
-        .bind(embedding_blob)
-        .bind(entry.id.to_string())
-        .execute(&self.pool)
-        .await?;
+        self.embedding_guard
+            .check_entries(std::slice::from_ref(&entry))?;
+        Self::bind_entry(sqlx::query(UPSERT_SQL), &entry)
+            .execute(&self.pool)
+            .await?;
+        self.sync_ann(std::slice::from_ref(&entry));
         Ok(())
     }
 
@@ -221,14 +725,21 @@ impl MemoryStore for SQLiteVectorStore {
             .bind(id.to_string())
             .execute(&self.pool)
             .await?;
+        if let Some(ann) = &self.ann {
+            ann.write(1, |index| index.remove(&id));
+        }
         Ok(())
     }
 
     async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
         info!(user_id = %user_id, "Querying SQLite vector store by user");
-        let rows = sqlx::query("SELECT * FROM memory_entries WHERE user_id = ?1 ORDER BY timestamp DESC")
-            .bind(user_id)
-            .fetch_all(&self.pool)
+        let rows = sqlx::query(
+            "SELECT * FROM memory_entries WHERE user_id = ?1 \
+             AND (expires_at IS NULL OR expires_at > ?2) ORDER BY timestamp DESC",
+        )
+        .bind(user_id)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_all(&self.pool)
             .await?;
         let mut entries = Vec::new();
         for row in rows {
@@ -243,9 +754,13 @@ impl MemoryStore for SQLiteVectorStore {
         conversation_id: &str,
     ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
         info!(conversation_id = %conversation_id, "Querying SQLite vector store by conversation");
-        let rows = sqlx::query("SELECT * FROM memory_entries WHERE conversation_id = ?1 ORDER BY timestamp DESC")
-            .bind(conversation_id)
-            .fetch_all(&self.pool)
+        let rows = sqlx::query(
+            "SELECT * FROM memory_entries WHERE conversation_id = ?1 \
+             AND (expires_at IS NULL OR expires_at > ?2) ORDER BY timestamp DESC",
+        )
+        .bind(conversation_id)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_all(&self.pool)
             .await?;
         let mut entries = Vec::new();
         for row in rows {
@@ -261,38 +776,183 @@ impl MemoryStore for SQLiteVectorStore {
         limit: usize,
         user_id: Option<&str>,
         conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        self.semantic_search_filtered(
+            query_embedding,
+            limit,
+            &SearchFilter::scoped(user_id, conversation_id),
+        )
+        .await
+    }
+
+    /// Uses the HNSW index when attached, falling back to exact search when it cannot answer.
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
     ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
         info!(
             dimension = query_embedding.len(),
             limit = limit,
-            user_id = ?user_id,
-            conversation_id = ?conversation_id,
+            filter = ?filter,
+            ann = self.ann.is_some(),
             "step: embedding SQLite semantic search"
         );
-        let rows = sqlx::query("SELECT * FROM memory_entries WHERE embedding IS NOT NULL")
-            .fetch_all(&self.pool)
-            .await?;
-        let mut similarities: Vec<(f32, MemoryEntry)> = Vec::new();
-        for row in rows {
-            let entry = Self::row_to_entry(&row)?;
-            let match_user = user_id
-                .map(|u| entry.metadata.user_id.as_deref() == Some(u))
-                .unwrap_or(true);
-            let match_conv = conversation_id
-                .map(|c| entry.metadata.conversation_id.as_deref() == Some(c))
-                .unwrap_or(true);
-            if match_user && match_conv {
-                if let Some(embedding) = &entry.embedding {
-                    let similarity = Self::cosine_similarity(query_embedding, embedding);
-                    similarities.push((similarity, entry));
-                }
+        self.embedding_guard.check_query(query_embedding)?;
+        if let Some(ann) = &self.ann {
+            if let Some(results) = self
+                .semantic_search_ann(ann, query_embedding, limit, filter)
+                .await?
+            {
+                info!(limit = limit, count = results.len(), "step: embedding SQLite semantic search done (hnsw)");
+                return Ok(results);
             }
+            info!("HNSW index could not answer; falling back to exact search");
         }
-        similarities.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
-        let results: Vec<(f32, MemoryEntry)> = similarities.into_iter().take(limit).collect();
+        let results = self
+            .semantic_search_exact(query_embedding, limit, filter)
+            .await?;
         info!(limit = limit, count = results.len(), "step: embedding SQLite semantic search done");
         Ok(results)
     }
+
+    /// Ranks with the FTS5 index (`bm25()`); the query's terms are OR-ed, each quoted so user text
+    /// cannot inject FTS syntax.
+    async fn keyword_search(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        if terms.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let fts_query = terms
+            .iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT memory_entries.*, bm25(memory_fts) AS bm25_score FROM memory_fts \
+             JOIN memory_entries ON memory_entries.rowid = memory_fts.rowid WHERE memory_fts MATCH ",
+        );
+        builder.push_bind(fts_query);
+        Self::push_filter(&mut builder, &filter.metadata);
+        if !filter.exclude_ids.is_empty() {
+            builder.push(" AND memory_entries.id NOT IN (");
+            let mut ids = builder.separated(", ");
+            for id in &filter.exclude_ids {
+                ids.push_bind(id.to_string());
+            }
+            builder.push(")");
+        }
+        let fetch = i64::try_from(limit).unwrap_or(i64::MAX);
+        builder.push(" ORDER BY bm25_score LIMIT ").push_bind(fetch);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let mut results = Vec::with_capacity(rows.len());
+        for row in &rows {
+            // bm25() is lower-is-better; negate so higher is better, as for similarities.
+            let score: f64 = row.try_get("bm25_score")?;
+            results.push((-score as f32, Self::row_to_entry(row)?));
+        }
+        info!(limit = limit, count = results.len(), "SQLite vector store keyword_search returned");
+        Ok(results)
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM memory_entries WHERE 1=1");
+        Self::push_filter(&mut builder, filter);
+        let (cmp, order_by) = match order {
+            MemoryOrder::NewestFirst => ("<", " ORDER BY timestamp DESC, id DESC"),
+            MemoryOrder::OldestFirst => (">", " ORDER BY timestamp ASC, id ASC"),
+        };
+        if let Some(cursor) = cursor {
+            let timestamp = cursor.timestamp.to_rfc3339();
+            builder
+                .push(format!(" AND (timestamp {} ", cmp))
+                .push_bind(timestamp.clone())
+                .push(" OR (timestamp = ")
+                .push_bind(timestamp)
+                .push(format!(" AND id {} ", cmp))
+                .push_bind(cursor.id.to_string())
+                .push("))");
+        }
+        builder.push(order_by);
+        // One extra row tells whether another page follows.
+        let fetch = i64::try_from(limit.saturating_add(1)).unwrap_or(i64::MAX);
+        builder.push(" LIMIT ").push_bind(fetch);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let entries = rows
+            .iter()
+            .map(Self::row_to_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        info!(filter = ?filter, order = ?order, limit = limit, count = entries.len(), "SQLite vector store list returned");
+        Ok(MemoryPage::from_sorted(entries, limit))
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM memory_entries WHERE 1=1");
+        Self::push_filter(&mut builder, filter);
+        let (count,): (i64,) = builder.build_query_as().fetch_one(&self.pool).await?;
+        Ok(count as u64)
+    }
+
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query("DELETE FROM memory_entries WHERE conversation_id = ?1")
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        if let Some(ann) = &self.ann {
+            ann.write(result.rows_affected() as usize, |index| {
+                index.remove_where(|node| node.conversation_id.as_deref() == Some(conversation_id))
+            });
+        }
+        info!(conversation_id = %conversation_id, deleted = result.rows_affected(), "SQLite vector store delete_by_conversation");
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        let result = sqlx::query("DELETE FROM memory_entries WHERE user_id = ?1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        if let Some(ann) = &self.ann {
+            ann.write(result.rows_affected() as usize, |index| {
+                index.remove_where(|node| node.user_id.as_deref() == Some(user_id))
+            });
+        }
+        info!(user_id = %user_id, deleted = result.rows_affected(), "SQLite vector store delete_by_user");
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes expired rows (found through `idx_expires_at`) and drops them from the HNSW index.
+    async fn delete_expired(&self, now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM memory_entries WHERE expires_at IS NOT NULL AND expires_at <= ?1 RETURNING id",
+        )
+        .bind(now.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+        if let Some(ann) = &self.ann {
+            ann.write(ids.len(), |index| {
+                for id in ids.iter().filter_map(|id| Uuid::from_str(id).ok()) {
+                    index.remove(&id);
+                }
+            });
+        }
+        info!(deleted = ids.len(), "SQLite vector store delete_expired");
+        Ok(ids.len() as u64)
+    }
 }
 
 #[cfg(test)]
@@ -309,15 +969,7 @@ mod tests {
     }
 
     fn create_test_entry(content: &str, user_id: &str) -> MemoryEntry {
-        let metadata = MemoryMetadata {
-            user_id: Some(user_id.to_string()),
-            conversation_id: None,
-            role: MemoryRole::User,
-            timestamp: Utc::now(),
-            tokens: None,
-            importance: None,
-        };
-        MemoryEntry::new(content.to_string(), metadata)
+        MemoryEntry::new(content.to_string(), MemoryMetadata::default().with_user(user_id))
     }
 
     #[tokio::test]
@@ -337,6 +989,187 @@ mod tests {
         assert!(found.is_none());
     }
 
+    #[tokio::test]
+    async fn test_add_batch() {
+        let store = create_test_store().await;
+        let mut with_embedding = create_test_entry("Embedded", "user123");
+        with_embedding.embedding = Some(vec![0.5, 0.25]);
+        let entries = vec![
+            create_test_entry("First", "user123"),
+            with_embedding.clone(),
+            create_test_entry("Third", "user456"),
+        ];
+        store.add_batch(entries).await.unwrap();
+        store.add_batch(Vec::new()).await.unwrap();
+
+        assert_eq!(store.search_by_user("user123").await.unwrap().len(), 2);
+        let found = store.get(with_embedding.id).await.unwrap().unwrap();
+        assert_eq!(found.embedding, Some(vec![0.5, 0.25]));
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_hidden_then_swept() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_str().unwrap().to_string();
+        // A table from before expiry existed, without the expires_at column.
+        let options = SqliteConnectOptions::new().create_if_missing(true).filename(&db_path);
+        let pool = SqlitePool::connect_with(options).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE memory_entries (id TEXT PRIMARY KEY, content TEXT NOT NULL, user_id TEXT, \
+             conversation_id TEXT, role TEXT NOT NULL, timestamp TEXT NOT NULL, tokens INTEGER, \
+             importance REAL, embedding BLOB)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        let store = SQLiteVectorStore::new(&db_path).await.unwrap();
+        let kept = create_test_entry("kept", "user123");
+        let mut expired = create_test_entry("expired", "user123");
+        expired.metadata.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        let mut later = create_test_entry("later", "user123");
+        later.metadata.expires_at = Some(Utc::now() + chrono::Duration::hours(1));
+        store
+            .add_batch(vec![kept.clone(), expired.clone(), later.clone()])
+            .await
+            .unwrap();
+
+        assert!(store.get(expired.id).await.unwrap().is_none());
+        assert_eq!(store.search_by_user("user123").await.unwrap().len(), 2);
+        assert_eq!(store.count(&MemoryFilter::for_user("user123")).await.unwrap(), 2);
+        assert_eq!(
+            store.get(later.id).await.unwrap().unwrap().metadata.expires_at,
+            later.metadata.expires_at
+        );
+
+        assert_eq!(store.delete_expired(Utc::now()).await.unwrap(), 1);
+        assert_eq!(store.delete_expired(Utc::now()).await.unwrap(), 0);
+        let (rows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM memory_entries")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 2);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_filtered_pushes_down_predicates() {
+        let store = create_test_store().await;
+        let now = Utc::now();
+        let mut entries = Vec::new();
+        for (content, role, age_days, importance) in [
+            ("recent important user", MemoryRole::User, 1, Some(0.8)),
+            ("recent unimportant user", MemoryRole::User, 1, Some(0.2)),
+            ("recent important assistant", MemoryRole::Assistant, 1, Some(0.9)),
+            ("old important user", MemoryRole::User, 60, Some(0.9)),
+            ("recent excluded user", MemoryRole::User, 2, Some(0.7)),
+        ] {
+            let mut entry = create_test_entry(content, "user123");
+            entry.metadata.role = role;
+            entry.metadata.timestamp = now - chrono::Duration::days(age_days);
+            entry.metadata.importance = importance;
+            entry.embedding = Some(vec![1.0, 0.0]);
+            entries.push(entry);
+        }
+        let excluded = entries[4].id;
+        store.add_batch(entries).await.unwrap();
+
+        let filter = SearchFilter::scoped(Some("user123"), None)
+            .with_role(MemoryRole::User)
+            .since(now - chrono::Duration::days(30))
+            .with_min_importance(0.5)
+            .excluding([excluded]);
+        let results = store
+            .semantic_search_filtered(&[1.0, 0.0], 1, &filter)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.content, "recent important user");
+    }
+
+    #[tokio::test]
+    async fn test_hnsw_index_tracks_writes_and_survives_reopen() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_str().unwrap().to_string();
+        let index_path = temp_dir.path().join("test.db.hnsw");
+
+        let store = SQLiteVectorStore::new(&db_path).await.unwrap();
+        let mut entries = Vec::new();
+        for i in 0..20 {
+            let mut entry = create_test_entry(&format!("entry {}", i), "user123");
+            entry.metadata.conversation_id = Some(if i % 2 == 0 { "even" } else { "odd" }.to_string());
+            entry.embedding = Some(vec![1.0, i as f32 / 10.0]);
+            entries.push(entry);
+        }
+        // Half written before the index exists (rebuild), half after (incremental).
+        store.add_batch(entries[..10].to_vec()).await.unwrap();
+        let store = store.with_hnsw(&index_path, HnswParams::default()).await.unwrap();
+        store.add_batch(entries[10..].to_vec()).await.unwrap();
+
+        let query = [1.0, 0.35];
+        let filter = SearchFilter::scoped(None, Some("odd"));
+        let ann = store.semantic_search_filtered(&query, 3, &filter).await.unwrap();
+        let exact = store.semantic_search_exact(&query, 3, &filter).await.unwrap();
+        let ids = |r: &[(f32, MemoryEntry)]| r.iter().map(|(_, e)| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(&ann), ids(&exact));
+        assert!(ann.iter().all(|(_, e)| e.metadata.conversation_id.as_deref() == Some("odd")));
+
+        let top = ann[0].1.id;
+        store.delete(top).await.unwrap();
+        assert_eq!(store.delete_by_conversation("even").await.unwrap(), 10);
+        store.save_hnsw().unwrap();
+        drop(store);
+
+        let reopened = SQLiteVectorStore::new(&db_path)
+            .await
+            .unwrap()
+            .with_hnsw(&index_path, HnswParams::default())
+            .await
+            .unwrap();
+        let results = reopened
+            .semantic_search(&query, 20, None, None)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 9);
+        assert!(results.iter().all(|(_, e)| e.id != top));
+    }
+
+    #[tokio::test]
+    async fn test_content_fts_index_is_rebuilt_over_tokenized_text() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db").to_str().unwrap().to_string();
+        let store = SQLiteVectorStore::new(&db_path).await.unwrap();
+        let entry = create_test_entry("我最喜欢喝绿茶", "user123");
+        store.add(entry.clone()).await.unwrap();
+
+        // Recreate the index layout of older versions: FTS over `content`, no `fts_text`.
+        sqlx::query(
+            r#"
+            DROP TRIGGER memory_fts_insert;
+            DROP TRIGGER memory_fts_delete;
+            DROP TRIGGER memory_fts_update;
+            DROP TABLE memory_fts;
+            ALTER TABLE memory_entries DROP COLUMN fts_text;
+            CREATE VIRTUAL TABLE memory_fts USING fts5(
+                content, content = 'memory_entries', content_rowid = 'rowid'
+            );
+            INSERT INTO memory_fts(memory_fts) VALUES ('rebuild');
+            "#,
+        )
+        .execute(&store.pool)
+        .await
+        .unwrap();
+        drop(store);
+
+        let reopened = SQLiteVectorStore::new(&db_path).await.unwrap();
+        let results = reopened
+            .keyword_search("绿茶", 5, &SearchFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.id, entry.id);
+    }
+
     #[tokio::test]
     async fn test_search_by_user() {
         let store = create_test_store().await;
@@ -349,4 +1182,45 @@ mod tests {
         let results = store.search_by_user("user123").await.unwrap();
         assert_eq!(results.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_list_count_and_delete_by_conversation() {
+        let store = create_test_store().await;
+        let base = Utc::now() - chrono::Duration::hours(1);
+        for (i, role) in [MemoryRole::User, MemoryRole::Assistant, MemoryRole::User]
+            .into_iter()
+            .enumerate()
+        {
+            let mut entry = create_test_entry(&format!("m{}", i), "user123");
+            entry.metadata.conversation_id = Some("chat1".to_string());
+            entry.metadata.role = role;
+            entry.metadata.timestamp = base + chrono::Duration::minutes(i as i64);
+            store.add(entry).await.unwrap();
+        }
+        store.add(create_test_entry("elsewhere", "user123")).await.unwrap();
+
+        let filter = MemoryFilter::for_conversation("chat1");
+        assert_eq!(store.count(&filter).await.unwrap(), 3);
+        assert_eq!(
+            store
+                .count(&filter.clone().with_role(MemoryRole::User))
+                .await
+                .unwrap(),
+            2
+        );
+
+        let page = store.list(&filter, MemoryOrder::NewestFirst, 2, None).await.unwrap();
+        let contents: Vec<&str> = page.entries.iter().map(|e| e.content.as_str()).collect();
+        assert_eq!(contents, vec!["m2", "m1"]);
+        let rest = store
+            .list(&filter, MemoryOrder::NewestFirst, 2, page.next_cursor.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(rest.entries.len(), 1);
+        assert_eq!(rest.entries[0].content, "m0");
+        assert!(rest.next_cursor.is_none());
+
+        assert_eq!(store.delete_by_conversation("chat1").await.unwrap(), 3);
+        assert_eq!(store.count(&MemoryFilter::for_user("user123")).await.unwrap(), 1);
+    }
 }
@@ -0,0 +1,152 @@
+//! Time-to-live policy for memory entries: how long an entry lives, by role and by source.
+//!
+//! The source of an entry is the Telegram chat type it came from (`private`, `group`,
+//! `supergroup`, `channel`), so transient group chatter can expire while private conversations
+//! persist. The resulting `expires_at` is stored in [`MemoryMetadata`](crate::memory_core::MemoryMetadata);
+//! stores hide expired entries at query time and the expiry sweeper deletes them.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::memory_core::MemoryRole;
+
+/// TTLs by role and by source; when several apply, the shortest wins. Entries no TTL applies to
+/// never expire.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TtlPolicy {
+    user: Option<Duration>,
+    assistant: Option<Duration>,
+    system: Option<Duration>,
+    sources: HashMap<String, Duration>,
+}
+
+impl TtlPolicy {
+    /// Policy under which nothing expires.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a policy from per-role TTLs in hours (`None` or `0` = no TTL) and per-source TTLs.
+    pub(crate) fn from_hours(
+        user: Option<u32>,
+        assistant: Option<u32>,
+        system: Option<u32>,
+        sources: HashMap<String, Duration>,
+    ) -> Self {
+        let hours = |h: Option<u32>| h.filter(|h| *h > 0).map(|h| Duration::hours(h as i64));
+        Self {
+            user: hours(user),
+            assistant: hours(assistant),
+            system: hours(system),
+            sources,
+        }
+    }
+
+    /// Entries of `role` expire `ttl` after their timestamp.
+    pub fn with_role_ttl(mut self, role: MemoryRole, ttl: Duration) -> Self {
+        *self.role_slot(role) = Some(ttl);
+        self
+    }
+
+    /// Entries from `source` (a chat type, compared case-insensitively) expire `ttl` after their timestamp.
+    pub fn with_source_ttl(mut self, source: impl AsRef<str>, ttl: Duration) -> Self {
+        self.sources
+            .insert(source.as_ref().trim().to_lowercase(), ttl);
+        self
+    }
+
+    /// True when at least one TTL is set (the expiry sweeper is only needed then).
+    pub fn is_enabled(&self) -> bool {
+        self.user.is_some()
+            || self.assistant.is_some()
+            || self.system.is_some()
+            || !self.sources.is_empty()
+    }
+
+    /// Shortest TTL applying to an entry of `role` from `source`.
+    pub fn ttl_for(&self, role: MemoryRole, source: Option<&str>) -> Option<Duration> {
+        let by_role = match role {
+            MemoryRole::User => self.user,
+            MemoryRole::Assistant => self.assistant,
+            MemoryRole::System => self.system,
+        };
+        let by_source = source.and_then(|s| self.sources.get(&s.trim().to_lowercase()).copied());
+        by_role.into_iter().chain(by_source).min()
+    }
+
+    /// Expiry of an entry of `role` from `source` written at `timestamp`; `None` when it never expires.
+    pub fn expires_at(
+        &self,
+        role: MemoryRole,
+        source: Option<&str>,
+        timestamp: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.ttl_for(role, source).map(|ttl| timestamp + ttl)
+    }
+
+    fn role_slot(&mut self, role: MemoryRole) -> &mut Option<Duration> {
+        match role {
+            MemoryRole::User => &mut self.user,
+            MemoryRole::Assistant => &mut self.assistant,
+            MemoryRole::System => &mut self.system,
+        }
+    }
+}
+
+/// Parses `source:hours` pairs separated by commas into per-source TTLs. Empty items are ignored;
+/// `0` hours sets no TTL for that source.
+pub(crate) fn parse_source_ttls(s: &str) -> Result<HashMap<String, Duration>> {
+    let mut ttls = HashMap::new();
+    for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let (source, hours) = item.split_once(':').ok_or_else(|| {
+            anyhow::anyhow!("MEMORY_TTL_SOURCES: expected source:hours, got {:?}", item)
+        })?;
+        let hours: u32 = hours
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("MEMORY_TTL_SOURCES: invalid hours {:?}: {}", hours, e))?;
+        if hours > 0 {
+            ttls.insert(source.trim().to_lowercase(), Duration::hours(hours as i64));
+        }
+    }
+    Ok(ttls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortest_applicable_ttl_wins() {
+        let policy = TtlPolicy::new()
+            .with_role_ttl(MemoryRole::User, Duration::hours(48))
+            .with_source_ttl("Group", Duration::hours(12));
+        let t = Utc::now();
+        assert_eq!(
+            policy.expires_at(MemoryRole::User, Some("group"), t),
+            Some(t + Duration::hours(12))
+        );
+        assert_eq!(
+            policy.expires_at(MemoryRole::User, Some("private"), t),
+            Some(t + Duration::hours(48))
+        );
+        assert_eq!(
+            policy.expires_at(MemoryRole::Assistant, Some("group"), t),
+            Some(t + Duration::hours(12))
+        );
+        assert_eq!(policy.expires_at(MemoryRole::Assistant, None, t), None);
+        assert!(!TtlPolicy::new().is_enabled());
+    }
+
+    #[test]
+    fn parses_source_ttls() {
+        let ttls = parse_source_ttls("group:72, supergroup:24,channel:0,").unwrap();
+        assert_eq!(ttls.get("group"), Some(&Duration::hours(72)));
+        assert_eq!(ttls.get("supergroup"), Some(&Duration::hours(24)));
+        assert!(!ttls.contains_key("channel"));
+        assert!(parse_source_ttls("group").is_err());
+        assert!(parse_source_ttls("group:soon").is_err());
+    }
+}
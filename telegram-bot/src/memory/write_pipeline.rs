@@ -0,0 +1,228 @@
+//! Write pipeline for conversation memory: annotates entries with a token count and importance,
+//! drops low-importance entries and merges near-duplicates of recent ones.
+
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::memory_core::keyword::tokenize;
+use crate::memory_core::{MemoryEntry, MemoryFilter, MemoryOrder, MemoryStore};
+
+use crate::tokenizer::{EstimateTokenizer, Tokenizer};
+
+use super::importance::{HeuristicImportanceScorer, ImportanceScorer};
+
+/// Default similarity at or above which an entry is a near-duplicate of a recent one.
+pub const DEFAULT_DEDUP_THRESHOLD: f32 = 0.95;
+/// Default number of recent entries (same conversation and role) checked for duplicates.
+pub const DEFAULT_DEDUP_WINDOW: usize = 20;
+/// Importance added to an entry each time a near-duplicate is merged into it (repetition signals
+/// that it matters).
+const REPEAT_BOOST: f32 = 0.05;
+
+/// What [`MemoryWritePipeline::write`] did with an entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteOutcome {
+    /// Written as a new entry.
+    Stored,
+    /// Not written: importance below the configured minimum.
+    Skipped { importance: f32 },
+    /// Not written: near-duplicate of `into`, which was refreshed (timestamp, importance) instead.
+    Merged { into: Uuid },
+}
+
+/// Scores entries and filters noise before they reach a [`MemoryStore`].
+///
+/// The scorer and tokenizer can be replaced after construction ([`set_scorer`](Self::set_scorer),
+/// [`set_tokenizer`](Self::set_tokenizer)), so an application can install an LLM scorer and the
+/// model's tokenizer once its client exists. When the scorer fails the heuristic score is used.
+/// Until a tokenizer is set, token counts come from [`EstimateTokenizer`].
+pub struct MemoryWritePipeline {
+    scorer: RwLock<Arc<dyn ImportanceScorer>>,
+    tokenizer: RwLock<Arc<dyn Tokenizer>>,
+    min_importance: f32,
+    dedup_threshold: Option<f32>,
+    dedup_window: usize,
+}
+
+impl Default for MemoryWritePipeline {
+    fn default() -> Self {
+        Self::new(Arc::new(HeuristicImportanceScorer))
+    }
+}
+
+impl MemoryWritePipeline {
+    /// Pipeline with `scorer`, no minimum importance and default duplicate detection.
+    pub fn new(scorer: Arc<dyn ImportanceScorer>) -> Self {
+        Self {
+            scorer: RwLock::new(scorer),
+            tokenizer: RwLock::new(Arc::new(EstimateTokenizer)),
+            min_importance: 0.0,
+            dedup_threshold: Some(DEFAULT_DEDUP_THRESHOLD),
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+        }
+    }
+
+    /// Entries scoring below `min_importance` are not written.
+    pub fn with_min_importance(mut self, min_importance: f32) -> Self {
+        self.min_importance = min_importance;
+        self
+    }
+
+    /// Near-duplicate detection against the last `window` entries; `None` disables it.
+    pub fn with_dedup(mut self, threshold: Option<f32>, window: usize) -> Self {
+        self.dedup_threshold = threshold;
+        self.dedup_window = window;
+        self
+    }
+
+    pub fn set_scorer(&self, scorer: Arc<dyn ImportanceScorer>) {
+        *self.scorer.write().unwrap_or_else(|e| e.into_inner()) = scorer;
+    }
+
+    fn scorer(&self) -> Arc<dyn ImportanceScorer> {
+        self.scorer.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set_tokenizer(&self, tokenizer: Arc<dyn Tokenizer>) {
+        *self.tokenizer.write().unwrap_or_else(|e| e.into_inner()) = tokenizer;
+    }
+
+    fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        self.tokenizer.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Fills in `tokens` and `importance` when missing.
+    pub async fn annotate(&self, entry: &mut MemoryEntry) {
+        if entry.metadata.tokens.is_none() {
+            entry.metadata.tokens = Some(self.tokenizer().count_tokens(&entry.content) as u32);
+        }
+        if entry.metadata.importance.is_none() {
+            let importance = match self.scorer().score(entry).await {
+                Ok(score) => score,
+                Err(e) => {
+                    warn!(error = %e, "Importance scorer failed, using heuristic score");
+                    HeuristicImportanceScorer::score_text(&entry.content, entry.metadata.role)
+                }
+            };
+            entry.metadata.importance = Some(importance.clamp(0.0, 1.0));
+        }
+    }
+
+    /// Annotates `entry`, then writes it unless it is below the minimum importance or a
+    /// near-duplicate of a recent entry of the same conversation and role.
+    pub async fn write(
+        &self,
+        store: &dyn MemoryStore,
+        mut entry: MemoryEntry,
+    ) -> Result<WriteOutcome, anyhow::Error> {
+        self.annotate(&mut entry).await;
+        let importance = entry.metadata.importance.unwrap_or_default();
+        if importance < self.min_importance {
+            debug!(entry_id = %entry.id, importance = importance, "Skipping low-importance memory entry");
+            return Ok(WriteOutcome::Skipped { importance });
+        }
+        if let Some(mut existing) = self.find_duplicate(store, &entry).await? {
+            let into = existing.id;
+            existing.metadata.timestamp = existing.metadata.timestamp.max(entry.metadata.timestamp);
+            // The repeat keeps the memory alive as long as the longer-lived of the two.
+            existing.metadata.expires_at =
+                match (existing.metadata.expires_at, entry.metadata.expires_at) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+            existing.metadata.importance = Some(
+                (existing.metadata.importance.unwrap_or_default().max(importance) + REPEAT_BOOST)
+                    .min(1.0),
+            );
+            store.update(existing).await?;
+            debug!(entry_id = %entry.id, into = %into, "Merged near-duplicate memory entry");
+            return Ok(WriteOutcome::Merged { into });
+        }
+        store.add(entry).await?;
+        Ok(WriteOutcome::Stored)
+    }
+
+    /// Most recent entry of the same conversation and role that `entry` nearly duplicates.
+    async fn find_duplicate(
+        &self,
+        store: &dyn MemoryStore,
+        entry: &MemoryEntry,
+    ) -> Result<Option<MemoryEntry>, anyhow::Error> {
+        let (Some(threshold), Some(conversation_id)) =
+            (self.dedup_threshold, entry.metadata.conversation_id.as_ref())
+        else {
+            return Ok(None);
+        };
+        if self.dedup_window == 0 {
+            return Ok(None);
+        }
+        let filter = MemoryFilter {
+            role: Some(entry.metadata.role),
+            ..MemoryFilter::for_conversation(conversation_id.as_str())
+        };
+        let recent = store
+            .list(&filter, MemoryOrder::NewestFirst, self.dedup_window, None)
+            .await?;
+        Ok(recent
+            .entries
+            .into_iter()
+            .find(|candidate| similarity(candidate, entry) >= threshold))
+    }
+}
+
+/// Cosine similarity of the embeddings when both have one, otherwise Jaccard similarity of the
+/// content's tokens.
+pub(crate) fn similarity(a: &MemoryEntry, b: &MemoryEntry) -> f32 {
+    if let (Some(x), Some(y)) = (&a.embedding, &b.embedding) {
+        if x.len() == y.len() {
+            let dot: f32 = x.iter().zip(y).map(|(p, q)| p * q).sum();
+            let norm = x.iter().map(|v| v * v).sum::<f32>().sqrt()
+                * y.iter().map(|v| v * v).sum::<f32>().sqrt();
+            return if norm == 0.0 { 0.0 } else { dot / norm };
+        }
+    }
+    let x: HashSet<String> = tokenize(&a.content).into_iter().collect();
+    let y: HashSet<String> = tokenize(&b.content).into_iter().collect();
+    if x.is_empty() && y.is_empty() {
+        return if a.content.trim() == b.content.trim() { 1.0 } else { 0.0 };
+    }
+    x.intersection(&y).count() as f32 / x.union(&y).count() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryVectorStore;
+    use crate::memory_core::MemoryMetadata;
+
+    #[tokio::test]
+    async fn test_pipeline_skips_fillers_and_merges_repeats() {
+        let store = InMemoryVectorStore::new();
+        let pipeline = MemoryWritePipeline::default().with_min_importance(0.2);
+        let chat = MemoryMetadata::default().with_user("u1").with_conversation("c1");
+        let entry = |content: &str| MemoryEntry::new(content.to_string(), chat.clone());
+
+        assert!(matches!(
+            pipeline.write(&store, entry("ok")).await.unwrap(),
+            WriteOutcome::Skipped { .. }
+        ));
+        let first = entry("When does the store on Main Street open?");
+        let first_id = first.id;
+        assert_eq!(pipeline.write(&store, first).await.unwrap(), WriteOutcome::Stored);
+        assert_eq!(
+            pipeline
+                .write(&store, entry("when does the store on main street open"))
+                .await
+                .unwrap(),
+            WriteOutcome::Merged { into: first_id }
+        );
+
+        let stored = store.search_by_conversation("c1").await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].metadata.tokens.is_some());
+        assert!(stored[0].metadata.importance.unwrap() > 0.2);
+    }
+}
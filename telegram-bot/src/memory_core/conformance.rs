@@ -0,0 +1,866 @@
+//! Conformance checks every [`MemoryStore`] implementation must pass.
+//!
+//! Each `check_*` function takes a fresh, empty store and panics with a descriptive message on the
+//! first violation. Backends run the whole suite with [`memory_store_conformance_tests!`](crate::memory_store_conformance_tests):
+//!
+//! ```rust,ignore
+//! async fn make_store() -> (TempDir, SQLiteVectorStore) { /* fresh store in a temp dir */ }
+//! telegram_bot::memory_store_conformance_tests!(make_store());
+//! ```
+//!
+//! Other crates enable the module with the `conformance` feature (in their dev-dependencies).
+//!
+//! The contract:
+//! - `update` is an upsert and fully replaces the entry; `delete` of an unknown id is not an error.
+//! - Entries without an embedding round-trip as `embedding: None` and never appear in semantic search.
+//! - Semantic scores lie in `[-1, 1]`, are sorted best first, and rank by cosine similarity.
+//! - User, conversation and [`SearchFilter`] predicates are exact (`non_empty` included); `list` pages
+//!   cover every entry once.
+//! - `keyword_search` ranks entries sharing the query's terms (with or without a vector) best first;
+//!   each CJK character is a term, so words match inside unsegmented CJK text.
+//! - A [`ScopedStore`](super::ScopedStore) never reads, writes, counts or deletes entries outside its
+//!   scope; by-id access to another scope, and bulk deletes and searches naming it, fail with
+//!   [`ScopeViolation`].
+//! - Once vectors are stored, writes and queries of another dimension fail with [`EmbeddingMismatch`];
+//!   binding another model fails; `reset_embeddings` drops vectors and records the new model.
+//!
+//! Embeddings used by the checks have [`CONFORMANCE_DIM`] dimensions.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use super::embedding_spec::{EmbeddingMismatch, EmbeddingSpec};
+use super::query::{MemoryFilter, MemoryOrder, SearchFilter};
+use super::scoped_store::{
+    get_scoped_store, get_store, get_user_store, MemoryScope, ScopeViolation,
+};
+use super::store::MemoryStore;
+use super::types::{MemoryEntry, MemoryMetadata, MemoryRole};
+
+/// Embedding dimension used by the checks (stores with a fixed dimension must be created with it).
+pub const CONFORMANCE_DIM: usize = 3;
+
+/// Tolerance for score comparisons (stores may compute in f32 or via distances).
+const EPS: f32 = 1e-4;
+
+fn entry(
+    content: &str,
+    user_id: &str,
+    conversation_id: &str,
+    role: MemoryRole,
+    age_secs: i64,
+    embedding: Option<[f32; CONFORMANCE_DIM]>,
+) -> MemoryEntry {
+    let metadata = MemoryMetadata::default()
+        .with_user(user_id)
+        .with_conversation(conversation_id)
+        .with_role(role)
+        .with_timestamp(Utc::now() - Duration::seconds(age_secs));
+    let entry = MemoryEntry::new(content.to_string(), metadata);
+    match embedding {
+        Some(embedding) => entry.with_embedding(embedding.to_vec()),
+        None => entry,
+    }
+}
+
+fn ids(entries: &[MemoryEntry]) -> HashSet<Uuid> {
+    entries.iter().map(|e| e.id).collect()
+}
+
+fn scored_ids(results: &[(f32, MemoryEntry)]) -> Vec<Uuid> {
+    results.iter().map(|(_, e)| e.id).collect()
+}
+
+/// add / get / update (upsert, full replacement) / delete.
+pub async fn check_crud(store: Arc<dyn MemoryStore>) {
+    let original = entry(
+        "original",
+        "u1",
+        "c1",
+        MemoryRole::User,
+        10,
+        Some([1.0, 0.0, 0.0]),
+    );
+    store.add(original.clone()).await.expect("add");
+
+    let got = store
+        .get(original.id)
+        .await
+        .expect("get")
+        .expect("added entry must be found");
+    assert_eq!(got.content, "original");
+    assert_eq!(
+        got.metadata, original.metadata,
+        "metadata must round-trip unchanged"
+    );
+    assert_eq!(
+        got.embedding, original.embedding,
+        "embedding must round-trip unchanged"
+    );
+    assert!(store
+        .get(Uuid::new_v4())
+        .await
+        .expect("get unknown")
+        .is_none());
+
+    let mut changed = original.clone();
+    changed.content = "changed".to_string();
+    changed.metadata.role = MemoryRole::Assistant;
+    changed.metadata.importance = Some(0.5);
+    changed.embedding = Some(vec![0.0, 1.0, 0.0]);
+    store.update(changed.clone()).await.expect("update");
+    let got = store
+        .get(original.id)
+        .await
+        .expect("get")
+        .expect("updated entry must be found");
+    assert_eq!(got.content, "changed", "update must replace content");
+    assert_eq!(
+        got.metadata, changed.metadata,
+        "update must replace metadata"
+    );
+    assert_eq!(
+        got.embedding, changed.embedding,
+        "update must replace the embedding"
+    );
+    assert_eq!(
+        store
+            .search_by_conversation("c1")
+            .await
+            .expect("search")
+            .len(),
+        1,
+        "update must not duplicate the entry"
+    );
+
+    let upserted = entry("upserted", "u1", "c1", MemoryRole::User, 5, None);
+    store
+        .update(upserted.clone())
+        .await
+        .expect("update of a missing id");
+    assert!(
+        store.get(upserted.id).await.expect("get").is_some(),
+        "update of a missing id must insert it"
+    );
+
+    store.delete(original.id).await.expect("delete");
+    assert!(
+        store.get(original.id).await.expect("get").is_none(),
+        "deleted entry must be gone"
+    );
+    store
+        .delete(original.id)
+        .await
+        .expect("delete of an unknown id must not fail");
+}
+
+/// Entries without embedding round-trip as `None` and are never returned by semantic search.
+pub async fn check_null_embeddings(store: Arc<dyn MemoryStore>) {
+    let plain = entry("no vector", "u1", "c1", MemoryRole::User, 10, None);
+    let embedded = entry(
+        "vector",
+        "u1",
+        "c1",
+        MemoryRole::User,
+        5,
+        Some([1.0, 0.0, 0.0]),
+    );
+    store.add(plain.clone()).await.expect("add");
+    store.add(embedded.clone()).await.expect("add");
+
+    let got = store
+        .get(plain.id)
+        .await
+        .expect("get")
+        .expect("entry must be found");
+    assert_eq!(
+        got.embedding, None,
+        "missing embedding must read back as None"
+    );
+
+    let results = store
+        .semantic_search(&[1.0, 0.0, 0.0], 10, None, None)
+        .await
+        .expect("semantic_search");
+    assert_eq!(
+        scored_ids(&results),
+        vec![embedded.id],
+        "only entries with an embedding may be returned"
+    );
+}
+
+/// search_by_user / search_by_conversation return exactly the matching entries.
+pub async fn check_search_by_scope(store: Arc<dyn MemoryStore>) {
+    let a = entry("a", "u1", "c1", MemoryRole::User, 30, None);
+    let b = entry("b", "u1", "c2", MemoryRole::User, 20, None);
+    let c = entry("c", "u2", "c1", MemoryRole::User, 10, None);
+    for e in [&a, &b, &c] {
+        store.add(e.clone()).await.expect("add");
+    }
+
+    let by_user = store.search_by_user("u1").await.expect("search_by_user");
+    assert_eq!(ids(&by_user), ids(&[a.clone(), b.clone()]));
+    let by_conversation = store
+        .search_by_conversation("c1")
+        .await
+        .expect("search_by_conversation");
+    assert_eq!(ids(&by_conversation), ids(&[a, c]));
+    assert!(store
+        .search_by_user("nobody")
+        .await
+        .expect("search")
+        .is_empty());
+}
+
+/// Scores are within [-1, 1], sorted best first, rank by cosine similarity, and respect limit and scope.
+pub async fn check_semantic_scoring(store: Arc<dyn MemoryStore>) {
+    let vectors: [[f32; CONFORMANCE_DIM]; 4] = [
+        [1.0, 0.0, 0.0],
+        [0.9, 0.1, 0.0],
+        [0.5, 0.5, 0.0],
+        [0.0, 1.0, 0.0],
+    ];
+    let mut expected = Vec::new();
+    for (i, v) in vectors.iter().enumerate() {
+        let e = entry(
+            &format!("v{}", i),
+            "u1",
+            "c1",
+            MemoryRole::User,
+            10 + i as i64,
+            Some(*v),
+        );
+        expected.push(e.id);
+        store.add(e).await.expect("add");
+    }
+    let other = entry(
+        "other chat",
+        "u1",
+        "c2",
+        MemoryRole::User,
+        1,
+        Some([1.0, 0.0, 0.0]),
+    );
+    store.add(other.clone()).await.expect("add");
+
+    let results = store
+        .semantic_search(&[1.0, 0.0, 0.0], 3, None, Some("c1"))
+        .await
+        .expect("semantic_search");
+    assert_eq!(results.len(), 3, "limit must be respected");
+    assert_eq!(
+        scored_ids(&results),
+        expected[..3].to_vec(),
+        "results must rank by cosine similarity"
+    );
+    for (score, _) in &results {
+        assert!(
+            (-1.0 - EPS..=1.0 + EPS).contains(score),
+            "score {} outside [-1, 1]",
+            score
+        );
+    }
+    for pair in results.windows(2) {
+        assert!(
+            pair[0].0 + EPS >= pair[1].0,
+            "scores must be sorted best first"
+        );
+    }
+    assert!(
+        results[0].0 >= 1.0 - 0.01,
+        "identical vector must score ~1, got {}",
+        results[0].0
+    );
+    assert!(
+        results.iter().all(|(_, e)| e.id != other.id),
+        "conversation scope must be exact"
+    );
+
+    let all = store
+        .semantic_search(&[1.0, 0.0, 0.0], 10, None, None)
+        .await
+        .expect("semantic_search");
+    assert_eq!(all.len(), 5);
+    let by_user = store
+        .semantic_search(&[1.0, 0.0, 0.0], 10, Some("nobody"), None)
+        .await
+        .expect("semantic_search");
+    assert!(by_user.is_empty(), "user scope must be exact");
+}
+
+/// semantic_search_filtered honours role, time range, importance and excluded ids.
+pub async fn check_filtered_search(store: Arc<dyn MemoryStore>) {
+    let v = Some([1.0, 0.0, 0.0]);
+    let mut wanted = entry("wanted", "u1", "c1", MemoryRole::User, 60, v);
+    wanted.metadata.importance = Some(0.8);
+    let mut assistant = entry("assistant", "u1", "c1", MemoryRole::Assistant, 60, v);
+    assistant.metadata.importance = Some(0.9);
+    let mut old = entry("old", "u1", "c1", MemoryRole::User, 40 * 24 * 3600, v);
+    old.metadata.importance = Some(0.9);
+    let mut unimportant = entry("unimportant", "u1", "c1", MemoryRole::User, 60, v);
+    unimportant.metadata.importance = Some(0.1);
+    let unrated = entry("unrated", "u1", "c1", MemoryRole::User, 60, v);
+    let mut excluded = entry("excluded", "u1", "c1", MemoryRole::User, 60, v);
+    excluded.metadata.importance = Some(0.9);
+    for e in [&wanted, &assistant, &old, &unimportant, &unrated, &excluded] {
+        store.add(e.clone()).await.expect("add");
+    }
+
+    let filter = SearchFilter::scoped(Some("u1"), Some("c1"))
+        .with_role(MemoryRole::User)
+        .since(Utc::now() - Duration::days(30))
+        .with_min_importance(0.5)
+        .excluding([excluded.id]);
+    let results = store
+        .semantic_search_filtered(&[1.0, 0.0, 0.0], 10, &filter)
+        .await
+        .expect("semantic_search_filtered");
+    assert_eq!(
+        scored_ids(&results),
+        vec![wanted.id],
+        "only the entry matching every predicate"
+    );
+
+    let limited = store
+        .semantic_search_filtered(
+            &[1.0, 0.0, 0.0],
+            1,
+            &SearchFilter::scoped(None, Some("c1")).with_role(MemoryRole::User),
+        )
+        .await
+        .expect("semantic_search_filtered");
+    assert_eq!(limited.len(), 1, "limit must count only matching entries");
+    assert_eq!(limited[0].1.metadata.role, MemoryRole::User);
+}
+
+/// list walks every matching entry exactly once in order; count matches.
+pub async fn check_list_and_count(store: Arc<dyn MemoryStore>) {
+    let mut in_conversation = Vec::new();
+    for i in 0..5 {
+        let role = if i % 2 == 0 {
+            MemoryRole::User
+        } else {
+            MemoryRole::Assistant
+        };
+        let e = entry(&format!("m{}", i), "u1", "c1", role, 100 - i * 10, None);
+        in_conversation.push(e.id);
+        store.add(e).await.expect("add");
+    }
+    store
+        .add(entry("elsewhere", "u1", "c2", MemoryRole::User, 1, None))
+        .await
+        .expect("add");
+
+    let filter = MemoryFilter::for_conversation("c1");
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = store
+            .list(&filter, MemoryOrder::NewestFirst, 2, cursor.as_ref())
+            .await
+            .expect("list");
+        assert!(page.entries.len() <= 2, "page size must be respected");
+        seen.extend(page.entries.iter().map(|e| e.id));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    let newest_first: Vec<Uuid> = in_conversation.iter().rev().copied().collect();
+    assert_eq!(
+        seen, newest_first,
+        "pages must cover every entry once, newest first"
+    );
+
+    let oldest = store
+        .list(&filter, MemoryOrder::OldestFirst, 1, None)
+        .await
+        .expect("list");
+    assert_eq!(oldest.entries[0].id, in_conversation[0]);
+
+    assert_eq!(store.count(&filter).await.expect("count"), 5);
+    assert_eq!(
+        store
+            .count(&filter.clone().with_role(MemoryRole::Assistant))
+            .await
+            .expect("count"),
+        2
+    );
+
+    // Empty entries are left out by the query, not after the limit.
+    store
+        .add(entry("", "u1", "c1", MemoryRole::Assistant, 0, None))
+        .await
+        .expect("add empty");
+    let non_empty = filter.clone().non_empty();
+    let newest = store
+        .list(&non_empty, MemoryOrder::NewestFirst, 2, None)
+        .await
+        .expect("list non_empty");
+    assert_eq!(
+        newest.entries.iter().map(|e| e.id).collect::<Vec<_>>(),
+        newest_first[..2].to_vec(),
+        "non_empty must skip empty entries before the limit"
+    );
+    assert_eq!(store.count(&non_empty).await.expect("count"), 5);
+}
+
+/// add_batch stores every entry; an empty batch is a no-op.
+pub async fn check_add_batch(store: Arc<dyn MemoryStore>) {
+    let batch: Vec<MemoryEntry> = (0..3)
+        .map(|i| {
+            entry(
+                &format!("b{}", i),
+                "u1",
+                "c1",
+                MemoryRole::User,
+                i,
+                Some([1.0, i as f32, 0.0]),
+            )
+        })
+        .collect();
+    store.add_batch(batch.clone()).await.expect("add_batch");
+    store.add_batch(Vec::new()).await.expect("empty add_batch");
+    let stored = store.search_by_conversation("c1").await.expect("search");
+    assert_eq!(ids(&stored), ids(&batch));
+}
+
+/// delete_by_conversation / delete_by_user remove exactly their scope and report the count.
+pub async fn check_delete_by_scope(store: Arc<dyn MemoryStore>) {
+    let keep = entry("keep", "u2", "c2", MemoryRole::User, 10, None);
+    for e in [
+        entry("c1 a", "u1", "c1", MemoryRole::User, 30, None),
+        entry("c1 b", "u2", "c1", MemoryRole::User, 20, None),
+        entry("u1 elsewhere", "u1", "c3", MemoryRole::User, 15, None),
+        keep.clone(),
+    ] {
+        store.add(e).await.expect("add");
+    }
+
+    assert_eq!(
+        store
+            .delete_by_conversation("c1")
+            .await
+            .expect("delete_by_conversation"),
+        2
+    );
+    assert!(store
+        .search_by_conversation("c1")
+        .await
+        .expect("search")
+        .is_empty());
+    assert_eq!(store.delete_by_user("u1").await.expect("delete_by_user"), 1);
+    assert!(store.search_by_user("u1").await.expect("search").is_empty());
+    assert!(
+        store.get(keep.id).await.expect("get").is_some(),
+        "other scopes must survive"
+    );
+    assert_eq!(
+        store
+            .delete_by_conversation("missing")
+            .await
+            .expect("delete"),
+        0
+    );
+}
+
+/// A chat-scoped view writes into its chat and never reads, counts or deletes another chat; by-id
+/// access to another chat's or user's entry and deleting another chat fail with [`ScopeViolation`].
+pub async fn check_chat_scoped_isolation(store: Arc<dyn MemoryStore>) {
+    let chat_a = get_store(store.clone(), "chat-a");
+    let chat_b = get_store(store.clone(), "chat-b");
+    let is_violation = |result: Result<(), anyhow::Error>| {
+        result.is_err_and(|e| e.downcast_ref::<ScopeViolation>().is_some())
+    };
+
+    assert!(
+        is_violation(
+            chat_a
+                .add(entry(
+                    "misrouted",
+                    "u1",
+                    "chat-b",
+                    MemoryRole::User,
+                    10,
+                    None
+                ))
+                .await
+        ),
+        "writes naming another chat must be refused"
+    );
+    let mut in_a = entry(
+        "secret of a",
+        "u1",
+        "chat-a",
+        MemoryRole::User,
+        10,
+        Some([1.0, 0.0, 0.0]),
+    );
+    in_a.metadata.conversation_id = None;
+    chat_a.add(in_a.clone()).await.expect("add via chat-a");
+    let stored = store
+        .get(in_a.id)
+        .await
+        .expect("get")
+        .expect("entry must be stored");
+    assert_eq!(
+        stored.metadata.conversation_id.as_deref(),
+        Some("chat-a"),
+        "writes without a chat must be assigned to the scoped chat"
+    );
+    assert!(chat_a.get(in_a.id).await.expect("get").is_some());
+    chat_b
+        .add(entry(
+            "b's own",
+            "u1",
+            "chat-b",
+            MemoryRole::User,
+            5,
+            Some([0.0, 1.0, 0.0]),
+        ))
+        .await
+        .expect("add via chat-b");
+
+    assert!(is_violation(chat_b.get(in_a.id).await.map(|_| ())));
+    assert!(is_violation(chat_b.delete(in_a.id).await));
+    let mut hijacked = stored.clone();
+    hijacked.metadata.conversation_id = Some("chat-b".to_string());
+    assert!(
+        is_violation(chat_b.update(hijacked).await),
+        "updates must not move another chat's entry"
+    );
+    assert!(is_violation(
+        get_user_store(store.clone(), "u2")
+            .get(in_a.id)
+            .await
+            .map(|_| ())
+    ));
+    assert!(
+        get_scoped_store(store.clone(), MemoryScope::chat_and_user("chat-a", "u1"))
+            .get(in_a.id)
+            .await
+            .expect("get in scope")
+            .is_some()
+    );
+
+    let query = [1.0, 0.0, 0.0];
+    let leaked = |results: &[(f32, MemoryEntry)]| results.iter().any(|(_, e)| e.id == in_a.id);
+    assert!(!leaked(
+        &chat_b
+            .semantic_search(&query, 10, None, Some("chat-a"))
+            .await
+            .expect("search")
+    ));
+    assert!(!leaked(
+        &chat_b
+            .semantic_search_filtered(&query, 10, &SearchFilter::scoped(None, Some("chat-a")))
+            .await
+            .expect("search")
+    ));
+    assert!(
+        is_violation(chat_b.search_by_conversation("chat-a").await.map(|_| ())),
+        "search_by_conversation naming another chat must fail"
+    );
+    let page = chat_b
+        .list(
+            &MemoryFilter::for_conversation("chat-a"),
+            MemoryOrder::NewestFirst,
+            10,
+            None,
+        )
+        .await
+        .expect("list");
+    assert!(page.entries.iter().all(|e| e.id != in_a.id));
+    assert_eq!(
+        chat_b
+            .count(&MemoryFilter::for_user("u1"))
+            .await
+            .expect("count"),
+        1
+    );
+
+    assert!(
+        is_violation(chat_b.delete_by_conversation("chat-a").await.map(|_| ())),
+        "deleting another chat through a chat scope must be refused"
+    );
+    assert_eq!(
+        chat_b
+            .count(&MemoryFilter::default())
+            .await
+            .expect("count"),
+        1,
+        "a refused delete must not touch the scoped chat"
+    );
+    assert_eq!(
+        chat_b.delete_by_user("u1").await.expect("delete_by_user"),
+        1
+    );
+    assert!(
+        store.get(in_a.id).await.expect("get").is_some(),
+        "another chat's deletes must not touch chat-a"
+    );
+}
+
+/// Vectors of another dimension or model are refused; reset_embeddings clears vectors for a migration.
+pub async fn check_embedding_spec(store: Arc<dyn MemoryStore>) {
+    let embedded = entry(
+        "embedded",
+        "u1",
+        "c1",
+        MemoryRole::User,
+        10,
+        Some([1.0, 0.0, 0.0]),
+    );
+    store.add(embedded.clone()).await.expect("add");
+
+    let spec = EmbeddingSpec::new("conformance-model", CONFORMANCE_DIM);
+    store
+        .bind_embedding_spec(&spec)
+        .await
+        .expect("bind matching spec");
+    store
+        .bind_embedding_spec(&spec)
+        .await
+        .expect("binding again is a no-op");
+    assert_eq!(
+        store.embedding_spec().await.expect("embedding_spec"),
+        Some(spec.clone())
+    );
+
+    let is_mismatch = |e: &anyhow::Error| e.downcast_ref::<EmbeddingMismatch>().is_some();
+    let mut wrong_dim = entry("wrong", "u1", "c1", MemoryRole::User, 5, None);
+    wrong_dim.embedding = Some(vec![1.0, 0.0]);
+    let err = store
+        .add(wrong_dim.clone())
+        .await
+        .expect_err("write of another dimension");
+    assert!(
+        is_mismatch(&err),
+        "expected EmbeddingMismatch, got {:#}",
+        err
+    );
+    let err = store
+        .semantic_search(&[1.0, 0.0], 5, None, None)
+        .await
+        .expect_err("query of another dimension");
+    assert!(
+        is_mismatch(&err),
+        "expected EmbeddingMismatch, got {:#}",
+        err
+    );
+    let err = store
+        .bind_embedding_spec(&EmbeddingSpec::new("other-model", CONFORMANCE_DIM))
+        .await
+        .expect_err("another model");
+    assert!(
+        is_mismatch(&err),
+        "expected EmbeddingMismatch, got {:#}",
+        err
+    );
+
+    let next = EmbeddingSpec::new("next-model", CONFORMANCE_DIM);
+    assert_eq!(
+        store
+            .reset_embeddings(&next)
+            .await
+            .expect("reset_embeddings"),
+        1
+    );
+    assert_eq!(
+        store.embedding_spec().await.expect("embedding_spec"),
+        Some(next)
+    );
+    let got = store
+        .get(embedded.id)
+        .await
+        .expect("get")
+        .expect("entry survives reset");
+    assert_eq!(got.content, "embedded");
+    assert_eq!(got.embedding, None, "reset must drop vectors");
+    let mut reembedded = got;
+    reembedded.embedding = Some(vec![0.0, 1.0, 0.0]);
+    store.update(reembedded).await.expect("re-embedded write");
+    let results = store
+        .semantic_search(&[0.0, 1.0, 0.0], 5, None, None)
+        .await
+        .expect("semantic_search");
+    assert_eq!(scored_ids(&results), vec![embedded.id]);
+}
+
+/// keyword_search: exact terms rank first, scope and excluded ids apply, entries without a vector
+/// are included, and updates and deletes are reflected.
+pub async fn check_keyword_search(store: Arc<dyn MemoryStore>) {
+    let order = entry(
+        "my order ORD-4471 has not arrived",
+        "u1",
+        "c1",
+        MemoryRole::User,
+        30,
+        None,
+    );
+    let chatter = entry(
+        "the order of the day is tea",
+        "u1",
+        "c1",
+        MemoryRole::User,
+        20,
+        Some([1.0, 0.0, 0.0]),
+    );
+    let unrelated = entry("good morning", "u1", "c1", MemoryRole::User, 10, None);
+    let other_chat = entry(
+        "ORD-4471 is someone else's order",
+        "u2",
+        "c2",
+        MemoryRole::User,
+        10,
+        None,
+    );
+    for e in [&order, &chatter, &unrelated, &other_chat] {
+        store.add(e.clone()).await.expect("add");
+    }
+
+    let c1 = SearchFilter::scoped(None, Some("c1"));
+    let results = store
+        .keyword_search("where is ORD-4471?", 5, &c1)
+        .await
+        .expect("keyword_search");
+    assert_eq!(
+        results.first().map(|(_, e)| e.id),
+        Some(order.id),
+        "exact identifier must rank first"
+    );
+    assert!(
+        results
+            .iter()
+            .all(|(_, e)| e.id != unrelated.id && e.id != other_chat.id),
+        "entries without shared terms or outside the scope must not match"
+    );
+    assert!(
+        results.windows(2).all(|w| w[0].0 >= w[1].0),
+        "results must be sorted best first"
+    );
+
+    let excluded = store
+        .keyword_search("ORD-4471", 5, &c1.clone().excluding([order.id]))
+        .await
+        .expect("keyword_search excluding");
+    assert!(excluded.iter().all(|(_, e)| e.id != order.id));
+
+    // CJK is written without spaces; a word inside a sentence must still match.
+    let cjk = entry("我最喜欢喝绿茶", "u1", "c1", MemoryRole::User, 5, None);
+    store.add(cjk.clone()).await.expect("add cjk");
+    let results = store
+        .keyword_search("绿茶", 5, &c1)
+        .await
+        .expect("keyword_search cjk");
+    assert_eq!(
+        results.first().map(|(_, e)| e.id),
+        Some(cjk.id),
+        "a CJK word must match inside unsegmented text"
+    );
+
+    let mut renamed = order.clone();
+    renamed.content = "my parcel has not arrived".to_string();
+    store.update(renamed).await.expect("update");
+    store.delete(chatter.id).await.expect("delete");
+    let results = store
+        .keyword_search("ORD-4471 order", 5, &c1)
+        .await
+        .expect("keyword_search after update");
+    assert!(
+        results.is_empty(),
+        "updated and deleted content must no longer match: {:?}",
+        scored_ids(&results)
+    );
+}
+
+/// Generates one `#[tokio::test]` per conformance check. `$make` is an expression evaluating to a
+/// future of `(guard, store)`: a fresh, empty store and a value kept alive until the check returns
+/// (e.g. the `TempDir` holding its files, or `()`). It is evaluated once per test inside a child
+/// module, so items it names must be visible from the invoking module.
+#[macro_export]
+macro_rules! memory_store_conformance_tests {
+    ($make:expr) => {
+        mod memory_store_conformance {
+            #[allow(unused_imports)]
+            use super::*;
+            use std::sync::Arc;
+            use $crate::memory_core::conformance;
+            use $crate::memory_core::MemoryStore;
+
+            async fn fresh() -> (impl Sized, Arc<dyn MemoryStore>) {
+                let (guard, store) = $make.await;
+                (guard, Arc::new(store) as Arc<dyn MemoryStore>)
+            }
+
+            #[tokio::test]
+            async fn crud() {
+                let (_guard, store) = fresh().await;
+                conformance::check_crud(store).await;
+            }
+
+            #[tokio::test]
+            async fn null_embeddings() {
+                let (_guard, store) = fresh().await;
+                conformance::check_null_embeddings(store).await;
+            }
+
+            #[tokio::test]
+            async fn search_by_scope() {
+                let (_guard, store) = fresh().await;
+                conformance::check_search_by_scope(store).await;
+            }
+
+            #[tokio::test]
+            async fn semantic_scoring() {
+                let (_guard, store) = fresh().await;
+                conformance::check_semantic_scoring(store).await;
+            }
+
+            #[tokio::test]
+            async fn filtered_search() {
+                let (_guard, store) = fresh().await;
+                conformance::check_filtered_search(store).await;
+            }
+
+            #[tokio::test]
+            async fn keyword_search() {
+                let (_guard, store) = fresh().await;
+                conformance::check_keyword_search(store).await;
+            }
+
+            #[tokio::test]
+            async fn list_and_count() {
+                let (_guard, store) = fresh().await;
+                conformance::check_list_and_count(store).await;
+            }
+
+            #[tokio::test]
+            async fn add_batch() {
+                let (_guard, store) = fresh().await;
+                conformance::check_add_batch(store).await;
+            }
+
+            #[tokio::test]
+            async fn delete_by_scope() {
+                let (_guard, store) = fresh().await;
+                conformance::check_delete_by_scope(store).await;
+            }
+
+            #[tokio::test]
+            async fn embedding_spec() {
+                let (_guard, store) = fresh().await;
+                conformance::check_embedding_spec(store).await;
+            }
+
+            #[tokio::test]
+            async fn chat_scoped_isolation() {
+                let (_guard, store) = fresh().await;
+                conformance::check_chat_scoped_isolation(store).await;
+            }
+        }
+    };
+}
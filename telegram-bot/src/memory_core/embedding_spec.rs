@@ -0,0 +1,184 @@
+//! Embedding model and dimension a store's vectors belong to, and the guard stores use to reject
+//! vectors from another model.
+
+use std::fmt;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::types::MemoryEntry;
+
+/// Embedding model id and vector dimension. An empty `model` means the model is unknown (the
+/// dimension was inferred from stored vectors).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingSpec {
+    pub model: String,
+    pub dimension: usize,
+}
+
+impl EmbeddingSpec {
+    pub fn new(model: impl Into<String>, dimension: usize) -> Self {
+        Self {
+            model: model.into(),
+            dimension,
+        }
+    }
+
+    /// Spec inferred from stored vectors, model unknown.
+    pub fn inferred(dimension: usize) -> Self {
+        Self::new(String::new(), dimension)
+    }
+
+    /// True when vectors recorded under `self` can be used with `other`: same dimension, and the
+    /// same model unless either model is unknown.
+    pub fn is_compatible_with(&self, other: &EmbeddingSpec) -> bool {
+        self.dimension == other.dimension
+            && (self.model.is_empty() || other.model.is_empty() || self.model == other.model)
+    }
+}
+
+impl fmt::Display for EmbeddingSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.model.is_empty() {
+            write!(f, "unknown model ({} dims)", self.dimension)
+        } else {
+            write!(f, "{} ({} dims)", self.model, self.dimension)
+        }
+    }
+}
+
+/// A vector or embedding configuration that does not match what a store holds.
+#[derive(Debug, Error, PartialEq)]
+pub enum EmbeddingMismatch {
+    #[error(
+        "{operation} has a {found}-dimensional vector but the store holds {expected}; \
+         re-embed the store (`reembed` command) after switching embedding models"
+    )]
+    Dimension {
+        operation: &'static str,
+        expected: EmbeddingSpec,
+        found: usize,
+    },
+    #[error(
+        "store vectors were written with {stored} but {configured} is configured; \
+         run the `reembed` command to migrate"
+    )]
+    Model {
+        stored: EmbeddingSpec,
+        configured: EmbeddingSpec,
+    },
+}
+
+/// Tracks a store's [`EmbeddingSpec`] and checks vectors against it. Until a spec is bound or
+/// inferred, the first vector written fixes the dimension.
+#[derive(Debug, Default)]
+pub struct EmbeddingGuard {
+    spec: RwLock<Option<EmbeddingSpec>>,
+}
+
+impl EmbeddingGuard {
+    pub fn new(spec: Option<EmbeddingSpec>) -> Self {
+        Self {
+            spec: RwLock::new(spec),
+        }
+    }
+
+    /// Current spec, if any.
+    pub fn spec(&self) -> Option<EmbeddingSpec> {
+        self.spec.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replaces the spec unconditionally (re-embed migration).
+    pub fn set(&self, spec: Option<EmbeddingSpec>) {
+        *self.spec.write().unwrap_or_else(|e| e.into_inner()) = spec;
+    }
+
+    /// Checks `configured` against the current spec and adopts it. Returns true when the spec changed
+    /// (first binding, or a known model replacing an inferred one) and should be persisted.
+    pub fn bind(&self, configured: &EmbeddingSpec) -> Result<bool, EmbeddingMismatch> {
+        let mut spec = self.spec.write().unwrap_or_else(|e| e.into_inner());
+        match spec.as_ref() {
+            Some(stored) if !stored.is_compatible_with(configured) => {
+                Err(EmbeddingMismatch::Model {
+                    stored: stored.clone(),
+                    configured: configured.clone(),
+                })
+            }
+            Some(stored) if stored == configured || configured.model.is_empty() => Ok(false),
+            _ => {
+                *spec = Some(configured.clone());
+                Ok(true)
+            }
+        }
+    }
+
+    /// Checks a query vector. Without a spec there is nothing to compare against.
+    pub fn check_query(&self, vector: &[f32]) -> Result<(), EmbeddingMismatch> {
+        match self.spec().as_ref() {
+            Some(spec) if spec.dimension != vector.len() => Err(EmbeddingMismatch::Dimension {
+                operation: "query",
+                expected: spec.clone(),
+                found: vector.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the embeddings of entries about to be written; the first one fixes the dimension
+    /// when no spec exists yet.
+    pub fn check_entries(&self, entries: &[MemoryEntry]) -> Result<(), EmbeddingMismatch> {
+        let mut vectors = entries.iter().filter_map(|e| e.embedding.as_deref());
+        let Some(first) = vectors.next() else {
+            return Ok(());
+        };
+        let expected = {
+            let mut spec = self.spec.write().unwrap_or_else(|e| e.into_inner());
+            spec.get_or_insert_with(|| EmbeddingSpec::inferred(first.len()))
+                .clone()
+        };
+        match std::iter::once(first)
+            .chain(vectors)
+            .find(|v| v.len() != expected.dimension)
+        {
+            Some(v) => Err(EmbeddingMismatch::Dimension {
+                operation: "write",
+                expected,
+                found: v.len(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_core::MemoryMetadata;
+
+    fn entry_with(embedding: Vec<f32>) -> MemoryEntry {
+        MemoryEntry::new("x".to_string(), MemoryMetadata::default()).with_embedding(embedding)
+    }
+
+    #[test]
+    fn test_guard_infers_binds_and_rejects() {
+        let guard = EmbeddingGuard::default();
+        guard.check_query(&[1.0]).unwrap();
+        guard.check_entries(&[entry_with(vec![1.0, 0.0])]).unwrap();
+        assert_eq!(guard.spec(), Some(EmbeddingSpec::inferred(2)));
+        assert!(matches!(
+            guard.check_entries(&[entry_with(vec![1.0, 0.0, 0.0])]),
+            Err(EmbeddingMismatch::Dimension { found: 3, .. })
+        ));
+        assert!(guard.check_query(&[1.0]).is_err());
+
+        // A known model upgrades the inferred spec; another model or dimension is refused.
+        assert!(guard.bind(&EmbeddingSpec::new("small", 2)).unwrap());
+        assert!(!guard.bind(&EmbeddingSpec::new("small", 2)).unwrap());
+        assert!(matches!(
+            guard.bind(&EmbeddingSpec::new("large", 2)),
+            Err(EmbeddingMismatch::Model { .. })
+        ));
+        assert!(guard.bind(&EmbeddingSpec::new("small", 3)).is_err());
+    }
+}
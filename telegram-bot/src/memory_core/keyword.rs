@@ -0,0 +1,116 @@
+//! Lexical (keyword) scoring: tokenizer and Okapi BM25 over memory entry content.
+//!
+//! Used by the default [`MemoryStore::keyword_search`](super::MemoryStore::keyword_search);
+//! stores with a full-text index (SQLite FTS5) rank in the database instead.
+
+use std::collections::{HashMap, HashSet};
+
+use super::types::MemoryEntry;
+
+/// BM25 term-frequency saturation.
+const BM25_K1: f32 = 1.2;
+/// BM25 document-length normalization.
+const BM25_B: f32 = 0.75;
+
+/// Lowercased tokens of `text`: runs of letters, digits and `_` (so code identifiers and numbers
+/// stay whole); each CJK ideograph or kana is its own token, as that text has no spaces.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() || c == '_' {
+            current.extend(c.to_lowercase());
+        } else if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xF900..=0xFAFF)  // CJK Compatibility Ideographs
+}
+
+/// Scores `entries` against `query` with BM25 (statistics taken from `entries` themselves) and
+/// returns the best `limit` with a positive score, highest first.
+pub fn bm25_rank(query: &str, entries: Vec<MemoryEntry>, limit: usize) -> Vec<(f32, MemoryEntry)> {
+    let terms: HashSet<String> = tokenize(query).into_iter().collect();
+    if terms.is_empty() || entries.is_empty() {
+        return Vec::new();
+    }
+    let docs: Vec<Vec<String>> = entries.iter().map(|e| tokenize(&e.content)).collect();
+    let n = docs.len() as f32;
+    let avg_len = (docs.iter().map(Vec::len).sum::<usize>() as f32 / n).max(1.0);
+    let mut doc_freq: HashMap<&str, usize> = HashMap::new();
+    for doc in &docs {
+        let unique: HashSet<&str> = doc.iter().map(String::as_str).collect();
+        for term in unique.into_iter().filter(|t| terms.contains(*t)) {
+            *doc_freq.entry(term).or_default() += 1;
+        }
+    }
+
+    let mut scored: Vec<(f32, MemoryEntry)> = docs
+        .iter()
+        .zip(entries)
+        .filter_map(|(doc, entry)| {
+            let mut tf: HashMap<&str, usize> = HashMap::new();
+            for token in doc.iter().filter(|t| terms.contains(*t)) {
+                *tf.entry(token.as_str()).or_default() += 1;
+            }
+            let len_norm = 1.0 - BM25_B + BM25_B * doc.len() as f32 / avg_len;
+            let score: f32 = tf
+                .into_iter()
+                .map(|(term, f)| {
+                    let df = doc_freq[term] as f32;
+                    let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                    let f = f as f32;
+                    idf * f * (BM25_K1 + 1.0) / (f + BM25_K1 * len_norm)
+                })
+                .sum();
+            (score > 0.0).then_some((score, entry))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(limit);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_core::MemoryMetadata;
+
+    #[test]
+    fn test_tokenize_keeps_identifiers_and_splits_cjk() {
+        assert_eq!(
+            tokenize("Call parse_config() on v2.1, 你好"),
+            vec!["call", "parse_config", "on", "v2", "1", "你", "好"]
+        );
+    }
+
+    #[test]
+    fn test_bm25_rank_prefers_rare_exact_terms() {
+        let entries = [
+            "the order is on its way",
+            "order ORD-4471 shipped yesterday",
+            "nothing relevant here",
+        ]
+        .map(|content| MemoryEntry::new(content.to_string(), MemoryMetadata::default()))
+        .to_vec();
+        let ranked = bm25_rank("where is ORD-4471", entries, 10);
+        assert_eq!(ranked[0].1.content, "order ORD-4471 shipped yesterday");
+        assert!(ranked.iter().all(|(_, e)| e.content != "nothing relevant here"));
+    }
+}
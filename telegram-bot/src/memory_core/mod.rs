@@ -1,11 +1,21 @@
 //! Core types and traits for memory storage and context strategies.
 
-pub mod chat_scoped_store;
+#[cfg(any(test, feature = "conformance"))]
+pub mod conformance;
+pub mod embedding_spec;
+pub mod keyword;
+pub mod query;
+pub mod scoped_store;
 pub mod store;
 pub mod strategy_result;
 pub mod types;
 
-pub use chat_scoped_store::{get_store, ChatScopedStore};
+pub use embedding_spec::{EmbeddingGuard, EmbeddingMismatch, EmbeddingSpec};
+pub use query::{MemoryCursor, MemoryFilter, MemoryOrder, MemoryPage, SearchFilter};
+pub use scoped_store::{
+    get_scoped_store, get_store, get_user_store, AnyScope, ChatScope, ChatScopedStore,
+    MemoryScope, ScopeKind, ScopeViolation, ScopedStore, UserScope, UserScopedStore,
+};
 pub use store::*;
 pub use strategy_result::*;
 pub use types::*;
@@ -0,0 +1,336 @@
+//! Filtered listing types for [`MemoryStore::list`](super::store::MemoryStore::list) and
+//! [`MemoryStore::count`](super::store::MemoryStore::count): filter, sort order, keyset cursor and page;
+//! plus [`SearchFilter`] for [`MemoryStore::semantic_search_filtered`](super::store::MemoryStore::semantic_search_filtered).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::types::{MemoryEntry, MemoryRole};
+
+/// Filters for listing and counting entries; unset fields match everything. Expired entries
+/// (see [`MemoryMetadata::expires_at`](super::types::MemoryMetadata::expires_at)) never match.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryFilter {
+    pub user_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub role: Option<MemoryRole>,
+    /// Only entries with timestamp on or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only entries with timestamp before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Only entries with importance >= this value (entries without importance are excluded).
+    pub min_importance: Option<f32>,
+    /// Only entries with non-empty content.
+    #[serde(default)]
+    pub non_empty: bool,
+}
+
+impl MemoryFilter {
+    /// Filter matching all entries of one conversation.
+    pub fn for_conversation(conversation_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: Some(conversation_id.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Filter matching all entries of one user.
+    pub fn for_user(user_id: impl Into<String>) -> Self {
+        Self {
+            user_id: Some(user_id.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_role(mut self, role: MemoryRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn with_min_importance(mut self, min_importance: f32) -> Self {
+        self.min_importance = Some(min_importance);
+        self
+    }
+
+    /// Leaves out entries with empty content, so a page of `limit` counts only non-empty ones.
+    pub fn non_empty(mut self) -> Self {
+        self.non_empty = true;
+        self
+    }
+
+    /// Returns true if the entry satisfies every set field and has not expired.
+    pub fn matches(&self, entry: &MemoryEntry) -> bool {
+        let m = &entry.metadata;
+        self.user_id
+            .as_deref()
+            .is_none_or(|u| m.user_id.as_deref() == Some(u))
+            && self
+                .conversation_id
+                .as_deref()
+                .is_none_or(|c| m.conversation_id.as_deref() == Some(c))
+            && self.role.is_none_or(|r| m.role == r)
+            && self.since.is_none_or(|s| m.timestamp >= s)
+            && self.until.is_none_or(|u| m.timestamp < u)
+            && self
+                .min_importance
+                .is_none_or(|min| m.importance.is_some_and(|i| i >= min))
+            && (!self.non_empty || !entry.content.is_empty())
+            && !m.is_expired(Utc::now())
+    }
+}
+
+/// Filters for semantic search: the metadata predicates of [`MemoryFilter`] plus ids to leave out.
+/// Stores push them down into their query so `limit` counts only matching entries.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchFilter {
+    /// user, conversation, role, time range and importance predicates.
+    pub metadata: MemoryFilter,
+    /// Entries never returned (e.g. messages already in the recent-messages section).
+    #[serde(default)]
+    pub exclude_ids: Vec<Uuid>,
+}
+
+impl SearchFilter {
+    /// Filter scoped to the optional user and conversation, as in the plain `semantic_search` arguments.
+    pub fn scoped(user_id: Option<&str>, conversation_id: Option<&str>) -> Self {
+        Self {
+            metadata: MemoryFilter {
+                user_id: user_id.map(str::to_string),
+                conversation_id: conversation_id.map(str::to_string),
+                ..Default::default()
+            },
+            exclude_ids: Vec::new(),
+        }
+    }
+
+    pub fn with_role(mut self, role: MemoryRole) -> Self {
+        self.metadata.role = Some(role);
+        self
+    }
+
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.metadata.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.metadata.until = Some(until);
+        self
+    }
+
+    pub fn with_min_importance(mut self, min_importance: f32) -> Self {
+        self.metadata.min_importance = Some(min_importance);
+        self
+    }
+
+    pub fn excluding(mut self, ids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.exclude_ids.extend(ids);
+        self
+    }
+
+    /// True when the filter has predicates beyond user and conversation.
+    pub fn is_narrowing(&self) -> bool {
+        let m = &self.metadata;
+        m.role.is_some()
+            || m.since.is_some()
+            || m.until.is_some()
+            || m.min_importance.is_some()
+            || m.non_empty
+            || !self.exclude_ids.is_empty()
+    }
+
+    /// Returns true if the entry satisfies the metadata filter and is not excluded.
+    pub fn matches(&self, entry: &MemoryEntry) -> bool {
+        self.metadata.matches(entry) && !self.exclude_ids.contains(&entry.id)
+    }
+}
+
+impl From<MemoryFilter> for SearchFilter {
+    fn from(metadata: MemoryFilter) -> Self {
+        Self {
+            metadata,
+            exclude_ids: Vec::new(),
+        }
+    }
+}
+
+/// Sort order for listing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemoryOrder {
+    /// timestamp DESC, id DESC.
+    #[default]
+    NewestFirst,
+    /// timestamp ASC, id ASC.
+    OldestFirst,
+}
+
+/// Position in the (timestamp, id) ordering; the next page starts strictly after it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryCursor {
+    /// timestamp of the last entry of the previous page.
+    pub timestamp: DateTime<Utc>,
+    /// id of the last entry of the previous page (tie-breaker for equal timestamps).
+    pub id: Uuid,
+}
+
+impl MemoryCursor {
+    /// Cursor pointing at the given entry.
+    pub fn from_entry(entry: &MemoryEntry) -> Self {
+        Self {
+            timestamp: entry.metadata.timestamp,
+            id: entry.id,
+        }
+    }
+
+    /// Returns true if `entry` comes strictly after this cursor in `order`.
+    pub fn precedes(&self, entry: &MemoryEntry, order: MemoryOrder) -> bool {
+        let key = (entry.metadata.timestamp, entry.id);
+        match order {
+            MemoryOrder::NewestFirst => key < (self.timestamp, self.id),
+            MemoryOrder::OldestFirst => key > (self.timestamp, self.id),
+        }
+    }
+}
+
+/// One page of entries plus the cursor for the next page (`None` when this is the last page).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPage {
+    /// Entries in this page, in the requested order.
+    pub entries: Vec<MemoryEntry>,
+    /// Cursor to pass to the next call; `None` when there are no more entries.
+    pub next_cursor: Option<MemoryCursor>,
+}
+
+impl MemoryPage {
+    /// Builds a page from unsorted candidates: applies `filter` and `cursor`, sorts by `order` and keeps `limit`.
+    /// Used by stores that filter in process (in-memory, and the default [`MemoryStore::list`](super::store::MemoryStore::list)).
+    pub fn paginate(
+        mut entries: Vec<MemoryEntry>,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Self {
+        entries.retain(|e| filter.matches(e) && cursor.is_none_or(|c| c.precedes(e, order)));
+        entries.sort_by_key(|e| (e.metadata.timestamp, e.id));
+        if order == MemoryOrder::NewestFirst {
+            entries.reverse();
+        }
+        Self::from_sorted(entries, limit)
+    }
+
+    /// Builds a page from entries already filtered and sorted, fetched with `limit + 1` so the presence
+    /// of a next page can be detected.
+    pub fn from_sorted(mut entries: Vec<MemoryEntry>, limit: usize) -> Self {
+        let has_more = entries.len() > limit;
+        entries.truncate(limit);
+        let next_cursor = if has_more {
+            entries.last().map(MemoryCursor::from_entry)
+        } else {
+            None
+        };
+        Self {
+            entries,
+            next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_core::MemoryMetadata;
+    use chrono::Duration;
+
+    #[test]
+    fn test_filter_matches() {
+        let metadata = MemoryMetadata::default()
+            .with_user("u1")
+            .with_conversation("c1")
+            .with_timestamp(Utc::now() - Duration::seconds(60))
+            .with_importance(0.5);
+        let e = MemoryEntry::new("hi".to_string(), metadata);
+        assert!(MemoryFilter::for_conversation("c1").matches(&e));
+        assert!(!MemoryFilter::for_conversation("c2").matches(&e));
+        assert!(!MemoryFilter::for_user("u1").with_role(MemoryRole::Assistant).matches(&e));
+        assert!(MemoryFilter::default().with_min_importance(0.5).matches(&e));
+        assert!(!MemoryFilter::default().with_min_importance(0.6).matches(&e));
+        assert!(MemoryFilter::default().until(Utc::now()).matches(&e));
+        assert!(!MemoryFilter::default().since(Utc::now()).matches(&e));
+    }
+
+    #[test]
+    fn test_filter_excludes_expired() {
+        let metadata = MemoryMetadata::default()
+            .with_conversation("c1")
+            .with_expires_at(Utc::now() + Duration::minutes(5));
+        let mut e = MemoryEntry::new("hi".to_string(), metadata);
+        assert!(MemoryFilter::for_conversation("c1").matches(&e));
+        e.metadata.expires_at = Some(Utc::now() - Duration::seconds(1));
+        assert!(!MemoryFilter::for_conversation("c1").matches(&e));
+        assert!(!SearchFilter::scoped(None, Some("c1")).matches(&e));
+    }
+
+    #[test]
+    fn test_search_filter_matches() {
+        let metadata = MemoryMetadata::default()
+            .with_user("u1")
+            .with_conversation("c1")
+            .with_importance(0.5);
+        let e = MemoryEntry::new("hi".to_string(), metadata);
+        let scoped = SearchFilter::scoped(Some("u1"), Some("c1"));
+        assert!(!scoped.is_narrowing());
+        assert!(scoped.matches(&e));
+        assert!(!SearchFilter::scoped(None, Some("c2")).matches(&e));
+        assert!(scoped.clone().with_min_importance(0.5).matches(&e));
+        assert!(!scoped.clone().with_role(MemoryRole::Assistant).matches(&e));
+        let excluded = scoped.excluding([e.id]);
+        assert!(excluded.is_narrowing());
+        assert!(!excluded.matches(&e));
+    }
+
+    #[test]
+    fn test_paginate_walks_all_pages() {
+        let entries: Vec<MemoryEntry> = (0..5)
+            .map(|i| {
+                let metadata = MemoryMetadata::default()
+                    .with_conversation("c1")
+                    .with_timestamp(Utc::now() - Duration::seconds(i * 10));
+                MemoryEntry::new(format!("m{}", i), metadata)
+            })
+            .collect();
+        let filter = MemoryFilter::for_conversation("c1");
+
+        let first =
+            MemoryPage::paginate(entries.clone(), &filter, MemoryOrder::NewestFirst, 2, None);
+        assert_eq!(first.entries.len(), 2);
+        assert_eq!(first.entries[0].id, entries[0].id);
+        let cursor = first.next_cursor.expect("more pages");
+
+        let second =
+            MemoryPage::paginate(entries.clone(), &filter, MemoryOrder::NewestFirst, 2, Some(&cursor));
+        assert_eq!(second.entries[0].id, entries[2].id);
+        let cursor = second.next_cursor.expect("more pages");
+
+        let last =
+            MemoryPage::paginate(entries.clone(), &filter, MemoryOrder::NewestFirst, 2, Some(&cursor));
+        assert_eq!(last.entries.len(), 1);
+        assert_eq!(last.entries[0].id, entries[4].id);
+        assert!(last.next_cursor.is_none());
+
+        let oldest =
+            MemoryPage::paginate(entries.clone(), &filter, MemoryOrder::OldestFirst, 1, None);
+        assert_eq!(oldest.entries[0].id, entries[4].id);
+    }
+}
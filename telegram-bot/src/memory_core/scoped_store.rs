@@ -0,0 +1,528 @@
+//! Scoped views of a [`MemoryStore`]: every operation is restricted to one chat (`conversation_id`),
+//! one user, or both. See plan-memory-store-get-store and plan-langgraph-bot-inject-vectorstore.
+//!
+//! Scoping is strict: reads, updates and deletes by id check that the stored entry lies in the
+//! scope, writes, bulk deletes and searches by chat or user may not name another chat or user, and
+//! queries are rewritten to the scope. A violation fails with [`ScopeViolation`] and is logged as a
+//! security event (tracing target `security`).
+//!
+//! The kind of scope is part of the type ([`ChatScopedStore`], [`UserScopedStore`]), so a chat view
+//! cannot be passed where a user view is expected.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::embedding_spec::EmbeddingSpec;
+use super::query::{MemoryCursor, MemoryFilter, MemoryOrder, MemoryPage, SearchFilter};
+use super::store::MemoryStore;
+use super::types::MemoryEntry;
+
+/// The chat, the user, or both that a [`ScopedStore`] is restricted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryScope {
+    conversation_id: Option<String>,
+    user_id: Option<String>,
+}
+
+impl MemoryScope {
+    /// One chat (all of its users).
+    pub fn chat(chat_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: Some(chat_id.into()),
+            user_id: None,
+        }
+    }
+
+    /// One user (across all chats).
+    pub fn user(user_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: None,
+            user_id: Some(user_id.into()),
+        }
+    }
+
+    /// One user's entries within one chat.
+    pub fn chat_and_user(chat_id: impl Into<String>, user_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: Some(chat_id.into()),
+            user_id: Some(user_id.into()),
+        }
+    }
+
+    pub fn conversation_id(&self) -> Option<&str> {
+        self.conversation_id.as_deref()
+    }
+
+    pub fn user_id(&self) -> Option<&str> {
+        self.user_id.as_deref()
+    }
+
+    /// True when the entry belongs to the scope.
+    pub fn contains(&self, entry: &MemoryEntry) -> bool {
+        let m = &entry.metadata;
+        self.conversation_id
+            .as_deref()
+            .is_none_or(|c| m.conversation_id.as_deref() == Some(c))
+            && self
+                .user_id
+                .as_deref()
+                .is_none_or(|u| m.user_id.as_deref() == Some(u))
+    }
+
+    /// `filter` with the scoped fields replaced by the scope.
+    fn restrict(&self, filter: &MemoryFilter) -> MemoryFilter {
+        let mut filter = filter.clone();
+        if let Some(ref c) = self.conversation_id {
+            filter.conversation_id = Some(c.clone());
+        }
+        if let Some(ref u) = self.user_id {
+            filter.user_id = Some(u.clone());
+        }
+        filter
+    }
+
+    /// Fills in unset scoped fields of an entry about to be written; `false` when it names
+    /// another chat or user.
+    fn claim(&self, entry: &mut MemoryEntry) -> bool {
+        let m = &mut entry.metadata;
+        for (scoped, field) in [
+            (&self.conversation_id, &mut m.conversation_id),
+            (&self.user_id, &mut m.user_id),
+        ] {
+            if let Some(scoped) = scoped {
+                if field.is_none() {
+                    *field = Some(scoped.clone());
+                } else if field.as_deref() != Some(scoped.as_str()) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for MemoryScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.conversation_id, &self.user_id) {
+            (Some(c), Some(u)) => write!(f, "chat {} / user {}", c, u),
+            (Some(c), None) => write!(f, "chat {}", c),
+            (None, Some(u)) => write!(f, "user {}", u),
+            (None, None) => write!(f, "everything"),
+        }
+    }
+}
+
+/// An operation through a [`ScopedStore`] that would reach outside its scope.
+#[derive(Debug, Error, PartialEq)]
+pub enum ScopeViolation {
+    /// `get`, `update` or `delete` of an entry stored in another chat or for another user.
+    #[error("{operation}: entry {id} is outside the scope ({scope})")]
+    ForeignEntry {
+        operation: &'static str,
+        id: Uuid,
+        scope: MemoryScope,
+    },
+    /// A write whose entry names another chat or user.
+    #[error("{operation}: entry {id} names another chat or user than the scope ({scope})")]
+    ForeignWrite {
+        operation: &'static str,
+        id: Uuid,
+        scope: MemoryScope,
+    },
+    /// A bulk delete or a search naming another chat or user than the scope.
+    #[error("{operation}: {target} is outside the scope ({scope})")]
+    ForeignTarget {
+        operation: &'static str,
+        target: String,
+        scope: MemoryScope,
+    },
+    /// An operation that affects the whole inner store.
+    #[error("{operation} is not allowed through a scoped store ({scope})")]
+    Forbidden {
+        operation: &'static str,
+        scope: MemoryScope,
+    },
+}
+
+/// Marker for the kind of [`MemoryScope`] a [`ScopedStore`] holds.
+pub trait ScopeKind: Send + Sync + 'static {}
+
+/// Scope of one chat ([`MemoryScope::chat`]).
+#[derive(Debug, Clone, Copy)]
+pub struct ChatScope;
+/// Scope of one user across chats ([`MemoryScope::user`]).
+#[derive(Debug, Clone, Copy)]
+pub struct UserScope;
+/// Any [`MemoryScope`], e.g. one user within one chat.
+#[derive(Debug, Clone, Copy)]
+pub struct AnyScope;
+
+impl ScopeKind for ChatScope {}
+impl ScopeKind for UserScope {}
+impl ScopeKind for AnyScope {}
+
+/// Wrapper around a [`MemoryStore`] that restricts all operations to a [`MemoryScope`].
+/// Used so that tools and agents cannot query, modify or delete other chats' or users' entries,
+/// even when they hold an entry id.
+#[derive(Clone)]
+pub struct ScopedStore<K: ScopeKind = AnyScope> {
+    inner: Arc<dyn MemoryStore>,
+    scope: MemoryScope,
+    kind: PhantomData<K>,
+}
+
+/// A [`ScopedStore`] restricted to one chat (see [`get_store`]).
+pub type ChatScopedStore = ScopedStore<ChatScope>;
+/// A [`ScopedStore`] restricted to one user (see [`get_user_store`]).
+pub type UserScopedStore = ScopedStore<UserScope>;
+
+impl ScopedStore<AnyScope> {
+    pub fn new(inner: Arc<dyn MemoryStore>, scope: MemoryScope) -> Self {
+        Self::with_scope(inner, scope)
+    }
+}
+
+impl ScopedStore<ChatScope> {
+    /// View of one chat: queries use `chat_id` as `conversation_id`; writes without a
+    /// conversation are assigned to it.
+    pub fn chat(inner: Arc<dyn MemoryStore>, chat_id: impl Into<String>) -> Self {
+        Self::with_scope(inner, MemoryScope::chat(chat_id))
+    }
+
+    pub fn chat_id(&self) -> &str {
+        self.scope.conversation_id().unwrap_or_default()
+    }
+}
+
+impl ScopedStore<UserScope> {
+    /// View of one user's entries across chats.
+    pub fn user(inner: Arc<dyn MemoryStore>, user_id: impl Into<String>) -> Self {
+        Self::with_scope(inner, MemoryScope::user(user_id))
+    }
+
+    pub fn user_id(&self) -> &str {
+        self.scope.user_id().unwrap_or_default()
+    }
+}
+
+impl<K: ScopeKind> ScopedStore<K> {
+    fn with_scope(inner: Arc<dyn MemoryStore>, scope: MemoryScope) -> Self {
+        Self {
+            inner,
+            scope,
+            kind: PhantomData,
+        }
+    }
+
+    pub fn scope(&self) -> &MemoryScope {
+        &self.scope
+    }
+
+    /// Logs `violation` as a security event and turns it into the returned error.
+    fn reject(&self, violation: ScopeViolation) -> anyhow::Error {
+        warn!(target: "security", scope = %self.scope, violation = %violation, "Memory scope violation");
+        violation.into()
+    }
+
+    fn claim(&self, entry: &mut MemoryEntry, operation: &'static str) -> Result<(), anyhow::Error> {
+        if self.scope.claim(entry) {
+            Ok(())
+        } else {
+            Err(self.reject(ScopeViolation::ForeignWrite {
+                operation,
+                id: entry.id,
+                scope: self.scope.clone(),
+            }))
+        }
+    }
+
+    /// Fails when the scope is restricted to another value than `target` (`scoped`).
+    fn check_target(
+        &self,
+        scoped: Option<&str>,
+        target: &str,
+        operation: &'static str,
+    ) -> Result<(), anyhow::Error> {
+        match scoped {
+            Some(scoped) if scoped != target => Err(self.reject(ScopeViolation::ForeignTarget {
+                operation,
+                target: target.to_string(),
+                scope: self.scope.clone(),
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails when `id` exists outside the scope; returns the stored entry when it is inside.
+    async fn check_existing(
+        &self,
+        id: Uuid,
+        operation: &'static str,
+    ) -> Result<Option<MemoryEntry>, anyhow::Error> {
+        match self.inner.get(id).await? {
+            Some(entry) if !self.scope.contains(&entry) => {
+                Err(self.reject(ScopeViolation::ForeignEntry {
+                    operation,
+                    id,
+                    scope: self.scope.clone(),
+                }))
+            }
+            found => Ok(found),
+        }
+    }
+
+    fn search_filter(&self, filter: &SearchFilter) -> SearchFilter {
+        SearchFilter {
+            metadata: self.scope.restrict(&filter.metadata),
+            exclude_ids: filter.exclude_ids.clone(),
+        }
+    }
+
+    /// Drops results outside the scope (a backend ignoring a filter), logging each as a security event.
+    fn retain_in_scope<T>(
+        &self,
+        mut results: Vec<T>,
+        entry: impl Fn(&T) -> &MemoryEntry,
+        operation: &'static str,
+    ) -> Vec<T> {
+        results.retain(|r| {
+            let e = entry(r);
+            let inside = self.scope.contains(e);
+            if !inside {
+                warn!(target: "security", scope = %self.scope, entry_id = %e.id, operation, "Inner store returned an entry outside the scope; dropped");
+            }
+            inside
+        });
+        results
+    }
+
+    /// All entries matching `filter` (already restricted to the scope).
+    async fn list_all(&self, filter: &MemoryFilter) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        let page = self
+            .inner
+            .list(filter, MemoryOrder::default(), usize::MAX, None)
+            .await?;
+        Ok(page.entries)
+    }
+
+    async fn delete_all(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        let entries = self.list_all(filter).await?;
+        for entry in &entries {
+            self.inner.delete(entry.id).await?;
+        }
+        Ok(entries.len() as u64)
+    }
+}
+
+#[async_trait]
+impl<K: ScopeKind> MemoryStore for ScopedStore<K> {
+    async fn add(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        let mut entry = entry;
+        self.claim(&mut entry, "add")?;
+        self.inner.add(entry).await
+    }
+
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.inner.flush().await
+    }
+
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        self.inner.embedding_spec().await
+    }
+
+    async fn bind_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        self.inner.bind_embedding_spec(spec).await
+    }
+
+    /// Refused: resetting vectors affects every chat in the inner store.
+    async fn reset_embeddings(&self, _spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        Err(self.reject(ScopeViolation::Forbidden {
+            operation: "reset_embeddings",
+            scope: self.scope.clone(),
+        }))
+    }
+
+    /// Fails without writing anything when any entry names another chat or user.
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        let mut entries = entries;
+        for entry in entries.iter_mut() {
+            self.claim(entry, "add_batch")?;
+        }
+        self.inner.add_batch(entries).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>, anyhow::Error> {
+        self.check_existing(id, "get").await
+    }
+
+    /// The replaced entry (if any) and the new one must both lie in the scope.
+    async fn update(&self, entry: MemoryEntry) -> Result<(), anyhow::Error> {
+        let mut entry = entry;
+        self.claim(&mut entry, "update")?;
+        self.check_existing(entry.id, "update").await?;
+        self.inner.update(entry).await
+    }
+
+    /// Deleting an unknown id is a no-op; deleting another scope's entry fails.
+    async fn delete(&self, id: Uuid) -> Result<(), anyhow::Error> {
+        if self.check_existing(id, "delete").await?.is_some() {
+            self.inner.delete(id).await?;
+        }
+        Ok(())
+    }
+
+    /// Naming another user than the scoped one fails with [`ScopeViolation::ForeignTarget`].
+    async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.check_target(self.scope.user_id(), user_id, "search_by_user")?;
+        self.list_all(&self.scope.restrict(&MemoryFilter::for_user(user_id)))
+            .await
+    }
+
+    /// Naming another chat than the scoped one fails with [`ScopeViolation::ForeignTarget`].
+    async fn search_by_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        self.check_target(
+            self.scope.conversation_id(),
+            conversation_id,
+            "search_by_conversation",
+        )?;
+        self.list_all(
+            &self
+                .scope
+                .restrict(&MemoryFilter::for_conversation(conversation_id)),
+        )
+        .await
+    }
+
+    async fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        user_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        let user_id = self.scope.user_id().or(user_id);
+        let conversation_id = self.scope.conversation_id().or(conversation_id);
+        let results = self
+            .inner
+            .semantic_search(query_embedding, limit, user_id, conversation_id)
+            .await?;
+        Ok(self.retain_in_scope(results, |(_, e)| e, "semantic_search"))
+    }
+
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        let results = self
+            .inner
+            .semantic_search_filtered(query_embedding, limit, &self.search_filter(filter))
+            .await?;
+        Ok(self.retain_in_scope(results, |(_, e)| e, "semantic_search_filtered"))
+    }
+
+    async fn keyword_search(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        let results = self
+            .inner
+            .keyword_search(query, limit, &self.search_filter(filter))
+            .await?;
+        Ok(self.retain_in_scope(results, |(_, e)| e, "keyword_search"))
+    }
+
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let mut page = self
+            .inner
+            .list(&self.scope.restrict(filter), order, limit, cursor)
+            .await?;
+        page.entries = self.retain_in_scope(page.entries, |e| e, "list");
+        Ok(page)
+    }
+
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        self.inner.count(&self.scope.restrict(filter)).await
+    }
+
+    /// Deletes the scope's entries in the conversation; naming another chat than the scoped one
+    /// fails with [`ScopeViolation::ForeignTarget`].
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        self.check_target(
+            self.scope.conversation_id(),
+            conversation_id,
+            "delete_by_conversation",
+        )?;
+        match (self.scope.conversation_id(), self.scope.user_id()) {
+            (Some(chat_id), None) => self.inner.delete_by_conversation(chat_id).await,
+            _ => {
+                self.delete_all(
+                    &self
+                        .scope
+                        .restrict(&MemoryFilter::for_conversation(conversation_id)),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Deletes the user's entries within the scope; naming another user than the scoped one fails
+    /// with [`ScopeViolation::ForeignTarget`].
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        self.check_target(self.scope.user_id(), user_id, "delete_by_user")?;
+        match (self.scope.conversation_id(), self.scope.user_id()) {
+            (None, Some(scoped_user)) => self.inner.delete_by_user(scoped_user).await,
+            _ => {
+                self.delete_all(&self.scope.restrict(&MemoryFilter::for_user(user_id)))
+                    .await
+            }
+        }
+    }
+
+    /// Sweeps the whole store, so it is left to the unscoped store.
+    async fn delete_expired(&self, _now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        Err(self.reject(ScopeViolation::Forbidden {
+            operation: "delete_expired",
+            scope: self.scope.clone(),
+        }))
+    }
+}
+
+/// Returns a [`MemoryStore`] that restricts all operations to the given `chat_id`
+/// (as `conversation_id`). Use this when injecting a store for a single chat so that
+/// tools and agents cannot access other chats.
+pub fn get_store(inner: Arc<dyn MemoryStore>, chat_id: impl Into<String>) -> Arc<dyn MemoryStore> {
+    Arc::new(ScopedStore::chat(inner, chat_id))
+}
+
+/// Returns a [`MemoryStore`] restricted to one user's entries across chats.
+pub fn get_user_store(
+    inner: Arc<dyn MemoryStore>,
+    user_id: impl Into<String>,
+) -> Arc<dyn MemoryStore> {
+    Arc::new(ScopedStore::user(inner, user_id))
+}
+
+/// Returns a [`MemoryStore`] restricted to `scope` (e.g. one user within one chat).
+pub fn get_scoped_store(inner: Arc<dyn MemoryStore>, scope: MemoryScope) -> Arc<dyn MemoryStore> {
+    Arc::new(ScopedStore::new(inner, scope))
+}
@@ -1,10 +1,20 @@
 //! Memory storage trait.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use super::embedding_spec::EmbeddingSpec;
+use super::keyword::bm25_rank;
+use super::query::{MemoryCursor, MemoryFilter, MemoryOrder, MemoryPage, SearchFilter};
 use super::types::MemoryEntry;
 
+/// Candidate multiplier used by the default [`MemoryStore::semantic_search_filtered`] when it filters after the search.
+const SEARCH_OVERFETCH: usize = 4;
+
+/// Most entries the default [`MemoryStore::keyword_search`] loads and ranks (the newest ones).
+pub const KEYWORD_SCAN_LIMIT: usize = 5_000;
+
 #[async_trait]
 pub trait MemoryStore: Send + Sync {
     async fn add(&self, entry: MemoryEntry) -> Result<(), anyhow::Error>;
@@ -23,4 +33,148 @@ pub trait MemoryStore: Send + Sync {
         user_id: Option<&str>,
         conversation_id: Option<&str>,
     ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error>;
+
+    /// Semantic search restricted by `filter` (role, time range, importance, excluded ids).
+    ///
+    /// The default runs `semantic_search` scoped to the filter's user/conversation, fetching
+    /// extra candidates when the filter narrows further, and drops non-matching hits; it may
+    /// return fewer than `limit` results. Stores override it to apply the filter in the query.
+    async fn semantic_search_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        let fetch = if filter.is_narrowing() {
+            limit
+                .saturating_mul(SEARCH_OVERFETCH)
+                .saturating_add(filter.exclude_ids.len())
+        } else {
+            limit
+        };
+        let mut results = self
+            .semantic_search(
+                query_embedding,
+                fetch,
+                filter.metadata.user_id.as_deref(),
+                filter.metadata.conversation_id.as_deref(),
+            )
+            .await?;
+        results.retain(|(_, entry)| filter.matches(entry));
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Lexical search: entries whose content shares terms with `query`, ranked by BM25 (higher is
+    /// better; scores are not comparable with similarities). Exact names, numbers and identifiers
+    /// match even when embeddings do not.
+    ///
+    /// The default lists the newest [`KEYWORD_SCAN_LIMIT`] entries of the filter with
+    /// [`list`](Self::list) and ranks them in process (see [`bm25_rank`]), so older entries of a
+    /// large scope are not searched; stores with a full-text index override it.
+    async fn keyword_search(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(f32, MemoryEntry)>, anyhow::Error> {
+        let mut entries = self
+            .list(&filter.metadata, MemoryOrder::NewestFirst, KEYWORD_SCAN_LIMIT, None)
+            .await?
+            .entries;
+        entries.retain(|entry| !filter.exclude_ids.contains(&entry.id));
+        Ok(bm25_rank(query, entries, limit))
+    }
+
+    /// Persists state the store keeps outside its primary storage (snapshot files, ANN indexes).
+    /// Called on shutdown; the default does nothing.
+    async fn flush(&self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// Embedding model and dimension of the stored vectors: recorded by
+    /// [`bind_embedding_spec`](Self::bind_embedding_spec) or inferred from stored vectors.
+    /// `None` when nothing is known (the default).
+    async fn embedding_spec(&self) -> Result<Option<EmbeddingSpec>, anyhow::Error> {
+        Ok(None)
+    }
+
+    /// Records the configured embedding model, or fails with
+    /// [`EmbeddingMismatch`](super::EmbeddingMismatch) when the stored vectors come from another
+    /// model or dimension. Stores that record a spec reject writes and queries of another dimension.
+    /// The default accepts any spec.
+    async fn bind_embedding_spec(&self, _spec: &EmbeddingSpec) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// First step of a re-embed migration: drops every stored vector (entries keep content and
+    /// metadata) and records `spec`, so entries can be rewritten with new embeddings via `update`.
+    /// Returns how many entries lost their vector. The default refuses.
+    async fn reset_embeddings(&self, _spec: &EmbeddingSpec) -> Result<u64, anyhow::Error> {
+        anyhow::bail!("this memory store does not support re-embedding")
+    }
+
+    /// Physically deletes entries whose `expires_at` is at or before `now`; returns how many were
+    /// deleted. Queries already hide expired entries, this reclaims their space. The default refuses.
+    async fn delete_expired(&self, _now: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        anyhow::bail!("this memory store does not support expiry sweeps")
+    }
+
+    /// Adds many entries at once. The default adds them one by one; stores override it with a
+    /// single write (one transaction, one batch). Entries already carry their embeddings.
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<(), anyhow::Error> {
+        for entry in entries {
+            self.add(entry).await?;
+        }
+        Ok(())
+    }
+
+    /// Lists up to `limit` entries matching `filter` in `order`, starting strictly after `cursor`.
+    ///
+    /// The default loads the filter's conversation (or user) with `search_by_*` and pages in memory;
+    /// it rejects filters with neither. Stores override it with a native query.
+    async fn list(
+        &self,
+        filter: &MemoryFilter,
+        order: MemoryOrder,
+        limit: usize,
+        cursor: Option<&MemoryCursor>,
+    ) -> Result<MemoryPage, anyhow::Error> {
+        let entries = match (&filter.conversation_id, &filter.user_id) {
+            (Some(conversation_id), _) => self.search_by_conversation(conversation_id).await?,
+            (None, Some(user_id)) => self.search_by_user(user_id).await?,
+            (None, None) => {
+                anyhow::bail!("list without user_id or conversation_id is not supported by this store")
+            }
+        };
+        Ok(MemoryPage::paginate(entries, filter, order, limit, cursor))
+    }
+
+    /// Number of entries matching `filter`. The default counts the result of [`list`](Self::list).
+    async fn count(&self, filter: &MemoryFilter) -> Result<u64, anyhow::Error> {
+        let page = self
+            .list(filter, MemoryOrder::default(), usize::MAX, None)
+            .await?;
+        Ok(page.entries.len() as u64)
+    }
+
+    /// Deletes every entry of the conversation; returns how many were deleted.
+    /// The default deletes the result of `search_by_conversation` one by one.
+    async fn delete_by_conversation(&self, conversation_id: &str) -> Result<u64, anyhow::Error> {
+        let entries = self.search_by_conversation(conversation_id).await?;
+        for entry in &entries {
+            self.delete(entry.id).await?;
+        }
+        Ok(entries.len() as u64)
+    }
+
+    /// Deletes every entry of the user; returns how many were deleted.
+    /// The default deletes the result of `search_by_user` one by one.
+    async fn delete_by_user(&self, user_id: &str) -> Result<u64, anyhow::Error> {
+        let entries = self.search_by_user(user_id).await?;
+        for entry in &entries {
+            self.delete(entry.id).await?;
+        }
+        Ok(entries.len() as u64)
+    }
 }
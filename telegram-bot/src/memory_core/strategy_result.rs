@@ -1,16 +1,67 @@
 //! Result type for context strategies.
 
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::types::{MemoryEntry, MemoryRole};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageCategory {
     Recent,
     Semantic,
 }
 
+/// One message of the context, with its role and where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextItem {
+    pub role: MemoryRole,
+    pub content: String,
+    /// User id of the sender for user messages; None for assistant and system entries.
+    pub author: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Memory entry the item was built from.
+    pub entry_id: Option<Uuid>,
+    /// Retrieval score (similarity or fused rank) of semantic results; None for history.
+    pub score: Option<f32>,
+}
+
+impl ContextItem {
+    pub fn from_entry(entry: &MemoryEntry) -> Self {
+        let author = match entry.metadata.role {
+            MemoryRole::User => entry.metadata.user_id.clone(),
+            MemoryRole::Assistant | MemoryRole::System => None,
+        };
+        Self {
+            role: entry.metadata.role,
+            content: entry.content.clone(),
+            author,
+            timestamp: entry.metadata.timestamp,
+            entry_id: Some(entry.id),
+            score: None,
+        }
+    }
+
+    pub fn with_score(mut self, score: f32) -> Self {
+        self.score = Some(score);
+        self
+    }
+
+    /// "Role: content", the flattened form used by [`format_for_model`](prompt::format_for_model).
+    pub fn to_line(&self) -> String {
+        let role = match self.role {
+            MemoryRole::User => "User",
+            MemoryRole::Assistant => "Assistant",
+            MemoryRole::System => "System",
+        };
+        format!("{}: {}", role, self.content)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum StrategyResult {
     Messages {
         category: MessageCategory,
-        messages: Vec<String>,
+        messages: Vec<ContextItem>,
     },
     Preferences(String),
     Empty,
@@ -21,6 +21,62 @@ pub struct MemoryMetadata {
     pub timestamp: DateTime<Utc>,
     pub tokens: Option<u32>,
     pub importance: Option<f32>,
+    /// After this instant the entry is treated as gone; the expiry sweeper deletes it later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A user message timestamped now, with no user, conversation or annotations; fill in the rest
+/// with the `with_*` builders.
+impl Default for MemoryMetadata {
+    fn default() -> Self {
+        Self {
+            user_id: None,
+            conversation_id: None,
+            role: MemoryRole::User,
+            timestamp: Utc::now(),
+            tokens: None,
+            importance: None,
+            expires_at: None,
+        }
+    }
+}
+
+impl MemoryMetadata {
+    /// True once `expires_at` is at or before `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    pub fn with_user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    pub fn with_conversation(mut self, conversation_id: impl Into<String>) -> Self {
+        self.conversation_id = Some(conversation_id.into());
+        self
+    }
+
+    pub fn with_role(mut self, role: MemoryRole) -> Self {
+        self.role = role;
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_importance(mut self, importance: f32) -> Self {
+        self.importance = Some(importance);
+        self
+    }
+
+    pub fn with_expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
 }
 
 /// A single memory entry in the conversation history.
@@ -41,4 +97,9 @@ impl MemoryEntry {
             metadata,
         }
     }
+
+    pub fn with_embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.embedding = Some(embedding);
+        self
+    }
 }
@@ -0,0 +1,56 @@
+//! Conversation summary context strategy: the latest compaction summaries of the conversation.
+
+use async_trait::async_trait;
+use crate::memory_core::{
+    ContextItem, MemoryFilter, MemoryOrder, MemoryRole, MemoryStore, MessageCategory, StrategyResult,
+};
+use tracing::{debug, info};
+
+use super::strategy::ContextStrategy;
+
+/// Loads the newest `limit` summaries (`System`-role entries written by
+/// [`MemoryCompactor`](crate::memory::MemoryCompactor)) of the conversation, oldest first, so the
+/// model sees older history condensed ahead of the recent messages. Add it before
+/// [`RecentMessagesStrategy`](super::RecentMessagesStrategy).
+#[derive(Debug, Clone)]
+pub struct ConversationSummaryStrategy {
+    limit: usize,
+}
+
+impl ConversationSummaryStrategy {
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+#[async_trait]
+impl ContextStrategy for ConversationSummaryStrategy {
+    fn name(&self) -> &str {
+        "ConversationSummary"
+    }
+    async fn build_context(
+        &self,
+        store: &dyn MemoryStore,
+        _user_id: &Option<String>,
+        conversation_id: &Option<String>,
+        _query: &Option<String>,
+    ) -> Result<StrategyResult, anyhow::Error> {
+        let Some(conv_id) = conversation_id else {
+            debug!("ConversationSummaryStrategy: no conversation_id, returning Empty");
+            return Ok(StrategyResult::Empty);
+        };
+        if self.limit == 0 {
+            return Ok(StrategyResult::Empty);
+        }
+        let filter = MemoryFilter::for_conversation(conv_id.as_str()).with_role(MemoryRole::System);
+        let page = store
+            .list(&filter, MemoryOrder::NewestFirst, self.limit, None)
+            .await?;
+        let messages: Vec<ContextItem> = page.entries.iter().rev().map(ContextItem::from_entry).collect();
+        info!(conversation_id = %conv_id, summary_count = messages.len(), "ConversationSummaryStrategy: summaries loaded");
+        if messages.is_empty() {
+            return Ok(StrategyResult::Empty);
+        }
+        Ok(StrategyResult::Messages { category: MessageCategory::Recent, messages })
+    }
+}
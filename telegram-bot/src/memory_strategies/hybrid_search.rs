@@ -0,0 +1,276 @@
+//! Hybrid search context strategy: keyword (BM25 / FTS) and vector search merged with reciprocal rank fusion.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use crate::embedding::EmbeddingService;
+use crate::memory::importance::importance_weighted;
+use crate::memory_core::{
+    ContextItem, MessageCategory, MemoryEntry, MemoryStore, SearchFilter, StrategyResult,
+};
+use tracing::{debug, info, warn};
+
+use super::rerank::{maximal_marginal_relevance, recency_decayed};
+use super::strategy::ContextStrategy;
+use super::utils::request_filter;
+
+/// Usual reciprocal rank fusion constant; larger values flatten the advantage of top ranks.
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Candidates fetched from each ranking per result slot, so fusion can promote entries that are
+/// ranked moderately by both searches.
+const CANDIDATE_FACTOR: usize = 3;
+
+/// Weight of each ranking in the fusion. A weight of 0 disables that search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HybridWeights {
+    pub vector: f32,
+    pub keyword: f32,
+}
+
+impl Default for HybridWeights {
+    fn default() -> Self {
+        Self { vector: 1.0, keyword: 1.0 }
+    }
+}
+
+/// Combines [`MemoryStore::keyword_search`] (exact names, numbers, identifiers) with
+/// [`MemoryStore::semantic_search_filtered`] (paraphrases), merged by weighted reciprocal rank
+/// fusion: `score = Σ weight / (k + rank)`. A drop-in replacement for
+/// [`SemanticSearchStrategy`](super::SemanticSearchStrategy); `min_score` applies to vector hits.
+/// When the query cannot be embedded, keyword results are used alone.
+pub struct HybridSearchStrategy {
+    limit: usize,
+    min_score: f32,
+    embedding_service: Arc<dyn EmbeddingService>,
+    weights: HybridWeights,
+    rrf_k: f32,
+    /// Extra predicates (role, time range, importance, excluded ids); the conversation comes from the request.
+    filter: SearchFilter,
+    /// When set, only entries newer than `now - max_age` are searched.
+    max_age: Option<Duration>,
+    /// Weight of entry importance applied to fused scores; 0 ranks by fusion alone.
+    importance_weight: f32,
+    /// When set, fused scores decay by half for every `half_life` of entry age.
+    recency_half_life: Option<Duration>,
+    /// When set, results are picked from the fused candidates by Maximal Marginal Relevance.
+    mmr_lambda: Option<f32>,
+}
+
+impl HybridSearchStrategy {
+    pub fn new(limit: usize, embedding_service: Arc<dyn EmbeddingService>, min_score: f32) -> Self {
+        Self {
+            limit,
+            min_score,
+            embedding_service,
+            weights: HybridWeights::default(),
+            rrf_k: DEFAULT_RRF_K,
+            filter: SearchFilter::default(),
+            max_age: None,
+            importance_weight: 0.0,
+            recency_half_life: None,
+            mmr_lambda: None,
+        }
+    }
+
+    pub fn with_weights(mut self, weights: HybridWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Sets the fusion constant `k` (default [`DEFAULT_RRF_K`]).
+    pub fn with_rrf_k(mut self, rrf_k: f32) -> Self {
+        self.rrf_k = rrf_k;
+        self
+    }
+
+    /// Restricts results with `filter`; its conversation_id is replaced by the request's conversation.
+    pub fn with_filter(mut self, filter: SearchFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Searches only entries from the last `max_age` (evaluated per request).
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Scales fused scores by importance (see [`importance_weighted`]).
+    pub fn with_importance_weight(mut self, weight: f32) -> Self {
+        self.importance_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Decays fused scores by entry age (see [`recency_decayed`]).
+    pub fn with_recency_half_life(mut self, half_life: Duration) -> Self {
+        self.recency_half_life = Some(half_life).filter(|h| *h > Duration::zero());
+        self
+    }
+
+    /// Picks results from the fused candidates by Maximal Marginal Relevance (see
+    /// [`maximal_marginal_relevance`]). `lambda` 1 is plain ranking; lower values favour diversity.
+    pub fn with_mmr_lambda(mut self, lambda: f32) -> Self {
+        self.mmr_lambda = Some(lambda.clamp(0.0, 1.0)).filter(|l| *l < 1.0);
+        self
+    }
+
+    /// Vector ranking above `min_score`; empty when the vector weight is 0 or embedding fails.
+    async fn vector_ranking(
+        &self,
+        store: &dyn MemoryStore,
+        query_text: &str,
+        candidates: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<MemoryEntry>, anyhow::Error> {
+        if self.weights.vector <= 0.0 {
+            return Ok(Vec::new());
+        }
+        let query_embedding = match self.embedding_service.embed(query_text).await {
+            Ok(emb) => emb,
+            Err(e) => {
+                warn!(error = %e, query = %query_text, "HybridSearchStrategy: embedding failed, using keyword results only");
+                return Ok(Vec::new());
+            }
+        };
+        let results = store
+            .semantic_search_filtered(&query_embedding, candidates, filter)
+            .await
+            .map_err(|e| anyhow::anyhow!("HybridSearchStrategy semantic_search failed: {}", e))?;
+        Ok(results
+            .into_iter()
+            .filter(|(score, _)| *score >= self.min_score)
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+}
+
+/// Weighted reciprocal rank fusion of best-first `rankings`: each entry scores
+/// `Σ weight / (k + rank)` (rank starting at 1) over the rankings it appears in. Returns the best
+/// `limit`, highest first; ties keep first-seen order.
+pub fn reciprocal_rank_fusion(
+    rankings: Vec<(f32, Vec<MemoryEntry>)>,
+    k: f32,
+    limit: usize,
+) -> Vec<(f32, MemoryEntry)> {
+    let mut fused: Vec<(f32, MemoryEntry)> = Vec::new();
+    let mut positions: HashMap<Uuid, usize> = HashMap::new();
+    for (weight, ranking) in rankings {
+        for (rank, entry) in ranking.into_iter().enumerate() {
+            let score = weight / (k + rank as f32 + 1.0);
+            match positions.get(&entry.id) {
+                Some(&i) => fused[i].0 += score,
+                None => {
+                    positions.insert(entry.id, fused.len());
+                    fused.push((score, entry));
+                }
+            }
+        }
+    }
+    fused.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    fused.truncate(limit);
+    fused
+}
+
+#[async_trait]
+impl ContextStrategy for HybridSearchStrategy {
+    fn name(&self) -> &str {
+        "HybridSearch"
+    }
+    async fn build_context(
+        &self,
+        store: &dyn MemoryStore,
+        _user_id: &Option<String>,
+        conversation_id: &Option<String>,
+        query: &Option<String>,
+    ) -> Result<StrategyResult, anyhow::Error> {
+        let query_text = match query {
+            Some(q) if !q.trim().is_empty() => q.trim(),
+            _ => {
+                debug!("HybridSearchStrategy: no query text, skipping hybrid search");
+                return Ok(StrategyResult::Empty);
+            }
+        };
+        let candidates = self.limit.saturating_mul(CANDIDATE_FACTOR);
+        let filter = request_filter(&self.filter, self.max_age, conversation_id);
+        info!(query_len = query_text.len(), limit = self.limit, weights = ?self.weights, "HybridSearchStrategy: starting hybrid search");
+
+        let vector = self.vector_ranking(store, query_text, candidates, &filter).await?;
+        let keyword: Vec<MemoryEntry> = if self.weights.keyword > 0.0 {
+            store
+                .keyword_search(query_text, candidates, &filter)
+                .await
+                .map_err(|e| anyhow::anyhow!("HybridSearchStrategy keyword_search failed: {}", e))?
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        info!(vector_hits = vector.len(), keyword_hits = keyword.len(), "step: hybrid search rankings");
+
+        let mut fused = reciprocal_rank_fusion(
+            vec![(self.weights.vector, vector), (self.weights.keyword, keyword)],
+            self.rrf_k,
+            candidates,
+        );
+        if self.importance_weight > 0.0 || self.recency_half_life.is_some() {
+            let now = Utc::now();
+            for (score, entry) in fused.iter_mut() {
+                if self.importance_weight > 0.0 {
+                    *score = importance_weighted(*score, entry, self.importance_weight);
+                }
+                if let Some(half_life) = self.recency_half_life {
+                    *score = recency_decayed(*score, entry, now, half_life);
+                }
+            }
+            fused.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        let selected = match self.mmr_lambda {
+            Some(lambda) => maximal_marginal_relevance(fused, self.limit, lambda),
+            None => {
+                fused.truncate(self.limit);
+                fused
+            }
+        };
+        let messages: Vec<ContextItem> = selected
+            .iter()
+            .map(|(score, entry)| ContextItem::from_entry(entry).with_score(*score))
+            .collect();
+        info!(query = %query_text, message_count = messages.len(), "HybridSearchStrategy: hybrid search returned messages");
+        Ok(StrategyResult::Messages { category: MessageCategory::Semantic, messages })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_core::MemoryMetadata;
+
+    #[test]
+    fn test_reciprocal_rank_fusion_rewards_agreement_and_weights() {
+        let [a, b, c] = ["a", "b", "c"]
+            .map(|content| MemoryEntry::new(content.to_string(), MemoryMetadata::default()));
+        // b is second in both rankings and beats a and c, each first in only one.
+        let fused = reciprocal_rank_fusion(
+            vec![
+                (1.0, vec![a.clone(), b.clone()]),
+                (1.0, vec![c.clone(), b.clone()]),
+            ],
+            DEFAULT_RRF_K,
+            10,
+        );
+        let order: Vec<&str> = fused.iter().map(|(_, e)| e.content.as_str()).collect();
+        assert_eq!(order, vec!["b", "a", "c"]);
+
+        // A heavier keyword weight lets its top hit win.
+        let fused = reciprocal_rank_fusion(
+            vec![(1.0, vec![a.clone()]), (2.0, vec![c.clone()])],
+            DEFAULT_RRF_K,
+            1,
+        );
+        assert_eq!(fused[0].1.content, "c");
+    }
+}
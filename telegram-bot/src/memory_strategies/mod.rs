@@ -1,12 +1,18 @@
 //! Context building strategies for conversation memory.
 
 mod strategy;
+mod conversation_summary;
+mod hybrid_search;
 mod recent_messages;
+mod rerank;
 mod semantic_search;
 mod user_preferences;
 mod utils;
 
+pub use conversation_summary::ConversationSummaryStrategy;
+pub use hybrid_search::{reciprocal_rank_fusion, HybridSearchStrategy, HybridWeights, DEFAULT_RRF_K};
 pub use recent_messages::RecentMessagesStrategy;
+pub use rerank::{maximal_marginal_relevance, recency_decayed};
 pub use semantic_search::SemanticSearchStrategy;
 pub use strategy::{ContextStrategy, StoreKind};
 pub use user_preferences::UserPreferencesStrategy;
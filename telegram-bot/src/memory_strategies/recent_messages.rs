@@ -1,12 +1,15 @@
 //! Recent messages context strategy.
 
 use async_trait::async_trait;
-use crate::memory_core::{MessageCategory, MemoryStore, StrategyResult};
+use crate::memory_core::{
+    ContextItem, MemoryFilter, MemoryOrder, MessageCategory, MemoryStore, StrategyResult,
+};
 use tracing::{debug, info};
 
 use super::strategy::{ContextStrategy, StoreKind};
-use super::utils::format_message;
 
+/// Loads the last `limit` messages of the conversation (or, without one, of the user) in chronological order.
+/// Uses [`MemoryStore::list`] so stores return only the newest page instead of the whole history.
 #[derive(Debug, Clone)]
 pub struct RecentMessagesStrategy {
     limit: usize,
@@ -16,6 +19,24 @@ impl RecentMessagesStrategy {
     pub fn new(limit: usize) -> Self {
         Self { limit }
     }
+
+    /// Newest `limit` entries matching `filter` (which leaves out empty ones), returned oldest first.
+    async fn recent_messages(
+        &self,
+        store: &dyn MemoryStore,
+        filter: &MemoryFilter,
+    ) -> Result<Vec<ContextItem>, anyhow::Error> {
+        let page = store
+            .list(filter, MemoryOrder::NewestFirst, self.limit, None)
+            .await?;
+        let messages: Vec<ContextItem> = page
+            .entries
+            .iter()
+            .rev()
+            .map(ContextItem::from_entry)
+            .collect();
+        Ok(messages)
+    }
 }
 
 #[async_trait]
@@ -34,35 +55,27 @@ impl ContextStrategy for RecentMessagesStrategy {
         _query: &Option<String>,
     ) -> Result<StrategyResult, anyhow::Error> {
         if let Some(conv_id) = conversation_id {
-            debug!(conversation_id = conv_id, limit = self.limit, "RecentMessagesStrategy: searching by conversation_id");
-            let mut entries = store.search_by_conversation(conv_id).await.map_err(|e| {
-                tracing::error!(error = %e, conversation_id = %conv_id, "RecentMessagesStrategy: search_by_conversation failed");
-                e
-            })?;
-            entries.retain(|e| !e.content.is_empty());
-            entries.sort_by_key(|e| e.metadata.timestamp);
-            let n = entries.len();
-            if n > self.limit {
-                entries = entries.into_iter().skip(n - self.limit).collect();
-            }
-            let messages: Vec<String> = entries.iter().map(|entry| format_message(entry)).collect();
-            info!(conversation_id = %conv_id, entry_count = entries.len(), message_count = messages.len(), "RecentMessagesStrategy: recent messages by conversation_id");
+            debug!(conversation_id = conv_id, limit = self.limit, "RecentMessagesStrategy: listing by conversation_id");
+            let messages = self
+                .recent_messages(store, &MemoryFilter::for_conversation(conv_id.as_str()).non_empty())
+                .await
+                .map_err(|e| {
+                    tracing::error!(error = %e, conversation_id = %conv_id, "RecentMessagesStrategy: list by conversation failed");
+                    e
+                })?;
+            info!(conversation_id = %conv_id, message_count = messages.len(), "RecentMessagesStrategy: recent messages by conversation_id");
             return Ok(StrategyResult::Messages { category: MessageCategory::Recent, messages });
         }
         if let Some(uid) = user_id {
-            debug!(user_id = uid, limit = self.limit, "RecentMessagesStrategy: searching by user_id");
-            let mut entries = store.search_by_user(uid).await.map_err(|e| {
-                tracing::error!(error = %e, user_id = %uid, "RecentMessagesStrategy: search_by_user failed");
-                e
-            })?;
-            entries.retain(|e| !e.content.is_empty());
-            entries.sort_by_key(|e| e.metadata.timestamp);
-            let n = entries.len();
-            if n > self.limit {
-                entries = entries.into_iter().skip(n - self.limit).collect();
-            }
-            let messages: Vec<String> = entries.iter().map(|entry| format_message(entry)).collect();
-            info!(user_id = %uid, entry_count = entries.len(), message_count = messages.len(), "RecentMessagesStrategy: recent messages by user_id");
+            debug!(user_id = uid, limit = self.limit, "RecentMessagesStrategy: listing by user_id");
+            let messages = self
+                .recent_messages(store, &MemoryFilter::for_user(uid.as_str()).non_empty())
+                .await
+                .map_err(|e| {
+                    tracing::error!(error = %e, user_id = %uid, "RecentMessagesStrategy: list by user failed");
+                    e
+                })?;
+            info!(user_id = %uid, message_count = messages.len(), "RecentMessagesStrategy: recent messages by user_id");
             return Ok(StrategyResult::Messages { category: MessageCategory::Recent, messages });
         }
         debug!("RecentMessagesStrategy: no user_id or conversation_id, returning Empty");
@@ -0,0 +1,129 @@
+//! Re-ranking of retrieved memories: recency decay and Maximal Marginal Relevance (MMR).
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::memory::write_pipeline::similarity;
+use crate::memory_core::MemoryEntry;
+
+/// Scales `score` by `0.5^(age / half_life)`: an entry `half_life` old keeps half its score, one
+/// twice as old a quarter. Entries dated in the future and a non-positive half-life leave the score
+/// unchanged.
+pub fn recency_decayed(
+    score: f32,
+    entry: &MemoryEntry,
+    now: DateTime<Utc>,
+    half_life: Duration,
+) -> f32 {
+    let half_life_secs = half_life.num_seconds();
+    if half_life_secs <= 0 {
+        return score;
+    }
+    let age_secs = (now - entry.metadata.timestamp).num_seconds().max(0);
+    score * 0.5f32.powf(age_secs as f32 / half_life_secs as f32)
+}
+
+/// Picks up to `limit` of `candidates` (score, entry) by Maximal Marginal Relevance: each step
+/// takes the candidate maximizing `lambda * relevance - (1 - lambda) * similarity`, where
+/// relevance is the score divided by the best candidate's (so reciprocal-rank or decayed scores
+/// weigh against similarity like cosine ones do) and similarity is the highest similarity to an entry
+/// already picked (cosine of the embeddings, token overlap without them). Returned scores are the
+/// original ones.
+///
+/// `lambda` 1 keeps the score order; lower values trade relevance for diversity, so near-identical
+/// messages do not fill every slot. Candidates are expected best first; ties keep that order.
+pub fn maximal_marginal_relevance(
+    candidates: Vec<(f32, MemoryEntry)>,
+    limit: usize,
+    lambda: f32,
+) -> Vec<(f32, MemoryEntry)> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    let max = candidates.iter().map(|(s, _)| *s).fold(0.0f32, f32::max);
+    let relevance = |score: f32| if max > f32::EPSILON { score / max } else { score };
+    let mut remaining = candidates;
+    let mut selected: Vec<(f32, MemoryEntry)> = Vec::with_capacity(limit.min(remaining.len()));
+    while selected.len() < limit && !remaining.is_empty() {
+        let mut best = 0;
+        let mut best_value = f32::NEG_INFINITY;
+        for (i, (score, entry)) in remaining.iter().enumerate() {
+            let redundancy = selected
+                .iter()
+                .map(|(_, picked)| similarity(entry, picked))
+                .fold(0.0f32, f32::max);
+            let value = lambda * relevance(*score) - (1.0 - lambda) * redundancy;
+            if value > best_value {
+                best = i;
+                best_value = value;
+            }
+        }
+        selected.push(remaining.remove(best));
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_core::MemoryMetadata;
+
+    #[test]
+    fn test_recency_decayed_halves_per_half_life() {
+        let now = Utc::now();
+        let week = Duration::days(7);
+        let fresh = MemoryEntry::new("a".to_string(), MemoryMetadata::default());
+        let old = MemoryEntry::new(
+            "b".to_string(),
+            MemoryMetadata::default().with_timestamp(now - Duration::days(14)),
+        );
+
+        assert!((recency_decayed(0.8, &fresh, now, week) - 0.8).abs() < 1e-3);
+        assert!((recency_decayed(0.8, &old, now, week) - 0.2).abs() < 1e-3);
+        assert_eq!(recency_decayed(0.8, &old, now, Duration::zero()), 0.8);
+    }
+
+    #[test]
+    fn test_mmr_skips_near_duplicates() {
+        let [a, a2, b] = [
+            ("cats sleep a lot", vec![1.0, 0.0]),
+            ("cats sleep a lot!", vec![0.99, 0.01]),
+            ("dogs need walks", vec![0.0, 1.0]),
+        ]
+        .map(|(content, embedding)| {
+            let entry = MemoryEntry::new(content.to_string(), MemoryMetadata::default());
+            entry.with_embedding(embedding)
+        });
+        let candidates = vec![(0.9, a.clone()), (0.89, a2.clone()), (0.6, b.clone())];
+
+        let top: Vec<_> = maximal_marginal_relevance(candidates.clone(), 2, 1.0)
+            .into_iter()
+            .map(|(_, e)| e.id)
+            .collect();
+        assert_eq!(top, vec![a.id, a2.id]);
+
+        let diverse: Vec<_> = maximal_marginal_relevance(candidates, 2, 0.5)
+            .into_iter()
+            .map(|(_, e)| e.id)
+            .collect();
+        assert_eq!(diverse, vec![a.id, b.id]);
+    }
+
+    #[test]
+    fn test_mmr_normalizes_small_scores() {
+        let [a, a2, b] = [
+            ("cats sleep a lot", vec![1.0, 0.0]),
+            ("cats sleep a lot!", vec![0.99, 0.01]),
+            ("dogs need walks", vec![0.0, 1.0]),
+        ]
+        .map(|(content, embedding)| {
+            let entry = MemoryEntry::new(content.to_string(), MemoryMetadata::default());
+            entry.with_embedding(embedding)
+        });
+        // Reciprocal-rank-sized scores: unnormalized, any redundancy would outweigh relevance.
+        let candidates = vec![(0.0328, a.clone()), (0.0325, a2.clone()), (0.0161, b.clone())];
+
+        let top: Vec<_> = maximal_marginal_relevance(candidates, 2, 0.9)
+            .into_iter()
+            .map(|(s, e)| (s, e.id))
+            .collect();
+        assert_eq!(top, vec![(0.0328, a.id), (0.0325, a2.id)]);
+    }
+}
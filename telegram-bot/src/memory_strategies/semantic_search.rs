@@ -2,22 +2,98 @@
 
 use std::sync::Arc;
 use async_trait::async_trait;
+use chrono::{Duration, Utc};
 use crate::embedding::EmbeddingService;
-use crate::memory_core::{MessageCategory, MemoryEntry, MemoryStore, StrategyResult};
+use crate::memory::importance::importance_weighted;
+use crate::memory_core::{
+    ContextItem, MessageCategory, MemoryEntry, MemoryStore, SearchFilter, StrategyResult,
+};
 use tracing::{debug, error, info, warn};
 
+use super::rerank::{maximal_marginal_relevance, recency_decayed};
 use super::strategy::ContextStrategy;
-use super::utils::format_message;
+use super::utils::request_filter;
+
+/// Candidates fetched per result slot when MMR is on, so there are alternatives to near-duplicates.
+const MMR_CANDIDATE_FACTOR: usize = 3;
 
 pub struct SemanticSearchStrategy {
     limit: usize,
     min_score: f32,
     embedding_service: Arc<dyn EmbeddingService>,
+    /// Extra predicates (role, time range, importance, excluded ids); the conversation comes from the request.
+    filter: SearchFilter,
+    /// When set, only entries newer than `now - max_age` are searched.
+    max_age: Option<Duration>,
+    /// Weight of entry importance in the ranking; 0 ranks by similarity alone.
+    importance_weight: f32,
+    /// When set, scores decay by half for every `half_life` of entry age.
+    recency_half_life: Option<Duration>,
+    /// When set, results are picked by Maximal Marginal Relevance with this lambda.
+    mmr_lambda: Option<f32>,
 }
 
 impl SemanticSearchStrategy {
     pub fn new(limit: usize, embedding_service: Arc<dyn EmbeddingService>, min_score: f32) -> Self {
-        Self { limit, min_score, embedding_service }
+        Self {
+            limit,
+            min_score,
+            embedding_service,
+            filter: SearchFilter::default(),
+            max_age: None,
+            importance_weight: 0.0,
+            recency_half_life: None,
+            mmr_lambda: None,
+        }
+    }
+
+    /// Restricts results with `filter`; its conversation_id is replaced by the request's conversation.
+    pub fn with_filter(mut self, filter: SearchFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Searches only entries from the last `max_age` (evaluated per request).
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Ranks by similarity scaled by importance (see [`importance_weighted`]); twice `limit`
+    /// candidates are fetched so important entries just outside the top can move up.
+    pub fn with_importance_weight(mut self, weight: f32) -> Self {
+        self.importance_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Ranks by similarity decayed by entry age (see [`recency_decayed`]), so recent memories beat
+    /// old ones of similar relevance; twice `limit` candidates are fetched.
+    pub fn with_recency_half_life(mut self, half_life: Duration) -> Self {
+        self.recency_half_life = Some(half_life).filter(|h| *h > Duration::zero());
+        self
+    }
+
+    /// Picks results by Maximal Marginal Relevance (see [`maximal_marginal_relevance`]) from
+    /// three times `limit` candidates. `lambda` 1 is plain ranking; lower values favour diversity.
+    pub fn with_mmr_lambda(mut self, lambda: f32) -> Self {
+        self.mmr_lambda = Some(lambda.clamp(0.0, 1.0)).filter(|l| *l < 1.0);
+        self
+    }
+
+    /// Whether similarity scores are rescaled (importance or recency) and need re-sorting.
+    fn rescores(&self) -> bool {
+        self.importance_weight > 0.0 || self.recency_half_life.is_some()
+    }
+
+    /// Number of search results fetched before re-ranking and truncation to `limit`.
+    fn candidate_count(&self) -> usize {
+        if self.mmr_lambda.is_some() {
+            self.limit.saturating_mul(MMR_CANDIDATE_FACTOR)
+        } else if self.rescores() {
+            self.limit.saturating_mul(2)
+        } else {
+            self.limit
+        }
     }
 }
 
@@ -50,7 +126,9 @@ impl ContextStrategy for SemanticSearchStrategy {
             }
         };
         info!(dimension = query_embedding.len(), limit = self.limit, min_score = self.min_score, "step: embedding semantic_search");
-        let scored_entries = match store.semantic_search(&query_embedding, self.limit, None, conversation_id.as_deref()).await {
+        let filter = request_filter(&self.filter, self.max_age, conversation_id);
+        let fetch = self.candidate_count();
+        let scored_entries = match store.semantic_search_filtered(&query_embedding, fetch, &filter).await {
             Ok(ent) => ent,
             Err(e) => {
                 error!(error = %e, query = %query_text, limit = self.limit, "SemanticSearchStrategy: semantic_search failed");
@@ -65,17 +143,38 @@ impl ContextStrategy for SemanticSearchStrategy {
             let mean_s = scores.iter().sum::<f32>() / scores.len() as f32;
             info!(count = count_before, score_min = %min_s, score_mean = %mean_s, score_max = %max_s, "step: embedding semantic_search score distribution");
         }
-        let entries: Vec<MemoryEntry> = scored_entries
+        let mut kept: Vec<(f32, MemoryEntry)> = scored_entries
             .into_iter()
             .filter(|(score, _)| *score >= self.min_score)
-            .map(|(_, entry)| entry)
             .collect();
-        if count_before > 0 && entries.is_empty() {
+        if self.rescores() {
+            let now = Utc::now();
+            for (score, entry) in kept.iter_mut() {
+                if self.importance_weight > 0.0 {
+                    *score = importance_weighted(*score, entry, self.importance_weight);
+                }
+                if let Some(half_life) = self.recency_half_life {
+                    *score = recency_decayed(*score, entry, now, half_life);
+                }
+            }
+            kept.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        }
+        let selected = match self.mmr_lambda {
+            Some(lambda) => maximal_marginal_relevance(kept, self.limit, lambda),
+            None => {
+                kept.truncate(self.limit);
+                kept
+            }
+        };
+        let messages: Vec<ContextItem> = selected
+            .iter()
+            .map(|(score, entry)| ContextItem::from_entry(entry).with_score(*score))
+            .collect();
+        if count_before > 0 && messages.is_empty() {
             warn!(query = %query_text, min_score = self.min_score, count_before = count_before, "SemanticSearchStrategy: all semantic results below threshold");
         }
-        let messages: Vec<String> = entries.iter().map(|entry| format_message(entry)).collect();
-        info!(entry_count = entries.len(), "step: embedding semantic_search done");
-        info!(query = %query_text, entry_count = entries.len(), message_count = messages.len(), "SemanticSearchStrategy: semantic search returned messages");
+        info!(entry_count = messages.len(), "step: embedding semantic_search done");
+        info!(query = %query_text, message_count = messages.len(), "SemanticSearchStrategy: semantic search returned messages");
         Ok(StrategyResult::Messages { category: MessageCategory::Semantic, messages })
     }
 }
@@ -2,20 +2,84 @@
 
 use async_trait::async_trait;
 use crate::memory_core::{MemoryStore, StrategyResult};
+use crate::profile::{FactKind, ProfileRepository, UserFact};
 use tracing::{debug, info};
 
 use super::strategy::{ContextStrategy, StoreKind};
 use super::utils::extract_preferences;
 
-#[derive(Debug, Clone)]
-pub struct UserPreferencesStrategy;
+/// Most facts of one kind put into the context (most confident first).
+const MAX_FACTS_PER_KIND: usize = 10;
+
+/// Loads the facts recorded about the user in the [`ProfileRepository`] (extracted from their
+/// messages by the [`ProfileUpdater`](crate::profile::ProfileUpdater)), e.g.
+/// "Name: Alice; Location: Berlin; Preferences: green tea, jazz".
+///
+/// Without a profile repository (fact extraction is off by default), or while the user has no facts
+/// yet, falls back to scanning the user's recent memories for "I like" / "I prefer".
+#[derive(Clone, Default)]
+pub struct UserPreferencesStrategy {
+    profiles: Option<ProfileRepository>,
+}
 
 impl UserPreferencesStrategy {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Serves the user's profile facts when there are any.
+    pub fn with_profile_repo(mut self, profiles: ProfileRepository) -> Self {
+        self.profiles = Some(profiles);
+        self
+    }
+
+    /// "User Preferences: ..." from the keyword heuristic over the user's memories.
+    async fn preferences_from_memories(
+        store: &dyn MemoryStore,
+        user_id: &str,
+    ) -> Result<StrategyResult, anyhow::Error> {
+        let entries = store.search_by_user(user_id).await.map_err(|e| {
+            tracing::error!(error = %e, user_id = %user_id, "UserPreferencesStrategy: search_by_user failed");
+            e
+        })?;
+        info!(user_id = %user_id, entry_count = entries.len(), "UserPreferencesStrategy: loaded entries for preference extraction");
+        let preferences = extract_preferences(&entries);
+        if preferences.is_empty() {
+            debug!(user_id = %user_id, "UserPreferencesStrategy: no preferences detected, returning Empty");
+            return Ok(StrategyResult::Empty);
+        }
+        let prefs_str = format!("User Preferences: {}", preferences.join(", "));
+        info!(user_id = %user_id, preference_count = preferences.len(), preferences = %prefs_str, "UserPreferencesStrategy: user preferences extracted");
+        Ok(StrategyResult::Preferences(prefs_str))
     }
 }
 
+/// One "Label: value, value" part per kind that has facts, in [`FactKind::ALL`] order.
+fn format_profile(facts: &[UserFact]) -> String {
+    FactKind::ALL
+        .iter()
+        .filter_map(|kind| {
+            let values: Vec<&str> = facts
+                .iter()
+                .filter(|f| f.kind == *kind)
+                .take(MAX_FACTS_PER_KIND)
+                .map(|f| f.value.as_str())
+                .collect();
+            if values.is_empty() {
+                return None;
+            }
+            let label = match kind {
+                FactKind::Name => "Name",
+                FactKind::Location => "Location",
+                FactKind::Preference => "Preferences",
+                FactKind::Project => "Projects",
+            };
+            Some(format!("{}: {}", label, values.join(", ")))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 #[async_trait]
 impl ContextStrategy for UserPreferencesStrategy {
     fn name(&self) -> &str {
@@ -38,19 +102,52 @@ impl ContextStrategy for UserPreferencesStrategy {
                 return Ok(StrategyResult::Empty);
             }
         };
-        let entries = store.search_by_user(user_id).await.map_err(|e| {
-            tracing::error!(error = %e, user_id = %user_id, "UserPreferencesStrategy: search_by_user failed");
+        let Some(profiles) = &self.profiles else {
+            return Self::preferences_from_memories(store, user_id).await;
+        };
+        let facts = profiles.facts_for_user(user_id).await.map_err(|e| {
+            tracing::error!(error = %e, user_id = %user_id, "UserPreferencesStrategy: loading profile failed");
             e
         })?;
-        info!(user_id = %user_id, entry_count = entries.len(), "UserPreferencesStrategy: loaded entries for preference extraction");
-        let preferences = extract_preferences(&entries);
-        if preferences.is_empty() {
-            debug!(user_id = %user_id, "UserPreferencesStrategy: no preferences detected, returning Empty");
-            Ok(StrategyResult::Empty)
-        } else {
-            let prefs_str = format!("User Preferences: {}", preferences.join(", "));
-            info!(user_id = %user_id, preference_count = preferences.len(), preferences = %prefs_str, "UserPreferencesStrategy: user preferences extracted");
-            Ok(StrategyResult::Preferences(prefs_str))
+        if facts.is_empty() {
+            debug!(user_id = %user_id, "UserPreferencesStrategy: no profile facts, falling back to memories");
+            return Self::preferences_from_memories(store, user_id).await;
+        }
+        let profile = format_profile(&facts);
+        info!(user_id = %user_id, fact_count = facts.len(), profile = %profile, "UserPreferencesStrategy: user profile loaded");
+        Ok(StrategyResult::Preferences(profile))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn fact(kind: FactKind, value: &str) -> UserFact {
+        UserFact {
+            id: value.to_string(),
+            user_id: "u1".to_string(),
+            kind,
+            value: value.to_string(),
+            confidence: 0.9,
+            mentions: 1,
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
         }
     }
+
+    #[test]
+    fn test_format_profile_groups_facts_by_kind() {
+        let facts = vec![
+            fact(FactKind::Name, "Alice"),
+            fact(FactKind::Preference, "green tea"),
+            fact(FactKind::Preference, "jazz"),
+            fact(FactKind::Project, "a Telegram bot"),
+        ];
+        assert_eq!(
+            format_profile(&facts),
+            "Name: Alice; Preferences: green tea, jazz; Projects: a Telegram bot"
+        );
+    }
 }
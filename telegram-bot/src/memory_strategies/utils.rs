@@ -1,16 +1,25 @@
 //! Shared utilities for context strategies.
 
-use crate::memory_core::{MemoryEntry, MemoryRole};
+use chrono::{Duration, Utc};
+use crate::memory_core::{MemoryEntry, SearchFilter};
 
-pub(crate) fn format_message(entry: &MemoryEntry) -> String {
-    let role = match entry.metadata.role {
-        MemoryRole::User => "User",
-        MemoryRole::Assistant => "Assistant",
-        MemoryRole::System => "System",
-    };
-    format!("{}: {}", role, entry.content)
+/// Search filter for one request: `filter` scoped to the conversation and the `max_age` window.
+pub(crate) fn request_filter(
+    filter: &SearchFilter,
+    max_age: Option<Duration>,
+    conversation_id: &Option<String>,
+) -> SearchFilter {
+    let mut filter = filter.clone();
+    filter.metadata.conversation_id = conversation_id.clone();
+    if let Some(max_age) = max_age {
+        let since = Utc::now() - max_age;
+        filter.metadata.since = Some(filter.metadata.since.map_or(since, |s| s.max(since)));
+    }
+    filter
 }
 
+/// Keyword heuristic used when no profile facts are available: the rest of each message from
+/// "i like" / "i prefer" on.
 pub(crate) fn extract_preferences(entries: &[MemoryEntry]) -> Vec<String> {
     let mut preferences = Vec::new();
     for entry in entries {
@@ -0,0 +1,133 @@
+//! Outbox configuration: trait, env-based implementation, and the resolved [`RetryPolicy`].
+
+use anyhow::Result;
+use chrono::Duration;
+use std::env;
+
+/// Outbox configuration interface: how failed writes are retried.
+pub trait OutboxConfig: Send + Sync {
+    /// Attempts (the failed original write included) before an item becomes a dead letter.
+    fn max_attempts(&self) -> u32;
+    /// Delay before the first retry, in seconds; doubles with every further attempt.
+    fn backoff_base_secs(&self) -> u64;
+    /// Upper bound of the retry delay, in seconds.
+    fn backoff_max_secs(&self) -> u64;
+    /// Interval between two outbox polls, in seconds.
+    fn poll_interval_secs(&self) -> u64;
+}
+
+/// Outbox config loaded from environment variables.
+#[derive(Debug, Clone)]
+pub struct EnvOutboxConfig {
+    pub outbox_max_attempts: u32,
+    pub outbox_backoff_base_secs: u64,
+    pub outbox_backoff_max_secs: u64,
+    pub outbox_poll_interval_secs: u64,
+}
+
+impl OutboxConfig for EnvOutboxConfig {
+    fn max_attempts(&self) -> u32 {
+        self.outbox_max_attempts
+    }
+    fn backoff_base_secs(&self) -> u64 {
+        self.outbox_backoff_base_secs
+    }
+    fn backoff_max_secs(&self) -> u64 {
+        self.outbox_backoff_max_secs
+    }
+    fn poll_interval_secs(&self) -> u64 {
+        self.outbox_poll_interval_secs
+    }
+}
+
+impl EnvOutboxConfig {
+    /// Load from environment variables.
+    ///
+    /// - `OUTBOX_MAX_ATTEMPTS`: attempts before an item is a dead letter (default 10)
+    /// - `OUTBOX_BACKOFF_BASE_SECS`: first retry delay (default 30)
+    /// - `OUTBOX_BACKOFF_MAX_SECS`: retry delay cap (default 3600)
+    /// - `OUTBOX_POLL_INTERVAL_SECS`: how often the worker looks for due items (default 30)
+    pub fn from_env() -> Result<Self> {
+        let positive = |name: &str, default: u64| {
+            env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        Ok(Self {
+            outbox_max_attempts: positive("OUTBOX_MAX_ATTEMPTS", 10).min(u32::MAX as u64) as u32,
+            outbox_backoff_base_secs: positive("OUTBOX_BACKOFF_BASE_SECS", 30),
+            outbox_backoff_max_secs: positive("OUTBOX_BACKOFF_MAX_SECS", 3600),
+            outbox_poll_interval_secs: positive("OUTBOX_POLL_INTERVAL_SECS", 30),
+        })
+    }
+}
+
+/// Exponential backoff with a cap, and the attempt limit after which an item is a dead letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base: Duration,
+    max: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(10, Duration::seconds(30), Duration::hours(1))
+    }
+}
+
+impl RetryPolicy {
+    /// Policy giving up after `max_attempts` (at least 1), waiting `base * 2^(n-1)` after the n-th
+    /// failed attempt, capped at `max`.
+    pub fn new(max_attempts: u32, base: Duration, max: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base,
+            max: max.max(base),
+        }
+    }
+
+    /// Resolves the policy from config.
+    pub fn from_config(cfg: &dyn OutboxConfig) -> Self {
+        Self::new(
+            cfg.max_attempts(),
+            Duration::seconds(cfg.backoff_base_secs() as i64),
+            Duration::seconds(cfg.backoff_max_secs() as i64),
+        )
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// True when an item that has failed `attempts` times should not be retried again.
+    pub fn exhausted(&self, attempts: u32) -> bool {
+        attempts >= self.max_attempts
+    }
+
+    /// Delay before the next attempt of an item that has failed `attempts` times.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let doublings = attempts.saturating_sub(1).min(30);
+        let secs = self.base.num_seconds().saturating_mul(1 << doublings);
+        Duration::seconds(secs.min(self.max.num_seconds()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = RetryPolicy::new(5, Duration::seconds(30), Duration::minutes(3));
+        assert_eq!(policy.backoff(1), Duration::seconds(30));
+        assert_eq!(policy.backoff(2), Duration::seconds(60));
+        assert_eq!(policy.backoff(3), Duration::seconds(120));
+        assert_eq!(policy.backoff(4), Duration::minutes(3));
+        assert_eq!(policy.backoff(40), Duration::minutes(3));
+        assert!(!policy.exhausted(4));
+        assert!(policy.exhausted(5));
+    }
+}
@@ -0,0 +1,19 @@
+//! Durable outbox for failed memory and message writes.
+//!
+//! Handlers queue a write that failed (or a memory entry whose embedding failed) here instead of
+//! dropping it; the worker retries it with backoff until it succeeds or becomes a dead letter,
+//! which the `outbox` CLI command lists and purges.
+//!
+//! ## Submodules
+//!
+//! - [`config`] – OutboxConfig, EnvOutboxConfig, RetryPolicy (attempt limit + exponential backoff)
+//! - [`repo`] – OutboxRepository: the `write_outbox` table (SQLite), OutboxPayload, OutboxItem
+//! - [`worker`] – OutboxWorker and the background job that runs it on an interval
+
+mod config;
+mod repo;
+mod worker;
+
+pub use config::{EnvOutboxConfig, OutboxConfig, RetryPolicy};
+pub use repo::{OutboxCounts, OutboxItem, OutboxPayload, OutboxRepository, OutboxStatus};
+pub use worker::{spawn_outbox_worker, OutboxReport, OutboxWorker};
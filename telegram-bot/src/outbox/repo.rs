@@ -0,0 +1,418 @@
+//! Outbox repository: the `write_outbox` table of failed writes awaiting retry.
+//!
+//! Uses SqlitePoolManager (same database file as messages). Payloads are stored as JSON and sealed
+//! with the [`FieldCipher`] when encryption at rest is enabled, like message content.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::encryption::FieldCipher;
+use crate::memory_core::MemoryEntry;
+use crate::storage::{MessageRecord, SqlitePoolManager};
+
+/// A write that failed and is retried by the [`OutboxWorker`](super::OutboxWorker).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutboxPayload {
+    /// Memory entry for the primary memory store; with `embed`, it is embedded before the write.
+    /// The write is an upsert, so an entry already stored without its embedding gets it added.
+    MemoryEntry { entry: MemoryEntry, embed: bool },
+    /// Message record for the messages table.
+    Message { record: MessageRecord },
+}
+
+impl OutboxPayload {
+    /// Value of the `kind` column.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OutboxPayload::MemoryEntry { .. } => "memory_entry",
+            OutboxPayload::Message { .. } => "message",
+        }
+    }
+
+    /// True when the payload belongs to the given Telegram user.
+    fn is_for_user(&self, user_id: i64) -> bool {
+        match self {
+            OutboxPayload::MemoryEntry { entry, .. } => {
+                entry.metadata.user_id.as_deref() == Some(user_id.to_string().as_str())
+            }
+            OutboxPayload::Message { record } => record.user_id == user_id,
+        }
+    }
+
+    /// True when the payload belongs to the given chat.
+    fn is_for_chat(&self, chat_id: i64) -> bool {
+        match self {
+            OutboxPayload::MemoryEntry { entry, .. } => {
+                entry.metadata.conversation_id.as_deref() == Some(chat_id.to_string().as_str())
+            }
+            OutboxPayload::Message { record } => record.chat_id == chat_id,
+        }
+    }
+}
+
+/// Whether an item is still retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxStatus {
+    Pending,
+    /// Gave up after the retry policy's last attempt; kept for inspection until purged.
+    Dead,
+}
+
+impl OutboxStatus {
+    /// Value of the `status` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Dead => "dead",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "dead" => OutboxStatus::Dead,
+            _ => OutboxStatus::Pending,
+        }
+    }
+}
+
+/// One outbox row with its decoded payload.
+#[derive(Debug, Clone)]
+pub struct OutboxItem {
+    pub id: String,
+    pub payload: OutboxPayload,
+    /// Failed attempts so far, the original write included.
+    pub attempts: u32,
+    /// Error of the latest failed attempt.
+    pub last_error: String,
+    pub status: OutboxStatus,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Number of pending items and dead letters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboxCounts {
+    pub pending: u64,
+    pub dead: u64,
+}
+
+#[derive(sqlx::FromRow)]
+struct OutboxRow {
+    id: String,
+    payload: String,
+    attempts: i64,
+    last_error: String,
+    status: String,
+    next_attempt_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+/// SQLite-backed outbox (enqueue, due, reschedule, complete, dead letters).
+#[derive(Clone)]
+pub struct OutboxRepository {
+    /// Shared SQLite pool used for all queries.
+    pool_manager: SqlitePoolManager,
+    /// When set, payloads are encrypted on enqueue and decrypted on read.
+    cipher: Option<Arc<FieldCipher>>,
+}
+
+impl OutboxRepository {
+    /// Creates a repository and initializes the write_outbox table and index.
+    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool_manager = SqlitePoolManager::new(database_url).await?;
+        let repo = Self {
+            pool_manager,
+            cipher: None,
+        };
+        repo.init().await?;
+        Ok(repo)
+    }
+
+    async fn init(&self) -> Result<(), sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS write_outbox (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                last_error TEXT NOT NULL,
+                status TEXT NOT NULL,
+                next_attempt_at TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_write_outbox_due ON write_outbox(status, next_attempt_at)",
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Encrypts payloads with the cipher's active key; rows written without encryption stay readable.
+    pub fn with_cipher(mut self, cipher: Arc<FieldCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Records a write that failed with `error`, due for retry immediately. Returns the item id.
+    pub async fn enqueue(
+        &self,
+        payload: &OutboxPayload,
+        error: &str,
+    ) -> Result<String, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let sealed = self.seal(payload)?;
+
+        warn!(id = %id, kind = payload.kind(), error = %error, "Write failed; queued in outbox for retry");
+
+        sqlx::query(
+            r#"
+            INSERT INTO write_outbox (id, kind, payload, attempts, last_error, status, next_attempt_at, created_at)
+            VALUES (?, ?, ?, 1, ?, 'pending', ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(payload.kind())
+        .bind(sealed)
+        .bind(error)
+        .bind(now)
+        .bind(now)
+        .execute(self.pool_manager.pool())
+        .await?;
+        Ok(id)
+    }
+
+    /// Pending items due at `now`, oldest first. Items whose payload cannot be decoded (e.g. sealed
+    /// with a key no longer configured) are turned into dead letters and skipped.
+    pub async fn due(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, sqlx::Error> {
+        let rows: Vec<OutboxRow> = sqlx::query_as(
+            r#"
+            SELECT id, payload, attempts, last_error, status, next_attempt_at, created_at
+            FROM write_outbox
+            WHERE status = 'pending' AND next_attempt_at <= ?
+            ORDER BY created_at, id
+            LIMIT ?
+            "#,
+        )
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(self.pool_manager.pool())
+        .await?;
+
+        let mut items = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id = row.id.clone();
+            match self.open(row) {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    warn!(id = %id, error = %e, "Outbox payload unreadable; moving to dead letters");
+                    self.mark_dead(&id, &format!("unreadable payload: {}", e))
+                        .await?;
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    /// Removes a delivered item.
+    pub async fn complete(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM write_outbox WHERE id = ?")
+            .bind(id)
+            .execute(self.pool_manager.pool())
+            .await?;
+        Ok(())
+    }
+
+    /// Records another failed attempt and schedules the next one at `next_attempt_at`.
+    pub async fn reschedule(
+        &self,
+        id: &str,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE write_outbox SET attempts = attempts + 1, last_error = ?, next_attempt_at = ? WHERE id = ?",
+        )
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(id)
+        .execute(self.pool_manager.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Records the last failed attempt and stops retrying the item.
+    pub async fn mark_dead(&self, id: &str, error: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE write_outbox SET attempts = attempts + 1, last_error = ?, status = 'dead' WHERE id = ?",
+        )
+        .bind(error)
+        .bind(id)
+        .execute(self.pool_manager.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Items with `status`, oldest first. Unreadable payloads are reported as errors.
+    pub async fn list(
+        &self,
+        status: OutboxStatus,
+        limit: usize,
+    ) -> Result<Vec<OutboxItem>, sqlx::Error> {
+        let rows: Vec<OutboxRow> = sqlx::query_as(
+            r#"
+            SELECT id, payload, attempts, last_error, status, next_attempt_at, created_at
+            FROM write_outbox
+            WHERE status = ?
+            ORDER BY created_at, id
+            LIMIT ?
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(limit as i64)
+        .fetch_all(self.pool_manager.pool())
+        .await?;
+        rows.into_iter().map(|row| self.open(row)).collect()
+    }
+
+    /// Number of pending items and dead letters.
+    pub async fn counts(&self) -> Result<OutboxCounts, sqlx::Error> {
+        let (pending, dead): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(status = 'pending'), 0), COALESCE(SUM(status = 'dead'), 0) FROM write_outbox",
+        )
+        .fetch_one(self.pool_manager.pool())
+        .await?;
+        Ok(OutboxCounts {
+            pending: pending as u64,
+            dead: dead as u64,
+        })
+    }
+
+    /// Deletes all dead letters. Returns how many were deleted.
+    pub async fn purge_dead(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM write_outbox WHERE status = 'dead'")
+            .execute(self.pool_manager.pool())
+            .await?;
+        info!(
+            purged = result.rows_affected(),
+            "Purged outbox dead letters"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes the items (pending or dead) of the given user. Payloads are sealed JSON, so every row
+    /// is decoded; rows whose payload cannot be decoded are kept and logged. Returns how many were
+    /// deleted.
+    pub async fn delete_by_user(&self, user_id: i64) -> Result<u64, sqlx::Error> {
+        self.delete_matching(|payload| payload.is_for_user(user_id)).await
+    }
+
+    /// Deletes the items (pending or dead) of the given chat; see [`delete_by_user`](Self::delete_by_user).
+    pub async fn delete_by_chat(&self, chat_id: i64) -> Result<u64, sqlx::Error> {
+        self.delete_matching(|payload| payload.is_for_chat(chat_id)).await
+    }
+
+    async fn delete_matching(
+        &self,
+        matches: impl Fn(&OutboxPayload) -> bool,
+    ) -> Result<u64, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+        let rows: Vec<OutboxRow> = sqlx::query_as(
+            "SELECT id, payload, attempts, last_error, status, next_attempt_at, created_at FROM write_outbox",
+        )
+        .fetch_all(pool)
+        .await?;
+        let mut deleted = 0;
+        for row in rows {
+            let id = row.id.clone();
+            match self.open(row) {
+                Ok(item) if matches(&item.payload) => {
+                    self.complete(&id).await?;
+                    deleted += 1;
+                }
+                Ok(_) => {}
+                Err(e) => warn!(id = %id, error = %e, "Outbox payload unreadable; not checked for deletion"),
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Re-encrypts every payload that is plaintext or sealed with a non-active key. Requires a cipher
+    /// holding the old keys. Returns how many rows were rewritten.
+    pub async fn reencrypt_payloads(&self) -> Result<u64, sqlx::Error> {
+        let Some(cipher) = self.cipher.as_ref() else {
+            return Err(sqlx::Error::Configuration(
+                "reencrypt_payloads requires an encryption key".into(),
+            ));
+        };
+        let pool = self.pool_manager.pool();
+        // The outbox only holds failed writes, so it is read in one go.
+        let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, payload FROM write_outbox")
+            .fetch_all(pool)
+            .await?;
+        let mut tx = pool.begin().await?;
+        let mut rewritten = 0u64;
+        for (id, payload) in rows.iter().filter(|(_, p)| !cipher.is_current(p)) {
+            let sealed = cipher
+                .reencrypt(payload)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+            sqlx::query("UPDATE write_outbox SET payload = ? WHERE id = ?")
+                .bind(sealed)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            rewritten += 1;
+        }
+        tx.commit().await?;
+        info!(rewritten = rewritten, active_key_id = %cipher.active_key_id(), "Outbox payloads re-encrypted");
+        Ok(rewritten)
+    }
+
+    /// Serializes `payload` to JSON, encrypted when a cipher is set.
+    fn seal(&self, payload: &OutboxPayload) -> Result<String, sqlx::Error> {
+        let json = serde_json::to_string(payload).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        match self.cipher.as_ref() {
+            Some(cipher) => cipher
+                .encrypt(&json)
+                .map_err(|e| sqlx::Error::Encode(Box::new(e))),
+            None => Ok(json),
+        }
+    }
+
+    /// Decrypts (when a cipher is set) and decodes the payload of a fetched row.
+    fn open(&self, row: OutboxRow) -> Result<OutboxItem, sqlx::Error> {
+        let json = match self.cipher.as_ref() {
+            Some(cipher) => cipher
+                .decrypt(&row.payload)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            None => row.payload,
+        };
+        let payload = serde_json::from_str(&json).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        Ok(OutboxItem {
+            id: row.id,
+            payload,
+            attempts: row.attempts.max(0) as u32,
+            last_error: row.last_error,
+            status: OutboxStatus::parse(&row.status),
+            next_attempt_at: row.next_attempt_at,
+            created_at: row.created_at,
+        })
+    }
+}
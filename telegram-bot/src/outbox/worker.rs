@@ -0,0 +1,152 @@
+//! [`OutboxWorker`]: retries outbox items with backoff, and the background job that runs it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use super::config::RetryPolicy;
+use super::repo::{OutboxItem, OutboxPayload, OutboxRepository};
+use crate::embedding::{with_usage_scope, EmbeddingService, UsageScope};
+use crate::memory_core::MemoryStore;
+use crate::storage::MessageRepository;
+
+/// Items fetched per poll.
+const OUTBOX_BATCH_SIZE: usize = 100;
+
+/// Result of one [`OutboxWorker::process_due`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboxReport {
+    /// Items written and removed from the outbox.
+    pub delivered: u64,
+    /// Items that failed again and were rescheduled.
+    pub rescheduled: u64,
+    /// Items that failed for the last time and became dead letters.
+    pub dead: u64,
+}
+
+/// Replays outbox items against the primary memory store and the message repository.
+pub struct OutboxWorker {
+    outbox: OutboxRepository,
+    memory_store: Arc<dyn MemoryStore>,
+    repo: MessageRepository,
+    embedding_service: Option<Arc<dyn EmbeddingService>>,
+    policy: RetryPolicy,
+}
+
+impl OutboxWorker {
+    pub fn new(
+        outbox: OutboxRepository,
+        memory_store: Arc<dyn MemoryStore>,
+        repo: MessageRepository,
+        policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            outbox,
+            memory_store,
+            repo,
+            embedding_service: None,
+            policy,
+        }
+    }
+
+    /// Embeds memory entries queued without their embedding. Without it such items keep failing.
+    pub fn with_embedding_service(mut self, embedding_service: Arc<dyn EmbeddingService>) -> Self {
+        self.embedding_service = Some(embedding_service);
+        self
+    }
+
+    /// Retries every item due at `now`: delivered items are removed, failed ones are rescheduled
+    /// with backoff or become dead letters once the policy's attempts are used up.
+    pub async fn process_due(&self, now: DateTime<Utc>) -> Result<OutboxReport> {
+        let mut report = OutboxReport::default();
+        let items = self
+            .outbox
+            .due(now, OUTBOX_BATCH_SIZE)
+            .await
+            .context("outbox: listing due items failed")?;
+        for item in items {
+            match self.deliver(&item, now).await {
+                Ok(()) => {
+                    self.outbox.complete(&item.id).await?;
+                    report.delivered += 1;
+                }
+                Err(e) => {
+                    let error = format!("{:#}", e);
+                    let attempts = item.attempts + 1;
+                    if self.policy.exhausted(attempts) {
+                        warn!(id = %item.id, attempts, error = %error, "Outbox item failed for the last time; moved to dead letters");
+                        self.outbox.mark_dead(&item.id, &error).await?;
+                        report.dead += 1;
+                    } else {
+                        let next = now + self.policy.backoff(attempts);
+                        self.outbox.reschedule(&item.id, &error, next).await?;
+                        report.rescheduled += 1;
+                    }
+                }
+            }
+        }
+        if report != OutboxReport::default() {
+            info!(report = ?report, "Outbox retry finished");
+        }
+        Ok(report)
+    }
+
+    async fn deliver(&self, item: &OutboxItem, now: DateTime<Utc>) -> Result<()> {
+        match &item.payload {
+            OutboxPayload::MemoryEntry { entry, embed } => {
+                if entry.metadata.is_expired(now) {
+                    // Nothing to repair: the entry would be swept anyway.
+                    return Ok(());
+                }
+                let mut entry = entry.clone();
+                if *embed && entry.embedding.is_none() {
+                    let svc = self
+                        .embedding_service
+                        .as_ref()
+                        .context("no embedding service to embed the entry")?;
+                    let scope = UsageScope::new(
+                        entry.metadata.conversation_id.as_deref().and_then(|id| id.parse().ok()),
+                        entry.metadata.user_id.as_deref().and_then(|id| id.parse().ok()),
+                    );
+                    let embedding = with_usage_scope(scope, svc.embed(&entry.content)).await?;
+                    entry.embedding = Some(embedding);
+                }
+                match self.memory_store.get(entry.id).await? {
+                    Some(_) => self.memory_store.update(entry).await,
+                    None => self.memory_store.add(entry).await,
+                }
+            }
+            OutboxPayload::Message { record } => {
+                if self.repo.get_message_by_id(&record.id).await?.is_some() {
+                    return Ok(());
+                }
+                self.repo.save(record).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Spawns a task that runs [`OutboxWorker::process_due`] every `interval` (first run immediately).
+/// Errors are logged and the next run still happens. Abort the returned handle to stop the job.
+pub fn spawn_outbox_worker(worker: OutboxWorker, interval: Duration) -> JoinHandle<()> {
+    info!(
+        policy = ?worker.policy,
+        interval_secs = interval.as_secs(),
+        "Starting outbox worker"
+    );
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = worker.process_due(Utc::now()).await {
+                error!(error = %e, "Outbox retry failed");
+            }
+        }
+    })
+}
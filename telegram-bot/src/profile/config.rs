@@ -0,0 +1,68 @@
+//! Profile configuration: trait and env-based implementation.
+
+use anyhow::Result;
+use std::env;
+
+/// Profile extraction configuration interface.
+pub trait ProfileConfig: Send + Sync {
+    /// Whether facts are extracted from user messages. Extraction also needs an extractor, which
+    /// the LLM application installs.
+    fn extraction_enabled(&self) -> bool;
+    /// User messages collected per user before one extraction call (1 = after every message).
+    fn batch_size(&self) -> usize;
+    /// Extracted facts below this confidence are discarded.
+    fn min_confidence(&self) -> f32;
+}
+
+/// Profile config loaded from environment variables.
+#[derive(Debug, Clone)]
+pub struct EnvProfileConfig {
+    pub profile_extraction: bool,
+    pub profile_batch_size: usize,
+    pub profile_min_confidence: f32,
+}
+
+impl ProfileConfig for EnvProfileConfig {
+    fn extraction_enabled(&self) -> bool {
+        self.profile_extraction
+    }
+    fn batch_size(&self) -> usize {
+        self.profile_batch_size
+    }
+    fn min_confidence(&self) -> f32 {
+        self.profile_min_confidence
+    }
+}
+
+impl EnvProfileConfig {
+    /// Load from environment variables.
+    ///
+    /// - `PROFILE_EXTRACTION`: extract user facts with the LLM, one extra call per batch (default false)
+    /// - `PROFILE_BATCH_SIZE`: user messages per extraction call (default 3)
+    /// - `PROFILE_MIN_CONFIDENCE`: lowest confidence kept, 0–1 (default 0.6)
+    pub fn from_env() -> Result<Self> {
+        let profile_extraction = env::var("PROFILE_EXTRACTION")
+            .ok()
+            .and_then(|s| match s.to_lowercase().as_str() {
+                "1" | "true" | "yes" => Some(true),
+                "0" | "false" | "no" => Some(false),
+                _ => None,
+            })
+            .unwrap_or(false);
+        let profile_batch_size = env::var("PROFILE_BATCH_SIZE")
+            .ok()
+            .and_then(|s| s.trim().parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(3);
+        let profile_min_confidence = env::var("PROFILE_MIN_CONFIDENCE")
+            .ok()
+            .and_then(|s| s.trim().parse::<f32>().ok())
+            .map(|v| v.clamp(0.0, 1.0))
+            .unwrap_or(0.6);
+        Ok(Self {
+            profile_extraction,
+            profile_batch_size,
+            profile_min_confidence,
+        })
+    }
+}
@@ -0,0 +1,181 @@
+//! Fact extraction: the pluggable [`FactExtractor`] and the [`ProfileUpdater`] that batches user
+//! messages, extracts facts from them and records the facts in the [`ProfileRepository`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tracing::{debug, info, warn};
+
+use super::config::ProfileConfig;
+use super::repo::{FactKind, ProfileRepository};
+use crate::memory_core::{MemoryEntry, MemoryRole};
+
+/// Default user messages per extraction call.
+pub const DEFAULT_PROFILE_BATCH_SIZE: usize = 3;
+
+/// A fact about the user returned by a [`FactExtractor`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedFact {
+    pub kind: FactKind,
+    /// Short statement of the fact, e.g. "likes green tea" or "Berlin".
+    pub value: String,
+    /// How sure the extractor is, in `[0, 1]`.
+    pub confidence: f32,
+}
+
+/// Extracts facts about a user from their messages.
+#[async_trait]
+pub trait FactExtractor: Send + Sync {
+    /// Facts about the author of `messages` (one user's messages, oldest first).
+    async fn extract(&self, messages: &[MemoryEntry]) -> Result<Vec<ExtractedFact>>;
+}
+
+/// Collects each user's messages and, every `batch_size` messages, extracts facts from them and
+/// records those at or above `min_confidence` in the profile repository.
+///
+/// Without an extractor (see [`set_extractor`](Self::set_extractor)) messages are ignored. Pending
+/// messages are held in memory only, so a restart drops a partial batch.
+pub struct ProfileUpdater {
+    repo: ProfileRepository,
+    batch_size: usize,
+    min_confidence: f32,
+    extractor: RwLock<Option<Arc<dyn FactExtractor>>>,
+    pending: Mutex<HashMap<String, Vec<MemoryEntry>>>,
+}
+
+impl ProfileUpdater {
+    /// Updater without an extractor, with the default batch size and no minimum confidence.
+    pub fn new(repo: ProfileRepository) -> Self {
+        Self {
+            repo,
+            batch_size: DEFAULT_PROFILE_BATCH_SIZE,
+            min_confidence: 0.0,
+            extractor: RwLock::new(None),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Updater with the batch size and minimum confidence of `config`.
+    pub fn from_config(repo: ProfileRepository, config: &dyn ProfileConfig) -> Self {
+        Self::new(repo)
+            .with_batch_size(config.batch_size())
+            .with_min_confidence(config.min_confidence())
+    }
+
+    /// Extracts after every `batch_size` messages of a user (at least 1).
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Facts extracted with a lower confidence are not recorded.
+    pub fn with_min_confidence(mut self, min_confidence: f32) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    pub fn with_extractor(self, extractor: Arc<dyn FactExtractor>) -> Self {
+        self.set_extractor(extractor);
+        self
+    }
+
+    pub fn set_extractor(&self, extractor: Arc<dyn FactExtractor>) {
+        *self.extractor.write().unwrap_or_else(|e| e.into_inner()) = Some(extractor);
+    }
+
+    pub fn has_extractor(&self) -> bool {
+        self.extractor().is_some()
+    }
+
+    fn extractor(&self) -> Option<Arc<dyn FactExtractor>> {
+        self.extractor
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn repo(&self) -> &ProfileRepository {
+        &self.repo
+    }
+
+    /// Adds a user message to its author's batch and, when the batch is full, extracts and records
+    /// its facts. Returns the number of facts recorded. Messages without a user id, empty ones and
+    /// non-user roles are ignored.
+    pub async fn observe(&self, entry: MemoryEntry) -> Result<usize> {
+        if entry.metadata.role != MemoryRole::User || entry.content.trim().is_empty() {
+            return Ok(0);
+        }
+        let Some(user_id) = entry.metadata.user_id.clone() else {
+            return Ok(0);
+        };
+        if !self.has_extractor() {
+            return Ok(0);
+        }
+        let batch = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            let messages = pending.entry(user_id.clone()).or_default();
+            messages.push(entry);
+            if messages.len() < self.batch_size {
+                return Ok(0);
+            }
+            pending.remove(&user_id).unwrap_or_default()
+        };
+        self.extract_for_user(&user_id, &batch).await
+    }
+
+    /// Extracts and records the facts of every pending batch, full or not. Returns the number of
+    /// facts recorded; a user whose extraction fails is skipped (see the log).
+    pub async fn flush(&self) -> usize {
+        let batches: Vec<(String, Vec<MemoryEntry>)> = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain()
+            .collect();
+        let mut recorded = 0;
+        for (user_id, messages) in batches {
+            match self.extract_for_user(&user_id, &messages).await {
+                Ok(n) => recorded += n,
+                Err(e) => warn!(error = %e, user_id = %user_id, "profile: extraction failed"),
+            }
+        }
+        recorded
+    }
+
+    /// Extracts facts from `messages` of one user and records those at or above the minimum
+    /// confidence. Returns the number of facts recorded.
+    pub async fn extract_for_user(&self, user_id: &str, messages: &[MemoryEntry]) -> Result<usize> {
+        let Some(extractor) = self.extractor() else {
+            return Ok(0);
+        };
+        let Some(seen_at) = messages.iter().map(|m| m.metadata.timestamp).max() else {
+            return Ok(0);
+        };
+        let facts = extractor.extract(messages).await?;
+        let mut recorded = 0;
+        for fact in facts {
+            if fact.value.trim().is_empty() || fact.confidence < self.min_confidence {
+                debug!(
+                    user_id = %user_id,
+                    kind = fact.kind.as_str(),
+                    confidence = fact.confidence,
+                    "profile: fact discarded"
+                );
+                continue;
+            }
+            self.repo
+                .record(user_id, fact.kind, &fact.value, fact.confidence, seen_at)
+                .await?;
+            recorded += 1;
+        }
+        info!(
+            user_id = %user_id,
+            messages = messages.len(),
+            recorded = recorded,
+            "profile: facts extracted"
+        );
+        Ok(recorded)
+    }
+}
@@ -0,0 +1,22 @@
+//! User profiles: typed facts about each user (name, location, preferences, projects) extracted
+//! from their messages by an LLM.
+//!
+//! The memory handler passes every user message to the [`ProfileUpdater`], which extracts facts
+//! from each user's messages in batches and records them, deduplicated, in the
+//! [`ProfileRepository`]. The extractor is pluggable ([`FactExtractor`]); the LLM application
+//! installs one backed by its LLM client. [`UserPreferencesStrategy`](crate::memory_strategies::UserPreferencesStrategy)
+//! puts the recorded facts into the context.
+//!
+//! ## Submodules
+//!
+//! - [`config`] – ProfileConfig, EnvProfileConfig (extraction switch, batch size, minimum confidence)
+//! - [`extractor`] – FactExtractor, ExtractedFact, ProfileUpdater (batching and recording)
+//! - [`repo`] – ProfileRepository: the `user_facts` table (SQLite), UserFact, FactKind
+
+mod config;
+mod extractor;
+mod repo;
+
+pub use config::{EnvProfileConfig, ProfileConfig};
+pub use extractor::{ExtractedFact, FactExtractor, ProfileUpdater, DEFAULT_PROFILE_BATCH_SIZE};
+pub use repo::{FactKind, ProfileRepository, UserFact};
@@ -0,0 +1,436 @@
+//! Profile repository: the `user_facts` table of facts extracted about each user.
+//!
+//! Uses SqlitePoolManager (same database file as messages). Fact values are sealed with the
+//! [`FieldCipher`] when encryption at rest is enabled, like message content, so duplicates are
+//! detected in process rather than with a unique index on the value.
+
+use chrono::{DateTime, Utc};
+use sqlx::SqliteConnection;
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::encryption::FieldCipher;
+use crate::storage::SqlitePoolManager;
+
+/// What a fact says about the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FactKind {
+    /// The user's name or how they want to be addressed.
+    Name,
+    /// Where the user lives or is based.
+    Location,
+    /// Likes, dislikes and habits.
+    Preference,
+    /// Work, study or hobby projects the user is busy with.
+    Project,
+}
+
+impl FactKind {
+    pub const ALL: [FactKind; 4] = [
+        FactKind::Name,
+        FactKind::Location,
+        FactKind::Preference,
+        FactKind::Project,
+    ];
+
+    /// Value of the `kind` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FactKind::Name => "name",
+            FactKind::Location => "location",
+            FactKind::Preference => "preference",
+            FactKind::Project => "project",
+        }
+    }
+
+    /// Kind from its name; also accepts plurals (`preferences`, `projects`).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "name" | "names" => Some(FactKind::Name),
+            "location" | "locations" => Some(FactKind::Location),
+            "preference" | "preferences" => Some(FactKind::Preference),
+            "project" | "projects" => Some(FactKind::Project),
+            _ => None,
+        }
+    }
+
+    /// A user has one name and one location: a new value replaces the old one instead of being
+    /// added next to it.
+    pub fn is_single_valued(self) -> bool {
+        matches!(self, FactKind::Name | FactKind::Location)
+    }
+}
+
+/// One stored fact about a user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserFact {
+    pub id: String,
+    /// Memory user id (Telegram user id as a string).
+    pub user_id: String,
+    pub kind: FactKind,
+    pub value: String,
+    /// Highest confidence the extractor gave the fact, in `[0, 1]`.
+    pub confidence: f32,
+    /// Number of extractions that found the fact.
+    pub mentions: u32,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct UserFactRow {
+    id: String,
+    user_id: String,
+    kind: String,
+    value: String,
+    confidence: f64,
+    mentions: i64,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+/// SQLite-backed user profiles (record, facts_for_user, delete_user).
+#[derive(Clone)]
+pub struct ProfileRepository {
+    /// Shared SQLite pool used for all queries.
+    pool_manager: SqlitePoolManager,
+    /// When set, values are encrypted on write and decrypted on read.
+    cipher: Option<Arc<FieldCipher>>,
+}
+
+impl ProfileRepository {
+    /// Creates a repository and initializes the user_facts table and index.
+    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool_manager = SqlitePoolManager::new(database_url).await?;
+        let repo = Self {
+            pool_manager,
+            cipher: None,
+        };
+        repo.init().await?;
+        Ok(repo)
+    }
+
+    async fn init(&self) -> Result<(), sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS user_facts (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                value TEXT NOT NULL,
+                confidence REAL NOT NULL,
+                mentions INTEGER NOT NULL,
+                first_seen TEXT NOT NULL,
+                last_seen TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_user_facts_user_id ON user_facts(user_id)")
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Encrypts values with the cipher's active key; rows written without encryption stay readable.
+    pub fn with_cipher(mut self, cipher: Arc<FieldCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Records that `value` was extracted about the user at `seen_at` and returns the stored fact.
+    ///
+    /// A fact of the same kind with the same value (ignoring case, spacing and trailing
+    /// punctuation) is updated instead: its mention count goes up and it keeps the higher
+    /// confidence. For [single-valued](FactKind::is_single_valued) kinds, a different value
+    /// replaces the current one unless the current one has the higher confidence.
+    ///
+    /// The read and the write run in one `BEGIN IMMEDIATE` transaction, which takes the database
+    /// write lock up front: concurrent extractions for the same user are serialized instead of
+    /// both missing the fact and inserting it twice.
+    pub async fn record(
+        &self,
+        user_id: &str,
+        kind: FactKind,
+        value: &str,
+        confidence: f32,
+        seen_at: DateTime<Utc>,
+    ) -> Result<UserFact, sqlx::Error> {
+        let mut tx = self
+            .pool_manager
+            .pool()
+            .begin_with("BEGIN IMMEDIATE")
+            .await?;
+        let fact = self
+            .record_in(&mut tx, user_id, kind, value, confidence, seen_at)
+            .await?;
+        tx.commit().await?;
+        Ok(fact)
+    }
+
+    async fn record_in(
+        &self,
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        kind: FactKind,
+        value: &str,
+        confidence: f32,
+        seen_at: DateTime<Utc>,
+    ) -> Result<UserFact, sqlx::Error> {
+        let value = value.trim();
+        let confidence = confidence.clamp(0.0, 1.0);
+        let existing: Vec<UserFact> = self
+            .load_facts(&mut *conn, user_id)
+            .await?
+            .into_iter()
+            .filter(|f| f.kind == kind)
+            .collect();
+        let key = normalize(value);
+
+        if let Some(mut fact) = existing
+            .iter()
+            .find(|f| normalize(&f.value) == key)
+            .cloned()
+        {
+            if confidence > fact.confidence {
+                fact.value = value.to_string();
+                fact.confidence = confidence;
+            }
+            fact.mentions += 1;
+            fact.last_seen = fact.last_seen.max(seen_at);
+            self.update(&mut *conn, &fact).await?;
+            debug!(
+                user_id = %user_id,
+                kind = kind.as_str(),
+                id = %fact.id,
+                "Repeated user fact updated"
+            );
+            return Ok(fact);
+        }
+
+        if kind.is_single_valued() {
+            if let Some(current) = existing.first() {
+                if current.confidence > confidence {
+                    debug!(
+                        user_id = %user_id,
+                        kind = kind.as_str(),
+                        "Kept more confident user fact"
+                    );
+                    return Ok(current.clone());
+                }
+                let fact = UserFact {
+                    id: current.id.clone(),
+                    user_id: user_id.to_string(),
+                    kind,
+                    value: value.to_string(),
+                    confidence,
+                    mentions: 1,
+                    first_seen: seen_at,
+                    last_seen: seen_at,
+                };
+                self.update(&mut *conn, &fact).await?;
+                info!(user_id = %user_id, kind = kind.as_str(), "User fact replaced");
+                return Ok(fact);
+            }
+        }
+
+        let fact = UserFact {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            kind,
+            value: value.to_string(),
+            confidence,
+            mentions: 1,
+            first_seen: seen_at,
+            last_seen: seen_at,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO user_facts (id, user_id, kind, value, confidence, mentions, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&fact.id)
+        .bind(&fact.user_id)
+        .bind(kind.as_str())
+        .bind(self.seal(&fact.value)?)
+        .bind(fact.confidence as f64)
+        .bind(fact.mentions as i64)
+        .bind(fact.first_seen)
+        .bind(fact.last_seen)
+        .execute(&mut *conn)
+        .await?;
+        info!(
+            user_id = %user_id,
+            kind = kind.as_str(),
+            confidence = confidence,
+            "User fact recorded"
+        );
+        Ok(fact)
+    }
+
+    async fn update(&self, conn: &mut SqliteConnection, fact: &UserFact) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE user_facts
+            SET value = ?, confidence = ?, mentions = ?, first_seen = ?, last_seen = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(self.seal(&fact.value)?)
+        .bind(fact.confidence as f64)
+        .bind(fact.mentions as i64)
+        .bind(fact.first_seen)
+        .bind(fact.last_seen)
+        .bind(&fact.id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// All facts about the user, grouped by kind, most confident and most mentioned first. Rows of
+    /// an unknown kind are skipped.
+    pub async fn facts_for_user(&self, user_id: &str) -> Result<Vec<UserFact>, sqlx::Error> {
+        let mut conn = self.pool_manager.pool().acquire().await?;
+        self.load_facts(&mut conn, user_id).await
+    }
+
+    async fn load_facts(
+        &self,
+        conn: &mut SqliteConnection,
+        user_id: &str,
+    ) -> Result<Vec<UserFact>, sqlx::Error> {
+        let rows: Vec<UserFactRow> = sqlx::query_as(
+            r#"
+            SELECT id, user_id, kind, value, confidence, mentions, first_seen, last_seen
+            FROM user_facts
+            WHERE user_id = ?
+            ORDER BY confidence DESC, mentions DESC, last_seen DESC, id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(conn)
+        .await?;
+
+        let mut facts = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Some(kind) = FactKind::parse(&row.kind) else {
+                continue;
+            };
+            facts.push(UserFact {
+                id: row.id,
+                user_id: row.user_id,
+                kind,
+                value: self.open(&row.value)?,
+                confidence: row.confidence as f32,
+                mentions: row.mentions.max(0) as u32,
+                first_seen: row.first_seen,
+                last_seen: row.last_seen,
+            });
+        }
+        facts.sort_by_key(|f| f.kind);
+        Ok(facts)
+    }
+
+    /// Re-encrypts every value that is plaintext or sealed with a non-active key. Requires a cipher
+    /// holding the old keys. Returns how many rows were rewritten.
+    pub async fn reencrypt_values(&self, batch_size: u32) -> Result<u64, sqlx::Error> {
+        let Some(cipher) = self.cipher.as_ref() else {
+            return Err(sqlx::Error::Configuration(
+                "reencrypt_values requires an encryption key".into(),
+            ));
+        };
+        let pool = self.pool_manager.pool();
+        let batch_size = batch_size.max(1) as i64;
+        let mut rewritten = 0u64;
+        let mut after = String::new();
+        loop {
+            // Values carry their key id in the envelope only, so rows are walked by id.
+            let rows: Vec<(String, String)> = sqlx::query_as(
+                "SELECT id, value FROM user_facts WHERE id > ? ORDER BY id LIMIT ?",
+            )
+            .bind(&after)
+            .bind(batch_size)
+            .fetch_all(pool)
+            .await?;
+            let Some((last, _)) = rows.last() else {
+                break;
+            };
+            after = last.clone();
+            let mut tx = pool.begin().await?;
+            for (id, value) in rows.iter().filter(|(_, v)| !cipher.is_current(v)) {
+                let sealed = cipher
+                    .reencrypt(value)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                sqlx::query("UPDATE user_facts SET value = ? WHERE id = ?")
+                    .bind(sealed)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                rewritten += 1;
+            }
+            tx.commit().await?;
+        }
+        info!(
+            rewritten = rewritten,
+            active_key_id = %cipher.active_key_id(),
+            "User facts re-encrypted"
+        );
+        Ok(rewritten)
+    }
+
+    /// Deletes every fact about the user. Returns the number of rows deleted.
+    pub async fn delete_user(&self, user_id: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM user_facts WHERE user_id = ?")
+            .bind(user_id)
+            .execute(self.pool_manager.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    fn seal(&self, value: &str) -> Result<String, sqlx::Error> {
+        match self.cipher.as_ref() {
+            Some(cipher) => cipher
+                .encrypt(value)
+                .map_err(|e| sqlx::Error::Encode(Box::new(e))),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    fn open(&self, value: &str) -> Result<String, sqlx::Error> {
+        match self.cipher.as_ref() {
+            Some(cipher) => cipher
+                .decrypt(value)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e))),
+            None => Ok(value.to_string()),
+        }
+    }
+}
+
+/// Comparison key of a value: lowercase, single spaces, no trailing punctuation.
+fn normalize(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(|c: char| c.is_ascii_punctuation() || "。！？，".contains(c))
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_ignores_case_spacing_and_trailing_punctuation() {
+        assert_eq!(normalize("  Green   Tea. "), "green tea");
+        assert_eq!(normalize("喜欢绿茶。"), "喜欢绿茶");
+        assert_eq!(FactKind::parse("Projects"), Some(FactKind::Project));
+        assert_eq!(FactKind::parse("hobby"), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! Retention configuration: trait, env-based implementation, and the resolved [`RetentionPolicy`].
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::env;
+
+/// Retention configuration interface: how long messages and memories are kept.
+pub trait RetentionConfig: Send + Sync {
+    /// Global retention in days; `None` keeps data forever unless a chat override applies.
+    fn default_days(&self) -> Option<u32>;
+    /// Per-chat retention in days; `0` keeps that chat's data forever.
+    fn chat_overrides(&self) -> &HashMap<i64, u32>;
+    /// Interval between two retention sweeps, in seconds.
+    fn sweep_interval_secs(&self) -> u64;
+}
+
+/// Retention config loaded from environment variables.
+#[derive(Debug, Clone)]
+pub struct EnvRetentionConfig {
+    pub retention_days: Option<u32>,
+    pub retention_chat_days: HashMap<i64, u32>,
+    pub retention_sweep_interval_secs: u64,
+}
+
+impl RetentionConfig for EnvRetentionConfig {
+    fn default_days(&self) -> Option<u32> {
+        self.retention_days
+    }
+    fn chat_overrides(&self) -> &HashMap<i64, u32> {
+        &self.retention_chat_days
+    }
+    fn sweep_interval_secs(&self) -> u64 {
+        self.retention_sweep_interval_secs
+    }
+}
+
+impl EnvRetentionConfig {
+    /// Load from environment variables.
+    ///
+    /// - `RETENTION_DAYS`: global retention in days (unset or `0` = keep forever)
+    /// - `RETENTION_CHAT_DAYS`: per-chat overrides, e.g. `-1001234:30,42:0`
+    /// - `RETENTION_SWEEP_INTERVAL_SECS`: sweep interval (default 3600)
+    pub fn from_env() -> Result<Self> {
+        let retention_days = env::var("RETENTION_DAYS")
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+            .filter(|d| *d > 0);
+        let retention_chat_days = match env::var("RETENTION_CHAT_DAYS") {
+            Ok(s) => parse_chat_overrides(&s)?,
+            Err(_) => HashMap::new(),
+        };
+        let retention_sweep_interval_secs = env::var("RETENTION_SWEEP_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(3600);
+        Ok(Self {
+            retention_days,
+            retention_chat_days,
+            retention_sweep_interval_secs,
+        })
+    }
+}
+
+/// Parses `chat_id:days` pairs separated by commas. Empty items are ignored.
+pub(crate) fn parse_chat_overrides(s: &str) -> Result<HashMap<i64, u32>> {
+    let mut overrides = HashMap::new();
+    for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let (chat_id, days) = item
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("RETENTION_CHAT_DAYS: expected chat_id:days, got {:?}", item))?;
+        let chat_id: i64 = chat_id
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("RETENTION_CHAT_DAYS: invalid chat id {:?}: {}", chat_id, e))?;
+        let days: u32 = days
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("RETENTION_CHAT_DAYS: invalid days {:?}: {}", days, e))?;
+        overrides.insert(chat_id, days);
+    }
+    Ok(overrides)
+}
+
+/// Resolved retention rules: a global default plus per-chat overrides.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    default_days: Option<u32>,
+    chat_overrides: HashMap<i64, u32>,
+}
+
+impl RetentionPolicy {
+    /// Creates a policy with the given global retention (`None` = keep forever).
+    pub fn new(default_days: Option<u32>) -> Self {
+        Self {
+            default_days,
+            chat_overrides: HashMap::new(),
+        }
+    }
+
+    /// Builds a policy from a [`RetentionConfig`].
+    pub fn from_config(config: &dyn RetentionConfig) -> Self {
+        Self {
+            default_days: config.default_days(),
+            chat_overrides: config.chat_overrides().clone(),
+        }
+    }
+
+    /// Overrides retention for one chat; `0` keeps that chat's data forever.
+    pub fn with_chat_override(mut self, chat_id: i64, days: u32) -> Self {
+        self.chat_overrides.insert(chat_id, days);
+        self
+    }
+
+    /// Returns the global retention in days, if any.
+    pub fn default_days(&self) -> Option<u32> {
+        self.default_days
+    }
+
+    /// Returns the per-chat overrides.
+    pub fn chat_overrides(&self) -> &HashMap<i64, u32> {
+        &self.chat_overrides
+    }
+
+    /// Returns the retention in days for the given chat, or `None` when its data is kept forever.
+    pub fn days_for_chat(&self, chat_id: i64) -> Option<u32> {
+        match self.chat_overrides.get(&chat_id) {
+            Some(0) => None,
+            Some(days) => Some(*days),
+            None => self.default_days,
+        }
+    }
+
+    /// True when at least one rule can expire data.
+    pub fn is_enabled(&self) -> bool {
+        self.default_days.is_some() || self.chat_overrides.values().any(|d| *d > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chat_overrides() {
+        let overrides = parse_chat_overrides("-1001234:30, 42:0,,").unwrap();
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides.get(&-1001234), Some(&30));
+        assert_eq!(overrides.get(&42), Some(&0));
+        assert!(parse_chat_overrides("42").is_err());
+        assert!(parse_chat_overrides("abc:1").is_err());
+    }
+
+    #[test]
+    fn test_days_for_chat() {
+        let policy = RetentionPolicy::new(Some(90))
+            .with_chat_override(1, 7)
+            .with_chat_override(2, 0);
+        assert_eq!(policy.days_for_chat(1), Some(7));
+        assert_eq!(policy.days_for_chat(2), None);
+        assert_eq!(policy.days_for_chat(3), Some(90));
+        assert!(policy.is_enabled());
+        assert!(!RetentionPolicy::new(None).with_chat_override(2, 0).is_enabled());
+    }
+}
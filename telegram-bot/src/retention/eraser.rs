@@ -0,0 +1,311 @@
+//! Deletion across all stores: "forget user", "forget chat", retention and expiry sweeps.
+//!
+//! [`DataEraser`] deletes from the [`MessageRepository`], every registered [`MemoryStore`]
+//! (primary store such as SQLite or Lance, and the optional recent store), the user profiles, the
+//! write outbox and the usage ledger, and returns a [`DeletionReport`].
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::config::RetentionPolicy;
+use crate::memory::{MemoryEntry, MemoryFilter, MemoryOrder, MemoryStore};
+use crate::outbox::OutboxRepository;
+use crate::profile::ProfileRepository;
+use crate::storage::{MessageRepository, UsageRepository};
+
+/// Number of memory entries deleted from one named store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreDeletion {
+    /// Store label given to [`DataEraser::with_store`] (e.g. "lance", "recent").
+    pub store: String,
+    /// Entries deleted from that store.
+    pub deleted: u64,
+}
+
+/// Summary of one deletion run: rows removed from the messages table and from each memory store.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeletionReport {
+    /// Rows deleted from the messages table.
+    pub messages: u64,
+    /// Entries deleted per memory store, in registration order.
+    pub memory_entries: Vec<StoreDeletion>,
+    /// Facts deleted from the user profiles.
+    pub profile_facts: u64,
+    /// Queued writes (pending or dead) deleted from the outbox.
+    pub outbox_items: u64,
+    /// Rows deleted from the usage ledger.
+    pub usage_rows: u64,
+}
+
+impl DeletionReport {
+    /// Total rows and entries deleted across all stores.
+    pub fn total(&self) -> u64 {
+        self.messages
+            + self.memory_entries.iter().map(|s| s.deleted).sum::<u64>()
+            + self.profile_facts
+            + self.outbox_items
+            + self.usage_rows
+    }
+
+    fn record_store(&mut self, store: &str, deleted: u64) {
+        match self.memory_entries.iter_mut().find(|s| s.store == store) {
+            Some(s) => s.deleted += deleted,
+            None => self.memory_entries.push(StoreDeletion {
+                store: store.to_string(),
+                deleted,
+            }),
+        }
+    }
+}
+
+/// Deletes user or chat data from the message repository, all registered memory stores, the user
+/// profiles, the write outbox and the usage ledger.
+#[derive(Clone)]
+pub struct DataEraser {
+    repo: MessageRepository,
+    stores: Vec<(String, Arc<dyn MemoryStore>)>,
+    profiles: Option<ProfileRepository>,
+    outbox: Option<OutboxRepository>,
+    usage: Option<UsageRepository>,
+}
+
+impl DataEraser {
+    /// Creates an eraser for the given repository with no memory stores registered yet.
+    pub fn new(repo: MessageRepository) -> Self {
+        Self {
+            repo,
+            stores: Vec::new(),
+            profiles: None,
+            outbox: None,
+            usage: None,
+        }
+    }
+
+    /// Also deletes the user's profile facts in [`forget_user`](Self::forget_user).
+    pub fn with_profile_repo(mut self, profiles: ProfileRepository) -> Self {
+        self.profiles = Some(profiles);
+        self
+    }
+
+    /// Also deletes the queued writes of the user or chat, so the outbox worker does not write them
+    /// back after [`forget_user`](Self::forget_user) / [`forget_chat`](Self::forget_chat).
+    pub fn with_outbox(mut self, outbox: OutboxRepository) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Also deletes rows from the usage ledger in [`forget_user`](Self::forget_user),
+    /// [`forget_chat`](Self::forget_chat) and [`apply_retention`](Self::apply_retention).
+    pub fn with_usage_repo(mut self, usage: UsageRepository) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Registers a memory store under `name`. A store already registered (same instance) is skipped,
+    /// e.g. when the recent store is the primary store.
+    pub fn with_store(mut self, name: impl Into<String>, store: Arc<dyn MemoryStore>) -> Self {
+        let already_registered = self
+            .stores
+            .iter()
+            .any(|(_, s)| std::ptr::addr_eq(s.as_ref() as *const _, store.as_ref() as *const _));
+        if !already_registered {
+            self.stores.push((name.into(), store));
+        }
+        self
+    }
+
+    /// Deletes every message, memory entry, profile fact, queued write and usage row of the given user
+    /// ("right to be forgotten").
+    pub async fn forget_user(&self, user_id: i64) -> Result<DeletionReport> {
+        info!(user_id = user_id, "Forgetting user across all stores");
+        let mut report = DeletionReport::default();
+        let user_key = user_id.to_string();
+        // The outbox goes first: a retry delivered meanwhile would restore deleted data.
+        if let Some(ref outbox) = self.outbox {
+            report.outbox_items = outbox
+                .delete_by_user(user_id)
+                .await
+                .context("forget_user: deleting outbox items failed")?;
+        }
+        for (name, store) in &self.stores {
+            let deleted = store
+                .delete_by_user(&user_key)
+                .await
+                .with_context(|| format!("forget_user: delete_by_user failed on store {}", name))?;
+            report.record_store(name, deleted);
+        }
+        if let Some(ref profiles) = self.profiles {
+            report.profile_facts = profiles
+                .delete_user(&user_key)
+                .await
+                .context("forget_user: deleting profile facts failed")?;
+        }
+        if let Some(ref usage) = self.usage {
+            report.usage_rows = usage
+                .delete_user(user_id)
+                .await
+                .context("forget_user: deleting usage rows failed")?;
+        }
+        report.messages = self
+            .repo
+            .delete_messages_by_user(user_id)
+            .await
+            .context("forget_user: deleting messages failed")?;
+        info!(user_id = user_id, total = report.total(), report = ?report, "User forgotten");
+        Ok(report)
+    }
+
+    /// Deletes every message, memory entry, queued write and usage row of the given chat.
+    pub async fn forget_chat(&self, chat_id: i64) -> Result<DeletionReport> {
+        info!(chat_id = chat_id, "Forgetting chat across all stores");
+        let mut report = DeletionReport::default();
+        let conversation_id = chat_id.to_string();
+        if let Some(ref outbox) = self.outbox {
+            report.outbox_items = outbox
+                .delete_by_chat(chat_id)
+                .await
+                .context("forget_chat: deleting outbox items failed")?;
+        }
+        for (name, store) in &self.stores {
+            let deleted = store
+                .delete_by_conversation(&conversation_id)
+                .await
+                .with_context(|| {
+                    format!("forget_chat: delete_by_conversation failed on store {}", name)
+                })?;
+            report.record_store(name, deleted);
+        }
+        if let Some(ref usage) = self.usage {
+            report.usage_rows = usage
+                .delete_chat(chat_id)
+                .await
+                .context("forget_chat: deleting usage rows failed")?;
+        }
+        report.messages = self
+            .repo
+            .delete_messages_by_chat(chat_id)
+            .await
+            .context("forget_chat: deleting messages failed")?;
+        info!(chat_id = chat_id, total = report.total(), report = ?report, "Chat forgotten");
+        Ok(report)
+    }
+
+    /// Deletes messages, memory entries and usage rows older than the policy allows.
+    ///
+    /// Each table and store is swept by timestamp: the default retention applies to everything
+    /// outside the overridden chats, then each override to its chat. A chat therefore expires
+    /// everywhere even when its messages are already gone (or were never persisted).
+    pub async fn apply_retention(&self, policy: &RetentionPolicy) -> Result<DeletionReport> {
+        let mut report = DeletionReport::default();
+        if !policy.is_enabled() {
+            return Ok(report);
+        }
+
+        let overrides = policy.chat_overrides();
+        if let Some(days) = policy.default_days() {
+            let filter = MemoryFilter::default().until(retention_cutoff(days));
+            let overridden = |entry: &MemoryEntry| {
+                entry
+                    .metadata
+                    .conversation_id
+                    .as_deref()
+                    .and_then(|c| c.parse::<i64>().ok())
+                    .is_some_and(|chat_id| overrides.contains_key(&chat_id))
+            };
+            for (name, store) in &self.stores {
+                match expire_entries(store.as_ref(), &filter, overridden).await {
+                    Ok(deleted) => report.record_store(name, deleted),
+                    Err(e) => warn!(error = %e, store = %name, "retention: memory sweep failed"),
+                }
+            }
+            let overridden: Vec<i64> = overrides.keys().copied().collect();
+            if let Some(ref usage) = self.usage {
+                report.usage_rows += usage
+                    .cleanup_old_rows_excluding_chats(days, &overridden)
+                    .await
+                    .context("retention: deleting old usage rows failed")?;
+            }
+            report.messages += self
+                .repo
+                .cleanup_old_messages_excluding_chats(days, &overridden)
+                .await
+                .context("retention: deleting old messages failed")?;
+        }
+        for (chat_id, days) in overrides {
+            if *days == 0 {
+                continue;
+            }
+            let filter =
+                MemoryFilter::for_conversation(chat_id.to_string()).until(retention_cutoff(*days));
+            for (name, store) in &self.stores {
+                match expire_entries(store.as_ref(), &filter, |_| false).await {
+                    Ok(deleted) => report.record_store(name, deleted),
+                    Err(e) => {
+                        warn!(error = %e, store = %name, chat_id = chat_id, "retention: memory sweep failed");
+                    }
+                }
+            }
+            if let Some(ref usage) = self.usage {
+                report.usage_rows += usage
+                    .cleanup_old_rows_in_chat(*chat_id, *days)
+                    .await
+                    .context("retention: deleting old chat usage rows failed")?;
+            }
+            report.messages += self
+                .repo
+                .cleanup_old_messages_in_chat(*chat_id, *days)
+                .await
+                .context("retention: deleting old chat messages failed")?;
+        }
+
+        info!(total = report.total(), report = ?report, "Retention sweep finished");
+        Ok(report)
+    }
+
+    /// Deletes memory entries whose TTL ran out by `now` from every store. A store that fails (or
+    /// does not support expiry sweeps) is logged and skipped; messages are not touched.
+    pub async fn sweep_expired(&self, now: DateTime<Utc>) -> DeletionReport {
+        let mut report = DeletionReport::default();
+        for (name, store) in &self.stores {
+            match store.delete_expired(now).await {
+                Ok(deleted) => report.record_store(name, deleted),
+                Err(e) => warn!(error = %e, store = %name, "expiry: memory sweep failed"),
+            }
+        }
+        info!(total = report.total(), report = ?report, "Expiry sweep finished");
+        report
+    }
+}
+
+/// Page size when listing expired entries.
+const EXPIRE_PAGE_SIZE: usize = 500;
+
+fn retention_cutoff(days: u32) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::days(days as i64)
+}
+
+/// Deletes the entries matching `filter`, oldest first, a page at a time; entries for which `keep`
+/// returns true stay.
+async fn expire_entries(
+    store: &dyn MemoryStore,
+    filter: &MemoryFilter,
+    keep: impl Fn(&MemoryEntry) -> bool,
+) -> Result<u64> {
+    let mut deleted = 0;
+    let mut cursor = None;
+    loop {
+        let page = store
+            .list(filter, MemoryOrder::OldestFirst, EXPIRE_PAGE_SIZE, cursor.as_ref())
+            .await?;
+        for entry in page.entries.iter().filter(|e| !keep(e)) {
+            store.delete(entry.id).await?;
+            deleted += 1;
+        }
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok(deleted),
+        }
+    }
+}
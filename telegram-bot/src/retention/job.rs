@@ -0,0 +1,51 @@
+//! Background jobs: periodically applies a [`RetentionPolicy`] through a [`DataEraser`], and sweeps
+//! expired memory entries.
+
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use super::config::RetentionPolicy;
+use super::eraser::DataEraser;
+
+/// Spawns a task that runs [`DataEraser::apply_retention`] every `interval` (first run immediately).
+/// Errors are logged and the next sweep still runs. Abort the returned handle to stop the job.
+pub fn spawn_retention_job(
+    eraser: DataEraser,
+    policy: RetentionPolicy,
+    interval: Duration,
+) -> JoinHandle<()> {
+    info!(
+        default_days = ?policy.default_days(),
+        chat_overrides = policy.chat_overrides().len(),
+        interval_secs = interval.as_secs(),
+        "Starting retention job"
+    );
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = eraser.apply_retention(&policy).await {
+                error!(error = %e, "Retention sweep failed");
+            }
+        }
+    })
+}
+
+/// Spawns a task that runs [`DataEraser::sweep_expired`] every `interval` (first run immediately).
+/// Abort the returned handle to stop the job.
+pub fn spawn_expiry_job(eraser: DataEraser, interval: Duration) -> JoinHandle<()> {
+    info!(
+        interval_secs = interval.as_secs(),
+        "Starting memory expiry job"
+    );
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            eraser.sweep_expired(chrono::Utc::now()).await;
+        }
+    })
+}
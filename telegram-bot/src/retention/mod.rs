@@ -0,0 +1,15 @@
+//! Data retention and deletion across all stores.
+//!
+//! ## Submodules
+//!
+//! - [`config`] – RetentionConfig, EnvRetentionConfig, RetentionPolicy (global default + per-chat overrides)
+//! - [`eraser`] – DataEraser: forget user / forget chat / retention and expiry sweeps, with a DeletionReport
+//! - [`job`] – Background jobs that apply the policy and sweep expired memory entries on an interval
+
+mod config;
+mod eraser;
+mod job;
+
+pub use config::{EnvRetentionConfig, RetentionConfig, RetentionPolicy};
+pub use eraser::{DataEraser, DeletionReport, StoreDeletion};
+pub use job::{spawn_expiry_job, spawn_retention_job};
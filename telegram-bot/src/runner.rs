@@ -3,12 +3,25 @@ use std::sync::Arc;
 use crate::core::{Bot, Handler, init_tracing, Message as CoreMessage, ToCoreMessage};
 use crate::telegram::{run_repl, TelegramMessageWrapper};
 use crate::chain::HandlerChain;
-use crate::memory::MemoryStore;
-use tracing::{error, info, instrument};
+use crate::embedding::{configured_embedding_spec, create_embedding_service};
+use crate::encryption::{reencrypt_all, EncryptedMemoryStore, ReencryptReport};
+use crate::memory::{
+    backfill_store, reembed_all, spawn_compaction_job, BackfillReport, MemoryStore, ReembedReport,
+};
+use crate::outbox::{
+    spawn_outbox_worker, OutboxCounts, OutboxItem, OutboxRepository, OutboxStatus, OutboxWorker,
+    RetryPolicy,
+};
+use crate::profile::ProfileRepository;
+use crate::storage::{MessageRepository, UsageRepository};
+use crate::retention::{spawn_expiry_job, spawn_retention_job, RetentionPolicy};
+use tracing::{error, info, instrument, warn};
 
 use super::components::{
-    build_bot_components, build_handler_chain, create_memory_stores, BotComponents,
+    build_bot_components, build_data_eraser, build_handler_chain, create_memory_stores,
+    BotComponents,
 };
+use super::cli::OutboxAction;
 use super::config::{AppExtensions, BotConfig};
 
 /// TelegramBot: config, components, and handler chain. Handler is injected from outside.
@@ -120,7 +133,51 @@ where
     );
 
     let components = build_bot_components(&config, memory_store, recent_store, None).await?;
+
+    if let Some(ret_cfg) = config.extensions().retention_config() {
+        let policy = RetentionPolicy::from_config(ret_cfg);
+        if policy.is_enabled() {
+            let eraser = build_data_eraser(&components, mem_cfg.store_type());
+            let interval = std::time::Duration::from_secs(ret_cfg.sweep_interval_secs());
+            spawn_retention_job(eraser, policy, interval);
+        } else {
+            info!("Retention disabled (RETENTION_DAYS / RETENTION_CHAT_DAYS not set)");
+        }
+    }
+
+    let outbox_cfg = config
+        .extensions()
+        .outbox_config()
+        .expect("BaseAppExtensions always has outbox");
+    let outbox_worker = OutboxWorker::new(
+        components.outbox.clone(),
+        components.memory_store.clone(),
+        components.repo.as_ref().clone(),
+        RetryPolicy::from_config(outbox_cfg),
+    )
+    .with_embedding_service(components.embedding_service.clone());
+    let interval = std::time::Duration::from_secs(outbox_cfg.poll_interval_secs());
+    spawn_outbox_worker(outbox_worker, interval);
+
+    if components.memory_ttl_policy.is_enabled() {
+        let eraser = build_data_eraser(&components, mem_cfg.store_type());
+        let interval = std::time::Duration::from_secs(mem_cfg.ttl_sweep_interval_secs());
+        spawn_expiry_job(eraser, interval);
+    }
+
+    let memory_store = components.memory_store.clone();
+    let recent_store = components.recent_store.clone();
     let handler = make_handler(&config, components.clone());
+
+    if let Some(ref compactor) = components.memory_compactor {
+        if compactor.has_summarizer() {
+            let interval = std::time::Duration::from_secs(mem_cfg.compaction_interval_secs());
+            spawn_compaction_job(compactor.clone(), interval);
+        } else {
+            warn!("MEMORY_COMPACTION_AFTER_DAYS is set but no summarizer is installed; compaction disabled");
+        }
+    }
+
     let handler_chain = build_handler_chain(&components, handler);
     let bot_username = components.bot_username.clone();
     let bot_user = components.bot_user.clone();
@@ -130,9 +187,239 @@ where
 
     run_repl(teloxide_bot, handler_chain, bot_username, bot_user).await?;
 
+    info!("Bot stopped; flushing memory stores");
+    for store in std::iter::once(memory_store).chain(recent_store) {
+        if let Err(e) = store.flush().await {
+            error!(error = %e, "Failed to flush memory store on shutdown");
+        }
+    }
+
     Ok(())
 }
 
+/// Re-encrypts stored messages and memory entries under the active key (`reencrypt` CLI command).
+/// Pass the raw stores from `create_memory_stores` (not the encrypted wrappers from `BotComponents`).
+#[instrument(skip(config, memory_store, recent_store))]
+pub async fn run_reencrypt(
+    config: BotConfig,
+    memory_store: Arc<dyn MemoryStore>,
+    recent_store: Option<Arc<dyn MemoryStore>>,
+    batch_size: u32,
+) -> Result<ReencryptReport> {
+    std::fs::create_dir_all("logs").expect("Failed to create logs directory");
+    init_tracing(config.base().log_file.as_str())?;
+
+    let cipher = config
+        .extensions()
+        .encryption_config()
+        .and_then(|c| c.cipher())
+        .ok_or_else(|| anyhow::anyhow!("reencrypt requires ENCRYPTION_KEY or ENCRYPTION_KEY_FILE"))?;
+    let mem_cfg = config
+        .extensions()
+        .memory_config()
+        .expect("BaseAppExtensions always has memory");
+
+    let repo = MessageRepository::new(config.base().database_url.as_str()).await?;
+    let profiles = ProfileRepository::new(config.base().database_url.as_str()).await?;
+    let outbox = OutboxRepository::new(config.base().database_url.as_str()).await?;
+    let mut stores = vec![(mem_cfg.store_type().to_string(), memory_store.clone())];
+    if let Some(recent) = recent_store {
+        if !std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            stores.push(("recent".to_string(), recent));
+        }
+    }
+    reencrypt_all(&repo, &profiles, &outbox, &stores, cipher, batch_size).await
+}
+
+/// Re-embeds memory entries with the configured embedding model (`reembed` CLI command), after
+/// EMBEDDING_MODEL / EMBEDDING_PROVIDER changed. Pass the raw stores from `create_memory_stores`;
+/// they are wrapped in `EncryptedMemoryStore` here when encryption is enabled.
+#[instrument(skip(config, memory_store, recent_store))]
+pub async fn run_reembed(
+    config: BotConfig,
+    memory_store: Arc<dyn MemoryStore>,
+    recent_store: Option<Arc<dyn MemoryStore>>,
+    batch_size: usize,
+) -> Result<ReembedReport> {
+    std::fs::create_dir_all("logs").expect("Failed to create logs directory");
+    init_tracing(config.base().log_file.as_str())?;
+
+    let emb_cfg = config
+        .extensions()
+        .embedding_config()
+        .ok_or_else(|| anyhow::anyhow!("Embedding config required"))?;
+    let spec = configured_embedding_spec(emb_cfg).ok_or_else(|| {
+        anyhow::anyhow!("reembed requires EMBEDDING_DIM when the embedding model's dimension is not known")
+    })?;
+    let mem_cfg = config
+        .extensions()
+        .memory_config()
+        .expect("BaseAppExtensions always has memory");
+    let cipher = config
+        .extensions()
+        .encryption_config()
+        .and_then(|c| c.cipher());
+
+    let usage_repo = UsageRepository::new(config.base().database_url.as_str()).await?;
+    let embedding =
+        create_embedding_service(emb_cfg, config.extensions().tokenizer_config(), usage_repo)?;
+
+    let mut stores = vec![(mem_cfg.store_type().to_string(), memory_store.clone())];
+    if let Some(recent) = recent_store {
+        if !std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            stores.push(("recent".to_string(), recent));
+        }
+    }
+    if let Some(cipher) = cipher {
+        for (_, store) in stores.iter_mut() {
+            *store = Arc::new(EncryptedMemoryStore::new(store.clone(), cipher.clone()));
+        }
+    }
+    let report = reembed_all(&stores, embedding.as_ref(), &spec, batch_size).await?;
+    for (_, store) in &stores {
+        store.flush().await?;
+    }
+    Ok(report)
+}
+
+/// Imports the messages table into memory (`backfill` CLI command): the primary store gets embedded
+/// entries, a separate recent store plain ones. Pass the raw stores from `create_memory_stores`; they
+/// (and the message repository) use encryption here when it is enabled.
+#[instrument(skip(config, memory_store, recent_store))]
+pub async fn run_backfill(
+    config: BotConfig,
+    memory_store: Arc<dyn MemoryStore>,
+    recent_store: Option<Arc<dyn MemoryStore>>,
+    batch_size: usize,
+) -> Result<BackfillReport> {
+    std::fs::create_dir_all("logs").expect("Failed to create logs directory");
+    init_tracing(config.base().log_file.as_str())?;
+
+    let mem_cfg = config
+        .extensions()
+        .memory_config()
+        .expect("BaseAppExtensions always has memory");
+    let cipher = config
+        .extensions()
+        .encryption_config()
+        .and_then(|c| c.cipher());
+    let usage_repo = UsageRepository::new(config.base().database_url.as_str()).await?;
+    let embedding = match config.extensions().embedding_config() {
+        Some(emb_cfg) => Some(create_embedding_service(
+            emb_cfg,
+            config.extensions().tokenizer_config(),
+            usage_repo,
+        )?),
+        None => None,
+    };
+
+    let repo = MessageRepository::new(config.base().database_url.as_str()).await?;
+    let repo = match cipher {
+        Some(ref cipher) => repo.with_cipher(cipher.clone()),
+        None => repo,
+    };
+    let mut stores = vec![(mem_cfg.store_type().to_string(), memory_store.clone(), embedding)];
+    if let Some(recent) = recent_store {
+        if !std::ptr::addr_eq(recent.as_ref() as *const _, memory_store.as_ref() as *const _) {
+            stores.push(("recent".to_string(), recent, None));
+        }
+    }
+    let mut report = BackfillReport {
+        memory_entries: Vec::new(),
+    };
+    for (name, store, embedding) in stores {
+        let store: Arc<dyn MemoryStore> = match cipher {
+            Some(ref cipher) => Arc::new(EncryptedMemoryStore::new(store, cipher.clone())),
+            None => store,
+        };
+        let written = backfill_store(&repo, store.clone(), embedding, batch_size).await?;
+        store.flush().await?;
+        report.memory_entries.push((name, written));
+    }
+    Ok(report)
+}
+
+/// Result of the `outbox` CLI command; `Display` prints it for the terminal.
+#[derive(Debug)]
+pub enum OutboxCommandReport {
+    Listed {
+        counts: OutboxCounts,
+        status: OutboxStatus,
+        items: Vec<OutboxItem>,
+    },
+    Purged {
+        dead_letters: u64,
+    },
+}
+
+impl std::fmt::Display for OutboxCommandReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutboxCommandReport::Listed {
+                counts,
+                status,
+                items,
+            } => {
+                writeln!(f, "pending: {}, dead letters: {}", counts.pending, counts.dead)?;
+                if items.is_empty() {
+                    return writeln!(f, "no {} items", status.as_str());
+                }
+                for item in items {
+                    writeln!(
+                        f,
+                        "{} {} attempts={} created={} next={} error={}",
+                        item.id,
+                        item.payload.kind(),
+                        item.attempts,
+                        item.created_at.to_rfc3339(),
+                        item.next_attempt_at.to_rfc3339(),
+                        item.last_error
+                    )?;
+                }
+                Ok(())
+            }
+            OutboxCommandReport::Purged { dead_letters } => {
+                writeln!(f, "purged {} dead letters", dead_letters)
+            }
+        }
+    }
+}
+
+/// Lists or purges outbox items (`outbox` CLI command). Payloads sealed under encryption at rest are
+/// opened with the configured keys.
+#[instrument(skip(config))]
+pub async fn run_outbox(config: BotConfig, action: OutboxAction) -> Result<OutboxCommandReport> {
+    std::fs::create_dir_all("logs").expect("Failed to create logs directory");
+    init_tracing(config.base().log_file.as_str())?;
+
+    let outbox = OutboxRepository::new(config.base().database_url.as_str()).await?;
+    let outbox = match config
+        .extensions()
+        .encryption_config()
+        .and_then(|c| c.cipher())
+    {
+        Some(cipher) => outbox.with_cipher(cipher),
+        None => outbox,
+    };
+    match action {
+        OutboxAction::List { pending, limit } => {
+            let status = if pending {
+                OutboxStatus::Pending
+            } else {
+                OutboxStatus::Dead
+            };
+            Ok(OutboxCommandReport::Listed {
+                counts: outbox.counts().await?,
+                status,
+                items: outbox.list(status, limit).await?,
+            })
+        }
+        OutboxAction::Purge => Ok(OutboxCommandReport::Purged {
+            dead_letters: outbox.purge_dead().await?,
+        }),
+    }
+}
+
 /// Builds components and handler chain without starting the REPL. Used by integration tests that inject a mock bot and drive the chain with fake messages.
 ///
 /// When `handler_bot_override` is `Some`, it is passed to `build_bot_components` so that `make_handler` receives it in `components.handler_bot`.
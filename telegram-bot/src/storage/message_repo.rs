@@ -3,23 +3,33 @@
 //! Uses SqlitePoolManager and the models (MessageRecord, MessageQuery, MessageStats).
 //! External: SQLite via sqlx; callers use save/get_messages/get_stats etc.
 
-use super::models::{MessageQuery, MessageRecord, MessageStats};
+use super::models::{MessageCursor, MessageOrder, MessagePage, MessageQuery, MessageRecord, MessageStats};
 use super::sqlite_pool::SqlitePoolManager;
-use chrono::{DateTime, Local, Utc};
+use crate::encryption::FieldCipher;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use sqlx::{QueryBuilder, Sqlite};
+use std::sync::Arc;
 use tracing::info;
 
-/// SQLite-backed message persistence and queries (save, get_message_by_id, get_recent_messages_by_chat, get_messages, get_stats).
+/// SQLite-backed message persistence and queries (save, get_message_by_id, get_recent_messages_by_chat, get_messages,
+/// keyset-paginated get_messages_page / stream_messages, get_stats).
 #[derive(Clone)]
 pub struct MessageRepository {
     /// Shared SQLite pool used for all queries.
     pool_manager: SqlitePoolManager,
+    /// When set, `content` is encrypted on save and decrypted on read (see [`FieldCipher`]).
+    cipher: Option<Arc<FieldCipher>>,
 }
 
 impl MessageRepository {
     /// Creates a repository and initializes the messages table and indexes.
     pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
         let pool_manager = SqlitePoolManager::new(database_url).await?;
-        let repo = Self { pool_manager };
+        let repo = Self {
+            pool_manager,
+            cipher: None,
+        };
         repo.init().await?;
         Ok(repo)
     }
@@ -54,6 +64,7 @@ impl MessageRepository {
             CREATE INDEX IF NOT EXISTS idx_messages_user_id ON messages(user_id);
             CREATE INDEX IF NOT EXISTS idx_messages_chat_id ON messages(chat_id);
             CREATE INDEX IF NOT EXISTS idx_messages_created_at ON messages(created_at);
+            CREATE INDEX IF NOT EXISTS idx_messages_created_at_id ON messages(created_at, id);
             CREATE INDEX IF NOT EXISTS idx_messages_direction ON messages(direction);
             CREATE INDEX IF NOT EXISTS idx_messages_message_type ON messages(message_type);
             "#,
@@ -71,13 +82,29 @@ impl MessageRepository {
         .execute(pool)
         .await;
 
+        // key_id of the encryption key that sealed `content` (NULL = plaintext).
+        let _ = sqlx::query("ALTER TABLE messages ADD COLUMN key_id TEXT")
+            .execute(pool)
+            .await;
+        let _ = sqlx::query("CREATE INDEX IF NOT EXISTS idx_messages_key_id ON messages(key_id)")
+            .execute(pool)
+            .await;
+
         info!("Database tables created successfully");
         Ok(())
     }
 
+    /// Enables encryption at rest: `content` is sealed with the cipher's active key on save and
+    /// decrypted on read. Rows written without encryption are still readable.
+    pub fn with_cipher(mut self, cipher: Arc<FieldCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
     /// Inserts a single message into the messages table.
     pub async fn save(&self, message: &MessageRecord) -> Result<(), sqlx::Error> {
         let pool = self.pool_manager.pool();
+        let (content, key_id) = self.seal_content(&message.content)?;
 
         info!(
             id = %message.id,
@@ -89,8 +116,8 @@ impl MessageRepository {
 
         sqlx::query(
             r#"
-            INSERT INTO messages (id, user_id, chat_id, username, first_name, last_name, message_type, content, direction, created_at, telegram_message_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO messages (id, user_id, chat_id, username, first_name, last_name, message_type, content, direction, created_at, telegram_message_id, key_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&message.id)
@@ -100,10 +127,11 @@ impl MessageRepository {
         .bind(&message.first_name)
         .bind(&message.last_name)
         .bind(&message.message_type)
-        .bind(&message.content)
+        .bind(&content)
         .bind(&message.direction)
         .bind(message.created_at)
         .bind(&message.telegram_message_id)
+        .bind(key_id)
         .execute(pool)
         .await?;
 
@@ -173,7 +201,8 @@ impl MessageRepository {
         })
     }
 
-    /// Returns messages matching the query (optional user_id, chat_id, limit, offset), ordered by created_at DESC.
+    /// Returns messages matching the query (optional user_id, chat_id, message_type, direction, date range,
+    /// limit, offset), ordered by created_at DESC.
     pub async fn get_messages(
         &self,
         query: &MessageQuery,
@@ -185,35 +214,28 @@ impl MessageRepository {
             "Querying messages"
         );
         let pool = self.pool_manager.pool();
-        let mut sql = String::from("SELECT * FROM messages WHERE 1=1");
-        let mut params: Vec<String> = Vec::new();
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM messages WHERE 1=1");
+        push_query_filters(&mut builder, query);
 
-        if let Some(uid) = query.user_id {
-            sql.push_str(" AND user_id = ?");
-            params.push(uid.to_string());
-        }
-
-        if let Some(cid) = query.chat_id {
-            sql.push_str(" AND chat_id = ?");
-            params.push(cid.to_string());
-        }
-
-        sql.push_str(" ORDER BY created_at DESC");
+        builder.push(" ORDER BY created_at DESC");
 
         if let Some(limit) = query.limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
+            builder.push(" LIMIT ").push_bind(limit);
         }
 
         if let Some(offset) = query.offset {
-            sql.push_str(&format!(" OFFSET {}", offset));
+            if query.limit.is_none() {
+                // SQLite requires LIMIT before OFFSET; -1 means no limit.
+                builder.push(" LIMIT -1");
+            }
+            builder.push(" OFFSET ").push_bind(offset);
         }
 
-        let mut query_builder = sqlx::query_as::<_, MessageRecord>(&sql);
-        for param in &params {
-            query_builder = query_builder.bind(param);
-        }
-
-        let messages: Vec<MessageRecord> = query_builder.fetch_all(pool).await?;
+        let messages: Vec<MessageRecord> = builder
+            .build_query_as::<MessageRecord>()
+            .fetch_all(pool)
+            .await?;
+        let messages = self.open_records(messages)?;
         info!(
             count = messages.len(),
             user_id = ?query.user_id,
@@ -224,7 +246,108 @@ impl MessageRepository {
         Ok(messages)
     }
 
+    /// Returns one page of messages matching the query's filters, using keyset pagination on (created_at, id).
+    ///
+    /// Pass `after = None` for the first page and `page.next_cursor` for the following ones. Unlike OFFSET,
+    /// the cost of a page does not grow with its position, and rows inserted meanwhile do not shift pages.
+    /// `query.limit` and `query.offset` are ignored; `page_size` bounds the page.
+    pub async fn get_messages_page(
+        &self,
+        query: &MessageQuery,
+        after: Option<&MessageCursor>,
+        order: MessageOrder,
+        page_size: u32,
+    ) -> Result<MessagePage, sqlx::Error> {
+        let page_size = page_size.max(1);
+        let pool = self.pool_manager.pool();
+        let (cmp, dir) = match order {
+            MessageOrder::NewestFirst => ("<", "DESC"),
+            MessageOrder::OldestFirst => (">", "ASC"),
+        };
+
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM messages WHERE 1=1");
+        push_query_filters(&mut builder, query);
+        if let Some(cursor) = after {
+            builder
+                .push(format!(" AND (created_at {} ", cmp))
+                .push_bind(cursor.created_at)
+                .push(" OR (created_at = ")
+                .push_bind(cursor.created_at)
+                .push(format!(" AND id {} ", cmp))
+                .push_bind(cursor.id.clone())
+                .push("))");
+        }
+        builder.push(format!(" ORDER BY created_at {dir}, id {dir} LIMIT ", dir = dir));
+        // Fetch one extra row to know whether another page exists.
+        builder.push_bind(page_size as i64 + 1);
+
+        let messages: Vec<MessageRecord> = builder
+            .build_query_as::<MessageRecord>()
+            .fetch_all(pool)
+            .await?;
+        let mut messages = self.open_records(messages)?;
+        let next_cursor = if messages.len() > page_size as usize {
+            messages.truncate(page_size as usize);
+            messages.last().map(MessageCursor::from_record)
+        } else {
+            None
+        };
+
+        info!(
+            count = messages.len(),
+            user_id = ?query.user_id,
+            chat_id = ?query.chat_id,
+            has_more = next_cursor.is_some(),
+            "Messages page query returned"
+        );
+        Ok(MessagePage {
+            messages,
+            next_cursor,
+        })
+    }
+
+    /// Streams all messages matching the query's filters, fetching `page_size` rows at a time via
+    /// [`get_messages_page`](Self::get_messages_page), so memory stays bounded for exports and backfills.
+    ///
+    /// `query.limit`, when set, caps the total number of yielded rows. The stream ends after the first error.
+    pub fn stream_messages(
+        &self,
+        query: MessageQuery,
+        order: MessageOrder,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<MessageRecord, sqlx::Error>> + Send + 'static {
+        let repo = self.clone();
+        let limit = query
+            .limit
+            .map(|l| l.max(0) as usize)
+            .unwrap_or(usize::MAX);
+        // State: Some(cursor) while pages remain, None once the last page was read.
+        let pages = stream::try_unfold(Some(None::<MessageCursor>), move |state| {
+            let repo = repo.clone();
+            let query = query.clone();
+            async move {
+                let Some(after) = state else {
+                    return Ok(None);
+                };
+                let page = repo
+                    .get_messages_page(&query, after.as_ref(), order, page_size)
+                    .await?;
+                if page.messages.is_empty() {
+                    return Ok(None);
+                }
+                let next_state = page.next_cursor.map(Some);
+                Ok::<_, sqlx::Error>(Some((page.messages, next_state)))
+            }
+        });
+        pages
+            .map_ok(|messages| stream::iter(messages.into_iter().map(Ok::<_, sqlx::Error>)))
+            .try_flatten()
+            .take(limit)
+    }
+
     /// Returns messages whose content contains the keyword (LIKE %keyword%), with optional limit.
+    /// With encryption enabled, content cannot be matched in SQL, so rows are streamed, decrypted and
+    /// filtered in memory instead.
     pub async fn search_messages(
         &self,
         keyword: &str,
@@ -235,6 +358,9 @@ impl MessageRepository {
             limit = ?limit,
             "Querying messages by keyword"
         );
+        if self.cipher.is_some() {
+            return self.search_messages_decrypted(keyword, limit).await;
+        }
         let pool = self.pool_manager.pool();
         let pattern = format!("%{}%", keyword);
         let mut sql =
@@ -254,24 +380,185 @@ impl MessageRepository {
         Ok(messages)
     }
 
+    async fn search_messages_decrypted(
+        &self,
+        keyword: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<MessageRecord>, sqlx::Error> {
+        let limit = limit.map(|l| l.max(0) as usize).unwrap_or(usize::MAX);
+        let messages: Vec<MessageRecord> = self
+            .stream_messages(MessageQuery::default(), MessageOrder::NewestFirst, 500)
+            .try_filter(|m| futures::future::ready(m.content.contains(keyword)))
+            .take(limit)
+            .try_collect()
+            .await?;
+        info!(
+            count = messages.len(),
+            keyword = %keyword,
+            "Messages keyword search (decrypted scan) returned"
+        );
+        Ok(messages)
+    }
+
+    /// Re-encrypts every row that is plaintext or sealed with a non-active key, `batch_size` rows per
+    /// transaction; returns the number of rows rewritten. Requires [`with_cipher`](Self::with_cipher).
+    pub async fn reencrypt_messages(&self, batch_size: u32) -> Result<u64, sqlx::Error> {
+        let Some(cipher) = self.cipher.as_ref() else {
+            return Err(sqlx::Error::Configuration(
+                "reencrypt_messages requires an encryption key".into(),
+            ));
+        };
+        let pool = self.pool_manager.pool();
+        let batch_size = batch_size.max(1) as i64;
+        let mut rewritten = 0u64;
+        loop {
+            // Rewritten rows no longer match, so each batch picks up where the previous one ended.
+            let rows: Vec<(String, String)> = sqlx::query_as(
+                "SELECT id, content FROM messages WHERE key_id IS NULL OR key_id != ? ORDER BY id LIMIT ?",
+            )
+            .bind(cipher.active_key_id())
+            .bind(batch_size)
+            .fetch_all(pool)
+            .await?;
+            if rows.is_empty() {
+                break;
+            }
+            let mut tx = pool.begin().await?;
+            for (id, content) in &rows {
+                let sealed = cipher
+                    .reencrypt(content)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                sqlx::query("UPDATE messages SET content = ?, key_id = ? WHERE id = ?")
+                    .bind(sealed)
+                    .bind(cipher.active_key_id())
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            rewritten += rows.len() as u64;
+            info!(rewritten = rewritten, "Re-encrypted message batch");
+        }
+        info!(
+            rewritten = rewritten,
+            active_key_id = %cipher.active_key_id(),
+            "Messages re-encrypted"
+        );
+        Ok(rewritten)
+    }
+
     /// Deletes messages older than the given number of days; returns the number of rows deleted.
     pub async fn cleanup_old_messages(&self, days: i32) -> Result<u64, sqlx::Error> {
+        self.cleanup_old_messages_excluding_chats(days.max(0) as u32, &[])
+            .await
+    }
+
+    /// Deletes messages older than `days` in every chat except `excluded_chat_ids`
+    /// (chats that have their own retention override); returns the number of rows deleted.
+    pub async fn cleanup_old_messages_excluding_chats(
+        &self,
+        days: u32,
+        excluded_chat_ids: &[i64],
+    ) -> Result<u64, sqlx::Error> {
         let pool = self.pool_manager.pool();
-        let cutoff_date = Local::now() - chrono::Duration::days(days as i64);
+        let cutoff_date = retention_cutoff(days);
+
+        // created_at is stored as RFC 3339 (DateTime<Utc>), so the cutoff must be bound the same way
+        // for the string comparison to be correct.
+        let mut sql = String::from("DELETE FROM messages WHERE created_at < ?");
+        if !excluded_chat_ids.is_empty() {
+            let placeholders = vec!["?"; excluded_chat_ids.len()].join(", ");
+            sql.push_str(&format!(" AND chat_id NOT IN ({})", placeholders));
+        }
+
+        let mut query = sqlx::query(&sql).bind(cutoff_date);
+        for chat_id in excluded_chat_ids {
+            query = query.bind(*chat_id);
+        }
+        let result = query.execute(pool).await?;
 
-        let result = sqlx::query("DELETE FROM messages WHERE created_at < ?")
-            .bind(cutoff_date.naive_utc())
+        info!(
+            deleted = result.rows_affected(),
+            days = days,
+            excluded_chats = excluded_chat_ids.len(),
+            "Deleted old messages"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes messages in one chat older than `days`; returns the number of rows deleted.
+    pub async fn cleanup_old_messages_in_chat(
+        &self,
+        chat_id: i64,
+        days: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+        let cutoff_date = retention_cutoff(days);
+
+        let result = sqlx::query("DELETE FROM messages WHERE chat_id = ? AND created_at < ?")
+            .bind(chat_id)
+            .bind(cutoff_date)
+            .execute(pool)
+            .await?;
+
+        info!(
+            chat_id = chat_id,
+            deleted = result.rows_affected(),
+            days = days,
+            "Deleted old messages in chat"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes every message sent by the given user and the bot's replies in their private chat (whose
+    /// chat id is the user id); returns the number of rows deleted. Bot replies in group chats stay.
+    pub async fn delete_messages_by_user(&self, user_id: i64) -> Result<u64, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        let result = sqlx::query(
+            "DELETE FROM messages WHERE user_id = ? OR (chat_id = ? AND direction = 'sent')",
+        )
+        .bind(user_id)
+        .bind(user_id)
+        .execute(pool)
+            .await?;
+
+        info!(
+            user_id = user_id,
+            deleted = result.rows_affected(),
+            "Deleted messages by user"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes every message in the given chat; returns the number of rows deleted.
+    pub async fn delete_messages_by_chat(&self, chat_id: i64) -> Result<u64, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        let result = sqlx::query("DELETE FROM messages WHERE chat_id = ?")
+            .bind(chat_id)
             .execute(pool)
             .await?;
 
         info!(
-            "Deleted {} old messages older than {} days",
-            result.rows_affected(),
-            days
+            chat_id = chat_id,
+            deleted = result.rows_affected(),
+            "Deleted messages by chat"
         );
         Ok(result.rows_affected())
     }
 
+    /// Returns the distinct chat ids that have at least one stored message.
+    pub async fn list_chat_ids(&self) -> Result<Vec<i64>, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        let rows: Vec<(i64,)> = sqlx::query_as("SELECT DISTINCT chat_id FROM messages")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|(chat_id,)| chat_id).collect())
+    }
+
     pub async fn get_message_by_id(&self, message_id: &str) -> Result<Option<MessageRecord>, sqlx::Error> {
         info!(message_id = %message_id, "Querying message by id");
         let pool = self.pool_manager.pool();
@@ -279,7 +566,9 @@ impl MessageRepository {
         let message = sqlx::query_as::<_, MessageRecord>("SELECT * FROM messages WHERE id = ?")
             .bind(message_id)
             .fetch_optional(pool)
-            .await?;
+            .await?
+            .map(|m| self.open_record(m))
+            .transpose()?;
 
         info!(
             message_id = %message_id,
@@ -305,7 +594,9 @@ impl MessageRepository {
         )
         .bind(telegram_message_id)
         .fetch_optional(pool)
-        .await?;
+        .await?
+        .map(|m| self.open_record(m))
+        .transpose()?;
 
         info!(
             telegram_message_id = %telegram_message_id,
@@ -335,6 +626,7 @@ impl MessageRepository {
         .bind(limit)
         .fetch_all(pool)
         .await?;
+        let messages = self.open_records(messages)?;
 
         info!(
             count = messages.len(),
@@ -345,4 +637,58 @@ impl MessageRepository {
 
         Ok(messages)
     }
+
+    /// Encrypts `content` when a cipher is set; returns the stored value and its key id (None = plaintext).
+    fn seal_content(&self, content: &str) -> Result<(String, Option<String>), sqlx::Error> {
+        match self.cipher.as_ref() {
+            Some(cipher) => {
+                let sealed = cipher
+                    .encrypt(content)
+                    .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+                Ok((sealed, Some(cipher.active_key_id().to_string())))
+            }
+            None => Ok((content.to_string(), None)),
+        }
+    }
+
+    /// Decrypts `content` of a fetched record (no-op without a cipher or for plaintext rows).
+    fn open_record(&self, mut record: MessageRecord) -> Result<MessageRecord, sqlx::Error> {
+        if let Some(cipher) = self.cipher.as_ref() {
+            record.content = cipher
+                .decrypt(&record.content)
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        }
+        Ok(record)
+    }
+
+    fn open_records(&self, records: Vec<MessageRecord>) -> Result<Vec<MessageRecord>, sqlx::Error> {
+        records.into_iter().map(|r| self.open_record(r)).collect()
+    }
+}
+
+/// Appends `AND ...` conditions for the filters set in `query` (user, chat, type, direction, date range).
+fn push_query_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &MessageQuery) {
+    if let Some(uid) = query.user_id {
+        builder.push(" AND user_id = ").push_bind(uid);
+    }
+    if let Some(cid) = query.chat_id {
+        builder.push(" AND chat_id = ").push_bind(cid);
+    }
+    if let Some(message_type) = &query.message_type {
+        builder.push(" AND message_type = ").push_bind(message_type.clone());
+    }
+    if let Some(direction) = &query.direction {
+        builder.push(" AND direction = ").push_bind(direction.clone());
+    }
+    if let Some(start) = query.start_date {
+        builder.push(" AND created_at >= ").push_bind(start);
+    }
+    if let Some(end) = query.end_date {
+        builder.push(" AND created_at <= ").push_bind(end);
+    }
+}
+
+/// Returns the instant before which messages are considered expired for a `days` retention window.
+fn retention_cutoff(days: u32) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::days(days as i64)
 }
@@ -3,9 +3,10 @@
 //! ## Submodules
 //!
 //! - [`error`] – Storage error types
-//! - [`models`] – MessageRecord, MessageQuery, MessageStats
+//! - [`models`] – MessageRecord, MessageQuery, MessageStats, UsageRecord, UsageQuery
 //! - [`repository`] – Repository trait
 //! - [`message_repo`] – MessageRepository (SQLite)
+//! - [`usage_repo`] – UsageRepository: `llm_usage` token ledger (SQLite)
 //! - [`sqlite_pool`] – SqlitePoolManager
 
 mod error;
@@ -13,9 +14,14 @@ mod message_repo;
 mod models;
 mod repository;
 mod sqlite_pool;
+mod usage_repo;
 
 pub use error::StorageError;
 pub use message_repo::MessageRepository;
-pub use models::{MessageQuery, MessageRecord, MessageStats};
+pub use models::{
+    MessageCursor, MessageOrder, MessagePage, MessageQuery, MessageRecord, MessageStats,
+    UsageGroupBy, UsageQuery, UsageRecord, UsageTotals, USAGE_KIND_CHAT, USAGE_KIND_EMBEDDING,
+};
 pub use repository::Repository;
 pub use sqlite_pool::SqlitePoolManager;
+pub use usage_repo::UsageRepository;
@@ -0,0 +1,46 @@
+//! Keyset pagination types for message queries.
+//!
+//! Used by MessageRepository::get_messages_page and MessageRepository::stream_messages.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::MessageRecord;
+
+/// Position in the (created_at, id) ordering; the next page starts strictly after it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageCursor {
+    /// created_at of the last message of the previous page.
+    pub created_at: DateTime<Utc>,
+    /// id of the last message of the previous page (tie-breaker for equal timestamps).
+    pub id: String,
+}
+
+impl MessageCursor {
+    /// Cursor pointing at the given record.
+    pub fn from_record(record: &MessageRecord) -> Self {
+        Self {
+            created_at: record.created_at,
+            id: record.id.clone(),
+        }
+    }
+}
+
+/// Sort order for keyset pagination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageOrder {
+    /// created_at DESC, id DESC (same order as get_messages).
+    #[default]
+    NewestFirst,
+    /// created_at ASC, id ASC (useful for backfills and exports).
+    OldestFirst,
+}
+
+/// One page of messages plus the cursor for the next page (`None` when this is the last page).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagePage {
+    /// Messages in this page, in the requested order.
+    pub messages: Vec<MessageRecord>,
+    /// Cursor to pass to the next call; `None` when there are no more rows.
+    pub next_cursor: Option<MessageCursor>,
+}
@@ -1,12 +1,12 @@
 //! Query parameters for listing/filtering messages.
 //!
-//! Used by MessageRepository::get_messages.
+//! Used by MessageRepository::get_messages, get_messages_page and stream_messages.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Query parameters for listing/filtering messages in MessageRepository::get_messages.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MessageQuery {
     /// Filter by Telegram user id.
     pub user_id: Option<i64>,
@@ -20,8 +20,8 @@ pub struct MessageQuery {
     pub start_date: Option<DateTime<Utc>>,
     /// Only messages on or before this time (optional).
     pub end_date: Option<DateTime<Utc>>,
-    /// Maximum number of rows to return.
+    /// Maximum number of rows to return (for streams: total rows yielded; ignored by get_messages_page).
     pub limit: Option<i64>,
-    /// Pagination offset (used with limit).
+    /// Pagination offset (used with limit; ignored by keyset pagination).
     pub offset: Option<i64>,
 }
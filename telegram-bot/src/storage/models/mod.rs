@@ -1,11 +1,17 @@
-//! Data models for storage (message records, queries, stats).
+//! Data models for storage (message records, queries, stats, LLM usage ledger).
 //!
 //! Used by MessageRepository and callers of the storage API.
 
+mod message_cursor;
 mod message_query;
 mod message_record;
 mod message_stats;
+mod usage_query;
+mod usage_record;
 
+pub use message_cursor::{MessageCursor, MessageOrder, MessagePage};
 pub use message_query::MessageQuery;
 pub use message_record::MessageRecord;
 pub use message_stats::MessageStats;
+pub use usage_query::{UsageGroupBy, UsageQuery, UsageTotals};
+pub use usage_record::{UsageRecord, USAGE_KIND_CHAT, USAGE_KIND_EMBEDDING};
@@ -0,0 +1,55 @@
+//! Filters and aggregates for the `llm_usage` ledger.
+//!
+//! Used by UsageRepository::totals and UsageRepository::totals_by.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Filters for usage totals; unset fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageQuery {
+    pub chat_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub model: Option<String>,
+    pub handler: Option<String>,
+    /// "chat" or "embedding".
+    pub kind: Option<String>,
+    /// Only calls on or after this time.
+    pub start_date: Option<DateTime<Utc>>,
+    /// Only calls before this time.
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+/// Summed usage over a set of calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Number of recorded calls.
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+}
+
+/// Column to group totals by in UsageRepository::totals_by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UsageGroupBy {
+    Chat,
+    User,
+    Model,
+    Handler,
+    /// Calendar day (UTC), as "YYYY-MM-DD".
+    Day,
+}
+
+impl UsageGroupBy {
+    /// SQL expression for the group key (always rendered as text).
+    pub(crate) fn sql_expr(&self) -> &'static str {
+        match self {
+            UsageGroupBy::Chat => "CAST(chat_id AS TEXT)",
+            UsageGroupBy::User => "CAST(user_id AS TEXT)",
+            UsageGroupBy::Model => "model",
+            UsageGroupBy::Handler => "handler",
+            UsageGroupBy::Day => "substr(created_at, 1, 10)",
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! LLM / embedding usage record for the `llm_usage` ledger.
+//!
+//! Written by UsageRepository::record.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// `kind` of chat completion calls.
+pub const USAGE_KIND_CHAT: &str = "chat";
+/// `kind` of embedding calls.
+pub const USAGE_KIND_EMBEDDING: &str = "embedding";
+
+/// One row of the llm_usage table: token usage of a single LLM or embedding call.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UsageRecord {
+    /// Primary key (UUID).
+    pub id: String,
+    /// Chat the call was made for (None for calls without chat context, e.g. embeddings).
+    pub chat_id: Option<i64>,
+    /// Telegram user the call was made for.
+    pub user_id: Option<i64>,
+    /// Model name (e.g. "gpt-4o-mini", "text-embedding-3-small").
+    pub model: String,
+    /// Component that made the call (e.g. "inline_llm", "react", "embedding").
+    pub handler: String,
+    /// "chat" or "embedding".
+    pub kind: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    /// True when counts are estimated locally because the provider did not report usage.
+    pub estimated: bool,
+    /// When the call finished.
+    pub created_at: DateTime<Utc>,
+}
+
+impl UsageRecord {
+    /// Creates a record with a generated UUID and current timestamp; total = prompt + completion.
+    pub fn new(
+        model: impl Into<String>,
+        handler: impl Into<String>,
+        kind: impl Into<String>,
+        prompt_tokens: i64,
+        completion_tokens: i64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            chat_id: None,
+            user_id: None,
+            model: model.into(),
+            handler: handler.into(),
+            kind: kind.into(),
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            estimated: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Sets the chat and user the call was made for.
+    pub fn for_message(mut self, chat_id: Option<i64>, user_id: Option<i64>) -> Self {
+        self.chat_id = chat_id;
+        self.user_id = user_id;
+        self
+    }
+
+    /// Overrides the total when the provider reports one that differs from prompt + completion.
+    pub fn with_total_tokens(mut self, total_tokens: i64) -> Self {
+        self.total_tokens = total_tokens;
+        self
+    }
+
+    /// Marks the counts as locally estimated.
+    pub fn estimated(mut self) -> Self {
+        self.estimated = true;
+        self
+    }
+}
@@ -0,0 +1,244 @@
+//! Usage repository: the `llm_usage` ledger of LLM and embedding token usage.
+//!
+//! Uses SqlitePoolManager (same database file as messages) and the models (UsageRecord, UsageQuery, UsageTotals).
+
+use super::models::{UsageGroupBy, UsageQuery, UsageRecord, UsageTotals};
+use super::sqlite_pool::SqlitePoolManager;
+use chrono::{DateTime, Utc};
+use sqlx::{QueryBuilder, Sqlite};
+use tracing::info;
+
+/// SQLite-backed token usage ledger (record, totals, totals_by, deletes by user, chat and age).
+#[derive(Clone)]
+pub struct UsageRepository {
+    /// Shared SQLite pool used for all queries.
+    pool_manager: SqlitePoolManager,
+}
+
+impl UsageRepository {
+    /// Creates a repository and initializes the llm_usage table and indexes.
+    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool_manager = SqlitePoolManager::new(database_url).await?;
+        let repo = Self { pool_manager };
+        repo.init().await?;
+        Ok(repo)
+    }
+
+    async fn init(&self) -> Result<(), sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS llm_usage (
+                id TEXT PRIMARY KEY,
+                chat_id INTEGER,
+                user_id INTEGER,
+                model TEXT NOT NULL,
+                handler TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                total_tokens INTEGER NOT NULL,
+                estimated INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_llm_usage_chat_id ON llm_usage(chat_id);
+            CREATE INDEX IF NOT EXISTS idx_llm_usage_user_id ON llm_usage(user_id);
+            CREATE INDEX IF NOT EXISTS idx_llm_usage_created_at ON llm_usage(created_at);
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Inserts one usage record.
+    pub async fn record(&self, usage: &UsageRecord) -> Result<(), sqlx::Error> {
+        let pool = self.pool_manager.pool();
+
+        info!(
+            chat_id = ?usage.chat_id,
+            user_id = ?usage.user_id,
+            model = %usage.model,
+            handler = %usage.handler,
+            kind = %usage.kind,
+            prompt_tokens = usage.prompt_tokens,
+            completion_tokens = usage.completion_tokens,
+            estimated = usage.estimated,
+            "Recording LLM usage"
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO llm_usage (id, chat_id, user_id, model, handler, kind, prompt_tokens, completion_tokens, total_tokens, estimated, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&usage.id)
+        .bind(usage.chat_id)
+        .bind(usage.user_id)
+        .bind(&usage.model)
+        .bind(&usage.handler)
+        .bind(&usage.kind)
+        .bind(usage.prompt_tokens)
+        .bind(usage.completion_tokens)
+        .bind(usage.total_tokens)
+        .bind(usage.estimated)
+        .bind(usage.created_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns summed usage over all calls matching the query.
+    pub async fn totals(&self, query: &UsageQuery) -> Result<UsageTotals, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT COUNT(*), COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0), \
+             COALESCE(SUM(total_tokens), 0) FROM llm_usage WHERE 1=1",
+        );
+        push_usage_filters(&mut builder, query);
+
+        let (calls, prompt_tokens, completion_tokens, total_tokens): (i64, i64, i64, i64) =
+            builder.build_query_as().fetch_one(pool).await?;
+        Ok(UsageTotals {
+            calls,
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+        })
+    }
+
+    /// Returns summed usage per group (chat, user, model, handler or day) for calls matching the query,
+    /// ordered by total tokens descending. Rows with no chat/user are grouped under the key `None`.
+    pub async fn totals_by(
+        &self,
+        query: &UsageQuery,
+        group_by: UsageGroupBy,
+    ) -> Result<Vec<(Option<String>, UsageTotals)>, sqlx::Error> {
+        let pool = self.pool_manager.pool();
+        let key = group_by.sql_expr();
+        let mut builder = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} AS group_key, COUNT(*), COALESCE(SUM(prompt_tokens), 0), \
+             COALESCE(SUM(completion_tokens), 0), COALESCE(SUM(total_tokens), 0) FROM llm_usage WHERE 1=1",
+            key
+        ));
+        push_usage_filters(&mut builder, query);
+        builder.push(" GROUP BY group_key ORDER BY SUM(total_tokens) DESC");
+
+        let rows: Vec<(Option<String>, i64, i64, i64, i64)> =
+            builder.build_query_as().fetch_all(pool).await?;
+        Ok(rows
+            .into_iter()
+            .map(|(key, calls, prompt_tokens, completion_tokens, total_tokens)| {
+                (
+                    key,
+                    UsageTotals {
+                        calls,
+                        prompt_tokens,
+                        completion_tokens,
+                        total_tokens,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Deletes every usage row of the given user. Returns the number of rows deleted.
+    pub async fn delete_user(&self, user_id: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM llm_usage WHERE user_id = ?")
+            .bind(user_id)
+            .execute(self.pool_manager.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes every usage row of the given chat. Returns the number of rows deleted.
+    pub async fn delete_chat(&self, chat_id: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM llm_usage WHERE chat_id = ?")
+            .bind(chat_id)
+            .execute(self.pool_manager.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes rows older than `days` days, except those of `excluded_chat_ids` (chats with their own
+    /// retention). Rows without a chat are included. Returns the number of rows deleted.
+    pub async fn cleanup_old_rows_excluding_chats(
+        &self,
+        days: u32,
+        excluded_chat_ids: &[i64],
+    ) -> Result<u64, sqlx::Error> {
+        let mut builder =
+            QueryBuilder::<Sqlite>::new("DELETE FROM llm_usage WHERE created_at < ");
+        builder.push_bind(retention_cutoff(days));
+        if !excluded_chat_ids.is_empty() {
+            builder.push(" AND (chat_id IS NULL OR chat_id NOT IN (");
+            let mut ids = builder.separated(", ");
+            for chat_id in excluded_chat_ids {
+                ids.push_bind(*chat_id);
+            }
+            builder.push("))");
+        }
+        let result = builder.build().execute(self.pool_manager.pool()).await?;
+        info!(
+            deleted = result.rows_affected(),
+            days = days,
+            excluded_chats = excluded_chat_ids.len(),
+            "Deleted old usage rows"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes the chat's rows older than `days` days. Returns the number of rows deleted.
+    pub async fn cleanup_old_rows_in_chat(&self, chat_id: i64, days: u32) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM llm_usage WHERE chat_id = ? AND created_at < ?")
+            .bind(chat_id)
+            .bind(retention_cutoff(days))
+            .execute(self.pool_manager.pool())
+            .await?;
+        info!(
+            chat_id = chat_id,
+            deleted = result.rows_affected(),
+            days = days,
+            "Deleted old usage rows in chat"
+        );
+        Ok(result.rows_affected())
+    }
+}
+
+fn retention_cutoff(days: u32) -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::days(days as i64)
+}
+
+/// Appends `AND ...` conditions for the filters set in `query`.
+fn push_usage_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &UsageQuery) {
+    if let Some(chat_id) = query.chat_id {
+        builder.push(" AND chat_id = ").push_bind(chat_id);
+    }
+    if let Some(user_id) = query.user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(model) = &query.model {
+        builder.push(" AND model = ").push_bind(model.clone());
+    }
+    if let Some(handler) = &query.handler {
+        builder.push(" AND handler = ").push_bind(handler.clone());
+    }
+    if let Some(kind) = &query.kind {
+        builder.push(" AND kind = ").push_bind(kind.clone());
+    }
+    if let Some(start) = query.start_date {
+        builder.push(" AND created_at >= ").push_bind(start);
+    }
+    if let Some(end) = query.end_date {
+        builder.push(" AND created_at < ").push_bind(end);
+    }
+}
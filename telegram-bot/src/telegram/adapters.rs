@@ -37,7 +37,7 @@ impl<'a> ToCoreMessage for TelegramMessageWrapper<'a> {
                 }),
             chat: Chat {
                 id: self.0.chat.id.0,
-                chat_type: format!("{:?}", self.0.chat.kind),
+                chat_type: chat_type(&self.0.chat).to_string(),
             },
             content: self.0.text().unwrap_or("").to_string(),
             message_type: "text".to_string(),
@@ -50,6 +50,19 @@ impl<'a> ToCoreMessage for TelegramMessageWrapper<'a> {
     }
 }
 
+/// Telegram's name for the kind of chat: `private`, `group`, `supergroup` or `channel`.
+fn chat_type(chat: &teloxide::types::Chat) -> &'static str {
+    if chat.is_private() {
+        "private"
+    } else if chat.is_group() {
+        "group"
+    } else if chat.is_supergroup() {
+        "supergroup"
+    } else {
+        "channel"
+    }
+}
+
 impl<'a> TelegramMessageWrapper<'a> {
     /// Returns the id of the replied-to message if present.
     fn get_reply_to_message_id(&self) -> Option<String> {
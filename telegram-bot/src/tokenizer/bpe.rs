@@ -0,0 +1,272 @@
+//! Byte-level BPE tokenizer for tiktoken vocabularies (cl100k_base, o200k_base).
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use fancy_regex::Regex;
+
+use super::Tokenizer;
+
+/// Pre-tokenization pattern of cl100k_base.
+const CL100K_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// Pre-tokenization pattern of o200k_base.
+const O200K_PATTERN: &str = concat!(
+    r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+);
+
+/// A BPE encoding family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// GPT-4, GPT-3.5 and the text-embedding-3 / ada-002 embedding models.
+    Cl100kBase,
+    /// GPT-4o, GPT-4.1, GPT-5 and the o-series reasoning models.
+    O200kBase,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::O200kBase => "o200k_base",
+        }
+    }
+
+    /// Encoding from its name (`cl100k_base`, `o200k_base`).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cl100k_base" => Some(Encoding::Cl100kBase),
+            "o200k_base" => Some(Encoding::O200kBase),
+            _ => None,
+        }
+    }
+
+    /// Encoding of an OpenAI model name, ignoring a `provider/` prefix. None for other models.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model
+            .rsplit('/')
+            .next()
+            .unwrap_or(model)
+            .to_ascii_lowercase();
+        const O200K_PREFIXES: [&str; 8] = [
+            "gpt-4o",
+            "chatgpt-4o",
+            "gpt-4.1",
+            "gpt-4.5",
+            "gpt-5",
+            "o1",
+            "o3",
+            "o4",
+        ];
+        const CL100K_PREFIXES: [&str; 5] = [
+            "gpt-4",
+            "gpt-3.5",
+            "gpt-35",
+            "text-embedding-3",
+            "text-embedding-ada-002",
+        ];
+        if O200K_PREFIXES.iter().any(|p| model.starts_with(p)) {
+            Some(Encoding::O200kBase)
+        } else if CL100K_PREFIXES.iter().any(|p| model.starts_with(p)) {
+            Some(Encoding::Cl100kBase)
+        } else {
+            None
+        }
+    }
+
+    /// Vocabulary file name, as published with tiktoken.
+    pub fn file_name(self) -> String {
+        format!("{}.tiktoken", self.name())
+    }
+
+    fn pattern(self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => CL100K_PATTERN,
+            Encoding::O200kBase => O200K_PATTERN,
+        }
+    }
+}
+
+/// Byte-level BPE over a tiktoken vocabulary: text is split with the encoding's pattern, and each
+/// piece is merged pairwise by rank, lowest first. Special tokens are counted as plain text.
+pub struct BpeTokenizer {
+    encoding: Encoding,
+    ranks: HashMap<Vec<u8>, u32>,
+    pattern: Regex,
+}
+
+impl BpeTokenizer {
+    /// Parses a `.tiktoken` vocabulary (`<base64 token> <rank>` per line).
+    pub fn from_tiktoken(encoding: Encoding, data: &str) -> Result<Self> {
+        let mut ranks = HashMap::new();
+        for (line_no, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (token, rank) = line
+                .split_once(' ')
+                .with_context(|| format!("line {}: expected `<token> <rank>`", line_no + 1))?;
+            let token = BASE64
+                .decode(token)
+                .with_context(|| format!("line {}: invalid base64 token", line_no + 1))?;
+            let rank: u32 = rank
+                .trim()
+                .parse()
+                .with_context(|| format!("line {}: invalid rank", line_no + 1))?;
+            ranks.insert(token, rank);
+        }
+        // Every piece must be encodable down to single bytes.
+        if let Some(byte) = (0..=255u8).find(|b| !ranks.contains_key(&[*b][..])) {
+            bail!("vocabulary has no token for byte {:#04x}", byte);
+        }
+        let pattern = Regex::new(encoding.pattern()).context("invalid pre-tokenization pattern")?;
+        Ok(Self {
+            encoding,
+            ranks,
+            pattern,
+        })
+    }
+
+    /// Loads a `.tiktoken` vocabulary file.
+    pub fn from_file(encoding: Encoding, path: &Path) -> Result<Self> {
+        let data =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::from_tiktoken(encoding, &data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Token ids of `text`.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        let mut ids = Vec::new();
+        for piece in self.pieces(text) {
+            let piece = piece.as_bytes();
+            if let Some(rank) = self.ranks.get(piece) {
+                ids.push(*rank);
+                continue;
+            }
+            let bounds = self.merge(piece);
+            ids.extend(bounds.windows(2).map(|w| self.ranks[&piece[w[0]..w[1]]]));
+        }
+        ids
+    }
+
+    /// Splits `text` with the encoding's pattern. If the regex engine gives up on some input, the
+    /// rest of the text becomes a single piece (encoded byte pair by byte pair).
+    fn pieces<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        let mut pieces = Vec::new();
+        let mut end = 0;
+        for m in self.pattern.find_iter(text) {
+            match m {
+                Ok(m) => {
+                    pieces.push(m.as_str());
+                    end = m.end();
+                }
+                Err(_) => {
+                    pieces.push(&text[end..]);
+                    return pieces;
+                }
+            }
+        }
+        pieces
+    }
+
+    /// Merges the bytes of `piece` by rank and returns the token boundaries (first is 0, last is
+    /// `piece.len()`).
+    fn merge(&self, piece: &[u8]) -> Vec<usize> {
+        let rank_of = |parts: &[(usize, u32)], i: usize| -> u32 {
+            if i + 2 < parts.len() {
+                self.ranks
+                    .get(&piece[parts[i].0..parts[i + 2].0])
+                    .copied()
+                    .unwrap_or(u32::MAX)
+            } else {
+                u32::MAX
+            }
+        };
+        // (start of part, rank of merging it with the next part)
+        let mut parts: Vec<(usize, u32)> = (0..=piece.len())
+            .map(|i| {
+                let rank = piece
+                    .get(i..i + 2)
+                    .and_then(|pair| self.ranks.get(pair))
+                    .copied();
+                (i, rank.unwrap_or(u32::MAX))
+            })
+            .collect();
+        while let Some((i, _)) = parts[..parts.len() - 1]
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, rank))| *rank != u32::MAX)
+            .min_by_key(|(_, (_, rank))| *rank)
+        {
+            parts.remove(i + 1);
+            parts[i].1 = rank_of(&parts, i);
+            if i > 0 {
+                parts[i - 1].1 = rank_of(&parts, i - 1);
+            }
+        }
+        parts.into_iter().map(|(start, _)| start).collect()
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> &str {
+        self.encoding.name()
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.pieces(text)
+            .into_iter()
+            .map(|piece| {
+                let piece = piece.as_bytes();
+                if self.ranks.contains_key(piece) {
+                    1
+                } else {
+                    self.merge(piece).len() - 1
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn models_map_to_their_encoding() {
+        assert_eq!(
+            Encoding::for_model("gpt-4o-mini"),
+            Some(Encoding::O200kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("openai/gpt-4.1"),
+            Some(Encoding::O200kBase)
+        );
+        assert_eq!(Encoding::for_model("o3-mini"), Some(Encoding::O200kBase));
+        assert_eq!(
+            Encoding::for_model("gpt-4-turbo"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("gpt-3.5-turbo"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("text-embedding-3-small"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(Encoding::for_model("glm-4-flash"), None);
+        assert_eq!(Encoding::parse("O200K_BASE"), Some(Encoding::O200kBase));
+        assert_eq!(Encoding::O200kBase.file_name(), "o200k_base.tiktoken");
+    }
+}
@@ -0,0 +1,52 @@
+//! Tokenizer configuration: trait and env-based implementation.
+
+use anyhow::Result;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Tokenizer configuration interface: where vocabularies live and which encoding to use.
+pub trait TokenizerConfig: Send + Sync {
+    /// Directory holding `<encoding>.tiktoken` vocabulary files.
+    fn vocab_dir(&self) -> &Path;
+    /// Encoding used for every model (e.g. `o200k_base` for a compatible model the bot does not
+    /// know); None picks the encoding from the model name.
+    fn encoding(&self) -> Option<&str>;
+}
+
+/// Tokenizer config loaded from environment variables.
+#[derive(Debug, Clone)]
+pub struct EnvTokenizerConfig {
+    pub tokenizer_vocab_dir: PathBuf,
+    pub tokenizer_encoding: Option<String>,
+}
+
+impl TokenizerConfig for EnvTokenizerConfig {
+    fn vocab_dir(&self) -> &Path {
+        &self.tokenizer_vocab_dir
+    }
+    fn encoding(&self) -> Option<&str> {
+        self.tokenizer_encoding.as_deref()
+    }
+}
+
+impl EnvTokenizerConfig {
+    /// Load from environment variables.
+    ///
+    /// - `TOKENIZER_VOCAB_DIR`: directory of `.tiktoken` files (default `./data/tokenizers`)
+    /// - `TOKENIZER_ENCODING`: `cl100k_base` or `o200k_base` for all models (optional)
+    pub fn from_env() -> Result<Self> {
+        let tokenizer_vocab_dir = env::var("TOKENIZER_VOCAB_DIR")
+            .ok()
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| "./data/tokenizers".to_string())
+            .into();
+        let tokenizer_encoding = env::var("TOKENIZER_ENCODING")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        Ok(Self {
+            tokenizer_vocab_dir,
+            tokenizer_encoding,
+        })
+    }
+}
@@ -0,0 +1,87 @@
+//! Token counting for context budgets and usage estimates.
+//!
+//! [`BpeTokenizer`] counts tokens exactly for the cl100k/o200k encodings used by OpenAI models, from
+//! `.tiktoken` vocabulary files on disk. Models without a known encoding, or without their
+//! vocabulary file, fall back to [`EstimateTokenizer`] (bytes / 4, one token per CJK character).
+//!
+//! ## Submodules
+//!
+//! - [`bpe`] – BpeTokenizer (byte-level BPE) and Encoding (cl100k_base, o200k_base; model lookup)
+//! - [`config`] – TokenizerConfig, EnvTokenizerConfig (vocabulary directory, encoding override)
+
+mod bpe;
+mod config;
+
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+pub use bpe::{BpeTokenizer, Encoding};
+pub use config::{EnvTokenizerConfig, TokenizerConfig};
+
+use crate::memory_core::keyword::is_cjk;
+
+/// Counts the tokens a text encodes to.
+pub trait Tokenizer: Send + Sync {
+    /// Encoding name (`cl100k_base`, `o200k_base`) or `estimate` for the fallback.
+    fn name(&self) -> &str;
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Fallback tokenizer: like [`estimate_tokens`] (bytes / 4, at least 1), except that each CJK
+/// character counts as one token, since BPE vocabularies rarely merge them below that.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EstimateTokenizer;
+
+impl Tokenizer for EstimateTokenizer {
+    fn name(&self) -> &str {
+        "estimate"
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        let (cjk_chars, other_bytes) = text.chars().fold((0, 0), |(cjk, other), c| {
+            if is_cjk(c) {
+                (cjk + 1, other)
+            } else {
+                (cjk, other + c.len_utf8())
+            }
+        });
+        (cjk_chars + other_bytes.div_ceil(4)).max(1)
+    }
+}
+
+/// Tokenizer for `model`: the configured encoding override or the model's known encoding, loaded
+/// from the vocabulary directory. Falls back to [`EstimateTokenizer`] when no encoding applies or
+/// the vocabulary cannot be loaded.
+pub fn load_tokenizer(cfg: Option<&dyn TokenizerConfig>, model: &str) -> Arc<dyn Tokenizer> {
+    let Some(cfg) = cfg else {
+        return Arc::new(EstimateTokenizer);
+    };
+    let encoding = match cfg.encoding() {
+        Some(name) => Encoding::parse(name).or_else(|| {
+            warn!(encoding = %name, "Unknown TOKENIZER_ENCODING; using the model's encoding");
+            Encoding::for_model(model)
+        }),
+        None => Encoding::for_model(model),
+    };
+    let Some(encoding) = encoding else {
+        info!(model = %model, "No BPE encoding known for model; estimating tokens");
+        return Arc::new(EstimateTokenizer);
+    };
+    let path = cfg.vocab_dir().join(encoding.file_name());
+    match BpeTokenizer::from_file(encoding, &path) {
+        Ok(tokenizer) => {
+            info!(model = %model, encoding = encoding.name(), "Using BPE tokenizer");
+            Arc::new(tokenizer)
+        }
+        Err(e) => {
+            warn!(
+                model = %model,
+                path = %path.display(),
+                error = %e,
+                "Failed to load tokenizer vocabulary; estimating tokens"
+            );
+            Arc::new(EstimateTokenizer)
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Tests for [`telegram_bot::memory::backfill_store`]: messages are imported into memory in batches,
+//! embedded with one `embed_batch` call and written with one `add_batch` call per batch.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use telegram_bot::embedding::EmbeddingService;
+use telegram_bot::memory::{backfill_store, InMemoryVectorStore, MemoryEntry, MemoryRole, MemoryStore};
+use telegram_bot::storage::{MessageRecord, MessageRepository};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+/// Embedding API that counts batch calls and rejects single embeds.
+#[derive(Default)]
+struct CountingEmbedding {
+    batches: AtomicUsize,
+}
+
+#[async_trait]
+impl EmbeddingService for CountingEmbedding {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        anyhow::bail!("single embed must not be used")
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.batches.fetch_add(1, Ordering::SeqCst);
+        Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+    }
+}
+
+/// Store that counts single and batch adds.
+#[derive(Default)]
+struct CountingStore {
+    inner: InMemoryVectorStore,
+    adds: AtomicUsize,
+    batches: AtomicUsize,
+}
+
+#[async_trait]
+impl MemoryStore for CountingStore {
+    async fn add(&self, entry: MemoryEntry) -> Result<()> {
+        self.adds.fetch_add(1, Ordering::SeqCst);
+        self.inner.add(entry).await
+    }
+
+    async fn add_batch(&self, entries: Vec<MemoryEntry>) -> Result<()> {
+        self.batches.fetch_add(1, Ordering::SeqCst);
+        self.inner.add_batch(entries).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>> {
+        self.inner.get(id).await
+    }
+
+    async fn update(&self, entry: MemoryEntry) -> Result<()> {
+        self.inner.update(entry).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>> {
+        self.inner.search_by_user(user_id).await
+    }
+
+    async fn search_by_conversation(&self, conversation_id: &str) -> Result<Vec<MemoryEntry>> {
+        self.inner.search_by_conversation(conversation_id).await
+    }
+
+    async fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        user_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>> {
+        self.inner
+            .semantic_search(query_embedding, limit, user_id, conversation_id)
+            .await
+    }
+}
+
+async fn repo_with_messages(count: usize) -> (TempDir, MessageRepository) {
+    let dir = TempDir::new().expect("temp dir");
+    let repo = MessageRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .expect("repo");
+    for i in 0..count {
+        let direction = if i % 2 == 0 { "received" } else { "sent" };
+        repo.save(&MessageRecord::new(
+            1,
+            10,
+            None,
+            None,
+            None,
+            "text".to_string(),
+            format!("message {}", i),
+            direction.to_string(),
+            None,
+        ))
+        .await
+        .expect("save");
+    }
+    (dir, repo)
+}
+
+#[tokio::test]
+async fn backfill_embeds_and_writes_each_batch_once() {
+    let (_dir, repo) = repo_with_messages(5).await;
+    let store = Arc::new(CountingStore::default());
+    let embedding = Arc::new(CountingEmbedding::default());
+
+    let written = backfill_store(&repo, store.clone(), Some(embedding.clone()), 64)
+        .await
+        .expect("backfill");
+
+    assert_eq!(written, 5);
+    assert_eq!(embedding.batches.load(Ordering::SeqCst), 1);
+    assert_eq!(store.batches.load(Ordering::SeqCst), 1);
+    assert_eq!(store.adds.load(Ordering::SeqCst), 0);
+    let entries = store.search_by_conversation("10").await.unwrap();
+    assert_eq!(entries.len(), 5);
+    assert!(entries.iter().all(|e| e.embedding.is_some()));
+    assert_eq!(
+        entries
+            .iter()
+            .filter(|e| e.metadata.role == MemoryRole::Assistant)
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn backfill_splits_batches_and_skips_imported_messages() {
+    let (_dir, repo) = repo_with_messages(5).await;
+    let store = Arc::new(CountingStore::default());
+    let embedding = Arc::new(CountingEmbedding::default());
+
+    let written = backfill_store(&repo, store.clone(), Some(embedding.clone()), 2)
+        .await
+        .expect("backfill");
+    assert_eq!(written, 5);
+    assert_eq!(embedding.batches.load(Ordering::SeqCst), 3);
+    assert_eq!(store.batches.load(Ordering::SeqCst), 3);
+
+    let again = backfill_store(&repo, store.clone(), Some(embedding.clone()), 2)
+        .await
+        .expect("re-run");
+    assert_eq!(again, 0);
+    assert_eq!(embedding.batches.load(Ordering::SeqCst), 3);
+    assert_eq!(store.search_by_conversation("10").await.unwrap().len(), 5);
+}
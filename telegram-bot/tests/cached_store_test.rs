@@ -0,0 +1,116 @@
+//! Tests for [`CachedMemoryStore`]: repeated reads are served from the cache, writes through the
+//! wrapper invalidate it, the least recently used key is evicted, and partial windows fall back
+//! to the inner store.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use telegram_bot::memory::{
+    CachedMemoryStore, MemoryEntry, MemoryFilter, MemoryMetadata, MemoryOrder, MemoryStore,
+};
+
+mod mock_memory_store;
+use mock_memory_store::MockMemoryStore;
+
+/// Metadata of a message by `u1` in `conversation_id`, sent `minutes_ago`.
+fn sent(conversation_id: &str, minutes_ago: i64) -> MemoryMetadata {
+    MemoryMetadata::default()
+        .with_user("u1")
+        .with_conversation(conversation_id)
+        .with_timestamp(Utc::now() - Duration::minutes(minutes_ago))
+}
+
+async fn recent(store: &dyn MemoryStore, conversation_id: &str, limit: usize) -> Vec<String> {
+    store
+        .list(
+            &MemoryFilter::for_conversation(conversation_id),
+            MemoryOrder::NewestFirst,
+            limit,
+            None,
+        )
+        .await
+        .unwrap()
+        .entries
+        .into_iter()
+        .map(|e| e.content)
+        .collect()
+}
+
+#[tokio::test]
+async fn repeated_reads_hit_cache_and_writes_invalidate() {
+    let mock = MockMemoryStore::new();
+    let cached = CachedMemoryStore::new(Arc::new(mock.clone()), 8);
+    cached.add(MemoryEntry::new("first".to_string(), sent("c1", 2))).await.unwrap();
+
+    assert_eq!(recent(&cached, "c1", 10).await, vec!["first"]);
+    assert_eq!(recent(&cached, "c1", 10).await, vec!["first"]);
+    assert_eq!(cached.search_by_conversation("c1").await.unwrap().len(), 1);
+    assert_eq!(
+        mock.get_query_call_count(),
+        1,
+        "only the first read reaches the store"
+    );
+    let stats = cached.stats();
+    assert_eq!((stats.hits, stats.misses), (2, 1));
+    assert_eq!(stats.conversations, 1);
+
+    let second = MemoryEntry::new("second".to_string(), sent("c1", 1));
+    cached.add(second.clone()).await.unwrap();
+    assert_eq!(recent(&cached, "c1", 10).await, vec!["second", "first"]);
+
+    let mut edited = second.clone();
+    edited.content = "second (edited)".to_string();
+    cached.update(edited).await.unwrap();
+    assert_eq!(recent(&cached, "c1", 1).await, vec!["second (edited)"]);
+
+    cached.delete(second.id).await.unwrap();
+    assert_eq!(recent(&cached, "c1", 10).await, vec!["first"]);
+    assert_eq!(cached.stats().misses, 4);
+}
+
+#[tokio::test]
+async fn least_recently_used_conversation_is_evicted() {
+    let cached = CachedMemoryStore::new(Arc::new(MockMemoryStore::new()), 1);
+    cached.add(MemoryEntry::new("a".to_string(), sent("c1", 1))).await.unwrap();
+    cached.add(MemoryEntry::new("b".to_string(), sent("c2", 1))).await.unwrap();
+
+    recent(&cached, "c1", 5).await;
+    recent(&cached, "c2", 5).await;
+    recent(&cached, "c1", 5).await;
+    let stats = cached.stats();
+    assert_eq!((stats.hits, stats.misses), (0, 3));
+    assert_eq!(stats.conversations, 1);
+}
+
+#[tokio::test]
+async fn partial_window_answers_short_pages_only() {
+    let cached = CachedMemoryStore::new(Arc::new(MockMemoryStore::new()), 8).with_window(2);
+    for i in 0..5 {
+        cached
+            .add(MemoryEntry::new(format!("m{}", i), sent("c1", 10 - i)))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(recent(&cached, "c1", 1).await, vec!["m4"]);
+    assert_eq!(recent(&cached, "c1", 1).await, vec!["m4"]);
+    assert_eq!(cached.stats().hits, 1);
+
+    // Longer pages and full listings need entries beyond the window.
+    assert_eq!(recent(&cached, "c1", 3).await, vec!["m4", "m3", "m2"]);
+    assert_eq!(cached.search_by_conversation("c1").await.unwrap().len(), 5);
+    assert_eq!(cached.stats().hits, 1);
+}
+
+#[tokio::test]
+async fn delete_by_conversation_drops_user_windows_holding_it() {
+    let cached = CachedMemoryStore::new(Arc::new(MockMemoryStore::new()), 8);
+    cached.add(MemoryEntry::new("in c1".to_string(), sent("c1", 2))).await.unwrap();
+    cached.add(MemoryEntry::new("in c2".to_string(), sent("c2", 1))).await.unwrap();
+    assert_eq!(cached.search_by_user("u1").await.unwrap().len(), 2);
+
+    assert_eq!(cached.delete_by_conversation("c1").await.unwrap(), 1);
+    let left = cached.search_by_user("u1").await.unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].content, "in c2");
+}
@@ -0,0 +1,273 @@
+//! Tests for memory compaction ([`MemoryCompactor`]) and [`ConversationSummaryStrategy`].
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, TimeZone, Utc};
+use telegram_bot::embedding::EmbeddingService;
+use telegram_bot::memory::{
+    CompactionPolicy, ContextStrategy, ConversationSummaryStrategy, InMemoryVectorStore,
+    MemoryCompactor, MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore, MemorySummarizer,
+    StrategyResult,
+};
+use telegram_bot::tokenizer::Tokenizer;
+use uuid::Uuid;
+
+struct FixedEmbedding;
+
+#[async_trait]
+impl EmbeddingService for FixedEmbedding {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Ok(vec![1.0, 0.0])
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+    }
+}
+
+/// Joins the contents of the window.
+struct JoiningSummarizer;
+
+#[async_trait]
+impl MemorySummarizer for JoiningSummarizer {
+    async fn summarize(&self, entries: &[MemoryEntry]) -> Result<String> {
+        Ok(entries
+            .iter()
+            .map(|e| e.content.as_str())
+            .collect::<Vec<_>>()
+            .join("; "))
+    }
+}
+
+/// Counts whitespace-separated words.
+struct WordTokenizer;
+
+impl Tokenizer for WordTokenizer {
+    fn name(&self) -> &str {
+        "words"
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+}
+
+/// Store whose deletes fail, as if the process died after the summary was written.
+struct FailingDeletes(Arc<InMemoryVectorStore>);
+
+#[async_trait]
+impl MemoryStore for FailingDeletes {
+    async fn add(&self, entry: MemoryEntry) -> Result<()> {
+        self.0.add(entry).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<MemoryEntry>> {
+        self.0.get(id).await
+    }
+
+    async fn update(&self, entry: MemoryEntry) -> Result<()> {
+        self.0.update(entry).await
+    }
+
+    async fn delete(&self, _id: Uuid) -> Result<()> {
+        anyhow::bail!("interrupted")
+    }
+
+    async fn search_by_user(&self, user_id: &str) -> Result<Vec<MemoryEntry>> {
+        self.0.search_by_user(user_id).await
+    }
+
+    async fn search_by_conversation(&self, conversation_id: &str) -> Result<Vec<MemoryEntry>> {
+        self.0.search_by_conversation(conversation_id).await
+    }
+
+    async fn semantic_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        user_id: Option<&str>,
+        conversation_id: Option<&str>,
+    ) -> Result<Vec<(f32, MemoryEntry)>> {
+        self.0
+            .semantic_search(query_embedding, limit, user_id, conversation_id)
+            .await
+    }
+}
+
+#[tokio::test]
+async fn compaction_summarizes_old_windows_and_archives_originals() {
+    let store = Arc::new(InMemoryVectorStore::new());
+    let archive = Arc::new(InMemoryVectorStore::new());
+    let day = Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap();
+    let now = day + Duration::days(40);
+    // Three entries in one old day, one alone in another old day, one recent.
+    let entries = [
+        ("flight on May 3", day + Duration::hours(1)),
+        ("seat 12A", day + Duration::hours(2)),
+        ("hotel booked", day + Duration::hours(3)),
+        ("lonely", day + Duration::days(2)),
+        ("today", now),
+    ];
+    let chat = MemoryMetadata::default().with_user("1").with_conversation("10");
+    for (content, timestamp) in entries {
+        let metadata = chat.clone().with_timestamp(timestamp);
+        store.add(MemoryEntry::new(content.to_string(), metadata)).await.unwrap();
+    }
+
+    let compactor = MemoryCompactor::new(
+        store.clone(),
+        Arc::new(FixedEmbedding),
+        CompactionPolicy::new(Duration::days(30), Duration::hours(24), 2),
+    )
+    .with_archive_store(archive.clone())
+    .with_summarizer(Arc::new(JoiningSummarizer));
+
+    let report = compactor.compact_conversation("10", now).await.unwrap();
+    assert_eq!(
+        (report.summaries, report.compacted, report.archived),
+        (1, 3, 3)
+    );
+
+    let remaining = store.search_by_conversation("10").await.unwrap();
+    let summary = remaining
+        .iter()
+        .find(|e| e.metadata.role == MemoryRole::System)
+        .expect("summary entry");
+    assert!(summary
+        .content
+        .contains("flight on May 3; seat 12A; hotel booked"));
+    assert!(summary.embedding.is_some());
+    assert_eq!(summary.metadata.user_id.as_deref(), Some("1"));
+    assert_eq!(remaining.len(), 3, "summary, lonely and today remain");
+    assert_eq!(archive.search_by_conversation("10").await.unwrap().len(), 3);
+
+    // Summaries are not compacted again.
+    let report = compactor.compact_conversation("10", now).await.unwrap();
+    assert_eq!(report.summaries, 0);
+
+    let result = ConversationSummaryStrategy::new(3)
+        .build_context(store.as_ref(), &None, &Some("10".to_string()), &None)
+        .await
+        .unwrap();
+    match result {
+        StrategyResult::Messages { messages, .. } => {
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].role, MemoryRole::System);
+            assert!(messages[0].content.starts_with("Summary of 3 messages"));
+        }
+        other => panic!("expected summaries, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn compaction_requires_summarizer() {
+    let compactor = MemoryCompactor::new(
+        Arc::new(InMemoryVectorStore::new()),
+        Arc::new(FixedEmbedding),
+        CompactionPolicy::new(Duration::days(30), Duration::hours(24), 2),
+    );
+    assert!(!compactor.has_summarizer());
+    assert!(compactor
+        .compact_conversation("10", Utc::now())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn compact_all_finds_conversations_in_the_store() {
+    let store = Arc::new(InMemoryVectorStore::new());
+    let day = Utc::now() - Duration::days(60);
+    for (i, chat) in ["10", "10", "77", "77"].iter().enumerate() {
+        let metadata = MemoryMetadata::default()
+            .with_conversation(*chat)
+            .with_timestamp(day + Duration::minutes(i as i64));
+        store.add(MemoryEntry::new(format!("message {}", i), metadata)).await.unwrap();
+    }
+
+    let compactor = MemoryCompactor::new(
+        store.clone(),
+        Arc::new(FixedEmbedding),
+        CompactionPolicy::new(Duration::days(30), Duration::hours(24), 2),
+    )
+    .with_summarizer(Arc::new(JoiningSummarizer));
+
+    let report = compactor.compact_all().await.unwrap();
+    assert_eq!((report.summaries, report.compacted), (2, 4));
+    for chat in ["10", "77"] {
+        let remaining = store.search_by_conversation(chat).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].metadata.role, MemoryRole::System);
+    }
+}
+
+#[tokio::test]
+async fn interrupted_compaction_is_finished_without_a_second_summary() {
+    let store = Arc::new(InMemoryVectorStore::new());
+    let archive = Arc::new(InMemoryVectorStore::new());
+    let day = Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap();
+    let chat = MemoryMetadata::default().with_user("1").with_conversation("10");
+    for (i, content) in ["flight on May 3", "seat 12A"].iter().enumerate() {
+        let metadata = chat.clone().with_timestamp(day + Duration::hours(i as i64 + 1));
+        store.add(MemoryEntry::new(content.to_string(), metadata)).await.unwrap();
+    }
+    let now = day + Duration::days(40);
+    let policy = CompactionPolicy::new(Duration::days(30), Duration::hours(24), 2);
+
+    let interrupted = MemoryCompactor::new(
+        Arc::new(FailingDeletes(store.clone())),
+        Arc::new(FixedEmbedding),
+        policy.clone(),
+    )
+    .with_archive_store(archive.clone())
+    .with_summarizer(Arc::new(JoiningSummarizer));
+    assert!(interrupted.compact_conversation("10", now).await.is_err());
+    assert_eq!(store.search_by_conversation("10").await.unwrap().len(), 3);
+
+    let compactor = MemoryCompactor::new(store.clone(), Arc::new(FixedEmbedding), policy)
+        .with_archive_store(archive.clone())
+        .with_summarizer(Arc::new(JoiningSummarizer));
+    let report = compactor.compact_conversation("10", now).await.unwrap();
+    assert_eq!((report.summaries, report.compacted), (0, 2));
+
+    let remaining = store.search_by_conversation("10").await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].metadata.role, MemoryRole::System);
+    assert_eq!(archive.search_by_conversation("10").await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn summary_tokens_come_from_the_configured_tokenizer() {
+    let store = Arc::new(InMemoryVectorStore::new());
+    let day = Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap();
+    let chat = MemoryMetadata::default().with_user("1").with_conversation("10");
+    for (content, hour) in [("flight on May 3", 1), ("seat 12A", 2)] {
+        let metadata = chat.clone().with_timestamp(day + Duration::hours(hour));
+        store.add(MemoryEntry::new(content.to_string(), metadata)).await.unwrap();
+    }
+
+    let compactor = MemoryCompactor::new(
+        store.clone(),
+        Arc::new(FixedEmbedding),
+        CompactionPolicy::new(Duration::days(30), Duration::hours(24), 2),
+    )
+    .with_summarizer(Arc::new(JoiningSummarizer))
+    .with_tokenizer(Arc::new(WordTokenizer));
+    compactor
+        .compact_conversation("10", day + Duration::days(40))
+        .await
+        .unwrap();
+
+    let summary = store
+        .search_by_conversation("10")
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|e| e.metadata.role == MemoryRole::System)
+        .expect("summary entry");
+    assert_eq!(
+        summary.metadata.tokens,
+        Some(summary.content.split_whitespace().count() as u32)
+    );
+}
@@ -0,0 +1,171 @@
+//! Tests for encryption at rest: [`MessageRepository::with_cipher`], [`EncryptedMemoryStore`] and key rotation.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use telegram_bot::encryption::{reencrypt_all, EncryptedMemoryStore, FieldCipher, KEY_LEN};
+use telegram_bot::memory::{InMemoryVectorStore, MemoryEntry, MemoryMetadata, MemoryStore};
+use telegram_bot::outbox::{OutboxPayload, OutboxRepository, OutboxStatus};
+use telegram_bot::profile::{FactKind, ProfileRepository};
+use telegram_bot::storage::{MessageRecord, MessageRepository};
+use tempfile::TempDir;
+
+fn cipher(key_id: &str, byte: u8) -> Arc<FieldCipher> {
+    Arc::new(FieldCipher::new(key_id, &[byte; KEY_LEN]).unwrap())
+}
+
+fn record(chat_id: i64, content: &str) -> MessageRecord {
+    MessageRecord::new(
+        1,
+        chat_id,
+        None,
+        None,
+        None,
+        "text".to_string(),
+        content.to_string(),
+        "received".to_string(),
+        None,
+    )
+}
+
+#[tokio::test]
+async fn repository_encrypts_content_at_rest() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db").to_string_lossy().into_owned();
+    let plain_repo = MessageRepository::new(&path).await.unwrap();
+    let repo = plain_repo.clone().with_cipher(cipher("k1", 1));
+
+    let msg = record(10, "secret plans");
+    repo.save(&msg).await.unwrap();
+
+    let read = repo.get_message_by_id(&msg.id).await.unwrap().unwrap();
+    assert_eq!(read.content, "secret plans");
+    let raw = plain_repo.get_message_by_id(&msg.id).await.unwrap().unwrap();
+    assert!(raw.content.starts_with("enc:v1:k1:"));
+    assert!(!raw.content.contains("secret"));
+
+    let found = repo.search_messages("plans", None).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].content, "secret plans");
+}
+
+#[tokio::test]
+async fn encrypted_store_keeps_semantic_search_working() {
+    let inner = InMemoryVectorStore::new();
+    let store = EncryptedMemoryStore::new(Arc::new(inner.clone()), cipher("k1", 1));
+
+    let chat = MemoryMetadata::default().with_conversation("10");
+    let tea = MemoryEntry::new("likes tea".to_string(), chat.clone());
+    let coffee = MemoryEntry::new("likes coffee".to_string(), chat);
+    store.add(tea.with_embedding(vec![1.0, 0.0])).await.unwrap();
+    store.add(coffee.with_embedding(vec![0.0, 1.0])).await.unwrap();
+
+    let raw = inner.search_by_conversation("10").await.unwrap();
+    assert!(raw.iter().all(|e| e.content.starts_with("enc:v1:k1:")));
+
+    let results = store.semantic_search(&[1.0, 0.1], 1, None, None).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.content, "likes tea");
+}
+
+#[tokio::test]
+async fn reencrypt_all_rotates_messages_and_entries() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db").to_string_lossy().into_owned();
+    let plain_repo = MessageRepository::new(&path).await.unwrap();
+    let outbox = OutboxRepository::new(&path).await.unwrap();
+    let profiles = ProfileRepository::new(&path).await.unwrap();
+    let inner = InMemoryVectorStore::new();
+
+    // One plaintext row (written before encryption was enabled) and one row under the old key.
+    plain_repo.save(&record(10, "legacy")).await.unwrap();
+    plain_repo
+        .clone()
+        .with_cipher(cipher("k1", 1))
+        .save(&record(10, "old key"))
+        .await
+        .unwrap();
+    let chat = MemoryMetadata::default().with_conversation("10");
+    inner
+        .add(MemoryEntry::new("legacy entry".to_string(), chat.clone()).with_embedding(vec![1.0]))
+        .await
+        .unwrap();
+    profiles
+        .record("1", FactKind::Name, "Alice", 0.9, Utc::now())
+        .await
+        .unwrap();
+    outbox
+        .clone()
+        .with_cipher(cipher("k1", 1))
+        .enqueue(&OutboxPayload::Message { record: record(10, "queued") }, "db locked")
+        .await
+        .unwrap();
+    EncryptedMemoryStore::new(Arc::new(inner.clone()), cipher("k1", 1))
+        .add(MemoryEntry::new("old key entry".to_string(), chat).with_embedding(vec![1.0]))
+        .await
+        .unwrap();
+    // A chat with memory entries but no rows in the messages table.
+    let other_chat = MemoryMetadata::default().with_conversation("99");
+    inner
+        .add(MemoryEntry::new("memory only".to_string(), other_chat).with_embedding(vec![1.0]))
+        .await
+        .unwrap();
+
+    let rotated = Arc::new(
+        FieldCipher::new("k2", &[2u8; KEY_LEN])
+            .unwrap()
+            .with_key("k1", &[1u8; KEY_LEN])
+            .unwrap(),
+    );
+    let stores: Vec<(String, Arc<dyn MemoryStore>)> = vec![("memory".to_string(), Arc::new(inner.clone()))];
+    let report = reencrypt_all(&plain_repo, &profiles, &outbox, &stores, rotated.clone(), 1)
+        .await
+        .unwrap();
+    assert_eq!(report.active_key_id, "k2");
+    assert_eq!(report.messages, 2);
+    assert_eq!(report.memory_entries, vec![("memory".to_string(), 3)]);
+    assert_eq!(report.profile_facts, 1);
+    assert_eq!(report.outbox_items, 1);
+
+    // Everything is now readable with the new key alone.
+    let new_only = cipher("k2", 2);
+    let repo = plain_repo.clone().with_cipher(new_only.clone());
+    let mut contents: Vec<String> = repo
+        .get_recent_messages_by_chat(10, 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| m.content)
+        .collect();
+    contents.sort();
+    assert_eq!(contents, vec!["legacy", "old key"]);
+    let entries = EncryptedMemoryStore::new(Arc::new(inner.clone()), new_only.clone())
+        .search_by_conversation("10")
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    let memory_only = EncryptedMemoryStore::new(Arc::new(inner.clone()), new_only.clone())
+        .search_by_conversation("99")
+        .await
+        .unwrap();
+    assert_eq!(memory_only[0].content, "memory only");
+    assert!(new_only.is_current(&inner.search_by_conversation("99").await.unwrap()[0].content));
+    let queued = outbox
+        .clone()
+        .with_cipher(new_only.clone())
+        .list(OutboxStatus::Pending, 10)
+        .await
+        .unwrap();
+    assert!(matches!(&queued[0].payload, OutboxPayload::Message { record } if record.content == "queued"));
+    let facts = profiles.clone().with_cipher(new_only).facts_for_user("1").await.unwrap();
+    assert_eq!(facts[0].value, "Alice");
+
+    // A second run has nothing left to do.
+    let report = reencrypt_all(&plain_repo, &profiles, &outbox, &stores, rotated, 1)
+        .await
+        .unwrap();
+    assert_eq!(report.messages, 0);
+    assert_eq!(report.memory_entries, vec![("memory".to_string(), 0)]);
+    assert_eq!(report.profile_facts, 0);
+    assert_eq!(report.outbox_items, 0);
+}
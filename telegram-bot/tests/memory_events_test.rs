@@ -0,0 +1,118 @@
+//! Tests for the memory change feed: [`ObservableMemoryStore`] publishes writes on [`MemoryEvents`]
+//! and [`spawn_replication`] mirrors them into another store.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use telegram_bot::memory::{
+    spawn_replication, InMemoryVectorStore, MemoryEntry, MemoryEvent, MemoryEvents, MemoryMetadata,
+    MemoryStore, ObservableMemoryStore,
+};
+
+/// Polls `store` until `conversation_id` holds `expected` entries (replication is asynchronous).
+async fn wait_for_count(store: &dyn MemoryStore, conversation_id: &str, expected: usize) {
+    for _ in 0..100 {
+        if store
+            .search_by_conversation(conversation_id)
+            .await
+            .unwrap()
+            .len()
+            == expected
+        {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!(
+        "conversation {} never reached {} entries",
+        conversation_id, expected
+    );
+}
+
+#[tokio::test]
+async fn observable_store_publishes_writes_in_order() {
+    let events = MemoryEvents::default();
+    let store = ObservableMemoryStore::new(Arc::new(InMemoryVectorStore::new()), events.clone());
+    let mut rx = events.subscribe();
+
+    let c1 = MemoryMetadata::default().with_conversation("c1");
+    let c2 = MemoryMetadata::default().with_conversation("c2");
+    let first = MemoryEntry::new("first".to_string(), c1);
+    store.add(first.clone()).await.unwrap();
+    let mut edited = first.clone();
+    edited.content = "first (edited)".to_string();
+    store.update(edited).await.unwrap();
+    store.delete(first.id).await.unwrap();
+    store
+        .add_batch(vec![
+            MemoryEntry::new("a".to_string(), c2.clone()),
+            MemoryEntry::new("b".to_string(), c2),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(store.delete_by_conversation("c2").await.unwrap(), 2);
+    // Reads are not published.
+    store.search_by_conversation("c1").await.unwrap();
+
+    assert!(matches!(rx.try_recv().unwrap(), MemoryEvent::Added(e) if e.id == first.id));
+    assert!(
+        matches!(rx.try_recv().unwrap(), MemoryEvent::Updated(e) if e.content == "first (edited)")
+    );
+    assert!(matches!(rx.try_recv().unwrap(), MemoryEvent::Deleted(id) if id == first.id));
+    assert!(matches!(rx.try_recv().unwrap(), MemoryEvent::Added(e) if e.content == "a"));
+    assert!(matches!(rx.try_recv().unwrap(), MemoryEvent::Added(e) if e.content == "b"));
+    assert!(matches!(
+        rx.try_recv().unwrap(),
+        MemoryEvent::ConversationDeleted { conversation_id, count: 2 } if conversation_id == "c2"
+    ));
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn replication_mirrors_the_primary_store() {
+    let events = MemoryEvents::default();
+    let primary = ObservableMemoryStore::new(Arc::new(InMemoryVectorStore::new()), events.clone());
+    let replica = Arc::new(InMemoryVectorStore::new());
+    let task = spawn_replication(&events, replica.clone());
+
+    let kept =
+        MemoryEntry::new("kept".to_string(), MemoryMetadata::default().with_conversation("c1"));
+    let dropped =
+        MemoryEntry::new("dropped".to_string(), MemoryMetadata::default().with_conversation("c2"));
+    primary.add(kept.clone()).await.unwrap();
+    primary.add(dropped).await.unwrap();
+    wait_for_count(replica.as_ref(), "c1", 1).await;
+    wait_for_count(replica.as_ref(), "c2", 1).await;
+
+    primary.delete_by_conversation("c2").await.unwrap();
+    wait_for_count(replica.as_ref(), "c2", 0).await;
+    let mirrored = replica
+        .get(kept.id)
+        .await
+        .unwrap()
+        .expect("replicated entry");
+    assert_eq!(mirrored.content, "kept");
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn replication_acknowledges_applied_writes() {
+    let events = MemoryEvents::default();
+    let primary = ObservableMemoryStore::new(Arc::new(InMemoryVectorStore::new()), events.clone());
+    let replica = Arc::new(InMemoryVectorStore::new());
+    let replication = spawn_replication(&events, replica.clone());
+
+    let metadata = MemoryMetadata::default().with_conversation("c1");
+    for content in ["a", "b", "c"] {
+        primary
+            .add(MemoryEntry::new(content.to_string(), metadata.clone()))
+            .await
+            .unwrap();
+    }
+    replication.caught_up().await;
+
+    assert_eq!(events.published(), 3);
+    assert_eq!(replica.search_by_conversation("c1").await.unwrap().len(), 3);
+    replication.abort();
+}
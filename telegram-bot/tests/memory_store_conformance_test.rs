@@ -0,0 +1,61 @@
+//! Runs the shared [`MemoryStore`](telegram_bot::memory::MemoryStore) conformance suite against
+//! every built-in backend: in-memory, SQLite, and SQLite with the HNSW index attached; plus the
+//! read cache around the in-memory store.
+
+use std::sync::Arc;
+
+use telegram_bot::memory::{CachedMemoryStore, HnswParams, InMemoryVectorStore, SQLiteVectorStore};
+use tempfile::TempDir;
+
+mod in_memory {
+    use super::*;
+
+    async fn make() -> ((), InMemoryVectorStore) {
+        ((), InMemoryVectorStore::new())
+    }
+
+    telegram_bot::memory_store_conformance_tests!(make());
+}
+
+mod sqlite {
+    use super::*;
+
+    async fn make() -> (TempDir, SQLiteVectorStore) {
+        let dir = TempDir::new().expect("tempdir");
+        let store = SQLiteVectorStore::new(dir.path().join("memory.db").to_str().unwrap())
+            .await
+            .expect("sqlite store");
+        (dir, store)
+    }
+
+    telegram_bot::memory_store_conformance_tests!(make());
+}
+
+mod sqlite_hnsw {
+    use super::*;
+
+    async fn make() -> (TempDir, SQLiteVectorStore) {
+        let dir = TempDir::new().expect("tempdir");
+        let store = SQLiteVectorStore::new(dir.path().join("memory.db").to_str().unwrap())
+            .await
+            .expect("sqlite store")
+            .with_hnsw(dir.path().join("memory.db.hnsw"), HnswParams::default())
+            .await
+            .expect("hnsw index");
+        (dir, store)
+    }
+
+    telegram_bot::memory_store_conformance_tests!(make());
+}
+
+mod cached_in_memory {
+    use super::*;
+
+    /// A small window so queries exercise both cached answers and fallbacks to the inner store.
+    async fn make() -> ((), CachedMemoryStore) {
+        let store = CachedMemoryStore::new(Arc::new(InMemoryVectorStore::new()), 4).with_window(3);
+        ((), store)
+    }
+
+    telegram_bot::memory_store_conformance_tests!(make());
+}
@@ -1,8 +1,9 @@
 //! Integration tests for [`telegram_bot::storage::MessageRepository`].
 //!
-//! Covers `get_message_by_id`, `get_recent_messages_by_chat`, get_stats, get_messages, search_messages, and chat filtering using an in-memory SQLite database.
+//! Covers `get_message_by_id`, `get_recent_messages_by_chat`, get_stats, get_messages, keyset pagination and streaming, search_messages, and chat filtering using an in-memory SQLite database.
 
-use telegram_bot::storage::{MessageQuery, MessageRecord, MessageRepository};
+use futures::TryStreamExt;
+use telegram_bot::storage::{MessageCursor, MessageOrder, MessageQuery, MessageRecord, MessageRepository};
 use tempfile::TempDir;
 
 /// Returns a fresh SQLite database path in a temp dir so each test gets an isolated DB.
@@ -461,3 +462,194 @@ async fn test_cleanup_old_messages() {
     let deleted = repo.cleanup_old_messages(-1).await.expect("cleanup");
     assert_eq!(deleted, 1);
 }
+
+/// Saves a text message for `user_id` in `chat_id`, created `age_days` ago.
+async fn save_aged_message(repo: &MessageRepository, user_id: i64, chat_id: i64, age_days: i64) {
+    let mut record = MessageRecord::new(
+        user_id,
+        chat_id,
+        None,
+        None,
+        None,
+        "text".to_string(),
+        format!("from {} in {}", user_id, chat_id),
+        "received".to_string(),
+        None,
+    );
+    record.created_at = chrono::Utc::now() - chrono::Duration::days(age_days);
+    repo.save(&record).await.expect("save");
+}
+
+/// **Test: delete_messages_by_user and delete_messages_by_chat only remove matching rows.**
+#[tokio::test]
+async fn test_delete_messages_by_user_and_chat() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = MessageRepository::new(&database_url)
+        .await
+        .expect("Failed to create repository");
+
+    save_aged_message(&repo, 1, 10, 0).await;
+    save_aged_message(&repo, 1, 20, 0).await;
+    save_aged_message(&repo, 2, 10, 0).await;
+    save_aged_message(&repo, 3, 30, 0).await;
+
+    assert_eq!(repo.delete_messages_by_user(1).await.expect("delete"), 2);
+    assert_eq!(repo.delete_messages_by_chat(10).await.expect("delete"), 1);
+
+    let stats = repo.get_stats().await.expect("stats");
+    assert_eq!(stats.total_messages, 1);
+    assert_eq!(repo.list_chat_ids().await.expect("chat ids"), vec![30]);
+}
+
+/// **Test: delete_messages_by_user also removes the bot's replies in the user's private chat, but not
+/// in group chats.**
+#[tokio::test]
+async fn test_delete_messages_by_user_removes_private_chat_replies() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = MessageRepository::new(&database_url)
+        .await
+        .expect("Failed to create repository");
+
+    for (user_id, chat_id, direction) in [(1, 1, "received"), (99, 1, "sent"), (99, 10, "sent")] {
+        repo.save(&MessageRecord::new(
+            user_id,
+            chat_id,
+            None,
+            None,
+            None,
+            "text".to_string(),
+            "hello".to_string(),
+            direction.to_string(),
+            None,
+        ))
+        .await
+        .expect("save");
+    }
+
+    assert_eq!(repo.delete_messages_by_user(1).await.expect("delete"), 2);
+    assert_eq!(repo.list_chat_ids().await.expect("chat ids"), vec![10]);
+}
+
+/// **Test: retention cleanup honors the cutoff and excluded chats.**
+#[tokio::test]
+async fn test_cleanup_old_messages_excluding_and_in_chat() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = MessageRepository::new(&database_url)
+        .await
+        .expect("Failed to create repository");
+
+    save_aged_message(&repo, 1, 10, 40).await;
+    save_aged_message(&repo, 1, 10, 1).await;
+    save_aged_message(&repo, 1, 20, 40).await;
+    save_aged_message(&repo, 1, 20, 10).await;
+
+    // Chat 20 has its own override, so the global sweep skips it.
+    let deleted = repo
+        .cleanup_old_messages_excluding_chats(30, &[20])
+        .await
+        .expect("cleanup");
+    assert_eq!(deleted, 1);
+
+    let deleted = repo.cleanup_old_messages_in_chat(20, 7).await.expect("cleanup");
+    assert_eq!(deleted, 2);
+
+    let stats = repo.get_stats().await.expect("stats");
+    assert_eq!(stats.total_messages, 1);
+}
+
+/// Saves `count` messages in `chat_id`, all with the same created_at so pagination must break ties on id.
+async fn save_same_instant(repo: &MessageRepository, chat_id: i64, count: usize) {
+    let created_at = chrono::Utc::now();
+    for i in 0..count {
+        let mut record = MessageRecord::new(
+            1,
+            chat_id,
+            None,
+            None,
+            None,
+            "text".to_string(),
+            format!("msg {}", i),
+            "received".to_string(),
+            None,
+        );
+        record.created_at = created_at;
+        repo.save(&record).await.expect("save");
+    }
+}
+
+/// **Test: get_messages_page walks all rows exactly once, including rows with equal created_at.**
+#[tokio::test]
+async fn test_get_messages_page_keyset() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = MessageRepository::new(&database_url)
+        .await
+        .expect("Failed to create repository");
+
+    save_same_instant(&repo, 1, 5).await;
+    save_aged_message(&repo, 1, 1, 1).await;
+    save_aged_message(&repo, 1, 2, 0).await;
+
+    let query = MessageQuery {
+        chat_id: Some(1),
+        ..Default::default()
+    };
+    let mut seen = Vec::new();
+    let mut cursor: Option<MessageCursor> = None;
+    loop {
+        let page = repo
+            .get_messages_page(&query, cursor.as_ref(), MessageOrder::NewestFirst, 2)
+            .await
+            .expect("page");
+        assert!(page.messages.len() <= 2);
+        seen.extend(page.messages.into_iter());
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen.len(), 6);
+    let mut ids: Vec<&str> = seen.iter().map(|m| m.id.as_str()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 6);
+    assert!(seen.iter().all(|m| m.chat_id == 1));
+    // Oldest message comes last when newest-first.
+    assert!(seen.last().unwrap().created_at < seen[0].created_at);
+}
+
+/// **Test: stream_messages yields every matching row in order and honors limit.**
+#[tokio::test]
+async fn test_stream_messages() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = MessageRepository::new(&database_url)
+        .await
+        .expect("Failed to create repository");
+
+    for age in [3, 2, 1] {
+        save_aged_message(&repo, 1, 1, age).await;
+    }
+    save_same_instant(&repo, 1, 4).await;
+
+    let all: Vec<MessageRecord> = repo
+        .stream_messages(MessageQuery::default(), MessageOrder::OldestFirst, 3)
+        .try_collect()
+        .await
+        .expect("stream");
+    assert_eq!(all.len(), 7);
+    assert!(all.windows(2).all(|w| w[0].created_at <= w[1].created_at));
+
+    let limited: Vec<MessageRecord> = repo
+        .stream_messages(
+            MessageQuery {
+                limit: Some(4),
+                ..Default::default()
+            },
+            MessageOrder::NewestFirst,
+            3,
+        )
+        .try_collect()
+        .await
+        .expect("stream");
+    assert_eq!(limited.len(), 4);
+}
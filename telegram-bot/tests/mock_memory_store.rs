@@ -10,7 +10,7 @@ use std::sync::{
 };
 
 use async_trait::async_trait;
-use telegram_bot::memory::{MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore};
+use telegram_bot::memory::{MemoryEntry, MemoryMetadata, MemoryStore};
 use uuid::Uuid;
 
 /// In-memory MemoryStore for tests. Counters (store_call_count, query_call_count, semantic_search_call_count) for assertions.
@@ -137,7 +137,6 @@ impl MemoryStore for MockMemoryStore {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
 
     #[tokio::test]
     async fn test_add_and_search_by_user_and_conversation_updates_counters() {
@@ -146,14 +145,9 @@ mod tests {
         assert_eq!(store.get_store_call_count(), 0);
         assert_eq!(store.get_query_call_count(), 0);
 
-        let metadata = MemoryMetadata {
-            user_id: Some("user1".to_string()),
-            conversation_id: Some("conv1".to_string()),
-            role: MemoryRole::User,
-            timestamp: Utc::now(),
-            tokens: None,
-            importance: None,
-        };
+        let metadata = MemoryMetadata::default()
+            .with_user("user1")
+            .with_conversation("conv1");
 
         let entry = MemoryEntry::new("hello".to_string(), metadata);
 
@@ -0,0 +1,178 @@
+//! Tests for the write outbox: [`MemoryHandler`] queues entries whose embedding failed,
+//! [`OutboxWorker`] repairs them, and items that keep failing become dead letters.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use telegram_bot::embedding::EmbeddingService;
+use telegram_bot::memory::{InMemoryVectorStore, MemoryEntry, MemoryMetadata, MemoryStore};
+use telegram_bot::outbox::{
+    OutboxCounts, OutboxPayload, OutboxRepository, OutboxStatus, OutboxWorker, RetryPolicy,
+};
+use telegram_bot::storage::{MessageRecord, MessageRepository};
+use telegram_bot::{Chat, Handler, MemoryConfig, MemoryHandler, Message, MessageDirection, User};
+use tempfile::TempDir;
+
+struct FixedEmbedding;
+
+#[async_trait]
+impl EmbeddingService for FixedEmbedding {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Ok(vec![1.0, 0.0])
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+    }
+}
+
+/// Embedding API that is down.
+struct FailingEmbedding;
+
+#[async_trait]
+impl EmbeddingService for FailingEmbedding {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        anyhow::bail!("503 Service Unavailable")
+    }
+
+    async fn embed_batch(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        anyhow::bail!("503 Service Unavailable")
+    }
+}
+
+fn fresh_db() -> (TempDir, String) {
+    let dir = TempDir::new().expect("temp dir");
+    let path = dir.path().join("test.db").to_string_lossy().to_string();
+    (dir, path)
+}
+
+fn message(content: &str) -> Message {
+    Message {
+        id: "m1".to_string(),
+        user: User {
+            id: 1,
+            username: None,
+            first_name: Some("Test".to_string()),
+            last_name: None,
+        },
+        chat: Chat {
+            id: 10,
+            chat_type: "private".to_string(),
+        },
+        content: content.to_string(),
+        message_type: "text".to_string(),
+        direction: MessageDirection::Incoming,
+        created_at: Utc::now(),
+        reply_to_message_id: None,
+        reply_to_message_from_bot: false,
+        reply_to_message_content: None,
+    }
+}
+
+#[tokio::test]
+async fn failed_embedding_is_queued_and_repaired_by_the_worker() {
+    let (_dir, db) = fresh_db();
+    let outbox = OutboxRepository::new(&db).await.unwrap();
+    let store = Arc::new(InMemoryVectorStore::new());
+    let handler = MemoryHandler::new(MemoryConfig {
+        store: store.clone(),
+        embedding_service: Some(Arc::new(FailingEmbedding)),
+        ..Default::default()
+    })
+    .with_outbox(outbox.clone());
+
+    handler
+        .before(&message("my flight is on May 3"))
+        .await
+        .unwrap();
+
+    // Saved without embedding so recent history stays complete, and queued for re-embedding.
+    let saved = store.search_by_conversation("10").await.unwrap();
+    assert_eq!(saved.len(), 1);
+    assert!(saved[0].embedding.is_none());
+    let pending = outbox.list(OutboxStatus::Pending, 10).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert!(pending[0].last_error.contains("503"));
+    assert!(matches!(
+        &pending[0].payload,
+        OutboxPayload::MemoryEntry { entry, embed: true } if entry.id == saved[0].id
+    ));
+
+    let worker = OutboxWorker::new(
+        outbox.clone(),
+        store.clone(),
+        MessageRepository::new(&db).await.unwrap(),
+        RetryPolicy::default(),
+    )
+    .with_embedding_service(Arc::new(FixedEmbedding));
+    let report = worker.process_due(Utc::now()).await.unwrap();
+    assert_eq!(report.delivered, 1);
+
+    let repaired = store.search_by_conversation("10").await.unwrap();
+    assert_eq!(repaired.len(), 1, "the entry is updated, not duplicated");
+    assert_eq!(repaired[0].embedding, Some(vec![1.0, 0.0]));
+    assert_eq!(outbox.counts().await.unwrap(), OutboxCounts::default());
+}
+
+#[tokio::test]
+async fn items_back_off_then_become_dead_letters() {
+    let (_dir, db) = fresh_db();
+    let outbox = OutboxRepository::new(&db).await.unwrap();
+    let repo = MessageRepository::new(&db).await.unwrap();
+    let record = MessageRecord::new(
+        1,
+        10,
+        None,
+        None,
+        None,
+        "llm_response".to_string(),
+        "hello".to_string(),
+        "sent".to_string(),
+        None,
+    );
+    outbox
+        .enqueue(&OutboxPayload::Message { record }, "database is locked")
+        .await
+        .unwrap();
+    let metadata = MemoryMetadata::default().with_user("1").with_conversation("10");
+    let entry = MemoryEntry::new("remember me".to_string(), metadata);
+    let entry_payload = OutboxPayload::MemoryEntry { entry, embed: true };
+    outbox.enqueue(&entry_payload, "timeout").await.unwrap();
+
+    let policy = RetryPolicy::new(3, Duration::seconds(30), Duration::hours(1));
+    let worker = OutboxWorker::new(
+        outbox.clone(),
+        Arc::new(InMemoryVectorStore::new()),
+        repo.clone(),
+        policy,
+    )
+    .with_embedding_service(Arc::new(FailingEmbedding));
+
+    let now = Utc::now();
+    let report = worker.process_due(now).await.unwrap();
+    assert_eq!((report.delivered, report.rescheduled), (1, 1));
+    assert_eq!(
+        repo.get_recent_messages_by_chat(10, 10)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Not due again until the backoff has passed.
+    assert_eq!(worker.process_due(now).await.unwrap().rescheduled, 0);
+    let report = worker
+        .process_due(now + Duration::seconds(60))
+        .await
+        .unwrap();
+    assert_eq!((report.rescheduled, report.dead), (0, 1));
+
+    let dead = outbox.list(OutboxStatus::Dead, 10).await.unwrap();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].attempts, 3);
+    assert!(dead[0].last_error.contains("503"));
+    assert_eq!(outbox.purge_dead().await.unwrap(), 1);
+    assert_eq!(outbox.counts().await.unwrap(), OutboxCounts::default());
+}
@@ -0,0 +1,271 @@
+//! Tests for [`telegram_bot::profile`]: fact dedup and replacement in [`ProfileRepository`],
+//! batching and confidence filtering in [`ProfileUpdater`], and the profile served by
+//! [`UserPreferencesStrategy`].
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use telegram_bot::encryption::{FieldCipher, KEY_LEN};
+use telegram_bot::memory::{
+    InMemoryVectorStore, MemoryEntry, MemoryMetadata, MemoryRole, MemoryStore, StrategyResult,
+};
+use telegram_bot::memory_strategies::{ContextStrategy, UserPreferencesStrategy};
+use telegram_bot::profile::{ExtractedFact, FactExtractor, FactKind, ProfileRepository, ProfileUpdater};
+use tempfile::TempDir;
+
+async fn fresh_repo() -> (TempDir, ProfileRepository) {
+    let dir = TempDir::new().expect("temp dir");
+    let path = dir.path().join("test.db");
+    let repo = ProfileRepository::new(&path.to_string_lossy())
+        .await
+        .expect("Failed to create repository");
+    (dir, repo)
+}
+
+/// Returns the same facts for every batch and records the batch sizes it was called with.
+struct FixedExtractor {
+    facts: Vec<ExtractedFact>,
+    calls: Mutex<Vec<usize>>,
+}
+
+impl FixedExtractor {
+    fn new(facts: Vec<ExtractedFact>) -> Arc<Self> {
+        Arc::new(Self {
+            facts,
+            calls: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn calls(&self) -> Vec<usize> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl FactExtractor for FixedExtractor {
+    async fn extract(&self, messages: &[MemoryEntry]) -> Result<Vec<ExtractedFact>> {
+        self.calls.lock().unwrap().push(messages.len());
+        Ok(self.facts.clone())
+    }
+}
+
+fn fact(kind: FactKind, value: &str, confidence: f32) -> ExtractedFact {
+    ExtractedFact {
+        kind,
+        value: value.to_string(),
+        confidence,
+    }
+}
+
+#[tokio::test]
+async fn repeated_fact_is_merged_and_counted() {
+    let (_dir, repo) = fresh_repo().await;
+    let earlier = Utc::now() - Duration::days(1);
+    let now = Utc::now();
+
+    repo.record("1", FactKind::Preference, "likes green tea", 0.7, earlier)
+        .await
+        .unwrap();
+    let merged = repo
+        .record("1", FactKind::Preference, "Likes green  tea.", 0.9, now)
+        .await
+        .unwrap();
+    repo.record("1", FactKind::Preference, "plays chess", 0.8, now)
+        .await
+        .unwrap();
+
+    assert_eq!(merged.mentions, 2);
+    assert_eq!(merged.confidence, 0.9);
+    assert!(merged.last_seen > merged.first_seen);
+
+    let facts = repo.facts_for_user("1").await.unwrap();
+    assert_eq!(facts.len(), 2);
+    assert_eq!(facts[0].value, "Likes green  tea.");
+    assert_eq!(facts[0].mentions, 2);
+    assert_eq!(facts[1].value, "plays chess");
+}
+
+#[tokio::test]
+async fn single_valued_fact_is_replaced_unless_less_confident() {
+    let (_dir, repo) = fresh_repo().await;
+    let now = Utc::now();
+
+    repo.record("1", FactKind::Location, "Berlin", 0.8, now).await.unwrap();
+    repo.record("1", FactKind::Location, "Munich", 0.9, now).await.unwrap();
+    repo.record("1", FactKind::Location, "Hamburg", 0.5, now).await.unwrap();
+
+    let facts = repo.facts_for_user("1").await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].value, "Munich");
+    assert_eq!(facts[0].mentions, 1);
+}
+
+#[tokio::test]
+async fn encrypted_values_round_trip_and_still_merge() {
+    let (_dir, repo) = fresh_repo().await;
+    let cipher = Arc::new(FieldCipher::new("k1", &[7u8; KEY_LEN]).unwrap());
+    let repo = repo.with_cipher(cipher);
+    let now = Utc::now();
+
+    repo.record("1", FactKind::Name, "Alice", 0.9, now).await.unwrap();
+    let merged = repo.record("1", FactKind::Name, "alice", 0.6, now).await.unwrap();
+
+    assert_eq!(merged.mentions, 2);
+    let facts = repo.facts_for_user("1").await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].value, "Alice");
+}
+
+#[tokio::test]
+async fn delete_user_removes_only_that_user() {
+    let (_dir, repo) = fresh_repo().await;
+    let now = Utc::now();
+    repo.record("1", FactKind::Name, "Alice", 0.9, now).await.unwrap();
+    repo.record("1", FactKind::Project, "a Telegram bot", 0.9, now).await.unwrap();
+    repo.record("2", FactKind::Name, "Bob", 0.9, now).await.unwrap();
+
+    assert_eq!(repo.delete_user("1").await.unwrap(), 2);
+    assert!(repo.facts_for_user("1").await.unwrap().is_empty());
+    assert_eq!(repo.facts_for_user("2").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn updater_extracts_per_full_batch_and_drops_low_confidence() {
+    let (_dir, repo) = fresh_repo().await;
+    let extractor = FixedExtractor::new(vec![
+        fact(FactKind::Name, "Alice", 0.95),
+        fact(FactKind::Preference, "maybe likes jazz", 0.3),
+    ]);
+    let updater = ProfileUpdater::new(repo.clone())
+        .with_batch_size(2)
+        .with_min_confidence(0.6)
+        .with_extractor(extractor.clone());
+
+    let alice = MemoryMetadata::default().with_user("1").with_conversation("10");
+    let bot = alice.clone().with_role(MemoryRole::Assistant);
+    let hello = MemoryEntry::new("Hi, I'm Alice".to_string(), alice.clone());
+    assert_eq!(updater.observe(hello).await.unwrap(), 0);
+    let reply = MemoryEntry::new("Hello!".to_string(), bot);
+    assert_eq!(updater.observe(reply).await.unwrap(), 0);
+    let jazz = MemoryEntry::new("I listen to jazz".to_string(), alice);
+    assert_eq!(updater.observe(jazz).await.unwrap(), 1);
+
+    assert_eq!(extractor.calls(), vec![2]);
+    let facts = repo.facts_for_user("1").await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].value, "Alice");
+}
+
+#[tokio::test]
+async fn flush_extracts_partial_batches() {
+    let (_dir, repo) = fresh_repo().await;
+    let extractor = FixedExtractor::new(vec![fact(FactKind::Project, "a Telegram bot", 0.8)]);
+    let updater = ProfileUpdater::new(repo.clone())
+        .with_batch_size(5)
+        .with_extractor(extractor.clone());
+
+    let chat = MemoryMetadata::default().with_conversation("10");
+    let first = MemoryEntry::new("I'm building a bot".to_string(), chat.clone().with_user("1"));
+    updater.observe(first).await.unwrap();
+    updater.observe(MemoryEntry::new("Me too".to_string(), chat.with_user("2"))).await.unwrap();
+    assert!(extractor.calls().is_empty());
+
+    assert_eq!(updater.flush().await, 2);
+    assert_eq!(extractor.calls(), vec![1, 1]);
+    assert_eq!(updater.flush().await, 0);
+}
+
+#[tokio::test]
+async fn updater_without_extractor_ignores_messages() {
+    let (_dir, repo) = fresh_repo().await;
+    let updater = ProfileUpdater::new(repo.clone()).with_batch_size(1);
+
+    assert!(!updater.has_extractor());
+    let alice = MemoryMetadata::default().with_user("1").with_conversation("10");
+    let hello = MemoryEntry::new("I'm Alice".to_string(), alice);
+    assert_eq!(updater.observe(hello).await.unwrap(), 0);
+    assert_eq!(updater.flush().await, 0);
+}
+
+#[tokio::test]
+async fn strategy_serves_recorded_profile() {
+    let (_dir, repo) = fresh_repo().await;
+    let now = Utc::now();
+    repo.record("1", FactKind::Preference, "green tea", 0.9, now).await.unwrap();
+    repo.record("1", FactKind::Name, "Alice", 0.9, now).await.unwrap();
+
+    let strategy = UserPreferencesStrategy::new().with_profile_repo(repo);
+    let store = InMemoryVectorStore::new();
+
+    let result = strategy
+        .build_context(&store, &Some("1".to_string()), &None, &None)
+        .await
+        .unwrap();
+    match result {
+        StrategyResult::Preferences(profile) => {
+            assert_eq!(profile, "Name: Alice; Preferences: green tea")
+        }
+        other => panic!("expected Preferences, got {:?}", other),
+    }
+
+    let unknown = strategy
+        .build_context(&store, &Some("2".to_string()), &None, &None)
+        .await
+        .unwrap();
+    assert!(matches!(unknown, StrategyResult::Empty));
+}
+
+#[tokio::test]
+async fn strategy_falls_back_to_memories_without_profile_facts() {
+    let (_dir, repo) = fresh_repo().await;
+    let store = InMemoryVectorStore::new();
+    let meta = MemoryMetadata::default().with_user("1").with_conversation("10");
+    store
+        .add(MemoryEntry::new("I like green tea".to_string(), meta.clone()))
+        .await
+        .unwrap();
+    store
+        .add(MemoryEntry::new("good morning".to_string(), meta))
+        .await
+        .unwrap();
+
+    for strategy in [
+        UserPreferencesStrategy::new(),
+        UserPreferencesStrategy::new().with_profile_repo(repo),
+    ] {
+        let result = strategy
+            .build_context(&store, &Some("1".to_string()), &None, &None)
+            .await
+            .unwrap();
+        match result {
+            StrategyResult::Preferences(prefs) => {
+                assert_eq!(prefs, "User Preferences: i like green tea")
+            }
+            other => panic!("expected Preferences, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn concurrent_records_of_the_same_fact_merge() {
+    let (_dir, repo) = fresh_repo().await;
+    let now = Utc::now();
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let repo = repo.clone();
+            tokio::spawn(async move {
+                repo.record("1", FactKind::Preference, "green tea", 0.8, now).await
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+
+    let facts = repo.facts_for_user("1").await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].mentions, 8);
+}
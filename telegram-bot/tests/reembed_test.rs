@@ -0,0 +1,78 @@
+//! Tests for the re-embed migration ([`reembed_all`]) after an embedding model change.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use telegram_bot::embedding::EmbeddingService;
+use telegram_bot::memory::{
+    reembed_all, EmbeddingSpec, MemoryEntry, MemoryMetadata, MemoryStore, SQLiteVectorStore,
+};
+use tempfile::TempDir;
+
+/// Three-dimensional "model": [text length, 1, 0].
+struct LengthEmbedding;
+
+#[async_trait]
+impl EmbeddingService for LengthEmbedding {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, anyhow::Error> {
+        Ok(vec![text.len() as f32, 1.0, 0.0])
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, anyhow::Error> {
+        Ok(texts.iter().map(|t| vec![t.len() as f32, 1.0, 0.0]).collect())
+    }
+}
+
+#[tokio::test]
+async fn reembed_all_migrates_store_to_new_model() {
+    let dir = TempDir::new().unwrap();
+    let store = Arc::new(
+        SQLiteVectorStore::new(&dir.path().join("memory.db").to_string_lossy())
+            .await
+            .unwrap(),
+    );
+    store
+        .bind_embedding_spec(&EmbeddingSpec::new("old-model", 2))
+        .await
+        .unwrap();
+    let chat = MemoryMetadata::default().with_conversation("10");
+    for content in ["short", "a longer message"] {
+        let entry = MemoryEntry::new(content.to_string(), chat.clone());
+        store.add(entry.with_embedding(vec![1.0, 0.0])).await.unwrap();
+    }
+    // Imported entry of a chat the messages table has never seen.
+    let imported = MemoryEntry::new("imported".to_string(), chat.with_conversation("99"))
+        .with_embedding(vec![1.0, 0.0]);
+    store.add(imported).await.unwrap();
+
+    let new_spec = EmbeddingSpec::new("new-model", 3);
+    assert!(store.bind_embedding_spec(&new_spec).await.is_err());
+
+    let stores: Vec<(String, Arc<dyn MemoryStore>)> =
+        vec![("sqlite".to_string(), store.clone())];
+    let report = reembed_all(&stores, &LengthEmbedding, &new_spec, 1)
+        .await
+        .unwrap();
+    assert_eq!(report.embedding, new_spec);
+    assert_eq!(report.memory_entries, vec![("sqlite".to_string(), 3)]);
+
+    assert_eq!(store.embedding_spec().await.unwrap(), Some(new_spec.clone()));
+    store.bind_embedding_spec(&new_spec).await.unwrap();
+    let results = store
+        .semantic_search(&[16.0, 1.0, 0.0], 1, None, Some("10"))
+        .await
+        .unwrap();
+    assert_eq!(results[0].1.content, "a longer message");
+
+    let imported = store
+        .semantic_search(&[8.0, 1.0, 0.0], 1, None, Some("99"))
+        .await
+        .unwrap();
+    assert_eq!(imported[0].1.content, "imported");
+
+    // Re-running finds nothing left to embed.
+    let report = reembed_all(&stores, &LengthEmbedding, &new_spec, 1)
+        .await
+        .unwrap();
+    assert_eq!(report.memory_entries, vec![("sqlite".to_string(), 0)]);
+}
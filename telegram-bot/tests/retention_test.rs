@@ -0,0 +1,277 @@
+//! Tests for [`telegram_bot::retention::DataEraser`]: forget user / forget chat across the message
+//! repository, memory stores and usage ledger, and retention sweeps with per-chat overrides.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use telegram_bot::memory::{InMemoryVectorStore, MemoryEntry, MemoryMetadata, MemoryStore};
+use telegram_bot::outbox::{OutboxPayload, OutboxRepository, OutboxStatus};
+use telegram_bot::profile::{FactKind, ProfileRepository};
+use telegram_bot::retention::{DataEraser, RetentionPolicy};
+use telegram_bot::storage::{
+    MessageRecord, MessageRepository, UsageQuery, UsageRecord, UsageRepository, USAGE_KIND_CHAT,
+};
+use tempfile::TempDir;
+
+async fn fresh_repo() -> (TempDir, MessageRepository) {
+    let dir = TempDir::new().expect("temp dir");
+    let path = dir.path().join("test.db");
+    let repo = MessageRepository::new(&path.to_string_lossy())
+        .await
+        .expect("Failed to create repository");
+    (dir, repo)
+}
+
+async fn save_message(repo: &MessageRepository, user_id: i64, chat_id: i64, age_days: i64) {
+    let mut record = MessageRecord::new(
+        user_id,
+        chat_id,
+        None,
+        None,
+        None,
+        "text".to_string(),
+        "hello".to_string(),
+        "received".to_string(),
+        None,
+    );
+    record.created_at = Utc::now() - Duration::days(age_days);
+    repo.save(&record).await.expect("save");
+}
+
+async fn record_usage(usage: &UsageRepository, user_id: i64, chat_id: i64, age_days: i64) {
+    let mut record = UsageRecord::new("gpt-4o-mini", "inline_llm", USAGE_KIND_CHAT, 10, 5)
+        .for_message(Some(chat_id), Some(user_id));
+    record.created_at = Utc::now() - Duration::days(age_days);
+    usage.record(&record).await.expect("record");
+}
+
+async fn add_entry(store: &InMemoryVectorStore, user_id: i64, chat_id: i64, age_days: i64) {
+    let metadata = MemoryMetadata::default()
+        .with_user(user_id.to_string())
+        .with_conversation(chat_id.to_string())
+        .with_timestamp(Utc::now() - Duration::days(age_days));
+    store
+        .add(MemoryEntry::new("hello".to_string(), metadata))
+        .await
+        .expect("add");
+}
+
+#[tokio::test]
+async fn forget_user_deletes_from_repo_and_every_store() {
+    let (_dir, repo) = fresh_repo().await;
+    let primary = InMemoryVectorStore::new();
+    let recent = InMemoryVectorStore::new();
+
+    save_message(&repo, 1, 10, 0).await;
+    save_message(&repo, 2, 10, 0).await;
+    add_entry(&primary, 1, 10, 0).await;
+    add_entry(&primary, 1, 20, 0).await;
+    add_entry(&primary, 2, 10, 0).await;
+    add_entry(&recent, 1, 10, 0).await;
+
+    let eraser = DataEraser::new(repo.clone())
+        .with_store("primary", Arc::new(primary.clone()))
+        .with_store("recent", Arc::new(recent.clone()));
+    let report = eraser.forget_user(1).await.expect("forget_user");
+
+    assert_eq!(report.messages, 1);
+    assert_eq!(report.memory_entries.len(), 2);
+    assert_eq!(report.memory_entries[0].store, "primary");
+    assert_eq!(report.memory_entries[0].deleted, 2);
+    assert_eq!(report.memory_entries[1].deleted, 1);
+    assert_eq!(report.total(), 4);
+    assert_eq!(primary.len().await, 1);
+    assert!(recent.is_empty().await);
+}
+
+#[tokio::test]
+async fn forget_user_deletes_profile_facts() {
+    let (dir, repo) = fresh_repo().await;
+    let profiles = ProfileRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .expect("profile repo");
+    profiles
+        .record("1", FactKind::Name, "Alice", 0.9, Utc::now())
+        .await
+        .expect("record");
+    profiles
+        .record("2", FactKind::Name, "Bob", 0.9, Utc::now())
+        .await
+        .expect("record");
+
+    let eraser = DataEraser::new(repo).with_profile_repo(profiles.clone());
+    let report = eraser.forget_user(1).await.expect("forget_user");
+
+    assert_eq!(report.profile_facts, 1);
+    assert_eq!(report.total(), 1);
+    assert!(profiles.facts_for_user("1").await.expect("facts").is_empty());
+    assert_eq!(profiles.facts_for_user("2").await.expect("facts").len(), 1);
+}
+
+#[tokio::test]
+async fn forget_user_deletes_usage_rows() {
+    let (dir, repo) = fresh_repo().await;
+    let usage = UsageRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .expect("usage repo");
+    for user_id in [1, 1, 2] {
+        usage
+            .record(
+                &UsageRecord::new("gpt-4o-mini", "inline_llm", USAGE_KIND_CHAT, 10, 5)
+                    .for_message(Some(100), Some(user_id)),
+            )
+            .await
+            .expect("record");
+    }
+
+    let eraser = DataEraser::new(repo).with_usage_repo(usage.clone());
+    let report = eraser.forget_user(1).await.expect("forget_user");
+
+    assert_eq!(report.usage_rows, 2);
+    assert_eq!(report.total(), 2);
+    let remaining = usage.totals(&UsageQuery::default()).await.expect("totals");
+    assert_eq!(remaining.calls, 1);
+}
+
+#[tokio::test]
+async fn forget_chat_deletes_only_that_chat() {
+    let (_dir, repo) = fresh_repo().await;
+    let primary = InMemoryVectorStore::new();
+
+    save_message(&repo, 1, 10, 0).await;
+    save_message(&repo, 1, 20, 0).await;
+    add_entry(&primary, 1, 10, 0).await;
+    add_entry(&primary, 1, 20, 0).await;
+
+    let eraser = DataEraser::new(repo.clone()).with_store("primary", Arc::new(primary.clone()));
+    let report = eraser.forget_chat(10).await.expect("forget_chat");
+
+    assert_eq!(report.messages, 1);
+    assert_eq!(report.total(), 2);
+    assert_eq!(primary.len().await, 1);
+    assert_eq!(repo.list_chat_ids().await.expect("chat ids"), vec![20]);
+}
+
+#[tokio::test]
+async fn forget_chat_deletes_usage_rows() {
+    let (dir, repo) = fresh_repo().await;
+    let usage = UsageRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .expect("usage repo");
+    for chat_id in [10, 10, 20] {
+        record_usage(&usage, 1, chat_id, 0).await;
+    }
+
+    let eraser = DataEraser::new(repo).with_usage_repo(usage.clone());
+    let report = eraser.forget_chat(10).await.expect("forget_chat");
+
+    assert_eq!(report.usage_rows, 2);
+    assert_eq!(report.total(), 2);
+    let remaining = usage.totals(&UsageQuery::default()).await.expect("totals");
+    assert_eq!(remaining.calls, 1);
+}
+
+#[tokio::test]
+async fn forget_user_and_chat_delete_queued_outbox_writes() {
+    let (dir, repo) = fresh_repo().await;
+    let outbox = OutboxRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .expect("outbox");
+    let queue = |user_id: i64, chat_id: i64| {
+        let record = MessageRecord::new(
+            user_id,
+            chat_id,
+            None,
+            None,
+            None,
+            "text".to_string(),
+            "hello".to_string(),
+            "received".to_string(),
+            None,
+        );
+        OutboxPayload::Message { record }
+    };
+    outbox.enqueue(&queue(1, 10), "db locked").await.expect("enqueue");
+    outbox.enqueue(&queue(2, 20), "db locked").await.expect("enqueue");
+    outbox.enqueue(&queue(3, 30), "db locked").await.expect("enqueue");
+
+    let eraser = DataEraser::new(repo).with_outbox(outbox.clone());
+    assert_eq!(eraser.forget_user(1).await.expect("forget_user").outbox_items, 1);
+    let report = eraser.forget_chat(20).await.expect("forget_chat");
+    assert_eq!(report.outbox_items, 1);
+    assert_eq!(report.total(), 1);
+
+    let left = outbox.list(OutboxStatus::Pending, 10).await.expect("list");
+    assert_eq!(left.len(), 1);
+    assert!(matches!(&left[0].payload, OutboxPayload::Message { record } if record.user_id == 3));
+}
+
+#[tokio::test]
+async fn same_store_registered_twice_is_swept_once() {
+    let (_dir, repo) = fresh_repo().await;
+    let store: Arc<dyn MemoryStore> = Arc::new(InMemoryVectorStore::new());
+
+    let eraser = DataEraser::new(repo)
+        .with_store("primary", store.clone())
+        .with_store("recent", store);
+    let report = eraser.forget_user(1).await.expect("forget_user");
+
+    assert_eq!(report.memory_entries.len(), 1);
+}
+
+#[tokio::test]
+async fn apply_retention_uses_default_and_chat_overrides() {
+    let (_dir, repo) = fresh_repo().await;
+    let primary = InMemoryVectorStore::new();
+
+    // Chat 10: default 30 days. Chat 20: override 7 days. Chat 30: override 0 (keep forever).
+    for chat_id in [10, 20, 30] {
+        save_message(&repo, 1, chat_id, 40).await;
+        save_message(&repo, 1, chat_id, 10).await;
+        add_entry(&primary, 1, chat_id, 40).await;
+        add_entry(&primary, 1, chat_id, 10).await;
+    }
+
+    let policy = RetentionPolicy::new(Some(30))
+        .with_chat_override(20, 7)
+        .with_chat_override(30, 0);
+    let eraser = DataEraser::new(repo.clone()).with_store("primary", Arc::new(primary.clone()));
+    let report = eraser.apply_retention(&policy).await.expect("apply_retention");
+
+    assert_eq!(report.messages, 3);
+    assert_eq!(report.memory_entries[0].deleted, 3);
+    assert_eq!(primary.len().await, 3);
+    assert_eq!(repo.get_stats().await.expect("stats").total_messages, 3);
+}
+
+#[tokio::test]
+async fn apply_retention_sweeps_chats_without_messages_and_usage_rows() {
+    let (dir, repo) = fresh_repo().await;
+    let usage = UsageRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .expect("usage repo");
+    let primary = InMemoryVectorStore::new();
+    // No messages at all: chats are known only to the memory store and the ledger.
+    for chat_id in [10, 20, 30] {
+        add_entry(&primary, 1, chat_id, 40).await;
+        add_entry(&primary, 1, chat_id, 10).await;
+        record_usage(&usage, 1, chat_id, 40).await;
+        record_usage(&usage, 1, chat_id, 10).await;
+    }
+
+    let policy = RetentionPolicy::new(Some(30))
+        .with_chat_override(20, 7)
+        .with_chat_override(30, 0);
+    let eraser = DataEraser::new(repo)
+        .with_store("primary", Arc::new(primary.clone()))
+        .with_usage_repo(usage.clone());
+    let report = eraser.apply_retention(&policy).await.expect("apply_retention");
+
+    assert_eq!(report.messages, 0);
+    assert_eq!(report.memory_entries[0].deleted, 3);
+    assert_eq!(report.usage_rows, 3);
+    assert_eq!(primary.len().await, 3);
+    assert_eq!(primary.search_by_conversation("30").await.unwrap().len(), 2);
+    let remaining = usage.totals(&UsageQuery::default()).await.expect("totals");
+    assert_eq!(remaining.calls, 3);
+}
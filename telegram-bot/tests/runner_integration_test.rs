@@ -176,6 +176,7 @@ async fn seed_memory_context(
             timestamp: base_time + chrono::Duration::minutes(*offset_min),
             tokens: None,
             importance: None,
+            expires_at: None,
         };
         let entry = MemoryEntry::new((*content).to_string(), metadata);
         store.add(entry).await?;
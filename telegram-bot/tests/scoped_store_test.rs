@@ -0,0 +1,221 @@
+//! Tests for [`ScopedStore`] ([`get_store`], [`get_user_store`], [`get_scoped_store`]):
+//! semantic_search (plain and filtered) is fixed to the scope, writes without a chat are assigned
+//! to it, writes, by-id access and searches naming another chat or user fail with [`ScopeViolation`].
+
+use std::sync::Arc;
+
+use telegram_bot::memory::{
+    get_scoped_store, get_store, get_user_store, ChatScopedStore, MemoryEntry, MemoryFilter,
+    MemoryMetadata, MemoryRole, MemoryScope, MemoryStore, ScopeViolation, SearchFilter,
+    UserScopedStore,
+};
+
+mod mock_memory_store;
+use mock_memory_store::MockMemoryStore;
+
+fn metadata(conversation_id: &str) -> MemoryMetadata {
+    MemoryMetadata::default()
+        .with_user("u1")
+        .with_conversation(conversation_id)
+}
+
+#[tokio::test]
+async fn semantic_search_uses_scoped_chat_id_ignores_caller_conversation_id() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+
+    inner
+        .add(MemoryEntry::new("in chat1".to_string(), metadata("chat1")))
+        .await
+        .unwrap();
+    inner
+        .add(MemoryEntry::new("in chat2".to_string(), metadata("chat2")))
+        .await
+        .unwrap();
+
+    let scoped = get_store(inner.clone(), "chat1");
+    let results = scoped
+        .semantic_search(&[], 10, None, Some("other"))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.content, "in chat1");
+    assert_eq!(
+        results[0].1.metadata.conversation_id.as_deref(),
+        Some("chat1")
+    );
+}
+
+#[tokio::test]
+async fn semantic_search_filtered_keeps_chat_scope_and_applies_filter() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+
+    let user_msg = MemoryEntry::new("user in chat1".to_string(), metadata("chat1"));
+    let mut assistant_meta = metadata("chat1");
+    assistant_meta.role = MemoryRole::Assistant;
+    let assistant_msg = MemoryEntry::new("assistant in chat1".to_string(), assistant_meta);
+    let excluded = MemoryEntry::new("excluded in chat1".to_string(), metadata("chat1"));
+    for entry in [
+        user_msg.clone(),
+        assistant_msg,
+        excluded.clone(),
+        MemoryEntry::new("user in chat2".to_string(), metadata("chat2")),
+    ] {
+        inner.add(entry).await.unwrap();
+    }
+
+    let scoped = get_store(inner, "chat1");
+    let filter = SearchFilter::scoped(None, Some("chat2"))
+        .with_role(MemoryRole::User)
+        .excluding([excluded.id]);
+    let results = scoped
+        .semantic_search_filtered(&[], 10, &filter)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].1.id, user_msg.id);
+}
+
+fn is_violation(result: anyhow::Result<impl Sized>) -> bool {
+    result.is_err_and(|e| e.downcast_ref::<ScopeViolation>().is_some())
+}
+
+#[tokio::test]
+async fn add_assigns_missing_conversation_and_refuses_other_chats() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+    let scoped = get_store(inner.clone(), "my_chat");
+
+    let mut meta = metadata("unused");
+    meta.conversation_id = None;
+    let entry = MemoryEntry::new("content".to_string(), meta);
+    let id = entry.id;
+    scoped.add(entry).await.unwrap();
+    let got = inner.get(id).await.unwrap().unwrap();
+    assert_eq!(got.metadata.conversation_id.as_deref(), Some("my_chat"));
+    assert_eq!(got.content, "content");
+
+    let foreign = MemoryEntry::new("elsewhere".to_string(), metadata("other_chat"));
+    let foreign_id = foreign.id;
+    assert!(is_violation(scoped.add(foreign.clone()).await));
+    assert!(is_violation(scoped.add_batch(vec![foreign]).await));
+    assert!(inner.get(foreign_id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn by_id_access_to_another_chat_fails_and_leaves_entry() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+    let other = MemoryEntry::new("secret".to_string(), metadata("c2"));
+    inner.add(other.clone()).await.unwrap();
+
+    let scoped = get_store(inner.clone(), "c1");
+    assert!(is_violation(scoped.get(other.id).await));
+    assert!(is_violation(scoped.delete(other.id).await));
+    let mut hijacked = other.clone();
+    hijacked.metadata.conversation_id = None;
+    hijacked.content = "overwritten".to_string();
+    assert!(is_violation(scoped.update(hijacked).await));
+
+    let stored = inner.get(other.id).await.unwrap().expect("entry kept");
+    assert_eq!(stored.content, "secret");
+    assert_eq!(stored.metadata.conversation_id.as_deref(), Some("c2"));
+    // Unknown ids are not violations.
+    scoped.delete(uuid::Uuid::new_v4()).await.unwrap();
+}
+
+#[tokio::test]
+async fn user_and_composite_scopes_restrict_to_their_entries() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+    let mut u2_meta = metadata("c1");
+    u2_meta.user_id = Some("u2".to_string());
+    let u2_entry = MemoryEntry::new("u2 in c1".to_string(), u2_meta);
+    for entry in [
+        MemoryEntry::new("u1 in c1".to_string(), metadata("c1")),
+        MemoryEntry::new("u1 in c2".to_string(), metadata("c2")),
+        u2_entry.clone(),
+    ] {
+        inner.add(entry).await.unwrap();
+    }
+
+    let user = get_user_store(inner.clone(), "u1");
+    assert_eq!(user.search_by_conversation("c1").await.unwrap().len(), 1);
+    assert_eq!(user.count(&MemoryFilter::default()).await.unwrap(), 2);
+    assert!(is_violation(user.get(u2_entry.id).await));
+
+    let composite = get_scoped_store(inner.clone(), MemoryScope::chat_and_user("c1", "u1"));
+    let page = composite
+        .list(&MemoryFilter::default(), Default::default(), 10, None)
+        .await
+        .unwrap();
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.entries[0].content, "u1 in c1");
+    assert_eq!(composite.delete_by_conversation("c1").await.unwrap(), 1);
+    let left = inner.search_by_conversation("c1").await.unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].id, u2_entry.id);
+}
+
+#[tokio::test]
+async fn search_by_conversation_naming_another_chat_fails() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+    inner
+        .add(MemoryEntry::new("a".to_string(), metadata("c1")))
+        .await
+        .unwrap();
+    inner
+        .add(MemoryEntry::new("b".to_string(), metadata("c2")))
+        .await
+        .unwrap();
+
+    let scoped = ChatScopedStore::chat(inner, "c1");
+    assert_eq!(scoped.chat_id(), "c1");
+    let err = scoped.search_by_conversation("c2").await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ScopeViolation>(),
+        Some(ScopeViolation::ForeignTarget {
+            operation: "search_by_conversation",
+            ..
+        })
+    ));
+    let list = scoped.search_by_conversation("c1").await.unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].content, "a");
+}
+
+#[tokio::test]
+async fn search_by_user_naming_another_user_fails() {
+    let mock = MockMemoryStore::new();
+    let inner = Arc::new(mock) as Arc<dyn MemoryStore>;
+    let mut u2_meta = metadata("c1");
+    u2_meta.user_id = Some("u2".to_string());
+    inner
+        .add(MemoryEntry::new("mine".to_string(), metadata("c1")))
+        .await
+        .unwrap();
+    inner
+        .add(MemoryEntry::new("theirs".to_string(), u2_meta))
+        .await
+        .unwrap();
+
+    let scoped = UserScopedStore::user(inner.clone(), "u1");
+    assert_eq!(scoped.user_id(), "u1");
+    let err = scoped.search_by_user("u2").await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ScopeViolation>(),
+        Some(ScopeViolation::ForeignTarget {
+            operation: "search_by_user",
+            ..
+        })
+    ));
+    let mine = scoped.search_by_user("u1").await.unwrap();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0].content, "mine");
+
+    // A chat scope does not restrict users: searching one user within the chat is allowed.
+    let chat = get_store(inner, "c1");
+    assert_eq!(chat.search_by_user("u2").await.unwrap().len(), 1);
+}
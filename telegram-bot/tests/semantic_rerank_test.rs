@@ -0,0 +1,104 @@
+//! Tests for re-ranking in [`SemanticSearchStrategy`] and [`HybridSearchStrategy`]: recency decay
+//! and MMR diversity over an in-memory store with fixed embeddings.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use telegram_bot::embedding::EmbeddingService;
+use telegram_bot::memory::{
+    ContextStrategy, HybridSearchStrategy, HybridWeights, InMemoryVectorStore, MemoryEntry,
+    MemoryMetadata, MemoryStore, SemanticSearchStrategy, StrategyResult,
+};
+
+/// Embeds every query as the same vector.
+struct QueryEmbedding;
+
+#[async_trait]
+impl EmbeddingService for QueryEmbedding {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Ok(vec![1.0, 1.0, 0.0])
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|_| vec![1.0, 1.0, 0.0]).collect())
+    }
+}
+
+async fn add(store: &InMemoryVectorStore, content: &str, embedding: Vec<f32>, age: Duration) {
+    let metadata = MemoryMetadata::default()
+        .with_conversation("10")
+        .with_timestamp(Utc::now() - age);
+    let entry = MemoryEntry::new(content.to_string(), metadata).with_embedding(embedding);
+    store.add(entry).await.expect("add");
+}
+
+/// Two near-identical old messages closest to the query and one different, recent message.
+async fn store() -> InMemoryVectorStore {
+    let store = InMemoryVectorStore::new();
+    add(&store, "my flight is on May 3", vec![1.0, 0.8, 0.0], Duration::days(60)).await;
+    add(&store, "my flight is on May 3!", vec![1.0, 0.75, 0.0], Duration::days(60)).await;
+    add(&store, "the flight moved to May 5", vec![0.0, 1.0, 0.3], Duration::hours(1)).await;
+    store
+}
+
+async fn contents(strategy: impl ContextStrategy, store: &InMemoryVectorStore) -> Vec<String> {
+    let result = strategy
+        .build_context(store, &None, &None, &Some("when is my flight?".to_string()))
+        .await
+        .expect("build_context");
+    match result {
+        StrategyResult::Messages { messages, .. } => {
+            messages.into_iter().map(|m| m.content).collect()
+        }
+        other => panic!("expected Messages, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn plain_ranking_returns_near_duplicates() {
+    let store = store().await;
+    let strategy = SemanticSearchStrategy::new(2, Arc::new(QueryEmbedding), 0.0);
+
+    assert_eq!(
+        contents(strategy, &store).await,
+        vec!["my flight is on May 3", "my flight is on May 3!"]
+    );
+}
+
+#[tokio::test]
+async fn recency_decay_ranks_recent_message_first() {
+    let store = store().await;
+    let strategy = SemanticSearchStrategy::new(2, Arc::new(QueryEmbedding), 0.0)
+        .with_recency_half_life(Duration::days(7));
+
+    let found = contents(strategy, &store).await;
+    assert_eq!(found[0], "the flight moved to May 5");
+}
+
+#[tokio::test]
+async fn mmr_replaces_near_duplicate_with_different_message() {
+    let store = store().await;
+    let strategy =
+        SemanticSearchStrategy::new(2, Arc::new(QueryEmbedding), 0.0).with_mmr_lambda(0.5);
+
+    assert_eq!(
+        contents(strategy, &store).await,
+        vec!["my flight is on May 3", "the flight moved to May 5"]
+    );
+}
+
+#[tokio::test]
+async fn hybrid_mmr_diversifies_fused_results() {
+    let store = store().await;
+    let weights = HybridWeights { vector: 1.0, keyword: 0.0 };
+    let strategy = HybridSearchStrategy::new(2, Arc::new(QueryEmbedding), 0.0)
+        .with_weights(weights)
+        .with_mmr_lambda(0.5);
+
+    assert_eq!(
+        contents(strategy, &store).await,
+        vec!["my flight is on May 3", "the flight moved to May 5"]
+    );
+}
@@ -0,0 +1,95 @@
+//! Tests for [`BpeTokenizer`] with a small tiktoken-format vocabulary, and model-based loading
+//! with the estimate fallback.
+
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use telegram_bot::tokenizer::{
+    load_tokenizer, BpeTokenizer, Encoding, EnvTokenizerConfig, EstimateTokenizer, Tokenizer,
+};
+use tempfile::TempDir;
+
+/// All single bytes (rank = byte value), then `ab`, ` a`, ` ab` and the two merges spelling `你`.
+fn vocab() -> String {
+    let mut tokens: Vec<Vec<u8>> = (0..=255u8).map(|b| vec![b]).collect();
+    tokens.push(b"ab".to_vec());
+    tokens.push(b" a".to_vec());
+    tokens.push(b" ab".to_vec());
+    tokens.push(vec![0xE4, 0xBD]);
+    tokens.push("你".as_bytes().to_vec());
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(rank, token)| format!("{} {}\n", BASE64.encode(token), rank))
+        .collect()
+}
+
+fn write_vocab(dir: &Path, encoding: Encoding) {
+    std::fs::write(dir.join(encoding.file_name()), vocab()).unwrap();
+}
+
+fn config(dir: &Path, encoding: Option<&str>) -> EnvTokenizerConfig {
+    EnvTokenizerConfig {
+        tokenizer_vocab_dir: dir.to_path_buf(),
+        tokenizer_encoding: encoding.map(String::from),
+    }
+}
+
+#[test]
+fn bpe_merges_pieces_by_rank() {
+    let bpe = BpeTokenizer::from_tiktoken(Encoding::Cl100kBase, &vocab()).unwrap();
+
+    // Pre-tokenized into "ab" and " ab", each a single token.
+    assert_eq!(bpe.encode("ab ab"), vec![256, 258]);
+    assert_eq!(bpe.count_tokens("ab ab"), 2);
+    assert_eq!(bpe.count_tokens(""), 0);
+
+    // "你" merges into one token; "好" has no merges and stays three byte tokens.
+    assert_eq!(bpe.encode("你好"), vec![260, 0xE5, 0xA5, 0xBD]);
+    assert_eq!(bpe.count_tokens("你好"), 4);
+    // The estimate counts each CJK character as a token, not six bytes / 4.
+    assert_eq!(EstimateTokenizer.count_tokens("你好"), 2);
+    assert_eq!(EstimateTokenizer.count_tokens("你好你好你好"), 6);
+    assert_eq!(EstimateTokenizer.count_tokens("ab 你好"), 3);
+}
+
+#[test]
+fn vocabulary_without_all_bytes_is_rejected() {
+    let partial: String = vocab()
+        .lines()
+        .skip(1)
+        .map(|l| format!("{}\n", l))
+        .collect();
+    let err = BpeTokenizer::from_tiktoken(Encoding::Cl100kBase, &partial)
+        .err()
+        .expect("missing byte 0x00");
+    assert!(err.to_string().contains("0x00"));
+    assert!(BpeTokenizer::from_tiktoken(Encoding::Cl100kBase, "not-a-vocab").is_err());
+}
+
+#[test]
+fn load_tokenizer_picks_encoding_by_model_and_falls_back() {
+    let dir = TempDir::new().unwrap();
+    write_vocab(dir.path(), Encoding::Cl100kBase);
+    let cfg = config(dir.path(), None);
+
+    assert_eq!(
+        load_tokenizer(Some(&cfg), "gpt-4-turbo").name(),
+        "cl100k_base"
+    );
+    assert_eq!(
+        load_tokenizer(Some(&cfg), "text-embedding-3-small").name(),
+        "cl100k_base"
+    );
+    // Known model, but its vocabulary file is missing.
+    assert_eq!(load_tokenizer(Some(&cfg), "gpt-4o").name(), "estimate");
+    // Unknown model without an override.
+    assert_eq!(load_tokenizer(Some(&cfg), "glm-4-flash").name(), "estimate");
+    assert_eq!(load_tokenizer(None, "gpt-4-turbo").name(), "estimate");
+
+    let forced = config(dir.path(), Some("cl100k_base"));
+    let tokenizer = load_tokenizer(Some(&forced), "glm-4-flash");
+    assert_eq!(tokenizer.name(), "cl100k_base");
+    assert_eq!(tokenizer.count_tokens("你好"), 4);
+}
@@ -0,0 +1,108 @@
+//! Tests for memory expiry: [`MemoryHandler`] stamps `expires_at` from a [`TtlPolicy`], stores hide
+//! expired entries, and [`DataEraser::sweep_expired`] deletes them.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use telegram_bot::memory::{
+    InMemoryVectorStore, MemoryEntry, MemoryMetadata, MemoryStore, TtlPolicy,
+};
+use telegram_bot::retention::DataEraser;
+use telegram_bot::storage::MessageRepository;
+use telegram_bot::{
+    Chat, Handler, HandlerResponse, MemoryHandler, Message, MessageDirection, User,
+};
+
+fn message(chat_id: i64, chat_type: &str, content: &str) -> Message {
+    Message {
+        id: format!("{}-{}", chat_id, content),
+        user: User {
+            id: 1,
+            username: None,
+            first_name: Some("Test".to_string()),
+            last_name: None,
+        },
+        chat: Chat {
+            id: chat_id,
+            chat_type: chat_type.to_string(),
+        },
+        content: content.to_string(),
+        message_type: "text".to_string(),
+        direction: MessageDirection::Incoming,
+        created_at: Utc::now(),
+        reply_to_message_id: None,
+        reply_to_message_from_bot: false,
+        reply_to_message_content: None,
+    }
+}
+
+#[tokio::test]
+async fn handler_stamps_expiry_for_group_chats_only() {
+    let store = Arc::new(InMemoryVectorStore::new());
+    let handler = MemoryHandler::with_store(store.clone())
+        .with_ttl_policy(TtlPolicy::new().with_source_ttl("group", Duration::hours(24)));
+
+    let group = message(-100, "group", "see you tonight");
+    handler.before(&group).await.unwrap();
+    handler
+        .after(&group, &HandlerResponse::Reply("have fun".to_string()))
+        .await
+        .unwrap();
+    handler
+        .before(&message(7, "private", "my birthday is May 3"))
+        .await
+        .unwrap();
+
+    let group_entries = store.search_by_conversation("-100").await.unwrap();
+    assert_eq!(group_entries.len(), 2);
+    for e in &group_entries {
+        let ttl = e.metadata.expires_at.expect("group entries expire") - e.metadata.timestamp;
+        assert_eq!(ttl, Duration::hours(24));
+    }
+    let private = store.search_by_conversation("7").await.unwrap();
+    assert_eq!(private.len(), 1);
+    assert_eq!(private[0].metadata.expires_at, None);
+}
+
+#[tokio::test]
+async fn expired_entries_are_hidden_then_swept() {
+    let store = Arc::new(InMemoryVectorStore::new());
+    let metadata = MemoryMetadata::default().with_conversation("10");
+    let expired = MemoryEntry::new(
+        "expired".to_string(),
+        metadata.clone().with_expires_at(Utc::now() - Duration::minutes(1)),
+    );
+    let expired_id = expired.id;
+    store.add(expired).await.unwrap();
+    let later = MemoryEntry::new(
+        "later".to_string(),
+        metadata.clone().with_expires_at(Utc::now() + Duration::hours(1)),
+    );
+    store.add(later).await.unwrap();
+    store
+        .add(MemoryEntry::new("forever".to_string(), metadata))
+        .await
+        .unwrap();
+
+    assert!(store.get(expired_id).await.unwrap().is_none());
+    let visible: Vec<String> = store
+        .search_by_conversation("10")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.content)
+        .collect();
+    assert_eq!(visible.len(), 2);
+    assert!(!visible.contains(&"expired".to_string()));
+
+    let repo = MessageRepository::new("sqlite::memory:").await.unwrap();
+    let eraser = DataEraser::new(repo).with_store("primary", store.clone());
+    let report = eraser.sweep_expired(Utc::now()).await;
+    assert_eq!(report.total(), 1);
+    assert_eq!(report.memory_entries[0].store, "primary");
+
+    // Entries still valid survive; a later sweep catches them once they expire.
+    let report = eraser.sweep_expired(Utc::now() + Duration::hours(2)).await;
+    assert_eq!(report.total(), 1);
+    assert_eq!(store.search_by_conversation("10").await.unwrap().len(), 1);
+}
@@ -0,0 +1,216 @@
+//! Integration tests for [`telegram_bot::storage::UsageRepository`].
+//!
+//! Covers record, filtered totals and grouped totals (by chat, model, day) of the `llm_usage` ledger,
+//! embedding usage attribution and per-user deletion.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use telegram_bot::embedding::{with_usage_scope, EmbeddingService, UsageScope, UsageTrackingEmbedding};
+use telegram_bot::storage::{
+    UsageGroupBy, UsageQuery, UsageRecord, UsageRepository, UsageTotals, USAGE_KIND_CHAT,
+    USAGE_KIND_EMBEDDING,
+};
+use tempfile::TempDir;
+
+/// Returns a fresh SQLite database path in a temp dir so each test gets an isolated DB.
+fn fresh_db_path() -> (TempDir, String) {
+    let dir = TempDir::new().expect("temp dir");
+    let path = dir.path().join("test.db");
+    let path_str = path.to_string_lossy().into_owned();
+    (dir, path_str)
+}
+
+/// Records two chat calls in chat 1 (users 10, 11), one in chat 2 and one embedding call without chat.
+async fn seed(repo: &UsageRepository) {
+    let records = [
+        UsageRecord::new("gpt-4o-mini", "inline_llm", USAGE_KIND_CHAT, 100, 20)
+            .for_message(Some(1), Some(10)),
+        UsageRecord::new("gpt-4o-mini", "inline_llm", USAGE_KIND_CHAT, 50, 10)
+            .for_message(Some(1), Some(11)),
+        UsageRecord::new("gpt-4o", "react", USAGE_KIND_CHAT, 300, 40)
+            .for_message(Some(2), Some(10))
+            .estimated(),
+        UsageRecord::new("text-embedding-3-small", "embedding", USAGE_KIND_EMBEDDING, 8, 0)
+            .estimated(),
+    ];
+    for record in &records {
+        repo.record(record).await.expect("record usage");
+    }
+}
+
+/// **Test: totals sum all calls, and filters by chat, user and kind narrow them.**
+#[tokio::test]
+async fn test_usage_totals_with_filters() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = UsageRepository::new(&database_url).await.expect("create repo");
+
+    let empty = repo.totals(&UsageQuery::default()).await.unwrap();
+    assert_eq!(empty, UsageTotals::default());
+
+    seed(&repo).await;
+
+    let all = repo.totals(&UsageQuery::default()).await.unwrap();
+    assert_eq!(all.calls, 4);
+    assert_eq!(all.prompt_tokens, 458);
+    assert_eq!(all.completion_tokens, 70);
+    assert_eq!(all.total_tokens, 528);
+
+    let chat_1 = repo
+        .totals(&UsageQuery {
+            chat_id: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(chat_1.calls, 2);
+    assert_eq!(chat_1.total_tokens, 180);
+
+    let user_10 = repo
+        .totals(&UsageQuery {
+            user_id: Some(10),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(user_10.calls, 2);
+    assert_eq!(user_10.total_tokens, 460);
+
+    let embeddings = repo
+        .totals(&UsageQuery {
+            kind: Some(USAGE_KIND_EMBEDDING.to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(embeddings.calls, 1);
+    assert_eq!(embeddings.prompt_tokens, 8);
+
+    let future = repo
+        .totals(&UsageQuery {
+            start_date: Some(Utc::now() + Duration::hours(1)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(future.calls, 0);
+}
+
+/// **Test: totals_by groups per chat (calls without chat under None), per model and per day.**
+#[tokio::test]
+async fn test_usage_totals_by_group() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = UsageRepository::new(&database_url).await.expect("create repo");
+    seed(&repo).await;
+
+    let by_chat = repo
+        .totals_by(&UsageQuery::default(), UsageGroupBy::Chat)
+        .await
+        .unwrap();
+    assert_eq!(by_chat.len(), 3);
+    assert_eq!(by_chat[0].0.as_deref(), Some("2"));
+    assert_eq!(by_chat[0].1.total_tokens, 340);
+    assert_eq!(by_chat[1].0.as_deref(), Some("1"));
+    assert_eq!(by_chat[1].1.calls, 2);
+    assert_eq!(by_chat[2].0, None);
+
+    let by_model = repo
+        .totals_by(
+            &UsageQuery {
+                kind: Some(USAGE_KIND_CHAT.to_string()),
+                ..Default::default()
+            },
+            UsageGroupBy::Model,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        by_model
+            .iter()
+            .map(|(k, t)| (k.as_deref().unwrap(), t.total_tokens))
+            .collect::<Vec<_>>(),
+        vec![("gpt-4o", 340), ("gpt-4o-mini", 180)]
+    );
+
+    let by_day = repo
+        .totals_by(&UsageQuery::default(), UsageGroupBy::Day)
+        .await
+        .unwrap();
+    assert_eq!(by_day.len(), 1);
+    assert_eq!(
+        by_day[0].0.as_deref(),
+        Some(Utc::now().format("%Y-%m-%d").to_string().as_str())
+    );
+    assert_eq!(by_day[0].1.calls, 4);
+}
+
+/// Embedding API returning a fixed vector.
+struct FixedEmbedding;
+
+#[async_trait]
+impl EmbeddingService for FixedEmbedding {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Ok(vec![1.0, 0.0])
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+    }
+}
+
+/// **Test: embedding calls inside a usage scope are recorded for that chat and user; outside one,
+/// without.**
+#[tokio::test]
+async fn test_embedding_usage_attributed_to_scope() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = UsageRepository::new(&database_url).await.expect("create repo");
+    let embedding = UsageTrackingEmbedding::new(
+        Arc::new(FixedEmbedding),
+        "text-embedding-3-small",
+        repo.clone(),
+    );
+
+    with_usage_scope(UsageScope::new(Some(1), Some(10)), async {
+        embedding.embed("hello world").await.expect("embed");
+    })
+    .await;
+    embedding.embed("hello world").await.expect("embed");
+
+    let scoped = repo
+        .totals(&UsageQuery {
+            chat_id: Some(1),
+            user_id: Some(10),
+            kind: Some(USAGE_KIND_EMBEDDING.to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(scoped.calls, 1);
+    assert!(scoped.prompt_tokens > 0);
+
+    let all = repo.totals(&UsageQuery::default()).await.unwrap();
+    assert_eq!(all.calls, 2);
+}
+
+/// **Test: delete_user removes only that user's rows.**
+#[tokio::test]
+async fn test_usage_delete_user() {
+    let (_dir, database_url) = fresh_db_path();
+    let repo = UsageRepository::new(&database_url).await.expect("create repo");
+    seed(&repo).await;
+
+    assert_eq!(repo.delete_user(10).await.unwrap(), 2);
+
+    let all = repo.totals(&UsageQuery::default()).await.unwrap();
+    assert_eq!(all.calls, 2);
+    let user_10 = repo
+        .totals(&UsageQuery {
+            user_id: Some(10),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(user_10.calls, 0);
+}
@@ -9,9 +9,13 @@ use tracing::{info, warn};
 use telegram_bot::{
     AppExtensions, BotComponents, BotConfig,
     memory::MemoryStore,
+    tokenizer::load_tokenizer,
 };
 
 use crate::handlers::InlineLLMHandler;
+use crate::importance::LlmImportanceScorer;
+use crate::profile::LlmFactExtractor;
+use crate::summarizer::LlmSummarizer;
 
 /// Builds the LLM handler from config and components.
 pub(crate) fn build_llm_handler(
@@ -52,10 +56,38 @@ pub(crate) fn build_llm_handler(
         .with_system_prompt_opt(system_prompt),
     );
 
+    let tokenizer = load_tokenizer(config.extensions().tokenizer_config(), llm_cfg.model());
+    components.memory_write_pipeline.set_tokenizer(tokenizer.clone());
+
+    match mem_cfg.importance_scorer() {
+        "llm" => {
+            info!("Scoring memory importance with the LLM");
+            components
+                .memory_write_pipeline
+                .set_scorer(Arc::new(
+                    LlmImportanceScorer::new(llm_client.clone())
+                        .with_usage_repo(components.usage_repo.as_ref().clone()),
+                ));
+        }
+        "heuristic" => {}
+        other => warn!(scorer = %other, "Unknown MEMORY_IMPORTANCE_SCORER; using heuristic"),
+    }
+    if let Some(ref compactor) = components.memory_compactor {
+        compactor.set_summarizer(Arc::new(LlmSummarizer::new(llm_client.clone())));
+        compactor.set_tokenizer(tokenizer.clone());
+    }
+    if let Some(ref updater) = components.profile_updater {
+        info!("Extracting user profile facts with the LLM");
+        updater.set_extractor(Arc::new(
+            LlmFactExtractor::new(llm_client.clone())
+                .with_usage_repo(components.usage_repo.as_ref().clone()),
+        ));
+    }
+
     let bot_adapter: Arc<dyn telegram_bot::Bot> =
         Arc::new(TelegramBotAdapter::new(components.teloxide_bot.clone()));
 
-    let handler = Arc::new(InlineLLMHandler::new(
+    let handler = InlineLLMHandler::new(
         components.bot_username.clone(),
         llm_client,
         bot_adapter,
@@ -69,9 +101,33 @@ pub(crate) fn build_llm_handler(
         mem_cfg.relevant_top_k() as usize,
         mem_cfg.semantic_min_score(),
         config.base().telegram_edit_interval_secs,
-    ));
+    )
+    .with_usage_repo(components.usage_repo.as_ref().clone())
+    .with_outbox(components.outbox.clone())
+    .with_profile_repo(components.profile_repo.clone())
+    .with_tokenizer(tokenizer)
+    .with_importance_weight(mem_cfg.importance_weight());
+    let handler = if components.memory_compactor.is_some() {
+        handler.with_conversation_summaries(mem_cfg.summary_limit())
+    } else {
+        handler
+    };
+    let handler = match mem_cfg.semantic_decay_half_life_hours() {
+        Some(hours) => handler.with_semantic_recency_decay(chrono::Duration::hours(hours as i64)),
+        None => handler,
+    };
+    let handler = match mem_cfg.semantic_mmr_lambda() {
+        Some(lambda) => handler.with_semantic_mmr(lambda),
+        None => handler,
+    };
+    let handler = if mem_cfg.hybrid_search() {
+        info!(weights = ?mem_cfg.hybrid_weights(), "Using hybrid keyword + vector memory search");
+        handler.with_hybrid_search(mem_cfg.hybrid_weights())
+    } else {
+        handler
+    };
 
-    Ok(handler)
+    Ok(Arc::new(handler))
 }
 
 /// Creates memory stores from config. Supports lance when built with `--features lance`.
@@ -91,14 +147,14 @@ pub(crate) async fn create_memory_stores_for_llm(
                     .lance_path()
                     .unwrap_or("./data/lance_db")
                     .to_string();
-                let emb_cfg = config.extensions().embedding_config();
-                let embedding_dim = if emb_cfg.map_or(false, |e: &dyn telegram_bot::embedding::EmbeddingConfig| {
-                    e.provider().eq_ignore_ascii_case("zhipuai")
-                }) {
-                    1024
-                } else {
-                    1536
-                };
+                let embedding_dim = config
+                    .extensions()
+                    .embedding_config()
+                    .and_then(telegram_bot::embedding::configured_embedding_spec)
+                    .map(|spec| spec.dimension)
+                    .ok_or_else(|| {
+                        anyhow!("Lance store needs the embedding dimension; set EMBEDDING_DIM for this EMBEDDING_MODEL")
+                    })?;
                 let lance_config = memory_lance::LanceConfig {
                     db_path: lance_path.clone(),
                     embedding_dim,
@@ -1,26 +1,33 @@
 //! Inline LLM handler: runs in the handler chain, calls LLM and returns `HandlerResponse::Reply(text)` so later handlers (e.g. memory) can save the reply in `after()`.
 
-use llm_client::{LlmClient, StreamChunk, StreamChunkCallback};
+use llm_client::{LlmClient, LlmUsage, StreamChunk, StreamChunkCallback};
 use telegram_bot::mention;
 use async_trait::async_trait;
 use telegram_bot::{Bot as CoreBot, Handler, HandlerResponse, Message, Result};
 use telegram_bot::embedding::EmbeddingService;
 use telegram_bot::memory::{
-    Context, ContextBuilder, MemoryStore, RecentMessagesStrategy, SemanticSearchStrategy,
+    Context, ContextBuilder, ContextStrategy, ConversationSummaryStrategy, HybridSearchStrategy,
+    HybridWeights, MemoryStore, RecentMessagesStrategy, SemanticSearchStrategy,
     UserPreferencesStrategy,
 };
 use prompt::ChatMessage;
 use std::sync::Arc;
 use std::time::Instant;
-use telegram_bot::storage::MessageRepository;
+use telegram_bot::outbox::{OutboxPayload, OutboxRepository};
+use telegram_bot::profile::ProfileRepository;
+use telegram_bot::storage::{MessageRepository, UsageRecord, UsageRepository, USAGE_KIND_CHAT};
+use telegram_bot::tokenizer::{EstimateTokenizer, Tokenizer};
 use tokio::time::sleep;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
 // --- User-facing fallback messages (sent to Telegram on errors) ---
 const MSG_SEND_FAILED: &str = "Sorry, something went wrong while sending the reply.";
 const MSG_REQUEST_FAILED: &str = "Sorry, something went wrong processing your request. Please try again later.";
 const MSG_STREAM_FAILED: &str = "Sorry, LLM response failed.";
 
+/// Handler name recorded in the usage ledger.
+const USAGE_HANDLER: &str = "inline_llm";
+
 /// Logs the exact messages submitted to the LLM (role + full content) for debugging.
 fn log_messages_submitted_to_llm(messages: &[ChatMessage]) {
     info!(count = messages.len(), "submit_to_llm: messages submitted to LLM");
@@ -36,7 +43,7 @@ fn log_messages_submitted_to_llm(messages: &[ChatMessage]) {
 
 /// Inline LLM handler: when the message is an LLM query (user replies to the bot's message, or @mentions the bot), builds context, calls the LLM, sends the reply to Telegram, and returns `HandlerResponse::Reply(response_text)` so later handlers can persist it in `after()` (e.g. memory handler).
 ///
-/// **External interactions:** Bot trait (send/edit), MessageRepository (log), MemoryStore (context build), EmbeddingService (semantic search), LlmClient (LLM).
+/// **External interactions:** Bot trait (send/edit), MessageRepository (log), OutboxRepository (failed log writes), UsageRepository (token usage), ProfileRepository (user facts), MemoryStore (context build), EmbeddingService (semantic search), LlmClient (LLM).
 #[derive(Clone)]
 pub struct InlineLLMHandler {
     pub(crate) bot_username: Arc<tokio::sync::RwLock<Option<String>>>,
@@ -44,7 +51,7 @@ pub struct InlineLLMHandler {
     pub(crate) bot: Arc<dyn CoreBot>,
     pub(crate) repo: MessageRepository,
     pub(crate) memory_store: Arc<dyn MemoryStore>,
-    /// When set, RecentMessagesStrategy and UserPreferencesStrategy use this store (e.g. SQLite); semantic search still uses `memory_store`.
+    /// When set, RecentMessagesStrategy uses this store (e.g. SQLite); semantic search still uses `memory_store`.
     pub(crate) recent_store: Option<Arc<dyn MemoryStore>>,
     pub(crate) embedding_service: Arc<dyn EmbeddingService>,
     pub(crate) use_streaming: bool,
@@ -55,8 +62,27 @@ pub struct InlineLLMHandler {
     pub(crate) memory_relevant_top_k: usize,
     /// Min similarity score for semantic results; entries below this are excluded from context; 0.0 = no filter (config MEMORY_SEMANTIC_MIN_SCORE).
     pub(crate) memory_semantic_min_score: f32,
+    /// When set, relevant memories come from [`HybridSearchStrategy`] (keyword + vector) with these weights (config MEMORY_HYBRID_SEARCH).
+    pub(crate) memory_hybrid_weights: Option<HybridWeights>,
+    /// Weight of entry importance in relevant-memory ranking; 0.0 = similarity only (config MEMORY_IMPORTANCE_WEIGHT).
+    pub(crate) memory_importance_weight: f32,
+    /// When set, semantic and hybrid search scores halve for every half-life of entry age (config MEMORY_SEMANTIC_DECAY_HALF_LIFE).
+    pub(crate) memory_semantic_decay_half_life: Option<chrono::Duration>,
+    /// When set, semantic and hybrid search results are diversified by MMR with this lambda (config MEMORY_SEMANTIC_MMR_LAMBDA).
+    pub(crate) memory_semantic_mmr_lambda: Option<f32>,
+    /// Number of compaction summaries placed before the recent messages; 0 = none (config MEMORY_SUMMARY_LIMIT).
+    pub(crate) memory_summary_limit: usize,
     /// Min interval (seconds) between edits of the same message when streaming; limits Telegram edit rate (config TELEGRAM_EDIT_INTERVAL_SECS, default 5).
     pub(crate) edit_interval_secs: u64,
+    /// When set, token usage reported by the LLM is recorded per chat/user (see [`with_usage_repo`](Self::with_usage_repo)).
+    pub(crate) usage_repo: Option<UsageRepository>,
+    /// When set, reply records that fail to save are queued here for retry (see [`with_outbox`](Self::with_outbox)).
+    pub(crate) outbox: Option<OutboxRepository>,
+    /// Counts tokens for the context budget; the LLM model's tokenizer (see [`with_tokenizer`](Self::with_tokenizer)).
+    pub(crate) tokenizer: Arc<dyn Tokenizer>,
+    /// When set, facts recorded about the user are added to the context (see [`with_profile_repo`](Self::with_profile_repo));
+    /// otherwise preferences come from the "I like" / "I prefer" heuristic over the user's memories.
+    pub(crate) profile_repo: Option<ProfileRepository>,
 }
 
 impl InlineLLMHandler {
@@ -91,10 +117,75 @@ impl InlineLLMHandler {
             memory_recent_limit,
             memory_relevant_top_k,
             memory_semantic_min_score,
+            memory_hybrid_weights: None,
+            memory_importance_weight: 0.0,
+            memory_semantic_decay_half_life: None,
+            memory_semantic_mmr_lambda: None,
+            memory_summary_limit: 0,
             edit_interval_secs,
+            usage_repo: None,
+            outbox: None,
+            tokenizer: Arc::new(EstimateTokenizer),
+            profile_repo: None,
         }
     }
 
+    /// Records token usage of each LLM call in the given ledger.
+    pub fn with_usage_repo(mut self, usage_repo: UsageRepository) -> Self {
+        self.usage_repo = Some(usage_repo);
+        self
+    }
+
+    /// Queues reply records that fail to save in the outbox for retry instead of dropping them.
+    pub fn with_outbox(mut self, outbox: OutboxRepository) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Counts context tokens with the LLM model's tokenizer instead of estimating them.
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Adds the facts recorded about the user (name, location, preferences, projects) to the context.
+    pub fn with_profile_repo(mut self, profile_repo: ProfileRepository) -> Self {
+        self.profile_repo = Some(profile_repo);
+        self
+    }
+
+    /// Retrieves relevant memories with hybrid keyword + vector search instead of vector search alone.
+    pub fn with_hybrid_search(mut self, weights: HybridWeights) -> Self {
+        self.memory_hybrid_weights = Some(weights);
+        self
+    }
+
+    /// Ranks relevant memories by similarity scaled by stored importance (0.0 disables).
+    pub fn with_importance_weight(mut self, weight: f32) -> Self {
+        self.memory_importance_weight = weight;
+        self
+    }
+
+    /// Decays semantic search scores with entry age so recent memories win over old near-matches.
+    /// Not applied by hybrid search.
+    pub fn with_semantic_recency_decay(mut self, half_life: chrono::Duration) -> Self {
+        self.memory_semantic_decay_half_life = Some(half_life);
+        self
+    }
+
+    /// Diversifies semantic search results with Maximal Marginal Relevance (`lambda` 1 = relevance
+    /// only, 0 = diversity only). Not applied by hybrid search.
+    pub fn with_semantic_mmr(mut self, lambda: f32) -> Self {
+        self.memory_semantic_mmr_lambda = Some(lambda);
+        self
+    }
+
+    /// Includes the latest `limit` conversation summaries (from memory compaction) in the context.
+    pub fn with_conversation_summaries(mut self, limit: usize) -> Self {
+        self.memory_summary_limit = limit;
+        self
+    }
+
     async fn get_bot_username(&self) -> Option<String> {
         self.bot_username.read().await.clone()
     }
@@ -133,6 +224,48 @@ impl InlineLLMHandler {
         }
     }
 
+    /// Strategy for relevant memories: hybrid when configured, otherwise vector search. Recency
+    /// decay and MMR apply to either.
+    fn relevant_memories_strategy(&self) -> Box<dyn ContextStrategy> {
+        match self.memory_hybrid_weights {
+            Some(weights) => {
+                let strategy = HybridSearchStrategy::new(
+                    self.memory_relevant_top_k,
+                    self.embedding_service.clone(),
+                    self.memory_semantic_min_score,
+                )
+                .with_weights(weights)
+                .with_importance_weight(self.memory_importance_weight);
+                let strategy = match self.memory_semantic_decay_half_life {
+                    Some(half_life) => strategy.with_recency_half_life(half_life),
+                    None => strategy,
+                };
+                let strategy = match self.memory_semantic_mmr_lambda {
+                    Some(lambda) => strategy.with_mmr_lambda(lambda),
+                    None => strategy,
+                };
+                Box::new(strategy)
+            }
+            None => {
+                let strategy = SemanticSearchStrategy::new(
+                    self.memory_relevant_top_k,
+                    self.embedding_service.clone(),
+                    self.memory_semantic_min_score,
+                )
+                .with_importance_weight(self.memory_importance_weight);
+                let strategy = match self.memory_semantic_decay_half_life {
+                    Some(half_life) => strategy.with_recency_half_life(half_life),
+                    None => strategy,
+                };
+                let strategy = match self.memory_semantic_mmr_lambda {
+                    Some(lambda) => strategy.with_mmr_lambda(lambda),
+                    None => strategy,
+                };
+                Box::new(strategy)
+            }
+        }
+    }
+
     async fn build_memory_context(
         &self,
         user_id: &str,
@@ -145,15 +278,22 @@ impl InlineLLMHandler {
         } else {
             builder
         };
+        let builder = if self.memory_summary_limit > 0 {
+            builder.with_strategy(Box::new(ConversationSummaryStrategy::new(self.memory_summary_limit)))
+        } else {
+            builder
+        };
         let builder = builder
             .with_strategy(Box::new(RecentMessagesStrategy::new(self.memory_recent_limit)))
-            .with_strategy(Box::new(SemanticSearchStrategy::new(
-                self.memory_relevant_top_k,
-                self.embedding_service.clone(),
-                self.memory_semantic_min_score,
-            )))
-            .with_strategy(Box::new(UserPreferencesStrategy::new()))
+            .with_strategy(self.relevant_memories_strategy());
+        let preferences = match self.profile_repo {
+            Some(ref profiles) => UserPreferencesStrategy::new().with_profile_repo(profiles.clone()),
+            None => UserPreferencesStrategy::new(),
+        };
+        let builder = builder.with_strategy(Box::new(preferences));
+        let builder = builder
             .with_token_limit(4096)
+            .with_tokenizer(self.tokenizer.clone())
             .for_user(user_id)
             .for_conversation(conversation_id)
             .with_query(question);
@@ -217,16 +357,43 @@ impl InlineLLMHandler {
             "sent".to_string(),
             None,
         );
-        self.repo
-            .save(&record)
+        let Err(e) = self.repo.save(&record).await else {
+            return Ok(());
+        };
+        error!(error = %e, "Failed to save LLM response");
+        let Some(ref outbox) = self.outbox else {
+            return Err(telegram_bot::DbotError::Database(e.to_string()));
+        };
+        outbox
+            .enqueue(&OutboxPayload::Message { record }, &e.to_string())
             .await
             .map_err(|e| {
-                error!(error = %e, "Failed to save LLM response");
+                error!(error = %e, "Failed to queue LLM response in outbox");
                 telegram_bot::DbotError::Database(e.to_string())
             })?;
         Ok(())
     }
 
+    /// Records LLM token usage for the message's chat/user. No-op without a ledger or reported usage;
+    /// failures are logged only.
+    async fn record_usage(&self, message: &Message, usage: Option<LlmUsage>) {
+        let (Some(repo), Some(usage)) = (self.usage_repo.as_ref(), usage) else {
+            return;
+        };
+        let record = UsageRecord::new(
+            usage.model,
+            USAGE_HANDLER,
+            USAGE_KIND_CHAT,
+            usage.prompt_tokens as i64,
+            usage.completion_tokens as i64,
+        )
+        .with_total_tokens(usage.total_tokens as i64)
+        .for_message(Some(message.chat.id), Some(message.user.id));
+        if let Err(e) = repo.record(&record).await {
+            warn!(error = %e, "Failed to record LLM usage");
+        }
+    }
+
     async fn send_fallback_and_stop(
         &self,
         message: &Message,
@@ -258,8 +425,15 @@ impl InlineLLMHandler {
         );
         log_messages_submitted_to_llm(&messages);
 
-        let response = match self.llm_client.get_llm_response_with_messages(messages).await {
-            Ok(r) => r,
+        let response = match self
+            .llm_client
+            .get_llm_response_with_messages_and_usage(messages)
+            .await
+        {
+            Ok(r) => {
+                self.record_usage(message, r.usage).await;
+                r.content
+            }
             Err(e) => {
                 Self::log_error_chain(&e, "Failed to get LLM response");
                 let err_str = e.to_string();
@@ -355,12 +529,15 @@ impl InlineLLMHandler {
         });
         match self
             .llm_client
-            .get_llm_response_stream_with_messages(messages, stream_callback.as_mut())
+            .get_llm_response_stream_with_messages_and_usage(messages, stream_callback.as_mut())
             .await
         {
             Ok(full_response) => {
-                let _ = self.log_llm_response_for_message(message, &full_response).await;
-                Ok(HandlerResponse::Reply(full_response))
+                self.record_usage(message, full_response.usage).await;
+                let _ = self
+                    .log_llm_response_for_message(message, &full_response.content)
+                    .await;
+                Ok(HandlerResponse::Reply(full_response.content))
             }
             Err(e) => {
                 Self::log_error_chain(&e, "LLM stream response failed");
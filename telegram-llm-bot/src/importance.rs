@@ -0,0 +1,77 @@
+//! LLM-based importance scoring for memory writes (config `MEMORY_IMPORTANCE_SCORER=llm`).
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use llm_client::LlmClient;
+use prompt::ChatMessage;
+use telegram_bot::memory::{ImportanceScorer, MemoryEntry};
+use telegram_bot::storage::UsageRepository;
+
+use crate::usage::record_entry_usage;
+
+/// Instruction sent with each entry; the model answers with a single 0–10 rating.
+const SCORING_PROMPT: &str =
+    "Rate how useful the following chat message would be to remember in later \
+conversations with this user, from 0 (small talk, acknowledgements) to 10 (facts, preferences, \
+decisions, commitments). Reply with the number only.";
+
+/// Longest message content sent for scoring, in characters.
+const MAX_SCORED_CHARS: usize = 2000;
+
+/// Handler name recorded in the usage ledger.
+const USAGE_HANDLER: &str = "importance";
+
+/// Asks the LLM to rate each entry from 0 to 10 and maps the rating to `[0, 1]`.
+///
+/// One extra LLM call per stored message; when the call fails or the reply has no number, the
+/// write pipeline falls back to the heuristic score.
+pub struct LlmImportanceScorer {
+    llm_client: Arc<dyn LlmClient>,
+    usage_repo: Option<UsageRepository>,
+}
+
+impl LlmImportanceScorer {
+    pub fn new(llm_client: Arc<dyn LlmClient>) -> Self {
+        Self {
+            llm_client,
+            usage_repo: None,
+        }
+    }
+
+    /// Records the token usage of each scoring call for the entry's chat/user.
+    pub fn with_usage_repo(mut self, usage_repo: UsageRepository) -> Self {
+        self.usage_repo = Some(usage_repo);
+        self
+    }
+}
+
+/// First number in `reply` (e.g. "7", "Rating: 8/10", "6.5"), divided by 10 and clamped to `[0, 1]`.
+pub fn parse_importance_rating(reply: &str) -> Option<f32> {
+    let start = reply.find(|c: char| c.is_ascii_digit())?;
+    let number: String = reply[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let rating: f32 = number.trim_end_matches('.').parse().ok()?;
+    Some((rating / 10.0).clamp(0.0, 1.0))
+}
+
+#[async_trait]
+impl ImportanceScorer for LlmImportanceScorer {
+    async fn score(&self, entry: &MemoryEntry) -> Result<f32> {
+        let content: String = entry.content.chars().take(MAX_SCORED_CHARS).collect();
+        let messages = vec![
+            ChatMessage::system(SCORING_PROMPT),
+            ChatMessage::user(format!("{:?} message:\n{}", entry.metadata.role, content)),
+        ];
+        let response = self
+            .llm_client
+            .get_llm_response_with_messages_and_usage(messages)
+            .await?;
+        record_entry_usage(self.usage_repo.as_ref(), USAGE_HANDLER, entry, response.usage).await;
+        parse_importance_rating(&response.content)
+            .ok_or_else(|| anyhow!("LLM importance reply has no rating: {:?}", response.content))
+    }
+}
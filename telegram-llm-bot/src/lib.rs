@@ -5,6 +5,13 @@
 mod assembly;
 mod facade;
 pub mod handlers;
+pub mod importance;
+pub mod profile;
+pub mod summarizer;
+mod usage;
 
 pub use facade::*;
 pub use handlers::{InlineLLMHandler, LLMDetectionHandler, LLMQuery};
+pub use importance::LlmImportanceScorer;
+pub use profile::LlmFactExtractor;
+pub use summarizer::LlmSummarizer;
@@ -3,8 +3,8 @@
 use anyhow::Result;
 use clap::Parser;
 use std::path::Path;
-use telegram_llm_bot::run_bot_with_llm;
-use telegram_bot::{load_config, Cli, Commands};
+use telegram_llm_bot::{create_memory_stores_for_llm, run_bot_with_llm};
+use telegram_bot::{load_config, run_backfill, run_outbox, run_reembed, run_reencrypt, Cli, Commands};
 
 /// Load .env: workspace root first (override so .env wins over shell env), then cwd as fallback.
 fn load_dotenv() {
@@ -30,5 +30,32 @@ async fn main() -> Result<()> {
             let config = load_config(token)?;
             run_bot_with_llm(config).await
         }
+        Commands::Reencrypt { batch_size } => {
+            let config = load_config(None)?;
+            let (memory_store, recent_store) = create_memory_stores_for_llm(&config).await?;
+            let report = run_reencrypt(config, memory_store, recent_store, batch_size).await?;
+            println!("{:#?}", report);
+            Ok(())
+        }
+        Commands::Reembed { batch_size } => {
+            let config = load_config(None)?;
+            let (memory_store, recent_store) = create_memory_stores_for_llm(&config).await?;
+            let report = run_reembed(config, memory_store, recent_store, batch_size).await?;
+            println!("{:#?}", report);
+            Ok(())
+        }
+        Commands::Backfill { batch_size } => {
+            let config = load_config(None)?;
+            let (memory_store, recent_store) = create_memory_stores_for_llm(&config).await?;
+            let report = run_backfill(config, memory_store, recent_store, batch_size).await?;
+            println!("{:#?}", report);
+            Ok(())
+        }
+        Commands::Outbox { action } => {
+            let config = load_config(None)?;
+            let report = run_outbox(config, action).await?;
+            print!("{}", report);
+            Ok(())
+        }
     }
 }
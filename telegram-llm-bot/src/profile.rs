@@ -0,0 +1,103 @@
+//! LLM-based user fact extraction for profiles (config `PROFILE_EXTRACTION`).
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use llm_client::LlmClient;
+use prompt::ChatMessage;
+use serde_json::Value;
+use telegram_bot::memory::MemoryEntry;
+use telegram_bot::profile::{ExtractedFact, FactExtractor, FactKind};
+use telegram_bot::storage::UsageRepository;
+
+use crate::usage::record_entry_usage;
+
+/// Instruction sent with each batch of user messages.
+const EXTRACTION_PROMPT: &str = "Extract facts the user states about themselves from the \
+following chat messages: their name, where they live, their preferences (likes, dislikes, \
+habits) and projects they are working on. Ignore questions, hypotheticals and facts about other \
+people. Reply with a JSON array only, one object per fact: {\"kind\": \"name\" | \"location\" | \
+\"preference\" | \"project\", \"value\": short statement in the language of the messages, \
+\"confidence\": number from 0 to 1}. Reply [] when there are none.";
+
+/// Longest single message included in the request, in characters.
+const MAX_MESSAGE_CHARS: usize = 1000;
+
+/// Handler name recorded in the usage ledger.
+const USAGE_HANDLER: &str = "profile";
+
+/// Extracts facts from a batch of one user's messages with one LLM call.
+pub struct LlmFactExtractor {
+    llm_client: Arc<dyn LlmClient>,
+    usage_repo: Option<UsageRepository>,
+}
+
+impl LlmFactExtractor {
+    pub fn new(llm_client: Arc<dyn LlmClient>) -> Self {
+        Self {
+            llm_client,
+            usage_repo: None,
+        }
+    }
+
+    /// Records the token usage of each extraction call for the user (and the chat of the batch's
+    /// last message).
+    pub fn with_usage_repo(mut self, usage_repo: UsageRepository) -> Self {
+        self.usage_repo = Some(usage_repo);
+        self
+    }
+}
+
+/// Facts in the JSON array of `reply` (code fences and text around the array are ignored). Objects
+/// with an unknown kind, an empty value or no numeric confidence are skipped.
+pub fn parse_facts(reply: &str) -> Result<Vec<ExtractedFact>> {
+    let (Some(start), Some(end)) = (reply.find('['), reply.rfind(']')) else {
+        return Err(anyhow!("LLM fact reply has no JSON array: {:?}", reply));
+    };
+    if end < start {
+        return Err(anyhow!("LLM fact reply has no JSON array: {:?}", reply));
+    }
+    let items: Vec<Value> = serde_json::from_str(&reply[start..=end])
+        .map_err(|e| anyhow!("LLM fact reply is not a JSON array ({}): {:?}", e, reply))?;
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let kind = FactKind::parse(item.get("kind")?.as_str()?)?;
+            let value = item.get("value")?.as_str()?.trim();
+            let confidence = item.get("confidence")?.as_f64()? as f32;
+            (!value.is_empty()).then(|| ExtractedFact {
+                kind,
+                value: value.to_string(),
+                confidence: confidence.clamp(0.0, 1.0),
+            })
+        })
+        .collect())
+}
+
+#[async_trait]
+impl FactExtractor for LlmFactExtractor {
+    async fn extract(&self, messages: &[MemoryEntry]) -> Result<Vec<ExtractedFact>> {
+        let Some(last) = messages.last() else {
+            return Ok(Vec::new());
+        };
+        let transcript = messages
+            .iter()
+            .map(|m| {
+                let content: String = m.content.chars().take(MAX_MESSAGE_CHARS).collect();
+                format!("- {}", content)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let request = vec![
+            ChatMessage::system(EXTRACTION_PROMPT),
+            ChatMessage::user(transcript),
+        ];
+        let response = self
+            .llm_client
+            .get_llm_response_with_messages_and_usage(request)
+            .await?;
+        record_entry_usage(self.usage_repo.as_ref(), USAGE_HANDLER, last, response.usage).await;
+        parse_facts(&response.content)
+    }
+}
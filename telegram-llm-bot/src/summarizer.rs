@@ -0,0 +1,67 @@
+//! LLM-based summaries for memory compaction (config `MEMORY_COMPACTION_AFTER_DAYS`).
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use llm_client::LlmClient;
+use prompt::ChatMessage;
+use telegram_bot::memory::{MemoryEntry, MemoryRole, MemorySummarizer};
+
+/// Instruction sent with each window of messages.
+const SUMMARY_PROMPT: &str = "Summarize the following chat excerpt for long-term memory. Keep \
+facts, decisions, preferences, names, dates, numbers and open questions, and who said them; drop \
+greetings and small talk. Write in the language of the conversation, in at most 150 words, as \
+plain text.";
+
+/// Longest single message included in the transcript, in characters.
+const MAX_MESSAGE_CHARS: usize = 1000;
+
+/// Summarizes a window of memory entries with one LLM call.
+pub struct LlmSummarizer {
+    llm_client: Arc<dyn LlmClient>,
+}
+
+impl LlmSummarizer {
+    pub fn new(llm_client: Arc<dyn LlmClient>) -> Self {
+        Self { llm_client }
+    }
+}
+
+/// One line per entry: `[2026-01-02 10:00] User 42: text`.
+fn transcript(entries: &[MemoryEntry]) -> String {
+    entries
+        .iter()
+        .map(|e| {
+            let speaker = match (e.metadata.role, e.metadata.user_id.as_deref()) {
+                (MemoryRole::User, Some(user_id)) => format!("User {}", user_id),
+                (MemoryRole::User, None) => "User".to_string(),
+                (MemoryRole::Assistant, _) => "Assistant".to_string(),
+                (MemoryRole::System, _) => "System".to_string(),
+            };
+            let content: String = e.content.chars().take(MAX_MESSAGE_CHARS).collect();
+            format!(
+                "[{}] {}: {}",
+                e.metadata.timestamp.format("%Y-%m-%d %H:%M"),
+                speaker,
+                content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[async_trait]
+impl MemorySummarizer for LlmSummarizer {
+    async fn summarize(&self, entries: &[MemoryEntry]) -> Result<String> {
+        let messages = vec![
+            ChatMessage::system(SUMMARY_PROMPT),
+            ChatMessage::user(transcript(entries)),
+        ];
+        let summary = self
+            .llm_client
+            .get_llm_response_with_messages(messages)
+            .await?;
+        Ok(summary.trim().to_string())
+    }
+}
@@ -0,0 +1,33 @@
+//! Usage ledger helper for background LLM calls (importance scoring, fact extraction).
+
+use llm_client::LlmUsage;
+use telegram_bot::memory::MemoryEntry;
+use telegram_bot::storage::{UsageRecord, UsageRepository, USAGE_KIND_CHAT};
+use tracing::warn;
+
+/// Records `usage` reported for a call made by `handler` on behalf of `entry`'s chat and user.
+/// No-op without a ledger or reported usage; failures are logged only.
+pub(crate) async fn record_entry_usage(
+    repo: Option<&UsageRepository>,
+    handler: &str,
+    entry: &MemoryEntry,
+    usage: Option<LlmUsage>,
+) {
+    let (Some(repo), Some(usage)) = (repo, usage) else {
+        return;
+    };
+    let chat_id = entry.metadata.conversation_id.as_deref().and_then(|id| id.parse().ok());
+    let user_id = entry.metadata.user_id.as_deref().and_then(|id| id.parse().ok());
+    let record = UsageRecord::new(
+        usage.model,
+        handler,
+        USAGE_KIND_CHAT,
+        usage.prompt_tokens as i64,
+        usage.completion_tokens as i64,
+    )
+    .with_total_tokens(usage.total_tokens as i64)
+    .for_message(chat_id, user_id);
+    if let Err(e) = repo.record(&record).await {
+        warn!(error = %e, handler, "Failed to record LLM usage");
+    }
+}
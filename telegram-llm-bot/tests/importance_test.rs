@@ -0,0 +1,51 @@
+//! Tests for LLM importance scoring: rating parsing and the scorer with a stub LLM client.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use llm_client::{LlmClient, StreamChunkCallback};
+use prompt::ChatMessage;
+use telegram_bot::memory::{ImportanceScorer, MemoryEntry, MemoryMetadata};
+use telegram_llm_bot::importance::parse_importance_rating;
+use telegram_llm_bot::LlmImportanceScorer;
+
+/// LLM client that always replies with a fixed text.
+struct FixedReply(&'static str);
+
+#[async_trait]
+impl LlmClient for FixedReply {
+    async fn get_llm_response_with_messages(
+        &self,
+        _messages: Vec<ChatMessage>,
+    ) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+
+    async fn get_llm_response_stream_with_messages(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _callback: &mut StreamChunkCallback,
+    ) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+}
+
+#[test]
+fn parse_importance_rating_reads_first_number() {
+    assert_eq!(parse_importance_rating("7"), Some(0.7));
+    assert_eq!(parse_importance_rating("Rating: 8/10."), Some(0.8));
+    assert_eq!(parse_importance_rating("about 6.5"), Some(0.65));
+    assert_eq!(parse_importance_rating("42"), Some(1.0));
+    assert_eq!(parse_importance_rating("not sure"), None);
+}
+
+#[tokio::test]
+async fn llm_importance_scorer_maps_reply_to_unit_range() {
+    let flight = MemoryEntry::new("My flight is on May 3".to_string(), MemoryMetadata::default());
+    let scorer = LlmImportanceScorer::new(Arc::new(FixedReply("9")));
+    let score = scorer.score(&flight).await.unwrap();
+    assert!((score - 0.9).abs() < 1e-6);
+
+    let scorer = LlmImportanceScorer::new(Arc::new(FixedReply("I can't rate that")));
+    assert!(scorer.score(&flight).await.is_err());
+}
@@ -1,16 +1,18 @@
 //! Unit tests for InlineLLMHandler.
 //!
-//! Covers: is_bot_mentioned, extract_question, get_question.
+//! Covers: is_bot_mentioned, extract_question, get_question, and the memory context sent to the LLM
+//! with the default configuration.
 //! Uses in-memory store, MockEmbeddingService, MockBot, and OpenAILlmClient (dummy key); does not call Telegram or OpenAI.
 
 use telegram_llm_bot::InlineLLMHandler;
-use llm_client::{LlmClient, OpenAILlmClient};
+use llm_client::{LlmClient, OpenAILlmClient, StreamChunkCallback};
+use prompt::ChatMessage;
 use async_trait::async_trait;
 use chrono::Utc;
 use telegram_bot::embedding::EmbeddingService;
-use std::sync::Arc;
-use telegram_bot::memory::{InMemoryVectorStore, MemoryStore};
-use telegram_bot::{Bot as CoreBot, Chat, Message, MessageDirection, Result as DbotResult, User};
+use std::sync::{Arc, Mutex};
+use telegram_bot::memory::{InMemoryVectorStore, MemoryEntry, MemoryMetadata, MemoryStore};
+use telegram_bot::{Bot as CoreBot, Handler, Chat, Message, MessageDirection, Result as DbotResult, User};
 use telegram_bot::storage::MessageRepository;
 
 /// Mock embedding service for tests: returns fixed-dimension vectors, no external API.
@@ -274,3 +276,75 @@ async fn test_reply_to_non_bot_with_content() {
     let q = h.get_question(&msg, Some("bot"));
     assert_eq!(q, None);
 }
+
+// --- memory context ---
+
+/// LLM client that records the messages of each request and replies "ok".
+#[derive(Default)]
+struct CapturingLlm {
+    requests: Mutex<Vec<Vec<ChatMessage>>>,
+}
+
+#[async_trait]
+impl LlmClient for CapturingLlm {
+    async fn get_llm_response_with_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> anyhow::Result<String> {
+        self.requests.lock().unwrap().push(messages);
+        Ok("ok".to_string())
+    }
+
+    async fn get_llm_response_stream_with_messages(
+        &self,
+        messages: Vec<ChatMessage>,
+        _callback: &mut StreamChunkCallback,
+    ) -> anyhow::Result<String> {
+        self.get_llm_response_with_messages(messages).await
+    }
+}
+
+#[tokio::test]
+async fn test_default_config_adds_preferences_from_memories() {
+    let llm = Arc::new(CapturingLlm::default());
+    let memory_store: Arc<dyn MemoryStore> = Arc::new(InMemoryVectorStore::new());
+    let metadata = MemoryMetadata::default().with_user("123").with_conversation("456");
+    memory_store
+        .add(MemoryEntry::new("I like green tea".to_string(), metadata))
+        .await
+        .unwrap();
+    let repo = MessageRepository::new("sqlite::memory:")
+        .await
+        .expect("in-memory repo");
+    // No profile repository: fact extraction is off by default.
+    let handler = InlineLLMHandler::new(
+        Arc::new(tokio::sync::RwLock::new(Some("bot".to_string()))),
+        llm.clone(),
+        Arc::new(MockBot),
+        repo,
+        memory_store,
+        None,
+        Arc::new(MockEmbeddingService),
+        false,
+        "Thinking...".to_string(),
+        10,
+        5,
+        0.0,
+        5,
+    );
+
+    handler
+        .handle(&make_message("@bot what should I drink?", None, false))
+        .await
+        .unwrap();
+
+    let requests = llm.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(
+        requests[0]
+            .iter()
+            .any(|m| m.content.contains("User Preferences: i like green tea")),
+        "preferences missing from {:?}",
+        requests[0]
+    );
+}
@@ -0,0 +1,134 @@
+//! Tests for LLM fact extraction: reply parsing and the extractor with a stub LLM client.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use llm_client::{LlmClient, LlmResponse, LlmUsage, StreamChunkCallback};
+use prompt::ChatMessage;
+use telegram_bot::memory::{MemoryEntry, MemoryMetadata};
+use telegram_bot::profile::{FactExtractor, FactKind};
+use telegram_bot::storage::{UsageQuery, UsageRepository};
+use telegram_llm_bot::profile::parse_facts;
+use telegram_llm_bot::LlmFactExtractor;
+
+/// LLM client that always replies with a fixed text.
+struct FixedReply(&'static str);
+
+#[async_trait]
+impl LlmClient for FixedReply {
+    async fn get_llm_response_with_messages(
+        &self,
+        _messages: Vec<ChatMessage>,
+    ) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+
+    async fn get_llm_response_stream_with_messages(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _callback: &mut StreamChunkCallback,
+    ) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+}
+
+/// Like [`FixedReply`], also reporting token usage.
+struct MeteredReply(&'static str);
+
+#[async_trait]
+impl LlmClient for MeteredReply {
+    async fn get_llm_response_with_messages(
+        &self,
+        _messages: Vec<ChatMessage>,
+    ) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+
+    async fn get_llm_response_stream_with_messages(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _callback: &mut StreamChunkCallback,
+    ) -> anyhow::Result<String> {
+        Ok(self.0.to_string())
+    }
+
+    async fn get_llm_response_with_messages_and_usage(
+        &self,
+        _messages: Vec<ChatMessage>,
+    ) -> anyhow::Result<LlmResponse> {
+        Ok(LlmResponse {
+            content: self.0.to_string(),
+            usage: Some(LlmUsage {
+                model: "test-model".to_string(),
+                prompt_tokens: 40,
+                completion_tokens: 2,
+                total_tokens: 42,
+            }),
+        })
+    }
+}
+
+#[test]
+fn parse_facts_reads_array_and_skips_invalid_objects() {
+    let reply = "```json\n[\
+        {\"kind\": \"name\", \"value\": \"Alice\", \"confidence\": 0.95},\
+        {\"kind\": \"Preferences\", \"value\": \" green tea \", \"confidence\": 1.4},\
+        {\"kind\": \"hobby\", \"value\": \"chess\", \"confidence\": 0.9},\
+        {\"kind\": \"project\", \"value\": \"\", \"confidence\": 0.9},\
+        {\"kind\": \"location\", \"value\": \"Berlin\"}\
+    ]\n```";
+    let facts = parse_facts(reply).unwrap();
+
+    assert_eq!(facts.len(), 2);
+    assert_eq!(facts[0].kind, FactKind::Name);
+    assert_eq!(facts[0].value, "Alice");
+    assert_eq!(facts[1].kind, FactKind::Preference);
+    assert_eq!(facts[1].value, "green tea");
+    assert_eq!(facts[1].confidence, 1.0);
+
+    assert!(parse_facts("[]").unwrap().is_empty());
+    assert!(parse_facts("No facts here.").is_err());
+    assert!(parse_facts("[not json]").is_err());
+}
+
+#[tokio::test]
+async fn llm_fact_extractor_parses_reply() {
+    let extractor = LlmFactExtractor::new(Arc::new(FixedReply(
+        "[{\"kind\": \"location\", \"value\": \"Berlin\", \"confidence\": 0.8}]",
+    )));
+    let message =
+        MemoryEntry::new("I moved to Berlin last year".to_string(), MemoryMetadata::default());
+    let facts = extractor.extract(std::slice::from_ref(&message)).await.unwrap();
+    assert_eq!(facts.len(), 1);
+    assert_eq!(facts[0].kind, FactKind::Location);
+    assert_eq!(facts[0].value, "Berlin");
+
+    let extractor = LlmFactExtractor::new(Arc::new(FixedReply("Sorry, I can't help")));
+    assert!(extractor.extract(&[message]).await.is_err());
+}
+
+#[tokio::test]
+async fn llm_fact_extractor_records_usage_for_user() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let usage = UsageRepository::new(&dir.path().join("test.db").to_string_lossy())
+        .await
+        .unwrap();
+    let extractor =
+        LlmFactExtractor::new(Arc::new(MeteredReply("[]"))).with_usage_repo(usage.clone());
+
+    let hello = MemoryMetadata::default().with_user("1").with_conversation("1");
+    let hello = MemoryEntry::new("hello".to_string(), hello);
+    assert!(extractor.extract(&[hello]).await.unwrap().is_empty());
+
+    let totals = usage
+        .totals(&UsageQuery {
+            user_id: Some(1),
+            chat_id: Some(1),
+            handler: Some("profile".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(totals.calls, 1);
+    assert_eq!(totals.total_tokens, 42);
+}
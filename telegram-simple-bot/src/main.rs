@@ -3,7 +3,10 @@
 use anyhow::Result;
 use clap::Parser;
 use std::sync::Arc;
-use telegram_bot::{load_config, run_bot, Cli, Commands, NoOpHandler};
+use telegram_bot::{
+    create_memory_stores, load_config, run_backfill, run_bot, run_outbox, run_reembed, run_reencrypt,
+    Cli, Commands, NoOpHandler,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,5 +19,32 @@ async fn main() -> Result<()> {
             let config = load_config(token)?;
             run_bot(config, |_config, _components| Arc::new(NoOpHandler::new())).await
         }
+        Commands::Reencrypt { batch_size } => {
+            let config = load_config(None)?;
+            let (memory_store, recent_store) = create_memory_stores(&config).await?;
+            let report = run_reencrypt(config, memory_store, recent_store, batch_size).await?;
+            println!("{:#?}", report);
+            Ok(())
+        }
+        Commands::Reembed { batch_size } => {
+            let config = load_config(None)?;
+            let (memory_store, recent_store) = create_memory_stores(&config).await?;
+            let report = run_reembed(config, memory_store, recent_store, batch_size).await?;
+            println!("{:#?}", report);
+            Ok(())
+        }
+        Commands::Backfill { batch_size } => {
+            let config = load_config(None)?;
+            let (memory_store, recent_store) = create_memory_stores(&config).await?;
+            let report = run_backfill(config, memory_store, recent_store, batch_size).await?;
+            println!("{:#?}", report);
+            Ok(())
+        }
+        Commands::Outbox { action } => {
+            let config = load_config(None)?;
+            let report = run_outbox(config, action).await?;
+            print!("{}", report);
+            Ok(())
+        }
     }
 }